@@ -0,0 +1,44 @@
+/* C API of mio, built with the `capi` feature. See src/capi.rs. */
+
+#ifndef MIO_H
+#define MIO_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define MIO_READABLE 0x1
+#define MIO_WRITABLE 0x2
+#define MIO_ERROR    0x4
+#define MIO_HUP      0x8
+
+#define MIO_LEVEL    0x0
+#define MIO_EDGE     0x1
+#define MIO_ONESHOT  0x2
+
+typedef struct mio_loop mio_loop;
+
+typedef void (*mio_fd_cb)(mio_loop *loop, int64_t handle, int fd, uint32_t events, void *data);
+typedef void (*mio_timer_cb)(mio_loop *loop, int64_t id, void *data);
+
+mio_loop *mio_loop_new(void);
+void mio_loop_free(mio_loop *loop);
+
+int64_t mio_register(mio_loop *loop, int fd, uint32_t interest, uint32_t opts, mio_fd_cb cb, void *data);
+int mio_reregister(mio_loop *loop, int64_t handle, uint32_t interest, uint32_t opts);
+int mio_deregister(mio_loop *loop, int64_t handle);
+
+int64_t mio_timeout(mio_loop *loop, uint64_t delay_ms, mio_timer_cb cb, void *data);
+int mio_clear_timeout(mio_loop *loop, int64_t id);
+
+int mio_run(mio_loop *loop);
+int mio_run_once(mio_loop *loop, uint64_t timeout_ms);
+void mio_stop(mio_loop *loop);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,153 @@
+use {io, EventLoop, Evented, EventSet, Handler, PollOpt, Token, TryWrite, Watermark, WriteQueue};
+use buf::SharedBuf;
+use std::fmt;
+use std::collections::HashMap;
+
+/// The progress of a `Broadcast` subscriber.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Messages queued for the subscriber since it subscribed.
+    pub queued: u64,
+    /// Messages written out entirely.
+    pub delivered: u64,
+    /// Bytes queued and not written yet.
+    pub pending: usize,
+}
+
+/// Writes the same messages to many connections.
+///
+/// Each subscriber has a `WriteQueue` of `SharedBuf`s, so a published
+/// message is stored once however many subscribers it is queued for, and
+/// freed once written to all of them. Subscribers are flushed and have
+/// their interest updated independently, as their connections become
+/// writable.
+pub struct Broadcast {
+    subscribers: HashMap<Token, Subscriber>,
+    high: usize,
+    low: usize,
+}
+
+struct Subscriber {
+    queue: WriteQueue<SharedBuf>,
+    queued: u64,
+}
+
+impl Broadcast {
+    /// Returns a broadcast whose subscriber queues use the `high` and `low`
+    /// watermarks, see `WriteQueue`.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `low` is greater than `high`.
+    pub fn new(high: usize, low: usize) -> Broadcast {
+        assert!(low <= high, "low-water mark must not be above the high-water mark");
+
+        Broadcast {
+            subscribers: HashMap::new(),
+            high: high,
+            low: low,
+        }
+    }
+
+    /// Adds the connection `token`, which receives the messages published
+    /// from now on. Returns false if it was already subscribed.
+    pub fn subscribe(&mut self, token: Token) -> bool {
+        if self.subscribers.contains_key(&token) {
+            return false;
+        }
+
+        self.subscribers.insert(token, Subscriber {
+            queue: WriteQueue::new(self.high, self.low),
+            queued: 0,
+        });
+
+        true
+    }
+
+    /// Removes the connection `token`, dropping the messages queued for it.
+    pub fn unsubscribe(&mut self, token: Token) -> bool {
+        self.subscribers.remove(&token).is_some()
+    }
+
+    pub fn is_subscribed(&self, token: Token) -> bool {
+        self.subscribers.contains_key(&token)
+    }
+
+    /// Returns the number of subscribers.
+    pub fn len(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    /// Queues `bytes` for every subscriber. Returns the subscribers whose
+    /// queue reached the high-water mark with it, which are falling behind.
+    pub fn publish(&mut self, bytes: Vec<u8>) -> Vec<Token> {
+        let buf = SharedBuf::from(bytes);
+        let mut slow = vec![];
+
+        for (&token, sub) in self.subscribers.iter_mut() {
+            sub.queued += 1;
+
+            if sub.queue.push(buf.clone()) == Some(Watermark::High) {
+                slow.push(token);
+            }
+        }
+
+        slow
+    }
+
+    /// Queues `bytes` for the subscriber `token` only, returning
+    /// `Watermark::High` if its queue reached the high-water mark. Does
+    /// nothing if `token` is not subscribed.
+    pub fn send(&mut self, token: Token, bytes: Vec<u8>) -> Option<Watermark> {
+        match self.subscribers.get_mut(&token) {
+            Some(sub) => {
+                sub.queued += 1;
+                sub.queue.push(SharedBuf::from(bytes))
+            }
+            None => None,
+        }
+    }
+
+    /// Writes the messages queued for `token` to `io`, see
+    /// `WriteQueue::flush`.
+    pub fn flush<W: TryWrite>(&mut self, token: Token, io: &mut W) -> io::Result<Option<Watermark>> {
+        match self.subscribers.get_mut(&token) {
+            Some(sub) => sub.queue.flush(io),
+            None => Ok(None),
+        }
+    }
+
+    /// Updates the writable interest of the connection of `token`, see
+    /// `WriteQueue::update`.
+    pub fn update<H, E: ?Sized>(&mut self, event_loop: &mut EventLoop<H>, io: &E, token: Token,
+                                interest: EventSet, opts: PollOpt) -> io::Result<()>
+        where H: Handler, E: Evented
+    {
+        match self.subscribers.get_mut(&token) {
+            Some(sub) => sub.queue.update(event_loop, io, token, interest, opts),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the progress of the subscriber `token`.
+    pub fn progress(&self, token: Token) -> Option<Progress> {
+        self.subscribers.get(&token).map(|sub| {
+            Progress {
+                queued: sub.queued,
+                delivered: sub.queued - sub.queue.bufs() as u64,
+                pending: sub.queue.len(),
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Broadcast {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Broadcast {{ subscribers: {}, high: {}, low: {} }}",
+               self.subscribers.len(), self.high, self.low)
+    }
+}
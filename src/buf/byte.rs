@@ -0,0 +1,284 @@
+use buf::{Buf, MutBuf};
+use std::{cmp, fmt, io};
+
+/// A `Buf` backed by a contiguous region of memory.
+///
+/// This `Buf` is better suited for cases where there is a clear delineation
+/// between reading and writing: bytes are written into a `MutByteBuf`, which
+/// is then flipped to a `ByteBuf` to read them back.
+///
+/// A parser that consumed part of the bytes can `compact` the buffer to
+/// write more after the remaining ones, or move parsed frames out with
+/// `split_to`, without copying through an intermediate buffer.
+pub struct ByteBuf {
+    mem: Vec<u8>,
+    pos: usize,
+    lim: usize,
+    mark: Option<usize>,
+}
+
+impl ByteBuf {
+    /// Create a new `ByteBuf` by copying the contents of the given slice.
+    pub fn from_slice(bytes: &[u8]) -> ByteBuf {
+        let mut buf = ByteBuf::mut_with_capacity(bytes.len());
+        buf.write_slice(bytes);
+        buf.flip()
+    }
+
+    /// Create a new `MutByteBuf` with the given capacity, rounded up to the
+    /// next power of two.
+    pub fn mut_with_capacity(capacity: usize) -> MutByteBuf {
+        MutByteBuf { buf: ByteBuf::new(capacity) }
+    }
+
+    pub fn none() -> ByteBuf {
+        ByteBuf::new(0)
+    }
+
+    fn new(capacity: usize) -> ByteBuf {
+        let cap = if capacity == 0 { 0 } else { capacity.next_power_of_two() };
+
+        ByteBuf {
+            mem: vec![0; cap],
+            pos: 0,
+            lim: cap,
+            mark: None,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.mem.len()
+    }
+
+    /// Flips the buffer to mutable, discarding its contents.
+    pub fn flip(self) -> MutByteBuf {
+        let mut buf = MutByteBuf { buf: self };
+        buf.clear();
+        buf
+    }
+
+    /// Flips the buffer back to mutable, resetting the write position
+    /// to the byte after the previous write.
+    pub fn resume(mut self) -> MutByteBuf {
+        self.pos = self.lim;
+        self.lim = self.mem.len();
+        self.mark = None;
+        MutByteBuf { buf: self }
+    }
+
+    /// Flips the buffer back to mutable after moving the bytes left to read
+    /// to the start of the buffer, so that they can be read again along
+    /// with the bytes written next.
+    pub fn compact(mut self) -> MutByteBuf {
+        let len = self.lim - self.pos;
+
+        for i in 0..len {
+            self.mem[i] = self.mem[self.pos + i];
+        }
+
+        self.pos = len;
+        self.lim = self.mem.len();
+        self.mark = None;
+        MutByteBuf { buf: self }
+    }
+
+    /// Discards the bytes left to read.
+    pub fn clear(&mut self) {
+        self.pos = self.lim;
+        self.mark = None;
+    }
+
+    /// Splits the bytes left to read at `at`. `self` keeps the first `at`
+    /// bytes, the remaining ones are moved to the returned buffer.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `at` is greater than the number of bytes
+    /// left to read.
+    pub fn split_off(&mut self, at: usize) -> ByteBuf {
+        assert!(at <= self.remaining(), "split position out of bounds");
+
+        let tail = ByteBuf::from_slice(&self.bytes()[at..]);
+        self.lim = self.pos + at;
+
+        if self.mark.map(|mark| mark > self.lim).unwrap_or(false) {
+            self.mark = None;
+        }
+
+        tail
+    }
+
+    /// Splits the bytes left to read at `at`. The first `at` bytes are moved
+    /// to the returned buffer, `self` keeps the remaining ones.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `at` is greater than the number of bytes
+    /// left to read.
+    pub fn split_to(&mut self, at: usize) -> ByteBuf {
+        assert!(at <= self.remaining(), "split position out of bounds");
+
+        let head = ByteBuf::from_slice(&self.bytes()[..at]);
+        self.pos += at;
+        self.mark = None;
+
+        head
+    }
+
+    pub fn read_slice(&mut self, dst: &mut [u8]) -> usize {
+        let len = cmp::min(dst.len(), self.remaining());
+
+        for i in 0..len {
+            dst[i] = self.mem[self.pos + i];
+        }
+
+        self.pos += len;
+        len
+    }
+
+    /// Marks the current read location.
+    ///
+    /// Together with `reset`, this can be used to read from a section of the
+    /// buffer multiple times. The marked location will be cleared when the
+    /// buffer is flipped.
+    pub fn mark(&mut self) {
+        self.mark = Some(self.pos);
+    }
+
+    /// Resets the read position to the previously marked position.
+    ///
+    /// Together with `mark`, this can be used to read from a section of the
+    /// buffer multiple times.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if no mark has been set.
+    pub fn reset(&mut self) {
+        self.pos = self.mark.take().expect("no mark set");
+    }
+}
+
+impl Buf for ByteBuf {
+    fn remaining(&self) -> usize {
+        self.lim - self.pos
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.mem[self.pos..self.lim]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.pos += cmp::min(cnt, self.lim - self.pos);
+    }
+
+    fn read_slice(&mut self, dst: &mut [u8]) -> usize {
+        ByteBuf::read_slice(self, dst)
+    }
+}
+
+impl io::Read for ByteBuf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.read_slice(buf))
+    }
+}
+
+impl fmt::Debug for ByteBuf {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "ByteBuf[.. {}]", self.lim - self.pos)
+    }
+}
+
+/// A `MutBuf` backed by a contiguous region of memory, flipped to a
+/// `ByteBuf` to read back the bytes written.
+pub struct MutByteBuf {
+    buf: ByteBuf,
+}
+
+impl MutByteBuf {
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Flips the buffer to read the bytes written.
+    pub fn flip(self) -> ByteBuf {
+        let mut buf = self.buf;
+
+        buf.lim = buf.pos;
+        buf.pos = 0;
+        buf
+    }
+
+    /// Discards the bytes written.
+    pub fn clear(&mut self) {
+        self.buf.pos = 0;
+        self.buf.lim = self.buf.mem.len();
+        self.buf.mark = None;
+    }
+
+    /// Splits the bytes written at `at`. `self` keeps the first `at` bytes
+    /// and can be written after them, the remaining ones are moved to the
+    /// returned buffer, ready to be read.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `at` is greater than the number of bytes
+    /// written.
+    pub fn split_off(&mut self, at: usize) -> ByteBuf {
+        assert!(at <= self.buf.pos, "split position out of bounds");
+
+        let tail = ByteBuf::from_slice(&self.bytes()[at..]);
+        self.buf.pos = at;
+
+        tail
+    }
+
+    #[inline]
+    pub fn write_slice(&mut self, src: &[u8]) -> usize {
+        let len = cmp::min(src.len(), self.buf.remaining());
+        let pos = self.buf.pos;
+
+        for i in 0..len {
+            self.buf.mem[pos + i] = src[i];
+        }
+
+        self.buf.pos += len;
+        len
+    }
+
+    /// Returns the bytes written.
+    pub fn bytes(&self) -> &[u8] {
+        &self.buf.mem[..self.buf.pos]
+    }
+}
+
+impl MutBuf for MutByteBuf {
+    fn remaining(&self) -> usize {
+        self.buf.remaining()
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.buf.advance(cnt)
+    }
+
+    fn mut_bytes(&mut self) -> &mut [u8] {
+        let pos = self.buf.pos;
+        let lim = self.buf.lim;
+        &mut self.buf.mem[pos..lim]
+    }
+}
+
+impl io::Write for MutByteBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(self.write_slice(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for MutByteBuf {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "MutByteBuf[.. {}]", self.buf.pos)
+    }
+}
@@ -0,0 +1,97 @@
+use buf::{Buf, MutBuf, VecBuf, VecMutBuf};
+use bytes04::{BufMut, Bytes, BytesMut};
+
+/// A `Buf` reading the contents of a `Bytes` of the standalone bytes crate,
+/// so that they can be written with `TryWrite::try_write_buf` without being
+/// copied. Advancing the buffer only moves the start of the shared slice.
+#[derive(Clone, Debug)]
+pub struct BytesBuf {
+    bytes: Bytes,
+}
+
+impl BytesBuf {
+    pub fn new(bytes: Bytes) -> BytesBuf {
+        BytesBuf { bytes: bytes }
+    }
+
+    /// Returns the bytes left to read.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+}
+
+impl From<Bytes> for BytesBuf {
+    fn from(bytes: Bytes) -> BytesBuf {
+        BytesBuf::new(bytes)
+    }
+}
+
+impl Buf for BytesBuf {
+    fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.bytes[..]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        let cnt = ::std::cmp::min(cnt, self.bytes.len());
+        self.bytes = self.bytes.slice_from(cnt);
+    }
+}
+
+impl VecBuf for BytesBuf {}
+
+/// A `MutBuf` writing into the spare capacity of a `BytesMut` of the
+/// standalone bytes crate, so that `TryRead::try_read_buf` reads straight
+/// into it. The bytes read are appended to the `BytesMut`, the buffer does
+/// not grow it, `reserve` capacity beforehand.
+#[derive(Debug)]
+pub struct BytesMutBuf {
+    bytes: BytesMut,
+}
+
+impl BytesMutBuf {
+    pub fn new(bytes: BytesMut) -> BytesMutBuf {
+        BytesMutBuf { bytes: bytes }
+    }
+
+    pub fn get_ref(&self) -> &BytesMut {
+        &self.bytes
+    }
+
+    pub fn get_mut(&mut self) -> &mut BytesMut {
+        &mut self.bytes
+    }
+
+    /// Returns the `BytesMut`, holding the bytes read.
+    pub fn into_inner(self) -> BytesMut {
+        self.bytes
+    }
+}
+
+impl From<BytesMut> for BytesMutBuf {
+    fn from(bytes: BytesMut) -> BytesMutBuf {
+        BytesMutBuf::new(bytes)
+    }
+}
+
+impl MutBuf for BytesMutBuf {
+    fn remaining(&self) -> usize {
+        self.bytes.remaining_mut()
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        let cnt = ::std::cmp::min(cnt, self.bytes.remaining_mut());
+        unsafe { self.bytes.advance_mut(cnt); }
+    }
+
+    fn mut_bytes(&mut self) -> &mut [u8] {
+        // The spare capacity may be uninitialized, it is only ever written
+        // to before being exposed by `advance`
+        unsafe { self.bytes.bytes_mut() }
+    }
+}
+
+impl VecMutBuf for BytesMutBuf {}
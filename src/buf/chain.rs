@@ -0,0 +1,73 @@
+use buf::{Buf, IoVec, VecBuf};
+use std::cmp;
+
+/// Two `Buf`s read one after the other as a single buffer, such as the
+/// header and body of a response living in separate buffers.
+///
+/// Chains nest, `Chain::new(Chain::new(header, body), trailer)` reads three
+/// buffers. When both buffers implement `VecBuf`, so does the chain, so that
+/// all of it can be written with a single `TryWriteV::try_writev`.
+#[derive(Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Buf, B: Buf> Chain<A, B> {
+    pub fn new(first: A, second: B) -> Chain<A, B> {
+        Chain {
+            first: first,
+            second: second,
+        }
+    }
+
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    pub fn first_mut(&mut self) -> &mut A {
+        &mut self.first
+    }
+
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+
+    pub fn second_mut(&mut self) -> &mut B {
+        &mut self.second
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: Buf, B: Buf> Buf for Chain<A, B> {
+    fn remaining(&self) -> usize {
+        self.first.remaining() + self.second.remaining()
+    }
+
+    fn bytes(&self) -> &[u8] {
+        if self.first.has_remaining() {
+            self.first.bytes()
+        } else {
+            self.second.bytes()
+        }
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        let first = cmp::min(cnt, self.first.remaining());
+
+        self.first.advance(first);
+        self.second.advance(cnt - first);
+    }
+}
+
+impl<A: VecBuf, B: VecBuf> VecBuf for Chain<A, B> {
+    /// Exposes the regions of the first buffer, followed by those of the
+    /// second one.
+    fn bytes_vec<'a>(&'a self, dst: &mut [IoVec<'a>]) -> usize {
+        let cnt = self.first.bytes_vec(dst);
+        cnt + self.second.bytes_vec(&mut dst[cnt..])
+    }
+}
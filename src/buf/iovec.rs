@@ -0,0 +1,172 @@
+use buf::{Buf, MutBuf, ByteBuf, MutByteBuf, SliceBuf, MutSliceBuf};
+use std::{cmp, fmt, slice};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// A slice of bytes to write, laid out as a `struct iovec` so that a slice of
+/// `IoVec`s can be handed to `writev(2)` as is. See `TryWriteV`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct IoVec<'a> {
+    base: *const u8,
+    len: usize,
+    marker: PhantomData<&'a [u8]>,
+}
+
+impl<'a> IoVec<'a> {
+    pub fn new(buf: &'a [u8]) -> IoVec<'a> {
+        IoVec {
+            base: buf.as_ptr(),
+            len: buf.len(),
+            marker: PhantomData,
+        }
+    }
+
+    /// An empty slice, to fill a slice of `IoVec`s with before passing it to
+    /// `VecBuf::bytes_vec`.
+    pub fn empty() -> IoVec<'a> {
+        IoVec::new(&[])
+    }
+
+    /// Shortens the slice to at most `len` bytes.
+    pub fn truncate(self, len: usize) -> IoVec<'a> {
+        IoVec {
+            base: self.base,
+            len: cmp::min(self.len, len),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a> Deref for IoVec<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.base, self.len) }
+    }
+}
+
+impl<'a> From<&'a [u8]> for IoVec<'a> {
+    fn from(buf: &'a [u8]) -> IoVec<'a> {
+        IoVec::new(buf)
+    }
+}
+
+impl<'a> fmt::Debug for IoVec<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "IoVec[.. {}]", self.len)
+    }
+}
+
+/// A slice of bytes to read into, laid out as a `struct iovec` so that a
+/// slice of `IoVecMut`s can be handed to `readv(2)` as is. See `TryReadV`.
+#[repr(C)]
+pub struct IoVecMut<'a> {
+    base: *mut u8,
+    len: usize,
+    marker: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> IoVecMut<'a> {
+    pub fn new(buf: &'a mut [u8]) -> IoVecMut<'a> {
+        IoVecMut {
+            base: buf.as_mut_ptr(),
+            len: buf.len(),
+            marker: PhantomData,
+        }
+    }
+
+    /// An empty slice, to fill a slice of `IoVecMut`s with before passing it
+    /// to `VecMutBuf::mut_bytes_vec`.
+    pub fn empty() -> IoVecMut<'a> {
+        IoVecMut::new(&mut [])
+    }
+
+    /// Shortens the slice to at most `len` bytes.
+    pub fn truncate(self, len: usize) -> IoVecMut<'a> {
+        IoVecMut {
+            base: self.base,
+            len: cmp::min(self.len, len),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a> Deref for IoVecMut<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.base, self.len) }
+    }
+}
+
+impl<'a> DerefMut for IoVecMut<'a> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.base, self.len) }
+    }
+}
+
+impl<'a> From<&'a mut [u8]> for IoVecMut<'a> {
+    fn from(buf: &'a mut [u8]) -> IoVecMut<'a> {
+        IoVecMut::new(buf)
+    }
+}
+
+impl<'a> fmt::Debug for IoVecMut<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "IoVecMut[.. {}]", self.len)
+    }
+}
+
+/// A `Buf` whose bytes to read may be split in several regions, such as a
+/// `RingBuf` that wrapped around, and that can expose all of them at once
+/// for a single `writev(2)`.
+pub trait VecBuf : Buf {
+    /// Fills `dst` with the regions of bytes to read, in order, and returns
+    /// the number of entries filled. Stops early when `dst` is too short.
+    ///
+    /// The default implementation exposes `Buf::bytes` as the only region.
+    fn bytes_vec<'a>(&'a self, dst: &mut [IoVec<'a>]) -> usize {
+        let bytes = self.bytes();
+
+        if dst.is_empty() || bytes.is_empty() {
+            return 0;
+        }
+
+        dst[0] = IoVec::new(bytes);
+        1
+    }
+}
+
+/// A `MutBuf` whose free space may be split in several regions, such as a
+/// `RingBuf` that wrapped around, and that can expose all of them at once
+/// for a single `readv(2)`.
+pub trait VecMutBuf : MutBuf {
+    /// Fills `dst` with the regions of free space, in order, and returns the
+    /// number of entries filled. Stops early when `dst` is too short.
+    ///
+    /// The default implementation exposes `MutBuf::mut_bytes` as the only
+    /// region.
+    fn mut_bytes_vec<'a>(&'a mut self, dst: &mut [IoVecMut<'a>]) -> usize {
+        if dst.is_empty() {
+            return 0;
+        }
+
+        let bytes = self.mut_bytes();
+
+        if bytes.is_empty() {
+            return 0;
+        }
+
+        dst[0] = IoVecMut::new(bytes);
+        1
+    }
+}
+
+impl VecBuf for ByteBuf {}
+
+impl<'a> VecBuf for SliceBuf<'a> {}
+
+impl VecMutBuf for MutByteBuf {}
+
+impl<'a> VecMutBuf for MutSliceBuf<'a> {}
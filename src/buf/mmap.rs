@@ -0,0 +1,199 @@
+use buf::{Buf, VecBuf};
+use libc;
+use std::{cmp, fmt, io, ptr, slice};
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Expected access pattern of a `MmapBuf`, passed to `madvise(2)`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Advice {
+    /// No particular pattern, the default.
+    Normal,
+    /// Pages are accessed in random order, read-ahead is less useful.
+    Random,
+    /// Pages are accessed in order, read-ahead can be more aggressive and
+    /// pages can be freed soon after they are accessed.
+    Sequential,
+    /// The pages will be accessed soon, start reading them in.
+    WillNeed,
+    /// The pages will not be accessed soon.
+    DontNeed,
+}
+
+/// A read-only `Buf` over the contents of a file, mapped in memory with
+/// `mmap(2)`.
+///
+/// Writing the buffer to a socket with `TryWrite::try_write_buf` sends the
+/// file contents straight from the page cache, without first copying them
+/// with `read(2)`. Pages are read from the file as they are accessed, use
+/// `advise` to tune read-ahead for the expected access pattern.
+///
+/// The file must not be truncated while it is mapped, accessing pages past
+/// its new end raises `SIGBUS`. Changes made to the file through other means
+/// may or may not be visible through the buffer.
+pub struct MmapBuf {
+    // Start of the mapping, page aligned
+    map: *mut libc::c_void,
+    map_len: usize,
+    // Offset of the requested range in the mapping
+    start: usize,
+    len: usize,
+    // Read cursor, relative to `start`
+    pos: usize,
+}
+
+unsafe impl Send for MmapBuf { }
+unsafe impl Sync for MmapBuf { }
+
+impl MmapBuf {
+    /// Maps the whole file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<MmapBuf> {
+        let file = try!(File::open(path));
+        MmapBuf::from_file(&file)
+    }
+
+    /// Maps the whole of `file`, which must have been opened for reading.
+    /// The mapping outlives the file handle.
+    pub fn from_file(file: &File) -> io::Result<MmapBuf> {
+        let len = try!(file.metadata()).len();
+
+        if len > usize::max_value() as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "file too large to be mapped"));
+        }
+
+        MmapBuf::from_file_range(file, 0, len as usize)
+    }
+
+    /// Maps `len` bytes of `file` starting at `offset`, which does not need
+    /// to be page aligned.
+    pub fn from_file_range(file: &File, offset: u64, len: usize) -> io::Result<MmapBuf> {
+        if len == 0 {
+            // mmap(2) rejects empty mappings
+            return Ok(MmapBuf { map: ptr::null_mut(), map_len: 0, start: 0, len: 0, pos: 0 });
+        }
+
+        let page = unsafe { ffi::getpagesize() } as u64;
+        let aligned = offset - offset % page;
+        let start = (offset - aligned) as usize;
+
+        let map = unsafe {
+            ffi::mmap(ptr::null_mut(),
+                      (start + len) as libc::size_t,
+                      ffi::PROT_READ,
+                      ffi::MAP_PRIVATE,
+                      file.as_raw_fd(),
+                      aligned as libc::off_t)
+        };
+
+        if map == ffi::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(MmapBuf {
+            map: map,
+            map_len: start + len,
+            start: start,
+            len: len,
+            pos: 0,
+        })
+    }
+
+    /// Returns the number of bytes mapped, including those already read.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the whole mapped range, including the bytes already read.
+    pub fn as_slice(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+
+        unsafe {
+            let base = (self.map as *const u8).offset(self.start as isize);
+            slice::from_raw_parts(base, self.len)
+        }
+    }
+
+    /// Tells the kernel how the mapping is going to be accessed.
+    pub fn advise(&self, advice: Advice) -> io::Result<()> {
+        if self.map_len == 0 {
+            return Ok(());
+        }
+
+        let advice = match advice {
+            Advice::Normal => ffi::MADV_NORMAL,
+            Advice::Random => ffi::MADV_RANDOM,
+            Advice::Sequential => ffi::MADV_SEQUENTIAL,
+            Advice::WillNeed => ffi::MADV_WILLNEED,
+            Advice::DontNeed => ffi::MADV_DONTNEED,
+        };
+
+        let res = unsafe { ffi::madvise(self.map, self.map_len as libc::size_t, advice) };
+
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+impl Buf for MmapBuf {
+    fn remaining(&self) -> usize {
+        self.len - self.pos
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.as_slice()[self.pos..]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.pos += cmp::min(cnt, self.len - self.pos);
+    }
+}
+
+impl VecBuf for MmapBuf {}
+
+impl fmt::Debug for MmapBuf {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "MmapBuf[.. {}]", self.len - self.pos)
+    }
+}
+
+impl Drop for MmapBuf {
+    fn drop(&mut self) {
+        if self.map_len > 0 {
+            unsafe { ffi::munmap(self.map, self.map_len as libc::size_t); }
+        }
+    }
+}
+
+/*
+ *
+ * ===== FFI =====
+ *
+ */
+
+mod ffi {
+    use libc;
+
+    pub const PROT_READ: libc::c_int = 1;
+    pub const MAP_PRIVATE: libc::c_int = 2;
+    pub const MAP_FAILED: *mut libc::c_void = !0 as *mut libc::c_void;
+
+    pub const MADV_NORMAL: libc::c_int = 0;
+    pub const MADV_RANDOM: libc::c_int = 1;
+    pub const MADV_SEQUENTIAL: libc::c_int = 2;
+    pub const MADV_WILLNEED: libc::c_int = 3;
+    pub const MADV_DONTNEED: libc::c_int = 4;
+
+    extern {
+        pub fn mmap(addr: *mut libc::c_void, len: libc::size_t, prot: libc::c_int,
+                    flags: libc::c_int, fd: libc::c_int, offset: libc::off_t) -> *mut libc::c_void;
+        pub fn munmap(addr: *mut libc::c_void, len: libc::size_t) -> libc::c_int;
+        pub fn getpagesize() -> libc::c_int;
+        pub fn madvise(addr: *mut libc::c_void, len: libc::size_t, advice: libc::c_int) -> libc::c_int;
+    }
+}
@@ -0,0 +1,33 @@
+//! Buffers to read into and write from, see `TryRead` and `TryWrite`.
+
+// Re-export bytes
+pub use bytes::{
+    Buf,
+    MutBuf,
+    SliceBuf,
+    MutSliceBuf,
+};
+
+pub use self::byte::{ByteBuf, MutByteBuf};
+#[cfg(feature = "bytes04")]
+pub use self::bytes04::{BytesBuf, BytesMutBuf};
+pub use self::chain::Chain;
+pub use self::iovec::{IoVec, IoVecMut, VecBuf, VecMutBuf};
+#[cfg(unix)]
+pub use self::mmap::{Advice, MmapBuf};
+pub use self::pool::{BufPool, BufPoolConfig, PooledBuf};
+pub use self::ring::RingBuf;
+pub use self::shared::SharedBuf;
+pub use self::take::Take;
+
+mod byte;
+#[cfg(feature = "bytes04")]
+mod bytes04;
+mod chain;
+mod iovec;
+#[cfg(unix)]
+mod mmap;
+mod pool;
+mod ring;
+mod shared;
+mod take;
@@ -0,0 +1,312 @@
+use buf::{Buf, MutBuf, VecBuf, VecMutBuf};
+use std::{cmp, fmt, io};
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT};
+use std::sync::atomic::Ordering::Relaxed;
+
+static NEXT_POOL_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// Buffers cached by the current thread, per pool and size class
+thread_local!(static CACHE: RefCell<Vec<ThreadCache>> = RefCell::new(Vec::new()));
+
+/// Configures the size classes and caching of a `BufPool`.
+#[derive(Clone, Debug)]
+pub struct BufPoolConfig {
+    /// Capacity of the buffers of each size class. A checkout is served by
+    /// the smallest class that fits the requested size.
+    pub classes: Vec<usize>,
+    /// Maximum number of free buffers kept by the pool per size class.
+    /// Buffers returned beyond it are deallocated.
+    pub max_free: usize,
+    /// Maximum number of free buffers each thread keeps per size class,
+    /// served without locking the pool. Buffers cached by a thread can only
+    /// be checked out by that thread, and are deallocated when it exits.
+    /// Zero disables the thread-local caches.
+    pub thread_cache: usize,
+}
+
+impl Default for BufPoolConfig {
+    fn default() -> BufPoolConfig {
+        BufPoolConfig {
+            classes: vec![512, 4_096, 16_384, 65_536],
+            max_free: 1_024,
+            thread_cache: 0,
+        }
+    }
+}
+
+/// A pool of fixed-size buffers.
+///
+/// Allocating a buffer per connection makes memory usage grow with the
+/// number of connections, even idle ones. Instead, a connection can check a
+/// buffer out when it becomes readable and drop it once its contents have
+/// been consumed, returning it to the pool for the next connection.
+///
+/// The pool can be cloned and shared between threads, and its buffers can be
+/// sent between threads, for example from an event loop to a worker. A
+/// buffer is returned to the pool it was checked out from, whichever thread
+/// drops it.
+#[derive(Clone)]
+pub struct BufPool {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    id: usize,
+    classes: Vec<Class>,
+    thread_cache: usize,
+}
+
+struct Class {
+    size: usize,
+    max_free: usize,
+    free: Mutex<Vec<Vec<u8>>>,
+}
+
+struct ThreadCache {
+    pool: usize,
+    class: usize,
+    free: Vec<Vec<u8>>,
+}
+
+impl BufPool {
+    /// Creates a pool with the default configuration.
+    pub fn new() -> BufPool {
+        BufPool::configured(BufPoolConfig::default())
+    }
+
+    /// Creates a pool with the given size classes and caching. No buffer is
+    /// allocated until the first checkout.
+    pub fn configured(config: BufPoolConfig) -> BufPool {
+        let max_free = config.max_free;
+        let thread_cache = config.thread_cache;
+        let mut sizes = config.classes;
+
+        sizes.sort();
+        sizes.dedup();
+
+        let classes = sizes.into_iter()
+            .map(|size| Class {
+                size: size,
+                max_free: max_free,
+                free: Mutex::new(Vec::new()),
+            })
+            .collect();
+
+        BufPool {
+            inner: Arc::new(Inner {
+                id: NEXT_POOL_ID.fetch_add(1, Relaxed),
+                classes: classes,
+                thread_cache: thread_cache,
+            }),
+        }
+    }
+
+    /// Checks out an empty buffer holding at least `size` bytes, from the
+    /// smallest size class that fits. Returns `None` if `size` exceeds the
+    /// largest class.
+    pub fn checkout(&self, size: usize) -> Option<PooledBuf> {
+        let class = match self.inner.classes.iter().position(|c| c.size >= size) {
+            Some(class) => class,
+            None => return None,
+        };
+
+        let mem = self.inner.pop(class)
+            .unwrap_or_else(|| vec![0; self.inner.classes[class].size]);
+
+        Some(PooledBuf {
+            mem: mem,
+            rd: 0,
+            wr: 0,
+            class: class,
+            pool: self.inner.clone(),
+        })
+    }
+
+    /// Returns the capacity of the buffers of each size class, smallest
+    /// first.
+    pub fn classes(&self) -> Vec<usize> {
+        self.inner.classes.iter().map(|c| c.size).collect()
+    }
+
+    /// Returns the number of free buffers held by the pool, not counting
+    /// those cached by threads.
+    pub fn free(&self) -> usize {
+        self.inner.classes.iter()
+            .fold(0, |n, c| n + c.free.lock().unwrap().len())
+    }
+}
+
+impl Inner {
+    fn pop(&self, class: usize) -> Option<Vec<u8>> {
+        if self.thread_cache > 0 {
+            let cached = self.with_cache(class, |free| free.pop());
+
+            if cached.is_some() {
+                return cached;
+            }
+        }
+
+        self.classes[class].free.lock().unwrap().pop()
+    }
+
+    fn push(&self, class: usize, mem: Vec<u8>) {
+        let mut mem = Some(mem);
+
+        if self.thread_cache > 0 {
+            let limit = self.thread_cache;
+
+            self.with_cache(class, |free| {
+                if free.len() < limit {
+                    free.push(mem.take().unwrap());
+                }
+            });
+        }
+
+        if let Some(mem) = mem {
+            let class = &self.classes[class];
+            let mut free = class.free.lock().unwrap();
+
+            if free.len() < class.max_free {
+                free.push(mem);
+            }
+        }
+    }
+
+    fn with_cache<F, T>(&self, class: usize, f: F) -> T
+            where F: FnOnce(&mut Vec<Vec<u8>>) -> T {
+        CACHE.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            let pos = cache.iter().position(|c| c.pool == self.id && c.class == class);
+
+            let idx = match pos {
+                Some(idx) => idx,
+                None => {
+                    cache.push(ThreadCache { pool: self.id, class: class, free: Vec::new() });
+                    cache.len() - 1
+                }
+            };
+
+            f(&mut cache[idx].free)
+        })
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        // Free the buffers this thread cached for the pool. Other threads
+        // free theirs when they exit.
+        if self.thread_cache > 0 {
+            let id = self.id;
+            CACHE.with(|cache| {
+                cache.borrow_mut().retain(|c| c.pool != id)
+            });
+        }
+    }
+}
+
+impl fmt::Debug for BufPool {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "BufPool {{ classes: {:?} }}", self.classes())
+    }
+}
+
+/// A buffer checked out of a `BufPool`, returned to it when dropped.
+///
+/// Bytes are written with `MutBuf` and read back with `Buf`, which makes it
+/// usable with both `TryRead::try_read_buf` and `TryWrite::try_write_buf`.
+/// Once all the written bytes have been read, the buffer starts over from
+/// its beginning.
+pub struct PooledBuf {
+    mem: Vec<u8>,
+    // Read cursor
+    rd: usize,
+    // Write cursor
+    wr: usize,
+    class: usize,
+    pool: Arc<Inner>,
+}
+
+impl PooledBuf {
+    /// Returns the number of bytes the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.mem.len()
+    }
+
+    /// Returns `true` if there are no bytes to read.
+    pub fn is_empty(&self) -> bool {
+        self.rd == self.wr
+    }
+
+    /// Discards the bytes to read.
+    pub fn clear(&mut self) {
+        self.rd = 0;
+        self.wr = 0;
+    }
+}
+
+impl Buf for PooledBuf {
+    fn remaining(&self) -> usize {
+        self.wr - self.rd
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.mem[self.rd..self.wr]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.rd += cmp::min(cnt, self.wr - self.rd);
+
+        if self.rd == self.wr {
+            self.clear();
+        }
+    }
+}
+
+impl MutBuf for PooledBuf {
+    fn remaining(&self) -> usize {
+        self.mem.len() - self.wr
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.wr += cmp::min(cnt, self.mem.len() - self.wr);
+    }
+
+    fn mut_bytes(&mut self) -> &mut [u8] {
+        &mut self.mem[self.wr..]
+    }
+}
+
+impl VecBuf for PooledBuf {}
+
+impl VecMutBuf for PooledBuf {}
+
+impl io::Read for PooledBuf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.read_slice(buf))
+    }
+}
+
+impl io::Write for PooledBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(self.write_slice(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for PooledBuf {
+    fn drop(&mut self) {
+        let mem = ::std::mem::replace(&mut self.mem, Vec::new());
+        self.pool.push(self.class, mem);
+    }
+}
+
+impl fmt::Debug for PooledBuf {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "PooledBuf[.. {}; cap={}]", self.wr - self.rd, self.mem.len())
+    }
+}
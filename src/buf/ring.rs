@@ -0,0 +1,341 @@
+use buf::{Buf, MutBuf, IoVec, IoVecMut, VecBuf, VecMutBuf};
+use std::{cmp, fmt, io};
+
+/// Buf backed by a continuous chunk of memory. Maintains a read cursor and a
+/// write cursor. When reads and writes reach the end of the allocated buffer,
+/// wraps around to the start.
+///
+/// This type is suited for use cases where reads and writes are intermixed.
+///
+/// A buffer created with `growable` doubles its capacity whenever a write
+/// runs out of space, up to a maximum, so that a single large message does
+/// not require switching to another buffer. Resizing moves the contents to
+/// the start of the new memory, the bytes to read and the mark are
+/// preserved.
+#[derive(Clone)]
+pub struct RingBuf {
+    mem: Vec<u8>,
+    // Offset of the read cursor
+    pos: usize,
+    // Number of bytes to read
+    len: usize,
+    // Marked read position, and the number of bytes to read from it
+    mark: Option<(usize, usize)>,
+    // Capacity the buffer was created with, `shrink_to_fit` does not go below
+    min: usize,
+    // Capacity writes may grow the buffer to
+    max: usize,
+}
+
+impl RingBuf {
+    /// Allocates a new `RingBuf` with the specified capacity, rounded up to
+    /// the next power of two.
+    pub fn new(capacity: usize) -> RingBuf {
+        RingBuf::growable(capacity, capacity)
+    }
+
+    /// Allocates a new `RingBuf` with the specified capacity, which doubles
+    /// when a write runs out of space until it reaches `max`. Both are
+    /// rounded up to the next power of two.
+    pub fn growable(capacity: usize, max: usize) -> RingBuf {
+        let cap = round_up(capacity);
+
+        RingBuf {
+            mem: vec![0; cap],
+            pos: 0,
+            len: 0,
+            mark: None,
+            min: cap,
+            max: cmp::max(round_up(max), cap),
+        }
+    }
+
+    /// Returns `true` if the buf cannot accept any further writes without
+    /// growing.
+    pub fn is_full(&self) -> bool {
+        self.capacity() == self.len
+    }
+
+    /// Returns `true` if the buf cannot accept any further reads.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes that the buf can hold.
+    pub fn capacity(&self) -> usize {
+        self.mem.len()
+    }
+
+    /// Returns the capacity writes may grow the buf to.
+    pub fn max_capacity(&self) -> usize {
+        self.max
+    }
+
+    /// Marks the current read location.
+    ///
+    /// Together with `reset`, this can be used to read from a section of the
+    /// buffer multiple times. The mark will be cleared if it is overwritten
+    /// during a write.
+    pub fn mark(&mut self) {
+        self.mark = Some((self.pos, self.len));
+    }
+
+    /// Resets the read position to the previously marked position.
+    ///
+    /// Together with `mark`, this can be used to read from a section of the
+    /// buffer multiple times.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if no mark has been set,
+    pub fn reset(&mut self) {
+        match self.mark.take() {
+            Some((pos, len)) => {
+                self.pos = pos;
+                self.len = len;
+            }
+            None => panic!("no mark set"),
+        }
+    }
+
+    /// Grows the buf so that at least `additional` more bytes can be written
+    /// without growing again. Not limited by the maximum capacity of a
+    /// growable buf.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.retained() + additional;
+
+        if needed > self.capacity() {
+            let cap = round_up(needed);
+
+            self.max = cmp::max(self.max, cap);
+            self.resize(cap);
+        }
+    }
+
+    /// Shrinks the buf to the smallest power of two holding its contents,
+    /// but not below the capacity it was created with.
+    pub fn shrink_to_fit(&mut self) {
+        let cap = cmp::max(round_up(self.retained()), self.min);
+
+        if cap < self.capacity() {
+            self.resize(cap);
+        }
+    }
+
+    // Number of bytes that must survive a resize, including the marked ones
+    // already read
+    fn retained(&self) -> usize {
+        match self.mark {
+            Some((_, len)) => len,
+            None => self.len,
+        }
+    }
+
+    // Moves the contents to the start of a buffer of `cap` bytes
+    fn resize(&mut self, cap: usize) {
+        let (from, len) = match self.mark {
+            Some(mark) => mark,
+            None => (self.pos, self.len),
+        };
+
+        let mut mem = vec![0; cap];
+
+        for i in 0..len {
+            mem[i] = self.mem[(from + i) % self.capacity()];
+        }
+
+        // The read cursor stays the same number of bytes past the mark
+        self.pos = len - self.len;
+        self.mem = mem;
+
+        if self.mark.is_some() {
+            self.mark = Some((0, len));
+        }
+    }
+
+    /// Returns the bytes to read, as up to two slices when they wrap around
+    /// the end of the buffer. The second slice is empty otherwise.
+    pub fn readable(&self) -> (&[u8], &[u8]) {
+        let cap = self.capacity();
+        let end = self.pos + self.len;
+
+        if end <= cap {
+            (&self.mem[self.pos..end], &[])
+        } else {
+            let (head, tail) = self.mem.split_at(self.pos);
+            (tail, &head[..end - cap])
+        }
+    }
+
+    /// Returns the free space, as up to two slices when it wraps around the
+    /// end of the buffer. The second slice is empty otherwise. Does not grow
+    /// the buf.
+    ///
+    /// Until it reaches its maximum capacity, a growable buf does not hand
+    /// out the space of marked bytes already read, writes grow it instead.
+    pub fn writable(&mut self) -> (&mut [u8], &mut [u8]) {
+        let cap = self.capacity();
+        let (start, used) = self.used();
+
+        if used == cap {
+            return (&mut [], &mut []);
+        }
+
+        let from = (start + used) % cap;
+
+        if from < start {
+            (&mut self.mem[from..start], &mut [])
+        } else {
+            let (head, tail) = self.mem.split_at_mut(from);
+            (tail, &mut head[..start])
+        }
+    }
+
+    // Doubles the capacity of a growable buf with no free space left
+    fn grow_if_full(&mut self) {
+        if self.used().1 == self.capacity() && self.capacity() < self.max {
+            let cap = cmp::max(self.capacity() * 2, 1);
+            self.resize(cap);
+        }
+    }
+
+    // Start and length of the bytes writes must not overwrite
+    fn used(&self) -> (usize, usize) {
+        match self.mark {
+            Some(mark) if self.capacity() < self.max => mark,
+            _ => (self.pos, self.len),
+        }
+    }
+
+    fn advance_reader(&mut self, cnt: usize) {
+        if self.capacity() == 0 {
+            return;
+        }
+
+        let cnt = cmp::min(cnt, self.len);
+
+        self.pos = (self.pos + cnt) % self.capacity();
+        self.len -= cnt;
+    }
+
+    fn advance_writer(&mut self, cnt: usize) {
+        let cnt = cmp::min(cnt, self.capacity() - self.len);
+        self.len += cnt;
+
+        // Adjust the mark to account for bytes written, and clear it if
+        // we've written past it.
+        if let Some((pos, len)) = self.mark {
+            self.mark = if len + cnt > self.capacity() {
+                None
+            } else {
+                Some((pos, len + cnt))
+            };
+        }
+    }
+}
+
+// Rounds to the next power of 2 for better alignment
+fn round_up(n: usize) -> usize {
+    if n == 0 { 0 } else { n.next_power_of_two() }
+}
+
+impl fmt::Debug for RingBuf {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "RingBuf[.. {}]", self.len)
+    }
+}
+
+impl Buf for RingBuf {
+    fn remaining(&self) -> usize {
+        self.len
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.readable().0
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.advance_reader(cnt)
+    }
+}
+
+impl MutBuf for RingBuf {
+    /// The free space, including the space growing the buf up to its maximum
+    /// capacity would provide.
+    fn remaining(&self) -> usize {
+        self.max - self.len
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.advance_writer(cnt)
+    }
+
+    /// Returns the free space up to the end of the buffer, or the space
+    /// before the read cursor once the writes wrapped around. A full buf is
+    /// grown first, if it has not reached its maximum capacity.
+    fn mut_bytes(&mut self) -> &mut [u8] {
+        self.grow_if_full();
+        self.writable().0
+    }
+}
+
+impl VecBuf for RingBuf {
+    /// Exposes both windows of `readable`.
+    fn bytes_vec<'a>(&'a self, dst: &mut [IoVec<'a>]) -> usize {
+        let (a, b) = self.readable();
+        let windows = vec![a, b].into_iter().filter(|w| !w.is_empty());
+
+        let mut cnt = 0;
+
+        for (slot, window) in dst.iter_mut().zip(windows) {
+            *slot = IoVec::new(window);
+            cnt += 1;
+        }
+
+        cnt
+    }
+}
+
+impl VecMutBuf for RingBuf {
+    /// Exposes both windows of `writable`, after growing a full buf as
+    /// `mut_bytes` does.
+    fn mut_bytes_vec<'a>(&'a mut self, dst: &mut [IoVecMut<'a>]) -> usize {
+        self.grow_if_full();
+
+        let (a, b) = self.writable();
+        let windows = vec![a, b].into_iter().filter(|w| !w.is_empty());
+
+        let mut cnt = 0;
+
+        for (slot, window) in dst.iter_mut().zip(windows) {
+            *slot = IoVecMut::new(window);
+            cnt += 1;
+        }
+
+        cnt
+    }
+}
+
+impl io::Read for RingBuf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !Buf::has_remaining(self) {
+            return Ok(0);
+        }
+
+        Ok(self.read_slice(buf))
+    }
+}
+
+impl io::Write for RingBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !MutBuf::has_remaining(self) {
+            return Ok(0);
+        }
+
+        Ok(self.write_slice(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use buf::{Buf, IoVec, VecBuf};
+use std::cmp;
+use std::sync::Arc;
+
+/// A read cursor over bytes shared with other cursors, such as the copies of
+/// a message broadcast to many connections.
+///
+/// Cloning only bumps the reference count of the bytes, the clone starting
+/// at the same position. The bytes are freed once every cursor is dropped.
+#[derive(Clone, Debug)]
+pub struct SharedBuf {
+    bytes: Arc<Vec<u8>>,
+    pos: usize,
+}
+
+impl SharedBuf {
+    pub fn new(bytes: Arc<Vec<u8>>) -> SharedBuf {
+        SharedBuf {
+            bytes: bytes,
+            pos: 0,
+        }
+    }
+
+    /// Returns all of the shared bytes, including those already read.
+    pub fn get_ref(&self) -> &Arc<Vec<u8>> {
+        &self.bytes
+    }
+
+    /// Returns the number of bytes read.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+impl From<Vec<u8>> for SharedBuf {
+    fn from(bytes: Vec<u8>) -> SharedBuf {
+        SharedBuf::new(Arc::new(bytes))
+    }
+}
+
+impl Buf for SharedBuf {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.bytes[self.pos..]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.pos += cmp::min(cnt, self.remaining());
+    }
+}
+
+impl VecBuf for SharedBuf {
+    fn bytes_vec<'a>(&'a self, dst: &mut [IoVec<'a>]) -> usize {
+        let bytes = self.bytes();
+
+        if dst.is_empty() || bytes.is_empty() {
+            return 0;
+        }
+
+        dst[0] = IoVec::new(bytes);
+        1
+    }
+}
@@ -0,0 +1,128 @@
+use buf::{Buf, MutBuf, IoVec, IoVecMut, VecBuf, VecMutBuf};
+use std::{cmp, mem};
+
+/// Limits the number of bytes read from or written into a buffer.
+///
+/// Wrapping the buffer of a length-delimited protocol with the length of the
+/// current frame makes `TryRead::try_read_buf` stop at the end of the frame,
+/// instead of reading into the next one. The limit is decremented as the
+/// buffer is advanced.
+#[derive(Debug)]
+pub struct Take<T> {
+    inner: T,
+    limit: usize,
+}
+
+impl<T> Take<T> {
+    pub fn new(inner: T, limit: usize) -> Take<T> {
+        Take {
+            inner: inner,
+            limit: limit,
+        }
+    }
+
+    /// Returns the number of bytes left before the limit is reached.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Buf> Buf for Take<T> {
+    fn remaining(&self) -> usize {
+        cmp::min(self.inner.remaining(), self.limit)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        let bytes = self.inner.bytes();
+        &bytes[..cmp::min(bytes.len(), self.limit)]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        let cnt = cmp::min(cnt, self.limit);
+
+        self.limit -= cnt;
+        self.inner.advance(cnt);
+    }
+}
+
+impl<T: MutBuf> MutBuf for Take<T> {
+    fn remaining(&self) -> usize {
+        cmp::min(self.inner.remaining(), self.limit)
+    }
+
+    fn mut_bytes(&mut self) -> &mut [u8] {
+        let limit = self.limit;
+        let bytes = self.inner.mut_bytes();
+        let len = cmp::min(bytes.len(), limit);
+
+        &mut bytes[..len]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        let cnt = cmp::min(cnt, self.limit);
+
+        self.limit -= cnt;
+        self.inner.advance(cnt);
+    }
+}
+
+impl<T: VecBuf> VecBuf for Take<T> {
+    /// Exposes the regions of the inner buffer, truncated to the limit.
+    fn bytes_vec<'a>(&'a self, dst: &mut [IoVec<'a>]) -> usize {
+        let cnt = self.inner.bytes_vec(dst);
+        let mut left = self.limit;
+
+        for i in 0..cnt {
+            let len = dst[i].len();
+
+            if len >= left {
+                dst[i] = dst[i].truncate(left);
+                return if left == 0 { i } else { i + 1 };
+            }
+
+            left -= len;
+        }
+
+        cnt
+    }
+}
+
+impl<T: VecMutBuf> VecMutBuf for Take<T> {
+    /// Exposes the regions of free space of the inner buffer, truncated to
+    /// the limit.
+    fn mut_bytes_vec<'a>(&'a mut self, dst: &mut [IoVecMut<'a>]) -> usize {
+        let limit = self.limit;
+        let cnt = self.inner.mut_bytes_vec(dst);
+        let mut left = limit;
+
+        for i in 0..cnt {
+            let len = dst[i].len();
+
+            if len >= left {
+                let iov = mem::replace(&mut dst[i], IoVecMut::empty());
+                dst[i] = iov.truncate(left);
+                return if left == 0 { i } else { i + 1 };
+            }
+
+            left -= len;
+        }
+
+        cnt
+    }
+}
@@ -0,0 +1,347 @@
+//! A C API to embed an event loop in a C host application.
+//!
+//! Enabled with the `capi` feature. The host creates a loop with
+//! `mio_loop_new`, registers its fds and timeouts with callbacks, and drives
+//! the loop with `mio_run` or `mio_run_once`. `include/mio.h` declares the
+//! functions and constants.
+//!
+//! Callbacks receive the loop and may call any of the functions below,
+//! except `mio_loop_free`, `mio_run` and `mio_run_once`. Those calls are
+//! applied once the callback returns. A registration that fails then is
+//! reported to its callback with `MIO_ERROR`.
+//!
+//! Functions returning an `int` return 0 or a negative errno on failure,
+//! -1 when the error has no errno.
+
+use {io, EventLoop, EventSet, Handler, PollOpt, Timeout, Token};
+use net::unix::EventedFd;
+use std::{mem, ptr};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::io::ErrorKind;
+use std::os::unix::io::RawFd;
+use std::rc::Rc;
+
+pub use libc::{c_int, c_void};
+
+pub const MIO_READABLE: u32 = 0x1;
+pub const MIO_WRITABLE: u32 = 0x2;
+pub const MIO_ERROR: u32 = 0x4;
+pub const MIO_HUP: u32 = 0x8;
+
+pub const MIO_LEVEL: u32 = 0x0;
+pub const MIO_EDGE: u32 = 0x1;
+pub const MIO_ONESHOT: u32 = 0x2;
+
+/// Called with the handle returned by `mio_register`, the fd and its
+/// readiness.
+pub type MioFdCallback = extern "C" fn(*mut MioLoop, i64, c_int, u32, *mut c_void);
+
+/// Called with the id returned by `mio_timeout`.
+pub type MioTimerCallback = extern "C" fn(*mut MioLoop, i64, *mut c_void);
+
+/// The opaque loop handle, `mio_loop` in C.
+pub struct MioLoop {
+    event_loop: EventLoop<CHandler>,
+    handler: CHandler,
+    shared: Rc<Shared>,
+}
+
+// State the API functions reach while a callback runs
+struct Shared {
+    ops: RefCell<VecDeque<Op>>,
+    next_id: Cell<i64>,
+    dispatching: Cell<bool>,
+}
+
+impl Shared {
+    fn next_id(&self) -> i64 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+}
+
+enum Op {
+    Register(i64, RawFd, u32, u32, MioFdCallback, *mut c_void),
+    Reregister(i64, u32, u32),
+    Deregister(i64),
+    Timeout(i64, u64, MioTimerCallback, *mut c_void),
+    ClearTimeout(i64),
+    Stop,
+}
+
+struct Fd {
+    fd: RawFd,
+    callback: MioFdCallback,
+    data: *mut c_void,
+}
+
+struct CHandler {
+    lp: *mut MioLoop,
+    shared: Rc<Shared>,
+    fds: HashMap<i64, Fd>,
+    timers: HashMap<i64, (Timeout, MioTimerCallback, *mut c_void)>,
+}
+
+impl CHandler {
+    fn apply(&mut self, event_loop: &mut EventLoop<CHandler>, op: Op) -> io::Result<()> {
+        match op {
+            Op::Register(id, fd, interest, opts, callback, data) => {
+                try!(event_loop.register_opt(&EventedFd(&fd), Token(id as usize), to_event_set(interest), to_poll_opt(opts)));
+                self.fds.insert(id, Fd { fd: fd, callback: callback, data: data });
+            }
+            Op::Reregister(id, interest, opts) => {
+                let fd = try!(self.fd(id));
+                try!(event_loop.reregister(&EventedFd(&fd), Token(id as usize), to_event_set(interest), to_poll_opt(opts)));
+            }
+            Op::Deregister(id) => {
+                let fd = try!(self.fd(id));
+                self.fds.remove(&id);
+                try!(event_loop.deregister(&EventedFd(&fd)));
+            }
+            Op::Timeout(id, delay, callback, data) => {
+                let timeout = try!(event_loop.timeout_ms(id, delay)
+                    .map_err(|_| io::Error::new(ErrorKind::Other, "timer overflow")));
+
+                self.timers.insert(id, (timeout, callback, data));
+            }
+            Op::ClearTimeout(id) => {
+                if let Some((timeout, _, _)) = self.timers.remove(&id) {
+                    event_loop.clear_timeout(timeout);
+                }
+            }
+            Op::Stop => event_loop.shutdown(),
+        }
+
+        Ok(())
+    }
+
+    fn fd(&self, id: i64) -> io::Result<RawFd> {
+        self.fds.get(&id)
+            .map(|fd| fd.fd)
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "unknown handle"))
+    }
+
+    // Applies the calls made by a callback
+    fn flush(&mut self, event_loop: &mut EventLoop<CHandler>) {
+        loop {
+            let op = match self.shared.ops.borrow_mut().pop_front() {
+                Some(op) => op,
+                None => return,
+            };
+
+            let failed = match op {
+                Op::Register(id, fd, _, _, callback, data) => Some((id, fd, callback, data)),
+                _ => None,
+            };
+
+            if let Err(err) = self.apply(event_loop, op) {
+                warn!("deferred C API call failed; err={:?}", err);
+
+                if let Some((id, fd, callback, data)) = failed {
+                    self.dispatch(event_loop, |lp| callback(lp, id, fd, MIO_ERROR, data));
+                }
+            }
+        }
+    }
+
+    fn dispatch<F: FnOnce(*mut MioLoop)>(&mut self, event_loop: &mut EventLoop<CHandler>, f: F) {
+        self.shared.dispatching.set(true);
+        f(self.lp);
+        self.shared.dispatching.set(false);
+        self.flush(event_loop);
+    }
+}
+
+impl Handler for CHandler {
+    type Timeout = i64;
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<CHandler>, token: Token, events: EventSet) {
+        let id = token.as_usize() as i64;
+
+        let (fd, callback, data) = match self.fds.get(&id) {
+            Some(fd) => (fd.fd, fd.callback, fd.data),
+            None => return,
+        };
+
+        self.dispatch(event_loop, |lp| callback(lp, id, fd, from_event_set(events), data));
+    }
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<CHandler>, id: i64) {
+        let (callback, data) = match self.timers.remove(&id) {
+            Some((_, callback, data)) => (callback, data),
+            None => return,
+        };
+
+        self.dispatch(event_loop, |lp| callback(lp, id, data));
+    }
+}
+
+// Applies `op` right away, or queues it when called from a callback
+unsafe fn call(lp: *mut MioLoop, op: Op) -> c_int {
+    if (*lp).shared.dispatching.get() {
+        (*lp).shared.ops.borrow_mut().push_back(op);
+        return 0;
+    }
+
+    match (*lp).handler.apply(&mut (*lp).event_loop, op) {
+        Ok(()) => 0,
+        Err(err) => to_errno(err),
+    }
+}
+
+fn to_errno(err: io::Error) -> c_int {
+    err.raw_os_error().map(|errno| -errno).unwrap_or(-1)
+}
+
+fn to_event_set(bits: u32) -> EventSet {
+    let mut events = EventSet::none();
+
+    if bits & MIO_READABLE != 0 { events = events | EventSet::readable(); }
+    if bits & MIO_WRITABLE != 0 { events = events | EventSet::writable(); }
+    if bits & MIO_ERROR != 0 { events = events | EventSet::error(); }
+    if bits & MIO_HUP != 0 { events = events | EventSet::hup(); }
+
+    events
+}
+
+fn from_event_set(events: EventSet) -> u32 {
+    let mut bits = 0;
+
+    if events.is_readable() { bits |= MIO_READABLE; }
+    if events.is_writable() { bits |= MIO_WRITABLE; }
+    if events.is_error() { bits |= MIO_ERROR; }
+    if events.is_hup() { bits |= MIO_HUP; }
+
+    bits
+}
+
+fn to_poll_opt(bits: u32) -> PollOpt {
+    let mut opts = if bits & MIO_EDGE != 0 { PollOpt::edge() } else { PollOpt::level() };
+
+    if bits & MIO_ONESHOT != 0 {
+        opts = opts | PollOpt::oneshot();
+    }
+
+    opts
+}
+
+/*
+ *
+ * ===== Functions =====
+ *
+ */
+
+/// Creates a loop, returns null on failure.
+#[no_mangle]
+pub extern "C" fn mio_loop_new() -> *mut MioLoop {
+    let event_loop = match EventLoop::new() {
+        Ok(event_loop) => event_loop,
+        Err(err) => {
+            warn!("failed to create event loop; err={:?}", err);
+            return ptr::null_mut();
+        }
+    };
+
+    let shared = Rc::new(Shared {
+        ops: RefCell::new(VecDeque::new()),
+        next_id: Cell::new(0),
+        dispatching: Cell::new(false),
+    });
+
+    let lp: *mut MioLoop = unsafe {
+        mem::transmute(Box::new(MioLoop {
+            event_loop: event_loop,
+            handler: CHandler {
+                lp: ptr::null_mut(),
+                shared: shared.clone(),
+                fds: HashMap::new(),
+                timers: HashMap::new(),
+            },
+            shared: shared,
+        }))
+    };
+
+    unsafe { (*lp).handler.lp = lp; }
+    lp
+}
+
+/// Frees the loop. The registered fds are left open.
+#[no_mangle]
+pub unsafe extern "C" fn mio_loop_free(lp: *mut MioLoop) {
+    if !lp.is_null() {
+        let _: Box<MioLoop> = mem::transmute(lp);
+    }
+}
+
+/// Registers `fd` for the `MIO_READABLE`, `MIO_WRITABLE` and `MIO_HUP` bits
+/// of `interest`, level-triggered unless `opts` has `MIO_EDGE`. Returns the
+/// handle passed to `callback`, or a negative errno.
+#[no_mangle]
+pub unsafe extern "C" fn mio_register(lp: *mut MioLoop, fd: c_int, interest: u32, opts: u32,
+                                      callback: MioFdCallback, data: *mut c_void) -> i64 {
+    let id = (*lp).shared.next_id();
+
+    match call(lp, Op::Register(id, fd, interest, opts, callback, data)) {
+        0 => id,
+        err => err as i64,
+    }
+}
+
+/// Changes the interest and options of a registered fd.
+#[no_mangle]
+pub unsafe extern "C" fn mio_reregister(lp: *mut MioLoop, handle: i64, interest: u32, opts: u32) -> c_int {
+    call(lp, Op::Reregister(handle, interest, opts))
+}
+
+/// Deregisters an fd, which must be done before closing it.
+#[no_mangle]
+pub unsafe extern "C" fn mio_deregister(lp: *mut MioLoop, handle: i64) -> c_int {
+    call(lp, Op::Deregister(handle))
+}
+
+/// Calls `callback` once, after `delay_ms` milliseconds. Returns the id of
+/// the timeout, or a negative errno.
+#[no_mangle]
+pub unsafe extern "C" fn mio_timeout(lp: *mut MioLoop, delay_ms: u64,
+                                     callback: MioTimerCallback, data: *mut c_void) -> i64 {
+    let id = (*lp).shared.next_id();
+
+    match call(lp, Op::Timeout(id, delay_ms, callback, data)) {
+        0 => id,
+        err => err as i64,
+    }
+}
+
+/// Cancels a timeout that did not fire yet.
+#[no_mangle]
+pub unsafe extern "C" fn mio_clear_timeout(lp: *mut MioLoop, id: i64) -> c_int {
+    call(lp, Op::ClearTimeout(id))
+}
+
+/// Runs the loop until `mio_stop` is called.
+#[no_mangle]
+pub unsafe extern "C" fn mio_run(lp: *mut MioLoop) -> c_int {
+    match (*lp).event_loop.run(&mut (*lp).handler) {
+        Ok(()) => 0,
+        Err(err) => to_errno(err),
+    }
+}
+
+/// Runs a single iteration of the loop, dispatching the events and timeouts
+/// that are ready, waiting for up to `timeout_ms` milliseconds.
+#[no_mangle]
+pub unsafe extern "C" fn mio_run_once(lp: *mut MioLoop, timeout_ms: u64) -> c_int {
+    match (*lp).event_loop.run_once_timeout(&mut (*lp).handler, timeout_ms as usize) {
+        Ok(()) => 0,
+        Err(err) => to_errno(err),
+    }
+}
+
+/// Stops `mio_run` once the current iteration completes.
+#[no_mangle]
+pub unsafe extern "C" fn mio_stop(lp: *mut MioLoop) {
+    call(lp, Op::Stop);
+}
@@ -0,0 +1,64 @@
+use clock_ticks::precise_time_ns;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+const NS_PER_MS: u64 = 1_000_000;
+
+/// The time source of the event loop's timer, set with
+/// `EventLoopConfig::clock`.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Returns the current time in milliseconds. The time must never go
+    /// backwards, but does not need to relate to the wall clock.
+    fn now_ms(&self) -> u64;
+
+    /// Returns true if the clock follows the monotonic system clock, in which
+    /// case the event loop may hand timeouts over to kernel timers. Defaults
+    /// to false.
+    fn is_system(&self) -> bool {
+        false
+    }
+}
+
+/// The monotonic system clock, used by default.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        precise_time_ns() / NS_PER_MS
+    }
+
+    fn is_system(&self) -> bool {
+        true
+    }
+}
+
+/// A clock that only advances when told to, for deterministic tests of
+/// timeout logic.
+///
+/// Clones share the same time, so one clone can be handed to the event loop
+/// config while another is used to advance it. Advancing the clock does not
+/// wake up the event loop, timeouts that became due fire on the next tick,
+/// for example with `EventLoop::run_once_timeout(handler, 0)`.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    now: Arc<Mutex<u64>>,
+}
+
+impl MockClock {
+    /// Returns a new clock, set to `now_ms`.
+    pub fn new(now_ms: u64) -> MockClock {
+        MockClock { now: Arc::new(Mutex::new(now_ms)) }
+    }
+
+    /// Moves the clock forward by `ms`.
+    pub fn advance_ms(&self, ms: u64) {
+        *self.now.lock().unwrap() += ms;
+    }
+}
+
+impl Clock for MockClock {
+    fn now_ms(&self) -> u64 {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,111 @@
+use {io, Evented, EventSet, Poll, PollOpt, Selector, Token};
+use std::fmt;
+
+/// Groups several IO handles under a single token.
+///
+/// The handles are registered with a `Poll` owned by the composite, which
+/// is itself registered with the event loop and becomes readable whenever
+/// one of the handles is ready. Once the event loop reports the composite's
+/// token as readable, `poll` returns the merged readiness of all the handles,
+/// and `fired` tells which of them are ready.
+///
+/// The composite does not own the handles, they must stay open while they
+/// are part of it.
+pub struct Composite {
+    poll: Poll,
+    // Index assigned to the next handle added
+    next: usize,
+    // Readiness of each ready handle, as of the last `poll`
+    fired: Vec<(usize, EventSet)>,
+}
+
+impl Composite {
+    pub fn new() -> io::Result<Composite> {
+        Ok(Composite {
+            poll: try!(Poll::new()),
+            next: 0,
+            fired: vec![],
+        })
+    }
+
+    /// Adds `io` to the composite, watching it for `interest`. Returns the
+    /// index identifying the handle in `fired`.
+    pub fn add<E: ?Sized>(&mut self, io: &E, interest: EventSet, opts: PollOpt) -> io::Result<usize>
+        where E: Evented
+    {
+        let idx = self.next;
+
+        try!(self.poll.register(io, Token(idx), interest, opts));
+        self.next += 1;
+
+        Ok(idx)
+    }
+
+    /// Changes the interest of a handle previously added under `idx`.
+    pub fn modify<E: ?Sized>(&mut self, io: &E, idx: usize, interest: EventSet, opts: PollOpt) -> io::Result<()>
+        where E: Evented
+    {
+        self.poll.reregister(io, Token(idx), interest, opts)
+    }
+
+    /// Removes `io` from the composite.
+    pub fn remove<E: ?Sized>(&mut self, io: &E) -> io::Result<()>
+        where E: Evented
+    {
+        self.poll.deregister(io)
+    }
+
+    /// Collects the readiness of the handles without blocking, returning the
+    /// union of the readiness of every ready handle.
+    pub fn poll(&mut self) -> io::Result<EventSet> {
+        let cnt = try!(self.poll.poll(0));
+        let mut merged = EventSet::none();
+
+        self.fired.clear();
+
+        for i in 0..cnt {
+            let evt = self.poll.event(i);
+
+            merged = merged | evt.kind;
+            self.fired.push((evt.token.as_usize(), evt.kind));
+        }
+
+        Ok(merged)
+    }
+
+    /// The index and readiness of each handle found ready by the last
+    /// `poll`.
+    pub fn fired(&self) -> &[(usize, EventSet)] {
+        &self.fired
+    }
+
+    /// The readiness of the handle added under `idx`, as of the last `poll`.
+    pub fn readiness(&self, idx: usize) -> EventSet {
+        self.fired.iter()
+            .find(|&&(i, _)| i == idx)
+            .map(|&(_, kind)| kind)
+            .unwrap_or(EventSet::none())
+    }
+}
+
+/// The composite is reported readable when any of its handles is ready, the
+/// interest it is registered with is ignored.
+impl Evented for Composite {
+    fn register(&self, selector: &mut Selector, token: Token, _: EventSet, opts: PollOpt) -> io::Result<()> {
+        Evented::register(&&self.poll, selector, token, EventSet::readable(), opts)
+    }
+
+    fn reregister(&self, selector: &mut Selector, token: Token, _: EventSet, opts: PollOpt) -> io::Result<()> {
+        Evented::reregister(&&self.poll, selector, token, EventSet::readable(), opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+        Evented::deregister(&&self.poll, selector)
+    }
+}
+
+impl fmt::Debug for Composite {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Composite {{ fired: {:?} }}", self.fired)
+    }
+}
@@ -0,0 +1,375 @@
+use {io, EventLoop, EventSet, Handler, PollOpt, Token, Transport, TryRead, TryWrite};
+use buf::{Buf, MutBuf, RingBuf};
+use net::ConnectState;
+use std::fmt;
+use std::io::ErrorKind;
+
+/// The stage of a `Connection`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Waiting for a non-blocking connect to complete.
+    Connecting,
+    /// Connected, waiting for the protocol to complete its handshake before
+    /// exchanging data.
+    Handshaking,
+    /// Exchanging data.
+    Streaming,
+    /// Flushing the bytes written before closing.
+    Closing,
+    /// Closed and deregistered, the connection can be dropped.
+    Closed,
+}
+
+/// The per-state hooks a `Connection` calls as it makes progress. All
+/// hooks but `received` have a default implementation.
+///
+/// Returning an error from a hook closes the connection with it.
+#[allow(unused_variables)]
+pub trait Protocol<T: Transport> {
+    /// The connect completed. The connection is `Streaming`, set it to
+    /// `Handshaking` to run a handshake first.
+    fn connected(&mut self, conn: &mut Connection<T>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The transport is ready while `Handshaking`, for the interest set
+    /// with `Connection::set_handshake_interest`. Set the connection to
+    /// `Streaming` once the handshake completes. The default does so right
+    /// away.
+    fn handshake(&mut self, conn: &mut Connection<T>, events: EventSet) -> io::Result<()> {
+        conn.set_state(ConnectionState::Streaming);
+        Ok(())
+    }
+
+    /// Bytes were appended to `Connection::received`. Consume them with
+    /// `Buf::advance`, or leave them until more bytes arrive. Reading stops
+    /// while the receive buffer is full.
+    fn received(&mut self, conn: &mut Connection<T>) -> io::Result<()>;
+
+    /// Every byte written with `Connection::write` has been sent.
+    fn drained(&mut self, conn: &mut Connection<T>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The connection is closed, with the error that caused it if any. It
+    /// has been deregistered already.
+    fn closed(&mut self, conn: &mut Connection<T>, err: Option<io::Error>) {
+    }
+}
+
+/// The skeleton of a connection handled by a state machine: a transport,
+/// its receive and send buffers, and the interest matching its state.
+///
+/// The transport is registered level-triggered under a single token. Pass
+/// the events received for the token to `ready`, and the errors to `error`,
+/// which read and write the buffers, call the `Protocol` hooks, and
+/// reregister the transport when its interest changed. After changing the
+/// connection outside of the hooks, with `write` or `close`, call `update`.
+///
+/// The interest follows the state: writable while connecting, the
+/// handshake interest while handshaking, readable while the receive buffer
+/// has room and writable while the send buffer has bytes when streaming,
+/// and writable until the send buffer is flushed when closing. It is then
+/// passed through `Transport::interest`. Hangups are asked for along with
+/// it, but not while the receive buffer is full nor when closing: the
+/// registration is level-triggered, and a hangup that cannot be acted on
+/// would be reported on every poll.
+pub struct Connection<T> {
+    transport: T,
+    token: Token,
+    state: ConnectionState,
+    rx: RingBuf,
+    tx: RingBuf,
+    handshake_interest: EventSet,
+    // The interest the transport is registered with, if registered
+    registered: Option<EventSet>,
+}
+
+impl<T: Transport> Connection<T> {
+    /// Wraps a connected transport, such as an accepted stream, starting
+    /// `Streaming`. Both buffers hold up to `capacity` bytes, rounded up to
+    /// the next power of two.
+    pub fn new(transport: T, token: Token, capacity: usize) -> Connection<T> {
+        Connection::with_state(transport, token, capacity, ConnectionState::Streaming)
+    }
+
+    /// Wraps a transport whose non-blocking connect is in progress.
+    pub fn connecting(transport: T, token: Token, capacity: usize) -> Connection<T> {
+        Connection::with_state(transport, token, capacity, ConnectionState::Connecting)
+    }
+
+    fn with_state(transport: T, token: Token, capacity: usize, state: ConnectionState) -> Connection<T> {
+        Connection {
+            transport: transport,
+            token: token,
+            state: state,
+            rx: RingBuf::new(capacity),
+            tx: RingBuf::new(capacity),
+            handshake_interest: EventSet::readable(),
+            registered: None,
+        }
+    }
+
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Moves the connection to `state`. Closing goes through `close`.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `state` is `Closing` or `Closed`.
+    pub fn set_state(&mut self, state: ConnectionState) {
+        assert!(state != ConnectionState::Closing && state != ConnectionState::Closed, "use Connection::close to close");
+
+        if self.state != ConnectionState::Closing && self.state != ConnectionState::Closed {
+            self.state = state;
+        }
+    }
+
+    /// Sets the readiness the handshake waits for, readable by default.
+    pub fn set_handshake_interest(&mut self, interest: EventSet) {
+        self.handshake_interest = interest;
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state == ConnectionState::Closed
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Returns the bytes received and not consumed yet.
+    pub fn received(&mut self) -> &mut RingBuf {
+        &mut self.rx
+    }
+
+    /// Queues `bytes` to be sent, returning how many fit in the send
+    /// buffer. Bytes written after `close` are discarded.
+    pub fn write(&mut self, bytes: &[u8]) -> usize {
+        if self.state == ConnectionState::Closing || self.state == ConnectionState::Closed {
+            return 0;
+        }
+
+        MutBuf::write_slice(&mut self.tx, bytes)
+    }
+
+    /// Returns the number of bytes queued and not sent yet.
+    pub fn pending(&self) -> usize {
+        Buf::remaining(&self.tx)
+    }
+
+    /// Closes the connection once the bytes queued are sent. Received bytes
+    /// are no longer read.
+    pub fn close(&mut self) {
+        if self.state != ConnectionState::Closed {
+            self.state = ConnectionState::Closing;
+        }
+    }
+
+    /// Returns the interest matching the state, see `Connection`.
+    pub fn interest(&self) -> EventSet {
+        let (interest, hup) = match self.state {
+            ConnectionState::Connecting => (EventSet::writable(), true),
+            ConnectionState::Handshaking => (self.handshake_interest, true),
+            ConnectionState::Streaming => {
+                let mut interest = EventSet::none();
+
+                // The hangup is read as EOF, once there is room for it
+                if !self.rx.is_full() {
+                    interest = interest | EventSet::readable();
+                }
+
+                if self.pending() > 0 {
+                    interest = interest | EventSet::writable();
+                }
+
+                (interest, !self.rx.is_full())
+            }
+            ConnectionState::Closing => (EventSet::writable(), false),
+            ConnectionState::Closed => return EventSet::none(),
+        };
+
+        let interest = self.transport.interest(interest);
+
+        if hup {
+            interest | EventSet::hup()
+        } else {
+            interest
+        }
+    }
+
+    /// Registers the transport with `event_loop`.
+    pub fn register<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        let interest = self.interest();
+
+        try!(event_loop.register_opt(&self.transport, self.token, interest, PollOpt::level()));
+        self.registered = Some(interest);
+        Ok(())
+    }
+
+    /// Applies changes made outside of the hooks: completes a `close` with
+    /// nothing left to send, and reregisters the transport if its interest
+    /// changed. Returns the state.
+    pub fn update<H, P>(&mut self, event_loop: &mut EventLoop<H>, protocol: &mut P) -> ConnectionState
+        where H: Handler, P: Protocol<T>
+    {
+        if self.state == ConnectionState::Closing && self.pending() == 0 {
+            self.finish(event_loop, protocol, None);
+        }
+
+        if self.state == ConnectionState::Closed {
+            return ConnectionState::Closed;
+        }
+
+        let interest = self.interest();
+
+        if self.registered.is_some() && self.registered != Some(interest) {
+            let res = event_loop.reregister(&self.transport, self.token, interest, PollOpt::level());
+
+            match res {
+                Ok(()) => self.registered = Some(interest),
+                Err(err) => self.finish(event_loop, protocol, Some(err)),
+            }
+        }
+
+        self.state
+    }
+
+    /// Makes progress with the readiness received from
+    /// `Handler::ready`, then applies the changes like `update`.
+    pub fn ready<H, P>(&mut self, event_loop: &mut EventLoop<H>, events: EventSet, protocol: &mut P) -> ConnectionState
+        where H: Handler, P: Protocol<T>
+    {
+        if self.state == ConnectionState::Closed {
+            return ConnectionState::Closed;
+        }
+
+        let events = self.transport.readiness(events);
+
+        if let Err(err) = self.step(events, protocol) {
+            self.finish(event_loop, protocol, Some(err));
+        }
+
+        self.update(event_loop, protocol)
+    }
+
+    /// Closes the connection with the error received from
+    /// `Handler::error`.
+    pub fn error<H, P>(&mut self, event_loop: &mut EventLoop<H>, err: io::Error, protocol: &mut P) -> ConnectionState
+        where H: Handler, P: Protocol<T>
+    {
+        if self.state != ConnectionState::Closed {
+            self.finish(event_loop, protocol, Some(err));
+        }
+
+        ConnectionState::Closed
+    }
+
+    fn step<P: Protocol<T>>(&mut self, events: EventSet, protocol: &mut P) -> io::Result<()> {
+        let state = self.state;
+
+        // The error of a failed connect is fetched below
+        if events.is_error() && state != ConnectionState::Connecting {
+            return Err(io::Error::new(ErrorKind::Other, "connection error"));
+        }
+
+        match state {
+            ConnectionState::Connecting => {
+                let failed = events.is_hup() || events.is_error();
+
+                if events.is_writable() || failed {
+                    // Being writable does not mean the connect succeeded,
+                    // its outcome is in `SO_ERROR`
+                    match try!(self.transport.finish_connect()) {
+                        ConnectState::Connected => {
+                            self.state = ConnectionState::Streaming;
+                            try!(protocol.connected(self));
+                        }
+                        ConnectState::InProgress if failed => {
+                            return Err(io::Error::new(ErrorKind::ConnectionRefused, "connect failed"));
+                        }
+                        ConnectState::InProgress => {}
+                    }
+                }
+            }
+            ConnectionState::Handshaking => {
+                if events != EventSet::none() {
+                    try!(protocol.handshake(self, events));
+                }
+            }
+            ConnectionState::Streaming => {
+                if events.is_writable() {
+                    try!(self.flush(protocol));
+                }
+
+                // A hangup is read as EOF, after the bytes still buffered
+                if events.is_readable() || events.is_hup() {
+                    let drain = try!(self.transport.try_read_buf_all(&mut self.rx));
+
+                    if drain.count > 0 {
+                        try!(protocol.received(self));
+                    }
+
+                    if drain.eof {
+                        self.close();
+                    }
+                }
+            }
+            ConnectionState::Closing => {
+                // A hangup is reported even though it is not asked for when
+                // the peer is gone, writing then fails
+                if events.is_writable() || events.is_hup() {
+                    try!(self.flush(protocol));
+                }
+            }
+            ConnectionState::Closed => {}
+        }
+
+        Ok(())
+    }
+
+    fn flush<P: Protocol<T>>(&mut self, protocol: &mut P) -> io::Result<()> {
+        if self.pending() == 0 {
+            return Ok(());
+        }
+
+        try!(self.transport.try_write_buf_all(&mut self.tx));
+
+        if self.pending() == 0 && self.state == ConnectionState::Streaming {
+            try!(protocol.drained(self));
+        }
+
+        Ok(())
+    }
+
+    fn finish<H, P>(&mut self, event_loop: &mut EventLoop<H>, protocol: &mut P, err: Option<io::Error>)
+        where H: Handler, P: Protocol<T>
+    {
+        self.state = ConnectionState::Closed;
+
+        if self.registered.take().is_some() {
+            // The transport is usually dropped next, which deregisters it
+            // anyway
+            let _ = event_loop.deregister(&self.transport);
+        }
+
+        protocol.closed(self, err);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Connection<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Connection {{ transport: {:?}, token: {:?}, state: {:?} }}",
+               self.transport, self.token, self.state)
+    }
+}
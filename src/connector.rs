@@ -0,0 +1,470 @@
+use {io, EventLoop, EventSet, Handler, PollOpt, Resolved, Resolver, Timeout, Token};
+use net::ConnectState;
+use net::tcp::{TcpSocket, TcpStream};
+use std::{cmp, fmt, mem};
+use std::collections::HashMap;
+use std::io::ErrorKind::{InvalidInput, Other, TimedOut};
+use std::net::SocketAddr;
+
+/// The timeouts scheduled by `Connector`, which the handler's timeout type
+/// must be convertible from. Pass them to `Connector::timeout`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Connecting {
+    /// The attempt to connect `Token` took too long.
+    Deadline(Token),
+    /// The backoff before the next attempt to connect `Token` is over.
+    Retry(Token),
+}
+
+/// The outcome of connecting a destination of a `Connector`: the
+/// established stream, or the error of the last attempt once the attempts
+/// are exhausted.
+#[derive(Debug)]
+pub struct Dialed {
+    pub token: Token,
+    pub result: io::Result<TcpStream>,
+}
+
+/// How `Connector` retries a destination.
+#[derive(Clone, Copy, Debug)]
+pub struct ConnectPolicy {
+    /// Time allowed to an attempt, resolving the host name included.
+    pub attempt_timeout_ms: u64,
+    /// Backoff after the first failed attempt, doubled after each further
+    /// failed attempt.
+    pub initial_backoff_ms: u64,
+    /// Longest backoff between two attempts.
+    pub max_backoff_ms: u64,
+    /// Number of attempts before giving up, at least one.
+    pub max_attempts: u32,
+}
+
+impl Default for ConnectPolicy {
+    fn default() -> ConnectPolicy {
+        ConnectPolicy {
+            attempt_timeout_ms: 10_000,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 10_000,
+            max_attempts: 5,
+        }
+    }
+}
+
+enum Target {
+    Host(String, u16),
+    Addrs(Vec<SocketAddr>),
+}
+
+enum State {
+    // Waiting for the resolver
+    Resolving,
+    // Connecting to `addrs[next - 1]`
+    Connecting {
+        stream: TcpStream,
+        addrs: Vec<SocketAddr>,
+        next: usize,
+    },
+    // Waiting for the backoff to pass
+    Waiting,
+    // Out of attempts, the error is delivered on the next `Connecting::Retry`
+    Failed(io::Error),
+}
+
+struct Dest {
+    target: Target,
+    attempts: u32,
+    state: State,
+    timeout: Option<Timeout>,
+}
+
+/// Establishes outbound TCP connections, retrying with exponential backoff.
+///
+/// Each destination is identified by a token. An attempt resolves the host
+/// name with the resolver, whose `Resolved` messages the handler passes to
+/// `resolved`, then connects to the addresses in turn. The stream being
+/// connected is registered under the destination's token: pass its events
+/// to `ready`, or to `error` when dispatched to `Handler::error`. Attempts
+/// that fail or take longer than the policy allows are retried after a
+/// backoff, using the `Connecting` timeouts passed to `timeout`.
+///
+/// `resolved`, `ready`, `error` and `timeout` return the outcome once a
+/// destination is connected, or out of attempts. The stream is then
+/// deregistered, and the destination forgotten.
+pub struct Connector<R> {
+    resolver: R,
+    policy: ConnectPolicy,
+    dests: HashMap<Token, Dest>,
+}
+
+impl<R: Resolver> Connector<R> {
+    pub fn new(resolver: R, policy: ConnectPolicy) -> Connector<R> {
+        Connector {
+            resolver: resolver,
+            policy: policy,
+            dests: HashMap::new(),
+        }
+    }
+
+    /// Starts connecting to `host` on `port`, under `token`. Connecting an
+    /// already connecting token starts over.
+    pub fn connect<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, host: &str, port: u16)
+        where H::Timeout: From<Connecting>
+    {
+        self.start(event_loop, token, Target::Host(host.to_string(), port));
+    }
+
+    /// Starts connecting to one of `addrs`, tried in order, without
+    /// resolving a host name.
+    pub fn connect_addrs<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, addrs: Vec<SocketAddr>)
+        where H::Timeout: From<Connecting>
+    {
+        self.start(event_loop, token, Target::Addrs(addrs));
+    }
+
+    /// Handles the resolution of a destination's host name.
+    pub fn resolved<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, resolved: Resolved) -> Option<Dialed>
+        where H::Timeout: From<Connecting>
+    {
+        let token = resolved.token;
+
+        match self.dests.get(&token) {
+            Some(&Dest { state: State::Resolving, .. }) => {}
+            // Cancelled, or timed out
+            _ => return None,
+        }
+
+        match resolved.result {
+            Ok(addrs) => self.dial(event_loop, token, addrs, 0),
+            Err(err) => self.failed(event_loop, token, err),
+        }
+    }
+
+    /// Handles readiness of a destination's stream.
+    pub fn ready<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, events: EventSet) -> Option<Dialed>
+        where H::Timeout: From<Connecting>
+    {
+        if !events.is_writable() && !events.is_hup() && !events.is_error() {
+            return None;
+        }
+
+        let res = match self.dests.get(&token) {
+            Some(&Dest { state: State::Connecting { ref stream, .. }, .. }) => stream.finish_connect(),
+            _ => return None,
+        };
+
+        match res {
+            Ok(ConnectState::InProgress) => None,
+            Ok(ConnectState::Connected) => {
+                let dest = self.remove(event_loop, token).unwrap();
+
+                match dest.state {
+                    State::Connecting { stream, .. } => {
+                        let _ = event_loop.deregister(&stream);
+                        Some(Dialed { token: token, result: Ok(stream) })
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Err(err) => self.refused(event_loop, token, err),
+        }
+    }
+
+    /// Handles the error of a destination's stream, captured by the event
+    /// loop when `EventLoopConfig::capture_errors` is set. The stream no
+    /// longer reports the error then, so the events of `Handler::error` must
+    /// be passed here rather than to `ready`.
+    pub fn error<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, events: EventSet, err: io::Error) -> Option<Dialed>
+        where H::Timeout: From<Connecting>
+    {
+        match self.dests.get(&token) {
+            Some(&Dest { state: State::Connecting { .. }, .. }) => {}
+            _ => return None,
+        }
+
+        debug!("connect error reported; token={:?}; events={:?}", token, events);
+        self.refused(event_loop, token, err)
+    }
+
+    /// Handles the `Connecting` timeouts.
+    pub fn timeout<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, timeout: Connecting) -> Option<Dialed>
+        where H::Timeout: From<Connecting>
+    {
+        match timeout {
+            Connecting::Deadline(token) => {
+                let state = match self.dests.get_mut(&token) {
+                    Some(dest) => {
+                        dest.timeout = None;
+                        dest.state.take()
+                    }
+                    None => return None,
+                };
+
+                if let State::Connecting { stream, .. } = state {
+                    let _ = event_loop.deregister(&stream);
+                }
+
+                self.failed(event_loop, token, io::Error::new(TimedOut, "connect timed out"))
+            }
+            Connecting::Retry(token) => {
+                let state = match self.dests.get_mut(&token) {
+                    Some(dest) => {
+                        dest.timeout = None;
+                        dest.state.take()
+                    }
+                    None => return None,
+                };
+
+                match state {
+                    State::Failed(err) => {
+                        self.dests.remove(&token);
+                        Some(Dialed { token: token, result: Err(err) })
+                    }
+                    _ => self.attempt(event_loop, token),
+                }
+            }
+        }
+    }
+
+    /// Stops connecting `token`, returning false if it was not connecting.
+    pub fn cancel<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token) -> bool {
+        match self.remove(event_loop, token) {
+            Some(dest) => {
+                if let State::Connecting { stream, .. } = dest.state {
+                    let _ = event_loop.deregister(&stream);
+                }
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_connecting(&self, token: Token) -> bool {
+        self.dests.contains_key(&token)
+    }
+
+    /// Returns the number of attempts made so far to connect `token`,
+    /// including the current one.
+    pub fn attempts(&self, token: Token) -> Option<u32> {
+        self.dests.get(&token).map(|dest| dest.attempts)
+    }
+
+    /// Returns the number of destinations being connected.
+    pub fn len(&self) -> usize {
+        self.dests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dests.is_empty()
+    }
+
+    pub fn resolver(&self) -> &R {
+        &self.resolver
+    }
+
+    fn start<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, target: Target)
+        where H::Timeout: From<Connecting>
+    {
+        self.cancel(event_loop, token);
+
+        self.dests.insert(token, Dest {
+            target: target,
+            attempts: 0,
+            state: State::Waiting,
+            timeout: None,
+        });
+
+        // Delivered by the timer, as the first attempt may fail right away
+        self.schedule(event_loop, token, Connecting::Retry(token), 0);
+    }
+
+    fn attempt<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token) -> Option<Dialed>
+        where H::Timeout: From<Connecting>
+    {
+        let resolve = {
+            let dest = self.dests.get_mut(&token).unwrap();
+            dest.attempts += 1;
+
+            match dest.target {
+                Target::Host(ref host, port) => {
+                    dest.state = State::Resolving;
+                    Some((host.clone(), port))
+                }
+                Target::Addrs(..) => None,
+            }
+        };
+
+        if !self.schedule(event_loop, token, Connecting::Deadline(token), self.policy.attempt_timeout_ms) {
+            return None;
+        }
+
+        match resolve {
+            Some((host, port)) => {
+                match self.resolver.resolve(&host, port, token) {
+                    Ok(()) => None,
+                    Err(err) => self.failed(event_loop, token, err),
+                }
+            }
+            None => {
+                let addrs = match self.dests[&token].target {
+                    Target::Addrs(ref addrs) => addrs.clone(),
+                    _ => unreachable!(),
+                };
+
+                self.dial(event_loop, token, addrs, 0)
+            }
+        }
+    }
+
+    // Connects to the addresses from `addrs[next]` on, until a connect is
+    // in progress
+    fn dial<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, addrs: Vec<SocketAddr>,
+                        mut next: usize) -> Option<Dialed>
+        where H::Timeout: From<Connecting>
+    {
+        let mut err = io::Error::new(InvalidInput, "no address to connect to");
+
+        while next < addrs.len() {
+            let addr = addrs[next];
+            next += 1;
+
+            match connect(event_loop, token, &addr) {
+                Ok(stream) => {
+                    self.dests.get_mut(&token).unwrap().state = State::Connecting {
+                        stream: stream,
+                        addrs: addrs,
+                        next: next,
+                    };
+
+                    return None;
+                }
+                Err(e) => {
+                    debug!("connect failed; token={:?}; addr={:?}; err={:?}", token, addr, e);
+                    err = e;
+                }
+            }
+        }
+
+        self.failed(event_loop, token, err)
+    }
+
+    // Connecting to the current address failed with `err`, tries the next
+    // address of the attempt, if any
+    fn refused<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, err: io::Error) -> Option<Dialed>
+        where H::Timeout: From<Connecting>
+    {
+        let (addrs, next) = match self.dests.get_mut(&token).unwrap().state.take() {
+            State::Connecting { stream, addrs, next } => {
+                let _ = event_loop.deregister(&stream);
+                (addrs, next)
+            }
+            _ => unreachable!(),
+        };
+
+        debug!("connect failed; token={:?}; addr={:?}; err={:?}", token, addrs[next - 1], err);
+
+        if next < addrs.len() {
+            self.dial(event_loop, token, addrs, next)
+        } else {
+            self.failed(event_loop, token, err)
+        }
+    }
+
+    // The current attempt failed with `err`, retries after the backoff or
+    // gives up
+    fn failed<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, err: io::Error) -> Option<Dialed>
+        where H::Timeout: From<Connecting>
+    {
+        let attempts = {
+            let dest = self.dests.get_mut(&token).unwrap();
+
+            if let Some(timeout) = dest.timeout.take() {
+                event_loop.clear_timeout(timeout);
+            }
+
+            dest.state = State::Waiting;
+            dest.attempts
+        };
+
+        if attempts >= cmp::max(self.policy.max_attempts, 1) {
+            self.dests.remove(&token);
+            return Some(Dialed { token: token, result: Err(err) });
+        }
+
+        let shift = cmp::min(attempts - 1, 32);
+        let backoff = cmp::min(self.policy.initial_backoff_ms.saturating_mul(1u64 << shift), self.policy.max_backoff_ms);
+
+        self.schedule(event_loop, token, Connecting::Retry(token), backoff);
+        None
+    }
+
+    // Replaces the timeout of `token`. If the timer is full, the destination
+    // fails on the next `Connecting::Retry` instead, which is assumed to
+    // get through.
+    fn schedule<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, timeout: Connecting, delay_ms: u64) -> bool
+        where H::Timeout: From<Connecting>
+    {
+        let dest = self.dests.get_mut(&token).unwrap();
+
+        if let Some(timeout) = dest.timeout.take() {
+            event_loop.clear_timeout(timeout);
+        }
+
+        match event_loop.timeout_ms(From::from(timeout), delay_ms) {
+            Ok(timeout) => {
+                dest.timeout = Some(timeout);
+                true
+            }
+            Err(err) => {
+                debug!("failed to schedule connect timeout; token={:?}; err={:?}", token, err);
+
+                if let State::Connecting { stream, .. } = dest.state.take() {
+                    let _ = event_loop.deregister(&stream);
+                }
+
+                dest.state = State::Failed(io::Error::new(Other, "failed to schedule a connect timeout"));
+                dest.timeout = event_loop.timeout_ms(From::from(Connecting::Retry(token)), 0).ok();
+                false
+            }
+        }
+    }
+
+    fn remove<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token) -> Option<Dest> {
+        let mut dest = match self.dests.remove(&token) {
+            Some(dest) => dest,
+            None => return None,
+        };
+
+        if let Some(timeout) = dest.timeout.take() {
+            event_loop.clear_timeout(timeout);
+        }
+
+        Some(dest)
+    }
+}
+
+impl<R> fmt::Debug for Connector<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Connector {{ policy: {:?}, connecting: {} }}", self.policy, self.dests.len())
+    }
+}
+
+impl State {
+    fn take(&mut self) -> State {
+        mem::replace(self, State::Waiting)
+    }
+}
+
+// Starts connecting to `addr`, registering the stream to learn when the
+// connect completes
+fn connect<H: Handler>(event_loop: &mut EventLoop<H>, token: Token, addr: &SocketAddr) -> io::Result<TcpStream> {
+    let sock = try!(match *addr {
+        SocketAddr::V4(..) => TcpSocket::v4(),
+        SocketAddr::V6(..) => TcpSocket::v6(),
+    });
+
+    // Registered even when connected right away, it is writable then
+    let (stream, _) = try!(sock.connect(addr));
+    try!(event_loop.register_opt(&stream, token, EventSet::writable() | EventSet::hup(), PollOpt::level()));
+    Ok(stream)
+}
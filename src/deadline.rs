@@ -0,0 +1,179 @@
+use {EventLoop, Handler, Timeout, TimerResult, Token};
+use std::{cmp, fmt};
+use std::collections::{HashMap, HashSet};
+
+/// The timeout scheduled by `DeadlineMap`, which the handler's timeout type
+/// must be convertible from. Pass it to `DeadlineMap::expire` to get the
+/// requests whose deadline passed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeadlineDue(u64);
+
+struct Bucket {
+    keys: Vec<(Token, u64)>,
+    // Number of keys still due in this bucket
+    live: usize,
+    timeout: Timeout,
+}
+
+/// Deadlines of requests, keyed by the token of their connection and a
+/// request id, such as answering an RPC within two seconds.
+///
+/// Deadlines are grouped in buckets `resolution_ms` wide, each scheduling a
+/// single event loop timeout. When it fires, `expire` returns the requests
+/// of the bucket in one batch. Cancelling a deadline once the request is
+/// answered is O(1), and clears the timeout of a bucket left empty.
+/// Deadlines are late by up to `resolution_ms`.
+pub struct DeadlineMap {
+    resolution_ms: u64,
+    // Bucket of each request
+    entries: HashMap<(Token, u64), u64>,
+    // Request ids of each token
+    tokens: HashMap<Token, HashSet<u64>>,
+    buckets: HashMap<u64, Bucket>,
+}
+
+impl DeadlineMap {
+    pub fn new(resolution_ms: u64) -> DeadlineMap {
+        DeadlineMap {
+            resolution_ms: cmp::max(resolution_ms, 1),
+            entries: HashMap::new(),
+            tokens: HashMap::new(),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Sets the deadline of the request `id` of `token` to `delay_ms` from
+    /// now, replacing its previous deadline if any.
+    pub fn insert<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, id: u64, delay_ms: u64) -> TimerResult<()>
+        where H::Timeout: From<DeadlineDue>
+    {
+        let now = event_loop.now_ms();
+        let at = now.saturating_add(delay_ms);
+        let bucket = at / self.resolution_ms + if at % self.resolution_ms == 0 { 0 } else { 1 };
+
+        if self.entries.get(&(token, id)) == Some(&bucket) {
+            return Ok(());
+        }
+
+        if !self.buckets.contains_key(&bucket) {
+            let delay = bucket.saturating_mul(self.resolution_ms).saturating_sub(now);
+            let timeout = try!(event_loop.timeout_ms(From::from(DeadlineDue(bucket)), delay));
+
+            self.buckets.insert(bucket, Bucket { keys: vec![], live: 0, timeout: timeout });
+        }
+
+        self.cancel(event_loop, token, id);
+
+        let b = self.buckets.get_mut(&bucket).unwrap();
+        b.keys.push((token, id));
+        b.live += 1;
+
+        self.entries.insert((token, id), bucket);
+        self.tokens.entry(token).or_insert_with(HashSet::new).insert(id);
+        Ok(())
+    }
+
+    /// Cancels the deadline of the request `id` of `token`, returning false
+    /// if it had none.
+    pub fn cancel<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, id: u64) -> bool {
+        let bucket = match self.entries.remove(&(token, id)) {
+            Some(bucket) => bucket,
+            None => return false,
+        };
+
+        self.forget(token, id);
+        self.release(event_loop, bucket);
+        true
+    }
+
+    /// Cancels the deadlines of every request of `token`, such as when its
+    /// connection is closed. Returns the number cancelled.
+    pub fn cancel_token<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token) -> usize {
+        let ids = match self.tokens.remove(&token) {
+            Some(ids) => ids,
+            None => return 0,
+        };
+
+        for &id in &ids {
+            if let Some(bucket) = self.entries.remove(&(token, id)) {
+                self.release(event_loop, bucket);
+            }
+        }
+
+        ids.len()
+    }
+
+    /// Handles `DeadlineDue`: returns the requests whose deadline passed, in
+    /// the order their deadlines were set. They are no longer tracked.
+    pub fn expire(&mut self, due: DeadlineDue) -> Vec<(Token, u64)> {
+        let bucket = match self.buckets.remove(&due.0) {
+            Some(bucket) => bucket,
+            None => return vec![],
+        };
+
+        let mut ret = Vec::with_capacity(bucket.live);
+
+        for key in bucket.keys {
+            // Cancelled, or moved to another bucket
+            if self.entries.get(&key) != Some(&due.0) {
+                continue;
+            }
+
+            self.entries.remove(&key);
+            self.forget(key.0, key.1);
+            ret.push(key);
+        }
+
+        ret
+    }
+
+    pub fn contains(&self, token: Token, id: u64) -> bool {
+        self.entries.contains_key(&(token, id))
+    }
+
+    /// Returns the number of deadlines tracked.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn forget(&mut self, token: Token, id: u64) {
+        let empty = match self.tokens.get_mut(&token) {
+            Some(ids) => {
+                ids.remove(&id);
+                ids.is_empty()
+            }
+            None => false,
+        };
+
+        if empty {
+            self.tokens.remove(&token);
+        }
+    }
+
+    // A key of `bucket` was cancelled, clears the bucket once none is left
+    fn release<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, bucket: u64) {
+        let empty = match self.buckets.get_mut(&bucket) {
+            Some(b) => {
+                b.live -= 1;
+                b.live == 0
+            }
+            None => false,
+        };
+
+        if empty {
+            let b = self.buckets.remove(&bucket).unwrap();
+            event_loop.clear_timeout(b.timeout);
+        }
+    }
+}
+
+impl fmt::Debug for DeadlineMap {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "DeadlineMap {{ resolution_ms: {}, deadlines: {}, buckets: {} }}",
+               self.resolution_ms, self.entries.len(), self.buckets.len())
+    }
+}
@@ -0,0 +1,59 @@
+//! Diagnostics of conditions the event loop recovers from on its own.
+//!
+//! These conditions have no caller to report an error to, and are otherwise
+//! only logged. A `Diagnostics` sink installed with
+//! `EventLoop::set_diagnostics` receives them, to be recorded without going
+//! through the logger.
+
+use io;
+use std::fmt;
+
+/// Receives the diagnostics of an event loop. All methods default to doing
+/// nothing, `NoDiagnostics` is installed until another sink is.
+#[allow(unused_variables)]
+pub trait Diagnostics: Send {
+    /// Applying a deferred registration change failed before polling. The
+    /// handle keeps its previous registration.
+    fn registration_error(&mut self, err: &io::Error) {
+    }
+
+    /// A kernel timer could not be armed or disarmed. When arming, the
+    /// timeout was scheduled on the timer wheel instead.
+    fn kernel_timer_error(&mut self, err: &io::Error) {
+    }
+
+    /// `dropped` timeouts scheduled from other threads with a `Scheduler`
+    /// were dropped, the timer holding `EventLoopConfig::timer_capacity`
+    /// timeouts already.
+    fn timer_overflow(&mut self, dropped: usize) {
+    }
+
+    /// `rejected` messages were handed back to their senders with
+    /// `NotifyError::Full` since the last report. Reported once per tick at
+    /// most.
+    fn notify_queue_full(&mut self, rejected: usize) {
+    }
+}
+
+/// A `Diagnostics` sink discarding everything.
+#[derive(Debug, Default)]
+pub struct NoDiagnostics;
+
+impl Diagnostics for NoDiagnostics {
+}
+
+pub struct Sink {
+    pub sink: Box<Diagnostics>,
+}
+
+impl Default for Sink {
+    fn default() -> Sink {
+        Sink { sink: Box::new(NoDiagnostics) }
+    }
+}
+
+impl fmt::Debug for Sink {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Sink {{ .. }}")
+    }
+}
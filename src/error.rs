@@ -0,0 +1,134 @@
+use {NotifyError, Token};
+use io::{self, FdLimit, Unsupported};
+use timer::TimerError;
+use std::{error, fmt};
+use std::io::ErrorKind;
+
+/// The errors specific to mio.
+///
+/// APIs returning an `io::Error` report them wrapped in it, and
+/// `Error::from` recovers them from the `io::Error`, along with the
+/// `Unsupported` and `FdLimit` errors. Any other `io::Error` is kept as
+/// `Error::Io`. The errors of the channel and the timer convert into them
+/// as well, so that `try!` can be used on all of them in a function
+/// returning `Error`.
+#[derive(Debug)]
+pub enum Error {
+    /// The channel of the event loop is full, see
+    /// `EventLoopConfig::notify_capacity`.
+    NotifyFull,
+    /// The event loop is gone, its channel is closed.
+    NotifyClosed,
+    /// The timer cannot hold more timeouts, see
+    /// `EventLoopConfig::timer_capacity`.
+    TimerOverflow,
+    /// The handle is already registered, under the given token if known.
+    TokenInUse(Option<Token>),
+    /// No handle has been registered under the token.
+    NotRegistered(Token),
+    /// The token is reserved for the event loop's own handles, see
+    /// `MAX_TOKEN`.
+    ReservedToken(Token),
+    /// The interest or the options of a registration can never report
+    /// events, or contradict each other.
+    InvalidInterest(&'static str),
+    /// The handle is registered with another event loop, see
+    /// `EventLoop::transfer`.
+    RegisteredElsewhere,
+    /// The operation is not supported on this platform or by this selector.
+    Unsupported(&'static str),
+    /// The process or the system ran out of file descriptors.
+    FdLimit,
+    /// Any other error.
+    Io(io::Error),
+}
+
+impl Error {
+    /// Returns the `io::ErrorKind` the error is reported with when wrapped
+    /// in an `io::Error`.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::NotifyFull => ErrorKind::WouldBlock,
+            Error::NotifyClosed => ErrorKind::BrokenPipe,
+            Error::TokenInUse(..) | Error::RegisteredElsewhere => ErrorKind::AlreadyExists,
+            Error::NotRegistered(..) => ErrorKind::NotFound,
+            Error::InvalidInterest(..) | Error::ReservedToken(..) => ErrorKind::InvalidInput,
+            Error::TimerOverflow | Error::Unsupported(..) | Error::FdLimit => ErrorKind::Other,
+            Error::Io(ref err) => err.kind(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::TokenInUse(Some(token)) => write!(fmt, "handle is already registered with {:?}", token),
+            Error::NotRegistered(token) => write!(fmt, "{:?} has not been registered", token),
+            Error::ReservedToken(token) => write!(fmt, "{:?} is reserved by the event loop", token),
+            Error::Io(ref err) => write!(fmt, "{}", err),
+            _ => fmt.write_str(error::Error::description(self)),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::NotifyFull => "event loop channel is full",
+            Error::NotifyClosed => "event loop channel is closed",
+            Error::TimerOverflow => "too many timer entries",
+            Error::TokenInUse(..) => "handle is already registered",
+            Error::NotRegistered(..) => "token has not been registered",
+            Error::ReservedToken(..) => "token is reserved by the event loop",
+            Error::InvalidInterest(desc) => desc,
+            Error::RegisteredElsewhere => "handle is registered with another event loop",
+            Error::Unsupported(desc) => desc,
+            Error::FdLimit => "too many open files",
+            Error::Io(ref err) => error::Error::description(err),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> io::Error {
+        match err {
+            Error::Io(err) => err,
+            err => io::Error::new(err.kind(), err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        if err.get_ref().map(|e| e.is::<Error>()).unwrap_or(false) {
+            let inner = err.into_inner().unwrap();
+            return *inner.downcast::<Error>().unwrap();
+        }
+
+        if let Some(unsupported) = err.get_ref().and_then(|e| e.downcast_ref::<Unsupported>()) {
+            return Error::Unsupported(unsupported.desc());
+        }
+
+        if FdLimit::is_fd_limit(&err) {
+            return Error::FdLimit;
+        }
+
+        Error::Io(err)
+    }
+}
+
+impl<M> From<NotifyError<M>> for Error {
+    fn from(err: NotifyError<M>) -> Error {
+        match err {
+            NotifyError::Io(err) => Error::Io(err),
+            NotifyError::Full(..) => Error::NotifyFull,
+            NotifyError::Closed(..) => Error::NotifyClosed,
+        }
+    }
+}
+
+impl From<TimerError> for Error {
+    fn from(_: TimerError) -> Error {
+        Error::TimerOverflow
+    }
+}
@@ -64,6 +64,13 @@ impl PollOpt {
     pub fn remove(&mut self, other: PollOpt) {
         self.0 &= !other.0;
     }
+
+    /// Returns an iterator over the options of the set, one option at a
+    /// time.
+    #[inline]
+    pub fn iter(&self) -> PollOptIter {
+        PollOptIter { bits: self.0 }
+    }
 }
 
 impl ops::BitOr for PollOpt {
@@ -111,20 +118,23 @@ impl ops::Not for PollOpt {
     }
 }
 
-impl fmt::Debug for PollOpt {
+impl fmt::Display for PollOpt {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let mut one = false;
-        let flags = [
+        if self.0 == 0 {
+            return write!(fmt, "Empty");
+        }
+
+        let names = [
             (PollOpt::edge(), "Edge-Triggered"),
             (PollOpt::level(), "Level-Triggered"),
             (PollOpt::oneshot(), "OneShot")];
 
-        for &(flag, msg) in flags.iter() {
-            if self.contains(flag) {
-                if one { try!(write!(fmt, " | ")) }
-                try!(write!(fmt, "{}", msg));
+        for (i, opt) in self.iter().enumerate() {
+            if i > 0 { try!(write!(fmt, " | ")) }
 
-                one = true
+            match names.iter().find(|&&(flag, _)| flag == opt) {
+                Some(&(_, name)) => try!(write!(fmt, "{}", name)),
+                None => try!(write!(fmt, "{:#x}", opt.0)),
             }
         }
 
@@ -132,8 +142,29 @@ impl fmt::Debug for PollOpt {
     }
 }
 
+impl fmt::Debug for PollOpt {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, fmt)
+    }
+}
+
+/// Iterator over the options of a `PollOpt`, see `PollOpt::iter`.
+#[derive(Clone, Debug)]
+pub struct PollOptIter {
+    bits: usize,
+}
+
+impl Iterator for PollOptIter {
+    type Item = PollOpt;
+
+    fn next(&mut self) -> Option<PollOpt> {
+        next_bit(&mut self.bits).map(PollOpt)
+    }
+}
+
+// The bits fit in 32 bits, which keeps `IoEvent` small
 #[derive(Copy, PartialEq, Eq, Clone, PartialOrd, Ord)]
-pub struct EventSet(usize);
+pub struct EventSet(u32);
 
 impl EventSet {
     pub fn none() -> EventSet {
@@ -155,6 +186,10 @@ impl EventSet {
         EventSet(0x004)
     }
 
+    /// Hangup readiness: the peer closed its end, or at least its writing
+    /// half. Every selector reports a hangup as `readable() | hup()`, so
+    /// that reading returns the end of stream, as well as any data still
+    /// buffered.
     #[inline]
     pub fn hup() -> EventSet {
         EventSet(0x008)
@@ -168,6 +203,32 @@ impl EventSet {
             EventSet::error()
     }
 
+    /// Returns user readiness bit `n`, which is never reported by the OS.
+    /// User bits are set by userspace readiness (see
+    /// `Registrar::set_readiness`) to pass application level readiness
+    /// through `Handler::ready`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not lower than `EventSet::user_count()`.
+    #[inline]
+    pub fn user(n: usize) -> EventSet {
+        assert!(n < USER_COUNT, "user readiness bit out of range; n={}", n);
+        EventSet(1 << (USER_SHIFT + n))
+    }
+
+    /// Returns the number of user readiness bits.
+    #[inline]
+    pub fn user_count() -> usize {
+        USER_COUNT
+    }
+
+    /// Returns all user readiness bits.
+    #[inline]
+    pub fn all_user() -> EventSet {
+        EventSet(((1 << USER_COUNT) - 1) << USER_SHIFT)
+    }
+
     #[inline]
     pub fn is_readable(&self) -> bool {
         self.contains(EventSet::readable())
@@ -188,6 +249,18 @@ impl EventSet {
         self.contains(EventSet::hup())
     }
 
+    /// Returns true if user readiness bit `n` is set.
+    #[inline]
+    pub fn is_user(&self, n: usize) -> bool {
+        self.contains(EventSet::user(n))
+    }
+
+    /// Returns only the user readiness bits of the set.
+    #[inline]
+    pub fn user_bits(&self) -> EventSet {
+        *self & EventSet::all_user()
+    }
+
     #[inline]
     pub fn insert(&mut self, other: EventSet) {
         self.0 |= other.0;
@@ -198,9 +271,16 @@ impl EventSet {
         self.0 &= !other.0;
     }
 
+    /// Returns an iterator over the readiness flags of the set, one flag at
+    /// a time, including user readiness bits.
+    #[inline]
+    pub fn iter(&self) -> EventSetIter {
+        EventSetIter { bits: self.0 as usize }
+    }
+
     #[inline]
     pub fn bits(&self) -> usize {
-        self.0
+        self.0 as usize
     }
 
     #[inline]
@@ -214,7 +294,7 @@ impl ops::BitOr for EventSet {
 
     #[inline]
     fn bitor(self, other: EventSet) -> EventSet {
-        EventSet(self.bits() | other.bits())
+        EventSet(self.0 | other.0)
     }
 }
 
@@ -223,7 +303,7 @@ impl ops::BitXor for EventSet {
 
     #[inline]
     fn bitxor(self, other: EventSet) -> EventSet {
-        EventSet(self.bits() ^ other.bits())
+        EventSet(self.0 ^ other.0)
     }
 }
 
@@ -232,7 +312,7 @@ impl ops::BitAnd for EventSet {
 
     #[inline]
     fn bitand(self, other: EventSet) -> EventSet {
-        EventSet(self.bits() & other.bits())
+        EventSet(self.0 & other.0)
     }
 }
 
@@ -241,7 +321,7 @@ impl ops::Sub for EventSet {
 
     #[inline]
     fn sub(self, other: EventSet) -> EventSet {
-        EventSet(self.bits() & !other.bits())
+        EventSet(self.0 & !other.0)
     }
 }
 
@@ -250,25 +330,31 @@ impl ops::Not for EventSet {
 
     #[inline]
     fn not(self) -> EventSet {
-        EventSet(!self.bits() & EventSet::all().bits())
+        EventSet(!self.0 & (EventSet::all() | EventSet::all_user()).0)
     }
 }
 
-impl fmt::Debug for EventSet {
+impl fmt::Display for EventSet {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let mut one = false;
-        let flags = [
+        if self.0 == 0 {
+            return write!(fmt, "None");
+        }
+
+        let names = [
             (EventSet::readable(), "Readable"),
             (EventSet::writable(), "Writable"),
-            (EventSet::error(),    "Error"),
-            (EventSet::hup(),      "Hup")];
-
-        for &(flag, msg) in flags.iter() {
-            if self.contains(flag) {
-                if one { try!(write!(fmt, " | ")) }
-                try!(write!(fmt, "{}", msg));
-
-                one = true
+            (EventSet::error(), "Error"),
+            (EventSet::hup(), "Hup")];
+
+        for (i, flag) in self.iter().enumerate() {
+            if i > 0 { try!(write!(fmt, " | ")) }
+
+            match names.iter().find(|&&(f, _)| f == flag) {
+                Some(&(_, name)) => try!(write!(fmt, "{}", name)),
+                None if flag.user_bits() == flag => {
+                    try!(write!(fmt, "User({})", flag.0.trailing_zeros() as usize - USER_SHIFT))
+                }
+                None => try!(write!(fmt, "{:#x}", flag.0)),
             }
         }
 
@@ -276,12 +362,53 @@ impl fmt::Debug for EventSet {
     }
 }
 
+impl fmt::Debug for EventSet {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, fmt)
+    }
+}
+
+/// Iterator over the readiness flags of an `EventSet`, see `EventSet::iter`.
+#[derive(Clone, Debug)]
+pub struct EventSetIter {
+    bits: usize,
+}
+
+impl Iterator for EventSetIter {
+    type Item = EventSet;
+
+    fn next(&mut self) -> Option<EventSet> {
+        next_bit(&mut self.bits).map(|bit| EventSet(bit as u32))
+    }
+}
+
+// Removes the lowest set bit of `bits` and returns it
+#[inline]
+fn next_bit(bits: &mut usize) -> Option<usize> {
+    if *bits == 0 {
+        return None;
+    }
+
+    let bit = *bits & bits.wrapping_neg();
+    *bits &= !bit;
+
+    Some(bit)
+}
+
+// User readiness bits start above the bits of the OS readiness
+const USER_SHIFT: usize = 16;
+const USER_COUNT: usize = 8;
 
 // Keep this struct internal to mio
-#[derive(Copy, Clone, Debug)]
+//
+// Events are copied from the selector buffer one at a time on dispatch, so
+// the struct is kept to 16 bytes on 64-bit targets: the token, the readiness
+// bits and the errno, 0 standing for no error.
+#[derive(Copy, Clone)]
 pub struct IoEvent {
+    pub token: Token,
     pub kind: EventSet,
-    pub token: Token
+    errno: i32,
 }
 
 /// IoEvent represents the raw event that the OS-specific selector
@@ -294,8 +421,29 @@ impl IoEvent {
     /// Create a new IoEvent.
     pub fn new(kind: EventSet, token: Token) -> IoEvent {
         IoEvent {
-            kind: kind,
             token: token,
+            kind: kind,
+            errno: 0,
         }
     }
+
+    /// Returns the pending socket error (errno) captured alongside error
+    /// readiness, if any.
+    #[inline]
+    pub fn error(&self) -> Option<i32> {
+        if self.errno == 0 { None } else { Some(self.errno) }
+    }
+
+    /// Sets the socket error reported with the event. A `code` of 0 clears
+    /// it.
+    #[inline]
+    pub fn set_error(&mut self, code: i32) {
+        self.errno = code;
+    }
+}
+
+impl fmt::Debug for IoEvent {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "IoEvent {{ token: {:?}, kind: {:?}, error: {:?} }}", self.token, self.kind, self.error())
+    }
 }
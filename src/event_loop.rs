@@ -1,34 +1,204 @@
-use {Handler, Evented, Poll, NotifyError, Token};
+use {sys, Backend, Handler, Evented, Poll, NotifyError, Selector, SelectorKind, Token};
+use handler::Callback;
+use clock::{Clock, SystemClock};
+use diagnostics::{self, Diagnostics};
 use event::{IoEvent, EventSet, PollOpt};
-use notify::Notify;
-use timer::{Timer, Timeout, TimerResult};
+use metrics::Metrics;
+use net::tcp::TcpStream;
+use notify::{Notify, Priority};
+use signal::Signal;
+use sys::Signals;
+use registrar::{Applied, Registrar, RegistrationGuard, Registrations};
+use test_selector::TestSelector;
+use token::NOTIFY;
+use timer::{self, Scheduler, Timer, Timeout, TimerResult};
+use trace::Sink;
+#[cfg(feature = "trace")]
+use trace::Trace;
+use clock_ticks::precise_time_ns;
+use std::collections::VecDeque;
+use std::any::Any;
 use std::default::Default;
-use std::{io, fmt, usize};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{cmp, i32, io, fmt, thread, usize};
+
+// Kernel timers take their delay as a C long, longer delays are left to the
+// timer wheel
+const MAX_KERNEL_TIMER_MS: u64 = i32::MAX as u64;
+
+// Reports a lifecycle event to the installed trace sink, if any. Expands to
+// nothing when the `trace` feature is disabled.
+#[cfg(feature = "trace")]
+macro_rules! trace_sink {
+    ($event_loop:expr, $method:ident($($arg:expr),*)) => {
+        if let Some(ref mut sink) = $event_loop.trace.sink {
+            sink.$method($($arg),*);
+        }
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+macro_rules! trace_sink {
+    ($($t:tt)*) => {}
+}
+
+/// How the event loop waits for IO events once it has nothing left to do,
+/// see `EventLoopConfig::idle`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IdleStrategy {
+    /// Blocks in the selector right away, for up to `io_poll_timeout_ms`.
+    /// Suits servers.
+    Block,
+    /// Polls without blocking for up to this many microseconds before
+    /// blocking, so that events arriving shortly are picked up without
+    /// going to sleep. Trades CPU for latency.
+    Spin(u64),
+    /// As `Spin`, yielding the thread to others between polls.
+    Yield(u64),
+    /// Blocks in the selector for up to this many milliseconds rather than
+    /// `io_poll_timeout_ms`, waking up for events and due timeouts only.
+    /// Suits power-constrained devices, see also `timer_slack_ms`.
+    Park(u64),
+}
 
 /// Configure EventLoop runtime details
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct EventLoopConfig {
     pub io_poll_timeout_ms: usize,
+    /// How to wait for IO events when idle. Defaults to
+    /// `IdleStrategy::Block`.
+    pub idle: IdleStrategy,
+    /// Deprecated, the same as setting `idle` to `IdleStrategy::Spin`.
+    /// Configuring the event loop fails with `ErrorKind::InvalidInput` if
+    /// it is set along with another strategy than `IdleStrategy::Block`.
+    #[deprecated(since = "0.4.0", note = "set `idle` to `IdleStrategy::Spin` instead")]
+    pub busy_poll_us: Option<u64>,
+    /// Resume polls interrupted by a signal for the remaining time. When
+    /// unset, an interrupted poll ends the tick early and
+    /// `Handler::interrupted` is invoked.
+    pub retry_interrupted: bool,
+    /// Capture the pending socket error (`SO_ERROR`) of every handle
+    /// reporting an error, and dispatch those events to `Handler::error`
+    /// along with it. Capturing the error clears it on the socket, so that
+    /// `take_socket_error` and `finish_connect` no longer see it. Disabled
+    /// by default.
+    pub capture_errors: bool,
+    /// Maximum number of IO events dispatched per tick. Events beyond the
+    /// budget are carried over and dispatched in the following ticks, before
+    /// polling for new events, so that notifications and timeouts keep being
+    /// processed when a flood of IO events arrives. Must be at least 1.
+    pub events_per_tick: usize,
+    /// When set, dispatch of each batch of polled events starts at a
+    /// rotating offset instead of always following the order returned by
+    /// the OS, so that under saturation every handle makes progress.
+    pub fair_dispatch: bool,
+    /// Bounds of the number of IO events a single poll may return. The
+    /// buffer starts at `events_capacity_min`, doubles when polls keep
+    /// returning it full, and shrinks back after a sustained period of low
+    /// usage, never growing past `events_capacity_max`.
+    pub events_capacity_min: usize,
+    pub events_capacity_max: usize,
+    /// Collect the metrics returned by `EventLoop::metrics`.
+    pub metrics: bool,
+    /// When set, every handler callback is timed, and those running for
+    /// longer than this many milliseconds are reported to
+    /// `Handler::slow_callback`.
+    pub slow_callback_ms: Option<u64>,
+    /// When set, the thread creating the event loop is restricted to these
+    /// CPUs, see `util::set_cpu_affinity`. Creating the event loop fails if
+    /// the affinity cannot be set.
+    pub cpu_affinity: Option<Vec<usize>>,
+    /// When set, a panic within a handler callback is caught by the event
+    /// loop and reported to `Handler::panicked`, instead of unwinding
+    /// through `run`. The event loop remains usable afterwards, but the
+    /// handler may have been left in an inconsistent state.
+    pub catch_panics: bool,
+    /// The OS readiness API to poll with. Defaults to `SelectorKind::Auto`,
+    /// the native selector, or `poll(2)` where the native selector cannot
+    /// be created. Ignored by `EventLoop::with_backend`.
+    pub selector: SelectorKind,
+    /// When set, exactly these signals are blocked while the event loop
+    /// sleeps in the selector, and the signal mask of the thread applies the
+    /// rest of the time. The mask is swapped atomically with the wait, with
+    /// `epoll_pwait`, so that a thread keeping signals blocked can still be
+    /// woken up by them without racing `sigprocmask`. Only supported by the
+    /// epoll selector, creating the event loop fails on other selectors.
+    pub poll_sigmask: Option<Vec<Signal>>,
 
     // == Notifications ==
     pub notify_capacity: usize,
+    /// Capacity of the lane for messages sent with `Priority::High`.
+    pub notify_high_capacity: usize,
     pub messages_per_tick: usize,
 
     // == Timer ==
     pub timer_tick_ms: u64,
     pub timer_wheel_size: usize,
     pub timer_capacity: usize,
+    /// Number of queues timeouts scheduled from other threads are spread
+    /// over, see `EventLoop::scheduler`.
+    pub timer_shards: usize,
+    /// Capacity of each of the `timer_shards` queues.
+    pub timer_shard_capacity: usize,
+    /// Time source of the timer. Replace with a `MockClock` to control time
+    /// in tests.
+    pub clock: Arc<Clock>,
+    /// On kqueue platforms, timeouts set with `EventLoop::timeout_ms` are
+    /// armed as `EVFILT_TIMER` kernel timers, firing with millisecond
+    /// precision instead of at `timer_tick_ms` granularity, and waking up
+    /// the poll by themselves. Only applies with the system clock, timeouts
+    /// from a `Scheduler` always go through the timer wheel.
+    pub kernel_timers: bool,
+    /// Reads the clock once per tick, once polling returns, rather than
+    /// every time a timeout is scheduled or fired. The delays of timeouts
+    /// scheduled by callbacks are then measured from the start of the
+    /// dispatch, as is `EventLoop::now_ms`. Disable it when callbacks run
+    /// long enough for this to matter, or use `EventLoop::precise_now_ms`.
+    pub cache_now: bool,
+    /// Lets timeouts fire up to this many milliseconds late, so that wakeups
+    /// are coalesced rather than following every deadline, to save power.
+    /// The deadlines of `EventLoop::timeout_ms` are rounded up to a
+    /// multiple of the slack, which `EventLoop::timeout_ms_with_slack`
+    /// overrides per timeout, and on Linux the thread creating the event
+    /// loop is given the slack with `PR_SET_TIMERSLACK`, which the kernel
+    /// applies to the timeout of the poll. Timeouts on the timer wheel fire
+    /// at `timer_tick_ms` granularity regardless.
+    pub timer_slack_ms: u64,
 }
 
 impl Default for EventLoopConfig {
+    #[allow(deprecated)]
     fn default() -> EventLoopConfig {
         EventLoopConfig {
             io_poll_timeout_ms: 1_000,
+            idle: IdleStrategy::Block,
+            busy_poll_us: None,
+            retry_interrupted: true,
+            capture_errors: false,
+            events_per_tick: usize::MAX,
+            fair_dispatch: false,
+            events_capacity_min: 1_024,
+            events_capacity_max: 16_384,
+            metrics: false,
+            slow_callback_ms: None,
+            cpu_affinity: None,
+            catch_panics: false,
+            selector: SelectorKind::Auto,
+            poll_sigmask: None,
             notify_capacity: 4_096,
+            notify_high_capacity: 64,
             messages_per_tick: 256,
             timer_tick_ms: 100,
             timer_wheel_size: 1_024,
             timer_capacity: 65_536,
+            timer_shards: 4,
+            timer_shard_capacity: 1_024,
+            clock: Arc::new(SystemClock),
+            kernel_timers: true,
+            cache_now: true,
+            timer_slack_ms: 0,
         }
     }
 }
@@ -37,15 +207,34 @@ impl Default for EventLoopConfig {
 #[derive(Debug)]
 pub struct EventLoop<H: Handler> {
     run: bool,
+    // Error passed to `shutdown_with`, returned from `run` / `run_once`
+    error: Option<io::Error>,
     poll: Poll,
+    // Of the `io_len` polled events, the first `io_next` have been dispatched,
+    // the rest were held back by the per-tick event budget. Dispatch starts
+    // at `io_start` and wraps around.
+    io_start: usize,
+    io_next: usize,
+    io_len: usize,
+    // Incremented on every poll, used to rotate `io_start`
+    io_rotation: usize,
     timer: Timer<H::Timeout>,
     notify: Notify<H::Message>,
-    config: EventLoopConfig,
+    // Messages queued with `defer`, delivered at the end of the tick
+    deferred: VecDeque<H::Message>,
+    // Operations queued by `Registrar`s on other threads
+    registrations: Registrations,
+    // Self-pipe delivering signals, registered under the `NOTIFY` token
+    signals: Option<Box<Signals>>,
+    // Allocated when `EventLoopConfig::metrics` is set
+    metrics: Option<Box<Metrics>>,
+    #[cfg_attr(not(feature = "trace"), allow(dead_code))]
+    trace: Sink,
+    diagnostics: diagnostics::Sink,
+    // Boxed to keep the event loop small
+    config: Box<EventLoopConfig>,
 }
 
-// Token used to represent notifications
-const NOTIFY: Token = Token(usize::MAX);
-
 impl<H: Handler> EventLoop<H> {
 
     /// Initializes a new event loop using default configuration settings. The
@@ -56,32 +245,92 @@ impl<H: Handler> EventLoop<H> {
 
     pub fn configured(config: EventLoopConfig) -> io::Result<EventLoop<H>> {
         // Create the IO poller
-        let mut poll = try!(Poll::new());
+        let poll = try!(Poll::with_kind(config.selector));
+        EventLoop::with_poll(poll, config)
+    }
+
+    /// Initializes a new event loop polling for IO events with a custom
+    /// `Backend`, for platforms without a native selector. The notification
+    /// wakeup pipe is registered with the backend like any other handle.
+    pub fn with_backend(config: EventLoopConfig, backend: Box<Backend>) -> io::Result<EventLoop<H>> {
+        EventLoop::with_poll(Poll::with_backend(backend), config)
+    }
+
+    fn with_poll(mut poll: Poll, config: EventLoopConfig) -> io::Result<EventLoop<H>> {
+        let config = try!(fold_busy_poll(config));
+
+        // No event would ever be dispatched, nor would the loop poll again
+        if config.events_per_tick == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "events_per_tick must be at least 1"));
+        }
+
+        if let Some(ref cpus) = config.cpu_affinity {
+            try!(sys::set_cpu_affinity(cpus));
+        }
+
+        if let Some(ref signals) = config.poll_sigmask {
+            try!(poll.set_sigmask(signals));
+        }
+
+        if config.timer_slack_ms > 0 {
+            try!(sys::set_timer_slack(config.timer_slack_ms.saturating_mul(1_000_000)));
+        }
+
+        poll.set_retry_interrupted(config.retry_interrupted);
+        poll.set_capture_errors(config.capture_errors);
+        poll.set_events_capacity(config.events_capacity_min, config.events_capacity_max);
 
         // Create the timer
         let mut timer = Timer::new(
             config.timer_tick_ms,
             config.timer_wheel_size,
-            config.timer_capacity);
+            config.timer_capacity,
+            config.clock.clone());
 
         // Create cross thread notification queue
-        let notify = try!(Notify::with_capacity(config.notify_capacity));
+        let notify = try!(Notify::with_capacity(config.notify_capacity, config.notify_high_capacity));
 
         // Register the notification wakeup FD with the IO poller
-        try!(poll.register(&notify, NOTIFY, EventSet::readable() | EventSet::writable() , PollOpt::edge()));
+        try!(poll.register_reserved(&notify, NOTIFY, EventSet::readable() | EventSet::writable() , PollOpt::edge()));
+
+        let registrations = Registrations::new(notify.waker());
 
         // Set the timer's starting time reference point
         timer.setup();
 
         Ok(EventLoop {
             run: true,
+            error: None,
             poll: poll,
+            io_start: 0,
+            io_next: 0,
+            io_len: 0,
+            io_rotation: 0,
             timer: timer,
             notify: notify,
-            config: config,
+            deferred: VecDeque::new(),
+            registrations: registrations,
+            signals: None,
+            metrics: if config.metrics { Some(Box::new(Metrics::default())) } else { None },
+            trace: Sink::default(),
+            diagnostics: diagnostics::Sink::default(),
+            config: Box::new(config),
         })
     }
 
+    /// Queues `msg` to be passed to
+    /// [Handler::notify](trait.Handler.html#method.notify) at the end of the
+    /// current tick, after IO events, messages and timeouts have been
+    /// processed and before the event loop sleeps again.
+    ///
+    /// Unlike sending through the `channel`, this does not involve any
+    /// synchronization. Messages deferred while deferred messages are being
+    /// delivered are delivered at the end of the next tick, which then does
+    /// not block.
+    pub fn defer(&mut self, msg: H::Message) {
+        self.deferred.push_back(msg);
+    }
+
     /// Returns a sender that allows sending messages to the event loop in a
     /// thread-safe way, waking up the event loop if needed.
     ///
@@ -134,6 +383,50 @@ impl<H: Handler> EventLoop<H> {
         Sender::new(self.notify.clone())
     }
 
+    /// Returns a registrar that allows registering IO handles with the event
+    /// loop from other threads.
+    ///
+    /// Operations are applied by the event loop at its next tick, waking it
+    /// up if needed. The returned `Completion` can be used to wait for the
+    /// result of the operation.
+    pub fn registrar(&self) -> Registrar {
+        self.registrations.registrar()
+    }
+
+    /// Switches the event loop to polling without blocking, and returns a
+    /// handle injecting readiness that is dispatched to the handler by the
+    /// next tick, as if the OS had reported it.
+    ///
+    /// Combined with `run_once` and a `MockClock`, this allows unit testing
+    /// a handler's state machine without real sockets or real timing:
+    ///
+    /// ```
+    /// use mio::{EventLoop, EventSet, Handler, Token};
+    ///
+    /// struct Conn { readable: bool }
+    ///
+    /// impl Handler for Conn {
+    ///     type Timeout = ();
+    ///     type Message = ();
+    ///
+    ///     fn ready(&mut self, _: &mut EventLoop<Conn>, _: Token, events: EventSet) {
+    ///         self.readable = events.is_readable();
+    ///     }
+    /// }
+    ///
+    /// let mut event_loop = EventLoop::new().unwrap();
+    /// let selector = event_loop.test_selector();
+    ///
+    /// selector.set_ready(Token(1), EventSet::readable());
+    ///
+    /// let mut handler = Conn { readable: false };
+    /// event_loop.run_once(&mut handler).unwrap();
+    /// assert!(handler.readable);
+    /// ```
+    pub fn test_selector(&mut self) -> TestSelector {
+        self.poll.test_selector()
+    }
+
     /// Schedules a timeout after the requested time interval. When the
     /// duration has been reached,
     /// [Handler::timeout](trait.Handler.html#method.timeout) will be invoked
@@ -163,14 +456,107 @@ impl<H: Handler> EventLoop<H> {
     /// let timeout = event_loop.timeout_ms(123, 300).unwrap();
     /// let _ = event_loop.run(&mut MyHandler);
     /// ```
+    ///
+    /// Delays too long to ever be reached, such as `u64::MAX` used as a
+    /// "never" sentinel, schedule a timeout that never fires.
+    ///
+    /// The timeout may fire up to `EventLoopConfig::timer_slack_ms` late.
     pub fn timeout_ms(&mut self, token: H::Timeout, delay: u64) -> TimerResult<Timeout> {
-        self.timer.timeout_ms(token, delay)
+        let slack = self.config.timer_slack_ms;
+        self.timeout_ms_with_slack(token, delay, slack)
+    }
+
+    /// Schedules a timeout after `delay` milliseconds that may fire up to
+    /// `slack` milliseconds late, rounding its deadline up to a multiple of
+    /// `slack`: the timeouts given the same slack that are due within the
+    /// same `slack` milliseconds then fire together. A slack of 0 keeps the
+    /// deadline as is, whatever `EventLoopConfig::timer_slack_ms` is.
+    pub fn timeout_ms_with_slack(&mut self, token: H::Timeout, delay: u64, slack: u64) -> TimerResult<Timeout> {
+        let delay = if slack > 0 {
+            let now = self.timer.now_ms();
+            timer::with_slack(now.saturating_add(delay), slack) - now
+        } else {
+            delay
+        };
+
+        if !self.kernel_timers() || delay > MAX_KERNEL_TIMER_MS {
+            return self.timer.timeout_ms(token, delay);
+        }
+
+        let (timeout, id) = try!(self.timer.insert_detached(token));
+
+        if let Err(e) = self.poll.set_timer(id, delay) {
+            warn!("failed to arm kernel timer, using the timer wheel; err={:?}", e);
+            self.diagnostics.sink.kernel_timer_error(&e);
+
+            let token = self.timer.take_detached(id).unwrap();
+            return self.timer.timeout_ms(token, delay);
+        }
+
+        Ok(timeout)
+    }
+
+    /// Schedules a timeout after `delay`, rounded up to the millisecond. See
+    /// [#timeout_ms](#method.timeout_ms).
+    pub fn timeout(&mut self, token: H::Timeout, delay: Duration) -> TimerResult<Timeout> {
+        self.timeout_ms(token, timer::duration_to_ms(delay))
     }
 
     /// If the supplied timeout has not been triggered, cancel it such that it
     /// will not be triggered in the future.
     pub fn clear_timeout(&mut self, timeout: Timeout) -> bool {
-        self.timer.clear(timeout)
+        match self.timer.clear_detached(timeout) {
+            Some(id) => {
+                if let Err(e) = self.poll.clear_timer(id) {
+                    warn!("failed to disarm kernel timer; err={:?}", e);
+                    self.diagnostics.sink.kernel_timer_error(&e);
+                }
+
+                true
+            }
+            None => self.timer.clear(timeout),
+        }
+    }
+
+    /// Returns the current time in milliseconds according to
+    /// `EventLoopConfig::clock`, the time timeouts are scheduled against.
+    /// During a tick, this is the time read when polling returned, see
+    /// `EventLoopConfig::cache_now`.
+    pub fn now_ms(&self) -> u64 {
+        self.timer.now_ms()
+    }
+
+    /// Returns the current time in milliseconds, reading
+    /// `EventLoopConfig::clock` even during a tick.
+    pub fn precise_now_ms(&self) -> u64 {
+        self.config.clock.now_ms()
+    }
+
+    // Whether timeouts are armed as kernel timers, see
+    // `EventLoopConfig::kernel_timers`
+    fn kernel_timers(&self) -> bool {
+        self.config.kernel_timers && self.config.clock.is_system() && self.poll.has_timers()
+    }
+
+    /// Invokes `Handler::timeout` for every timeout that is due according to
+    /// `EventLoopConfig::clock`, without polling for IO events. Returns the
+    /// number of timeouts fired.
+    ///
+    /// Combined with a `MockClock`, this steps through timeout logic
+    /// deterministically: advance the clock, then fire the due timeouts.
+    pub fn fire_timeouts(&mut self, handler: &mut H) -> usize {
+        if self.config.cache_now {
+            self.timer.cache_now();
+        }
+
+        let fired = self.timer_process(handler);
+        self.timer.uncache_now();
+
+        if let Some(ref mut m) = self.metrics {
+            m.timeouts_fired += fired as u64;
+        }
+
+        fired
     }
 
     /// Tells the event loop to exit after it is done handling all events in the
@@ -179,6 +565,19 @@ impl<H: Handler> EventLoop<H> {
         self.run = false;
     }
 
+    /// Tells the event loop to exit after it is done handling all events in
+    /// the current iteration, returning `err` from
+    /// [#run](#method.run) (or [#run_once](#method.run_once)).
+    ///
+    /// If called several times in the same iteration, the first error wins.
+    pub fn shutdown_with(&mut self, err: io::Error) {
+        self.run = false;
+
+        if self.error.is_none() {
+            self.error = Some(err);
+        }
+    }
+
     /// Indicates whether the event loop is currently running. If it's not it has either
     /// stopped or is scheduled to stop on the next tick.
     pub fn is_running(&self) -> bool {
@@ -186,24 +585,183 @@ impl<H: Handler> EventLoop<H> {
     }
 
     /// Registers an IO handle with the event loop.
+    ///
+    /// Registering a handle that is already registered fails with
+    /// `ErrorKind::AlreadyExists`, naming the token it is registered with.
+    /// Use [#reregister](#method.reregister) to change an existing
+    /// registration.
+    ///
+    /// When a handle is registered under a token while the events of the
+    /// last poll are being dispatched, the events of that poll for the same
+    /// token are dropped: they were reported for the handle previously
+    /// registered under the token, which has since been closed.
     pub fn register<E: ?Sized>(&mut self, io: &E, token: Token) -> io::Result<()>
         where E: Evented
     {
-        self.poll.register(io, token, EventSet::all(), PollOpt::level())
+        self.register_opt(io, token, EventSet::all(), PollOpt::level())
     }
 
     /// Registers an IO handle with the event loop.
+    ///
+    /// `interest` must include `readable`, `writable` or `hup`; `error` is
+    /// always reported, and user readiness bits are never reported by the
+    /// OS. `opt` must include exactly one of `edge` or `level`, optionally
+    /// with `oneshot`. Other combinations would never report events and fail
+    /// with `ErrorKind::InvalidInput`. A re-registration may have no
+    /// interest, which stops reporting readiness until the next one.
     pub fn register_opt<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opt: PollOpt) -> io::Result<()>
         where E: Evented
     {
-        self.poll.register(io, token, interest, opt)
+        let res = self.poll.register(io, token, interest, opt);
+        trace_sink!(self, register(token, interest, opt, &res));
+        res
     }
 
     /// Re-Registers an IO handle with the event loop.
+    ///
+    /// Nothing is done if the handle is registered under `token` with the
+    /// same interest and options already, so calling it on every event
+    /// costs no syscall. Oneshot registrations are always re-armed. Use
+    /// [#reregister_force](#method.reregister_force) to re-register anyway,
+    /// such as to be notified again of the readiness of an edge-triggered
+    /// handle.
     pub fn reregister<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opt: PollOpt) -> io::Result<()>
         where E: Evented
     {
-        self.poll.reregister(io, token, interest, opt)
+        let res = self.poll.reregister(io, token, interest, opt);
+        trace_sink!(self, reregister(token, interest, opt, &res));
+        res
+    }
+
+    /// Re-Registers an IO handle with the event loop, even if nothing
+    /// changed.
+    pub fn reregister_force<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opt: PollOpt) -> io::Result<()>
+        where E: Evented
+    {
+        let res = self.poll.reregister_force(io, token, interest, opt);
+        trace_sink!(self, reregister(token, interest, opt, &res));
+        res
+    }
+
+    /// Registers an IO handle that was registered with another event loop.
+    ///
+    /// A handle can only be registered with one event loop at a time:
+    /// registering it with a second one fails with
+    /// `ErrorKind::AlreadyExists`, as the events would be split between
+    /// them. To move a handle, deregister it from the first event loop
+    /// before registering it with the second one. When the first event loop
+    /// was dropped without deregistering it, transfer it instead.
+    ///
+    /// A handle still registered with a running event loop keeps receiving
+    /// events there after being transferred.
+    pub fn transfer<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opt: PollOpt) -> io::Result<()>
+        where E: Evented
+    {
+        let res = self.poll.transfer(io, token, interest, opt);
+        trace_sink!(self, register(token, interest, opt, &res));
+        res
+    }
+
+    /// Registers an IO handle with the event loop, returning a guard that
+    /// owns the handle and deregisters it when dropped. See
+    /// [RegistrationGuard](struct.RegistrationGuard.html).
+    pub fn register_guarded<E>(&mut self, io: E, token: Token, interest: EventSet, opt: PollOpt) -> io::Result<RegistrationGuard<E>>
+        where E: Evented + Send + 'static
+    {
+        try!(self.register_opt(&io, token, interest, opt));
+        Ok(RegistrationGuard::new(io, token, self.registrar()))
+    }
+
+    /// Deregisters the handle of `guard` immediately and returns it. The
+    /// `Handler::deregistered` hook is not invoked.
+    pub fn release<E>(&mut self, guard: RegistrationGuard<E>) -> io::Result<E>
+        where E: Evented + Send + 'static
+    {
+        let io = guard.into_inner();
+        try!(self.deregister(&io));
+        Ok(io)
+    }
+
+    /// Registers an IO handle with the event loop, queueing the change to be
+    /// applied together with all other queued changes right before the next
+    /// poll.
+    ///
+    /// Multiple queued changes for the same handle are coalesced into a
+    /// single system call. Errors from applying queued changes before a poll
+    /// are logged and otherwise ignored; use
+    /// [#flush_deferred](#method.flush_deferred) to observe them.
+    pub fn register_deferred<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opt: PollOpt) -> io::Result<()>
+        where E: Evented
+    {
+        let res = self.poll.register_deferred(io, token, interest, opt);
+        trace_sink!(self, register(token, interest, opt, &res));
+        res
+    }
+
+    /// Re-registers an IO handle with the event loop, queueing the change.
+    /// See [#register_deferred](#method.register_deferred).
+    pub fn reregister_deferred<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opt: PollOpt) -> io::Result<()>
+        where E: Evented
+    {
+        let res = self.poll.reregister_deferred(io, token, interest, opt);
+        trace_sink!(self, reregister(token, interest, opt, &res));
+        res
+    }
+
+    /// Immediately applies all queued registration changes, returning the
+    /// first error encountered.
+    pub fn flush_deferred(&mut self) -> io::Result<()> {
+        self.poll.flush_deferred()
+    }
+
+    /// Re-arms an IO handle using the interest and options it was most
+    /// recently registered (or re-registered) with under `token`.
+    ///
+    /// This is primarily useful with `PollOpt::oneshot()`, where the handle
+    /// must be re-registered after every event.
+    pub fn rearm<E: ?Sized>(&mut self, io: &E, token: Token) -> io::Result<()>
+        where E: Evented
+    {
+        let res = self.poll.rearm(io, token);
+        trace_sink!(self, rearm(token, &res));
+        res
+    }
+
+    /// Stops reporting readiness for an IO handle until it is re-armed with
+    /// [#rearm](#method.rearm), which restores the interest and options it
+    /// was most recently registered with under `token`.
+    ///
+    /// This is how a listener stops accepting once the process ran out of
+    /// file descriptors: pause it when `accept` fails with `FdLimit`, and
+    /// re-arm it from a timeout or once a connection is closed.
+    pub fn pause<E: ?Sized>(&mut self, io: &E, token: Token) -> io::Result<()>
+        where E: Evented
+    {
+        self.poll.pause(io, token)
+    }
+
+    /// Delivers the given signals to
+    /// [Handler::signal](trait.Handler.html#method.signal) instead of their
+    /// default disposition. May be called again to add more signals.
+    ///
+    /// Signal handlers are process wide, so only one event loop at a time can
+    /// handle signals; other event loops get an `ErrorKind::AlreadyExists`
+    /// error. The previous handlers are restored when the event loop is
+    /// dropped.
+    pub fn register_signals(&mut self, signals: &[Signal]) -> io::Result<()> {
+        if self.signals.is_none() {
+            let sigs = Box::new(try!(Signals::new()));
+            try!(self.poll.register_reserved(&*sigs, NOTIFY, EventSet::readable(), PollOpt::edge()));
+            self.signals = Some(sigs);
+        }
+
+        let sigs = self.signals.as_mut().unwrap();
+
+        for &signal in signals {
+            try!(sigs.add(signal));
+        }
+
+        Ok(())
     }
 
     /// Keep spinning the event loop indefinitely, and notify the handler whenever
@@ -219,118 +777,601 @@ impl<H: Handler> EventLoop<H> {
         Ok(())
     }
 
+    /// Keep spinning the event loop until it is idle, or until it is shut
+    /// down. See [#is_idle](#method.is_idle).
+    pub fn run_until_idle(&mut self, handler: &mut H) -> io::Result<()> {
+        self.run = true;
+
+        loop {
+            // Handles dropped by the last callbacks no longer count
+            self.poll.forget_dropped();
+
+            if !self.run || self.is_idle() {
+                return Ok(());
+            }
+
+            try!(self.run_once(handler));
+        }
+    }
+
+    /// Returns true if the event loop has nothing left to do: no IO handles
+    /// are registered (including for signals), no timeouts are pending, no
+    /// messages are waiting in the channel or have been deferred, and all
+    /// polled events and queued registrations have been processed.
+    ///
+    /// Handles count as registered until they are deregistered or dropped.
+    /// Dropping a registered handle deregisters it, the event loop forgets
+    /// it before polling again.
+    pub fn is_idle(&self) -> bool {
+        // The notify wakeup is always registered
+        self.poll.registered() <= 1 &&
+            self.timer.count() == 0 &&
+            self.notify.pending() == 0 &&
+            self.deferred.is_empty() &&
+            self.io_next >= self.io_len &&
+            self.registrations.is_empty()
+    }
+
     /// Deregisters an IO handle with the event loop.
     pub fn deregister<E: ?Sized>(&mut self, io: &E) -> io::Result<()> where E: Evented {
-        self.poll.deregister(io)
+        let res = self.poll.deregister(io);
+        trace_sink!(self, deregister(&res));
+        res
+    }
+
+    /// Hands `stream` over to blocking code, such as a job run by a
+    /// `WorkerPool`, as a std `TcpStream` in blocking mode. A registered
+    /// stream is deregistered, events already polled for it are not
+    /// delivered.
+    pub fn hand_off(&mut self, stream: TcpStream) -> io::Result<::std::net::TcpStream> {
+        let stream = try!(stream.into_stream());
+        self.poll.forget_dropped();
+        Ok(stream)
+    }
+
+    /// Takes back a stream from blocking code, switched to non-blocking
+    /// mode and registered with `token`.
+    pub fn take_back(&mut self, stream: ::std::net::TcpStream, token: Token, interest: EventSet, opt: PollOpt) -> io::Result<TcpStream> {
+        let stream = try!(TcpStream::from_stream(stream));
+        try!(self.register_opt(&stream, token, interest, opt));
+        Ok(stream)
     }
 
     /// Spin the event loop once, with a timeout of one second, and notify the
     /// handler if any of the registered handles become ready during that
     /// time.
     pub fn run_once(&mut self, handler: &mut H) -> io::Result<()> {
+        self.run_once_timeout(handler, usize::MAX)
+    }
+
+    /// Spin the event loop once, like [#run_once](#method.run_once), but
+    /// wait at most `timeout_ms` milliseconds for IO events.
+    ///
+    /// Passing 0 never blocks, which is useful when the event loop is driven
+    /// by another event loop that waits on the fd of this one. See
+    /// [#prepare_poll](#method.prepare_poll).
+    pub fn run_once_timeout(&mut self, handler: &mut H, timeout_ms: usize) -> io::Result<()> {
         let mut messages;
 
         trace!("event loop tick");
 
-        // Check the notify channel for any pending messages. If there are any,
-        // avoid blocking when polling for IO events. Messages will be
-        // processed after IO events.
-        messages = self.notify.check(self.config.messages_per_tick, true);
-        let pending = messages > 0;
-
-        // Check the registered IO handles for any new events. Each poll
-        // is for one second, so a shutdown request can last as long as
-        // one second before it takes effect.
-        let events = match self.io_poll(pending) {
-            Ok(e) => e,
-            Err(err) => {
-                if err.kind() == io::ErrorKind::Interrupted {
-                    handler.interrupted(self);
-                    0
-                } else {
-                    return Err(err);
+        // Left over if a callback of the previous tick panicked
+        self.timer.uncache_now();
+
+        let (reallocations, deferred_capacity) = if self.metrics.is_some() {
+            (self.poll.reallocations(), self.deferred.capacity())
+        } else {
+            (0, 0)
+        };
+
+        // Guards dropped during the previous tick must not be polled again
+        self.apply_registrations(handler);
+
+        if self.io_next < self.io_len {
+            // Events left over from a previous tick are dispatched before
+            // polling for new ones, so there is no sleeping this tick.
+            messages = self.notify.check(self.config.messages_per_tick, false);
+        } else {
+            // Check the notify channel for any pending messages. If there are
+            // any, avoid blocking when polling for IO events. Messages will be
+            // processed after IO events.
+            // Deferred messages also prevent blocking.
+            let will_sleep = self.deferred.is_empty();
+            messages = self.notify.check(self.config.messages_per_tick, will_sleep);
+            let pending = messages > 0 || !will_sleep;
+
+            // Check the registered IO handles for any new events. Each poll
+            // is for one second, so a shutdown request can last as long as
+            // one second before it takes effect.
+            let events = match self.io_poll(pending, timeout_ms) {
+                Ok(e) => e,
+                Err(err) => {
+                    if err.kind() == io::ErrorKind::Interrupted {
+                        handler.interrupted(self);
+                        0
+                    } else {
+                        return Err(err);
+                    }
                 }
+            };
+
+            self.io_start = 0;
+            self.io_next = 0;
+            self.io_len = events;
+
+            if self.config.fair_dispatch && events > 0 {
+                self.io_start = self.io_rotation % events;
+                self.io_rotation = self.io_rotation.wrapping_add(1);
             }
-        };
 
-        if !pending {
-            // Indicate that the sleep period is over, also grab any additional
-            // messages
-            let remaining = self.config.messages_per_tick - messages;
-            messages += self.notify.check(remaining, false);
+            if !pending {
+                // Indicate that the sleep period is over, also grab any
+                // additional messages
+                let remaining = self.config.messages_per_tick - messages;
+                messages += self.notify.check(remaining, false);
+            }
+        }
+
+        if self.config.cache_now {
+            self.timer.cache_now();
         }
 
-        self.io_process(handler, events);
+        // Apply registrations queued from other threads while polling.
+        // Events for newly registered handles are delivered on the next tick.
+        self.apply_registrations(handler);
+
+        let dispatch_start = if self.metrics.is_some() { precise_time_ns() } else { 0 };
+
+        let events = self.io_process(handler);
         self.notify(handler, messages);
-        self.timer_process(handler);
+        let timeouts = self.timer_process(handler);
+        self.deferred_process(handler);
+        self.dispatch(handler, Callback::Tick, |h, el| h.tick(el));
+
+        if let Some(ref mut m) = self.metrics {
+            m.ticks += 1;
+            m.io_events += events as u64;
+            m.max_io_events_per_tick = cmp::max(m.max_io_events_per_tick, events);
+            m.dispatch_ns += precise_time_ns() - dispatch_start;
+            m.timeouts_fired += timeouts as u64;
+            m.messages += messages as u64;
+            m.buffer_reallocations += self.poll.reallocations() - reallocations;
+
+            if self.deferred.capacity() != deferred_capacity {
+                m.buffer_reallocations += 1;
+            }
+        }
+
+        self.timer.uncache_now();
+
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
         Ok(())
     }
 
+    /// Prepares the event loop for an external wait on its fd (see
+    /// `AsRawFd`) and returns the maximum number of milliseconds the wait
+    /// may last before timeouts need to be processed.
+    ///
+    /// Returns 0 if there is pending work that requires the event loop to
+    /// be spun right away. Once the wait completes,
+    /// [#run_once_timeout](#method.run_once_timeout) should be called with a
+    /// timeout of 0. The fd becomes readable when new IO events are available
+    /// or messages are sent over the event loop's channel.
+    pub fn prepare_poll(&mut self) -> usize {
+        if self.io_next < self.io_len || !self.deferred.is_empty() || !self.notify.prepare_sleep() {
+            return 0;
+        }
+
+        self.sleep_ms(usize::MAX)
+    }
+
+    /// Installs a sink receiving the lifecycle events of the event loop,
+    /// replacing any previously installed sink. Only available with the
+    /// `trace` feature.
+    #[cfg(feature = "trace")]
+    pub fn set_trace<T: Trace + 'static>(&mut self, sink: T) {
+        self.trace.sink = Some(Box::new(sink));
+    }
+
+    /// Installs a sink receiving the diagnostics of the event loop, replacing
+    /// the previously installed sink. The default sink discards them.
+    pub fn set_diagnostics<D: Diagnostics + 'static>(&mut self, sink: D) {
+        self.diagnostics.sink = Box::new(sink);
+    }
+
+    /// Returns a snapshot of the event loop's metrics. Metrics are only
+    /// collected when `EventLoopConfig::metrics` is set.
+    pub fn metrics(&self) -> Metrics {
+        match self.metrics {
+            Some(ref metrics) => {
+                let mut metrics = **metrics;
+
+                metrics.timeouts_pending = self.timer.count();
+                metrics.notify_queue_depth = self.notify.pending();
+
+                metrics
+            }
+            None => Metrics::default(),
+        }
+    }
+
+    /// Resets the metrics counters to zero.
+    pub fn reset_metrics(&mut self) {
+        if let Some(ref mut metrics) = self.metrics {
+            **metrics = Metrics::default();
+        }
+    }
+
     #[inline]
-    fn io_poll(&mut self, immediate: bool) -> io::Result<usize> {
-        if immediate {
-            self.poll.poll(0)
-        } else {
-            let mut sleep = self.timer.next_tick_in_ms() as usize;
+    fn io_poll(&mut self, immediate: bool, timeout_ms: usize) -> io::Result<usize> {
+        let sleep = if immediate { 0 } else { self.sleep_ms(timeout_ms) };
+
+        // Applied here rather than by the selector, which has no one to
+        // report the error to
+        if let Err(e) = self.poll.flush_deferred() {
+            warn!("failed to apply deferred registration; err={:?}", e);
+            self.diagnostics.sink.registration_error(&e);
+        }
+
+        trace_sink!(self, poll_enter(sleep));
+
+        let idle = self.config.idle;
 
-            if sleep > self.config.io_poll_timeout_ms {
-                sleep = self.config.io_poll_timeout_ms;
+        let res = match self.metrics {
+            Some(ref mut metrics) => {
+                let start = precise_time_ns();
+                let res = poll_wait(&mut self.poll, idle, sleep);
+                metrics.poll_wait_ns += precise_time_ns() - start;
+
+                if let Ok((_, blocked)) = res {
+                    if blocked {
+                        metrics.idle_blocks += 1;
+                    } else if sleep > 0 {
+                        metrics.idle_spin_hits += 1;
+                    }
+                }
+
+                res
             }
+            None => poll_wait(&mut self.poll, idle, sleep),
+        };
+
+        let res = res.map(|(cnt, _)| cnt);
 
-            self.poll.poll(sleep)
+        trace_sink!(self, poll_exit(&res));
+        res
+    }
+
+    // How long to block waiting for IO events, at most `max_ms`. The wait
+    // ends when the earliest pending timeout is due.
+    fn sleep_ms(&self, max_ms: usize) -> usize {
+        let mut sleep = match self.config.idle {
+            IdleStrategy::Park(ms) if ms > usize::MAX as u64 => usize::MAX,
+            IdleStrategy::Park(ms) => ms as usize,
+            _ => self.config.io_poll_timeout_ms,
+        };
+
+        if let Some(ms) = self.timer.next_timeout_in_ms() {
+            if ms < sleep as u64 {
+                sleep = ms as usize;
+            }
         }
+
+        if sleep > max_ms {
+            sleep = max_ms;
+        }
+
+        sleep
     }
 
-    // Process IO events that have been previously polled
-    fn io_process(&mut self, handler: &mut H, cnt: usize) {
-        let mut i = 0;
+    // Process IO events that have been previously polled, up to the per-tick
+    // event budget
+    // Returns the number of events dispatched to the handler
+    fn io_process(&mut self, handler: &mut H) -> usize {
+        let mut budget = self.config.events_per_tick;
+        let mut dispatched = 0;
 
         // Iterate over the notifications. Each event provides the token
         // it was registered with (which usually represents, at least, the
         // handle that the event is about) as well as information about
         // what kind of event occurred (readable, writable, signal, etc.)
-        while i < cnt {
-            let evt = self.poll.event(i);
+        while self.io_next < self.io_len && budget > 0 {
+            // Handles dropped by the previous callback must not be
+            // dispatched the events polled for them
+            self.poll.forget_dropped();
+
+            let evt = self.poll.event((self.io_start + self.io_next) % self.io_len);
+            self.io_next += 1;
 
             trace!("event={:?}", evt);
 
             match evt.token {
-                NOTIFY => self.notify.cleanup(),
-                _ => self.io_event(handler, evt)
+                NOTIFY => {
+                    self.notify.cleanup();
+                    self.signal_process(handler);
+                }
+                // The token was deregistered, dropped or registered again,
+                // by a handler callback earlier in this batch. The event is for
+                // the handle previously registered under the token.
+                token if self.poll.is_stale(token) => {
+                    trace!("dropping stale event; token={:?}", token);
+                }
+                // Reported for a handle re-registered with no interest, for
+                // example
+                _ if evt.kind == EventSet::none() && evt.error().is_none() => {
+                    trace!("dropping empty event; token={:?}", evt.token);
+                }
+                _ => {
+                    self.io_event(handler, evt);
+                    budget -= 1;
+                    dispatched += 1;
+                }
+            }
+        }
+
+        dispatched
+    }
+
+    // Invokes a handler callback. The callback is timed when
+    // `EventLoopConfig::slow_callback_ms` is set, and a panic within it is
+    // caught and reported to `Handler::panicked` when
+    // `EventLoopConfig::catch_panics` is set.
+    fn dispatch<F>(&mut self, handler: &mut H, callback: Callback, f: F)
+        where F: FnOnce(&mut H, &mut EventLoop<H>)
+    {
+        let start = if self.config.slow_callback_ms.is_some() { precise_time_ns() } else { 0 };
+
+        if self.config.catch_panics {
+            let res = panic::catch_unwind(AssertUnwindSafe(|| f(handler, self)));
+
+            if let Err(payload) = res {
+                let msg = panic_message(&*payload);
+
+                trace_sink!(self, panicked(callback));
+                handler.panicked(self, callback, msg);
+            }
+        } else {
+            f(handler, self);
+        }
+
+        if let Some(ms) = self.config.slow_callback_ms {
+            let elapsed = precise_time_ns() - start;
+
+            if elapsed >= ms * 1_000_000 {
+                trace_sink!(self, slow_callback(callback, elapsed));
+                handler.slow_callback(self, callback, elapsed);
+            }
+        }
+    }
+
+    fn deferred_process(&mut self, handler: &mut H) {
+        // Only deliver the messages queued so far, so that a handler
+        // deferring from `notify` can't starve the event loop
+        for _ in 0..self.deferred.len() {
+            match self.deferred.pop_front() {
+                Some(msg) => self.dispatch(handler, Callback::Notify, |h, el| h.notify(el, msg)),
+                None => return,
             }
+        }
+    }
+
+    fn apply_registrations(&mut self, handler: &mut H) {
+        for applied in self.registrations.apply(&mut self.poll) {
+            match applied {
+                Applied::Released(token) => handler.deregistered(self, token),
+                Applied::Ready(token, events) => {
+                    trace_sink!(self, dispatch(token, events));
 
-            i += 1;
+                    self.dispatch(handler, Callback::Ready(token, events), |h, el| h.ready(el, token, events));
+                }
+            }
+        }
+    }
+
+    fn signal_process(&mut self, handler: &mut H) {
+        let mut buf = [0; 64];
+
+        loop {
+            let n = match self.signals {
+                Some(ref mut signals) => signals.read(&mut buf),
+                None => return,
+            };
+
+            if n == 0 {
+                return;
+            }
+
+            for &signal in &buf[..n] {
+                let signal = signal as Signal;
+                self.dispatch(handler, Callback::Signal(signal), |h, el| h.signal(el, signal));
+            }
         }
     }
 
     fn io_event(&mut self, handler: &mut H, evt: IoEvent) {
-        handler.ready(self, evt.token, evt.kind);
+        trace_sink!(self, dispatch(evt.token, evt.kind));
+
+        let IoEvent { token, kind, .. } = evt;
+
+        match evt.error() {
+            Some(code) => {
+                let err = io::Error::from_raw_os_error(code);
+                self.dispatch(handler, Callback::Error(token, kind), |h, el| h.error(el, token, kind, err));
+            }
+            None => self.dispatch(handler, Callback::Ready(token, kind), |h, el| h.ready(el, token, kind)),
+        }
     }
 
     fn notify(&mut self, handler: &mut H, mut cnt: usize) {
+        let rejected = self.notify.take_rejected();
+
+        if rejected > 0 {
+            self.diagnostics.sink.notify_queue_full(rejected);
+        }
+
         while cnt > 0 {
             let msg = self.notify.poll()
                 .expect("[BUG] at this point there should always be a message");
 
-            handler.notify(self, msg);
+            trace_sink!(self, notify());
+
+            self.dispatch(handler, Callback::Notify, |h, el| h.notify(el, msg));
+
             cnt -= 1;
         }
     }
 
-    fn timer_process(&mut self, handler: &mut H) {
+    // Returns the number of timeouts fired
+    fn timer_process(&mut self, handler: &mut H) -> usize {
+        let dropped = self.timer.merge_remote();
+
+        if dropped > 0 {
+            self.diagnostics.sink.timer_overflow(dropped);
+        }
+
         let now = self.timer.now();
+        let mut fired = 0;
 
         loop {
             match self.timer.tick_to(now) {
-                Some(t) => handler.timeout(self, t),
-                _ => return
+                Some(t) => {
+                    trace_sink!(self, timeout());
+
+                    self.dispatch(handler, Callback::Timeout, |h, el| h.timeout(el, t));
+
+                    fired += 1;
+                }
+                _ => break,
             }
         }
+
+        // Timeouts driven by kernel timers that fired during the last poll
+        while let Some(id) = self.poll.pop_timer() {
+            if let Some(t) = self.timer.take_detached(id) {
+                trace_sink!(self, timeout());
+
+                self.dispatch(handler, Callback::Timeout, |h, el| h.timeout(el, t));
+
+                fired += 1;
+            }
+        }
+
+        fired
+    }
+}
+
+impl<H: Handler> EventLoop<H> where H::Timeout: Send {
+    /// Returns a handle that schedules timeouts from other threads. Each
+    /// clone of the handle pushes to its own queue, out of
+    /// `EventLoopConfig::timer_shards`, so producers do not contend with
+    /// each other; the queues are merged into the timer on every tick.
+    pub fn scheduler(&mut self) -> Scheduler<H::Timeout> {
+        self.timer.scheduler(self.notify.waker(), self.config.timer_shards, self.config.timer_shard_capacity)
+    }
+}
+
+// Extracts the message passed to `panic!`, when it is a string
+fn panic_message(payload: &(Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&'static str>() {
+        return s.to_string();
+    }
+
+    match payload.downcast_ref::<String>() {
+        Some(s) => s.clone(),
+        None => "Box<Any>".to_string(),
+    }
+}
+
+// Replaces the deprecated `busy_poll_us` with the idle strategy it stands
+// for, which must not contradict `idle`
+#[allow(deprecated)]
+fn fold_busy_poll(mut config: EventLoopConfig) -> io::Result<EventLoopConfig> {
+    if let Some(us) = config.busy_poll_us.take() {
+        if config.idle != IdleStrategy::Block {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "busy_poll_us is set along with another idle strategy"));
+        }
+
+        config.idle = IdleStrategy::Spin(us);
+    }
+
+    Ok(config)
+}
+
+// Polls for IO events, blocking for at most `sleep_ms`. When spinning or
+// yielding, polls without blocking first, up to the blocking duration.
+// Returns whether it blocked, that is the spin found no events, along with
+// the number of events.
+fn poll_wait(poll: &mut Poll, idle: IdleStrategy, sleep_ms: usize) -> io::Result<(usize, bool)> {
+    let (spin_us, yielding) = match idle {
+        IdleStrategy::Spin(us) if sleep_ms > 0 => (us, false),
+        IdleStrategy::Yield(us) if sleep_ms > 0 => (us, true),
+        _ => return poll.poll(sleep_ms).map(|cnt| (cnt, sleep_ms > 0)),
+    };
+
+    let spin_ns = cmp::min(spin_us.saturating_mul(1_000), (sleep_ms as u64).saturating_mul(1_000_000));
+    let start = precise_time_ns();
+
+    loop {
+        let cnt = try!(poll.poll(0));
+
+        if cnt > 0 {
+            return Ok((cnt, false));
+        }
+
+        let elapsed = precise_time_ns() - start;
+
+        if elapsed >= spin_ns {
+            let spent_ms = (elapsed / 1_000_000) as usize;
+            return poll.poll(sleep_ms.saturating_sub(spent_ms)).map(|cnt| (cnt, true));
+        }
+
+        if yielding {
+            thread::yield_now();
+        }
     }
 }
 
 unsafe impl<H: Handler> Sync for EventLoop<H> { }
 
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
+
+/// The fd of the OS selector backing the event loop (epoll, kqueue...). It
+/// becomes readable when the event loop has events to process, allowing the
+/// event loop to be embedded in a foreign event loop.
+#[cfg(unix)]
+impl<H: Handler> AsRawFd for EventLoop<H> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.poll.as_raw_fd()
+    }
+}
+
+/// An event loop can be registered with another event loop, becoming readable
+/// when it has events to process. The parent should then spin the child with
+/// `run_once_timeout(handler, 0)`, followed by `prepare_poll()` so that
+/// messages sent to the child also make it readable.
+///
+/// As with `Poll`, the implementation is on a reference so that it does not
+/// shadow the inherent registration methods:
+/// `event_loop.register(&&child, token)`.
+#[cfg(unix)]
+impl<'a, H: Handler> Evented for &'a EventLoop<H> {
+    fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        Evented::register(&&self.poll, selector, token, interest, opts)
+    }
+
+    fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        Evented::reregister(&&self.poll, selector, token, interest, opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+        Evented::deregister(&&self.poll, selector)
+    }
+}
+
 impl <H: Handler> Drop for EventLoop<H> {
     fn drop(&mut self) {
         self.notify.close();
@@ -338,6 +1379,15 @@ impl <H: Handler> Drop for EventLoop<H> {
 }
 
 /// Sends messages to the EventLoop from other threads.
+///
+/// Senders are `Send` and `Sync`, and cloning one only bumps a reference
+/// count, so each producer thread can hold its own clone, or share a single
+/// one, without any extra locking.
+///
+/// Sending does not allocate: messages are moved into the slots of a ring
+/// buffer allocated once, when the event loop is created, and the slots are
+/// reused as the event loop consumes the messages. Only message types that
+/// own heap data, such as `Box` or `String`, allocate on the sender's side.
 pub struct Sender<M: Send> {
     notify: Notify<M>
 }
@@ -361,8 +1411,94 @@ impl<M: Send> Sender<M> {
         Sender { notify: notify }
     }
 
+    /// Sends `msg` to the event loop, waking it up if it is sleeping.
+    ///
+    /// Sending never blocks. The queue holds at most
+    /// `EventLoopConfig::notify_capacity` messages, when it is full the
+    /// message is handed back in `NotifyError::Full`, and it is up to the
+    /// caller to drop it or retry later. Once the event loop has been
+    /// dropped, `NotifyError::Closed` is returned instead.
     pub fn send(&self, msg: M) -> Result<(), NotifyError<M>> {
-        self.notify.notify(msg)
+        self.notify.notify(msg, Priority::Normal)
+    }
+
+    /// Sends `msg` on the given priority lane. `Priority::High` messages are
+    /// delivered ahead of the normal messages already queued, which is
+    /// useful for control messages such as shutdown requests. Each lane has
+    /// its own capacity, a full normal lane does not prevent sending on the
+    /// high priority one.
+    pub fn send_priority(&self, msg: M, priority: Priority) -> Result<(), NotifyError<M>> {
+        self.notify.notify(msg, priority)
+    }
+}
+
+/// Creates a channel that is not tied to an event loop, holding up to
+/// `capacity` messages per priority lane. The `Receiver` can be registered
+/// with any `EventLoop` or `Poll`, or even several in turn.
+pub fn channel<M: Send>(capacity: usize) -> io::Result<(Sender<M>, Receiver<M>)> {
+    let notify = try!(Notify::with_capacity(capacity, capacity));
+
+    // The first message wakes up the receiver
+    notify.prepare_sleep();
+
+    Ok((Sender::new(notify.clone()), Receiver { notify: notify }))
+}
+
+/// The receiving half of a channel created with `channel`.
+///
+/// The receiver becomes readable when messages are queued. Once readable,
+/// receive messages with `try_recv` until it returns `None`, which clears
+/// the readiness until the next message is sent. Dropping the receiver
+/// closes the channel, sending then fails with `NotifyError::Closed`.
+pub struct Receiver<M: Send> {
+    notify: Notify<M>
+}
+
+impl<M: Send> Receiver<M> {
+    /// Receives the next message, high priority messages first, without
+    /// blocking.
+    pub fn try_recv(&self) -> Option<M> {
+        loop {
+            if self.notify.check(1, false) == 1 {
+                return self.notify.poll();
+            }
+
+            // Clear the readiness, unless a message was sent in the
+            // meantime, in which case it is received instead
+            self.notify.cleanup();
+
+            if self.notify.prepare_sleep() {
+                return None;
+            }
+        }
+    }
+}
+
+impl<M: Send> Evented for Receiver<M> {
+    fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        self.notify.register(selector, token, interest, opts)
+    }
+
+    fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        self.notify.reregister(selector, token, interest, opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+        self.notify.deregister(selector)
+    }
+}
+
+impl<M: Send> fmt::Debug for Receiver<M> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Receiver<?> {{ pending: {} }}", self.notify.pending())
+    }
+}
+
+unsafe impl<M: Send> Send for Receiver<M> { }
+
+impl<M: Send> Drop for Receiver<M> {
+    fn drop(&mut self) {
+        self.notify.close();
     }
 }
 
@@ -0,0 +1,89 @@
+//! Tracking of the file descriptors owned by mio, enabled with the
+//! `fd-tracking` cargo feature.
+//!
+//! Every `Io` records the thread and backtrace it was created from, until it
+//! is dropped. `open_fds` lists the descriptors still open, which helps
+//! attributing the descriptors a long-running process leaks. Capturing a
+//! backtrace per descriptor is slow, the feature is meant for debugging.
+
+use backtrace::Backtrace;
+use std::{fmt, io, mem, thread};
+use std::collections::HashMap;
+use std::os::unix::io::RawFd;
+use std::sync::{Mutex, Once, ONCE_INIT};
+
+/// A descriptor owned by mio that has not been closed yet.
+pub struct OpenFd {
+    /// The descriptor.
+    pub fd: RawFd,
+    /// The name of the thread that created it, if any.
+    pub thread: Option<String>,
+    /// Where it was created.
+    pub backtrace: Backtrace,
+}
+
+impl fmt::Debug for OpenFd {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "fd {} created on thread {}\n{:?}",
+               self.fd,
+               self.thread.as_ref().map(|s| &s[..]).unwrap_or("<unnamed>"),
+               self.backtrace)
+    }
+}
+
+/// Returns the descriptors owned by mio that are currently open, in
+/// ascending order.
+pub fn open_fds() -> Vec<OpenFd> {
+    let fds = registry().lock().unwrap();
+    let mut open: Vec<OpenFd> = fds.iter()
+        .map(|(&fd, &(ref thread, ref backtrace))| {
+            OpenFd { fd: fd, thread: thread.clone(), backtrace: backtrace.clone() }
+        })
+        .collect();
+
+    open.sort_by(|a, b| a.fd.cmp(&b.fd));
+    open
+}
+
+/// Writes the descriptors owned by mio that are currently open to `dst`,
+/// along with where they were created.
+pub fn dump_open_fds<W: io::Write>(dst: &mut W) -> io::Result<()> {
+    let open = open_fds();
+
+    try!(writeln!(dst, "{} open fds", open.len()));
+
+    for fd in open {
+        try!(writeln!(dst, "{:?}", fd));
+    }
+
+    Ok(())
+}
+
+// Records that `fd` was created
+#[doc(hidden)]
+pub fn opened(fd: RawFd) {
+    let thread = thread::current().name().map(|s| s.to_string());
+    registry().lock().unwrap().insert(fd, (thread, Backtrace::new()));
+}
+
+// Records that `fd` was closed
+#[doc(hidden)]
+pub fn closed(fd: RawFd) {
+    registry().lock().unwrap().remove(&fd);
+}
+
+type Registry = Mutex<HashMap<RawFd, (Option<String>, Backtrace)>>;
+
+fn registry() -> &'static Registry {
+    static INIT: Once = ONCE_INIT;
+    static mut REGISTRY: *const Registry = 0 as *const Registry;
+
+    unsafe {
+        INIT.call_once(|| {
+            let registry: Box<Registry> = Box::new(Mutex::new(HashMap::new()));
+            REGISTRY = mem::transmute(registry);
+        });
+
+        &*REGISTRY
+    }
+}
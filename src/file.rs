@@ -0,0 +1,157 @@
+use {sys, Completed, Offload, Sender, Token};
+use buf::{Buf, BufPool, MutBuf, PooledBuf};
+use std::{fmt, io};
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A file opened by `FileIo`, shared with the pool threads running its
+/// operations. Cloning it is cheap, the file is closed once every clone is
+/// dropped and its operations completed.
+#[derive(Clone, Debug)]
+pub struct AsyncFile {
+    file: Arc<File>,
+}
+
+impl AsyncFile {
+    /// Wraps a file opened elsewhere.
+    pub fn from_file(file: File) -> AsyncFile {
+        AsyncFile { file: Arc::new(file) }
+    }
+
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+}
+
+/// The outcome of a `FileIo` operation, delivered to the handler in a
+/// `Completed` message tagged with the token of the operation.
+#[derive(Debug)]
+pub enum FileOp {
+    /// The result of `FileIo::open`.
+    Opened(io::Result<AsyncFile>),
+    /// The result of `FileIo::read_at`: the bytes read, fewer than
+    /// requested at the end of the file.
+    Read(io::Result<PooledBuf>),
+    /// The result of `FileIo::write_at`: the number of bytes written.
+    Written(io::Result<usize>),
+}
+
+/// Regular file IO run on a pool of threads.
+///
+/// Regular files are always readable and writable as far as the selector
+/// is concerned, and reading them may still block on the disk. `FileIo`
+/// runs `open`, `pread(2)` and `pwrite(2)` on an `Offload` pool instead,
+/// and delivers the completions as `Completed<FileOp>` messages, which the
+/// handler's message type must be convertible from.
+///
+/// Reads fill buffers checked out of a `BufPool`, which are returned to it
+/// once the handler drops them.
+pub struct FileIo {
+    pool: Offload<FileOp>,
+    bufs: BufPool,
+}
+
+impl FileIo {
+    /// Spawns `threads` threads delivering completions with `sender`,
+    /// usually obtained with `EventLoop::channel`, and reading into buffers
+    /// from `bufs`.
+    pub fn new<M>(threads: usize, sender: Sender<M>, bufs: BufPool) -> io::Result<FileIo>
+        where M: From<Completed<FileOp>> + Send + 'static
+    {
+        Ok(FileIo {
+            pool: try!(Offload::new(threads, sender)),
+            bufs: bufs,
+        })
+    }
+
+    /// Opens the file at `path` with `options`.
+    pub fn open<P: AsRef<Path>>(&self, token: Token, path: P, options: &OpenOptions) -> io::Result<()> {
+        let path: PathBuf = path.as_ref().to_path_buf();
+        let options = options.clone();
+
+        self.pool.submit(token, move || {
+            FileOp::Opened(options.open(&path).map(AsyncFile::from_file))
+        })
+    }
+
+    /// Reads up to `len` bytes of `file` from `offset`, into a buffer
+    /// checked out of the pool. Fails right away with
+    /// `ErrorKind::InvalidInput` if `len` exceeds the largest buffers of the
+    /// pool.
+    pub fn read_at(&self, token: Token, file: &AsyncFile, offset: u64, len: usize) -> io::Result<()> {
+        let mut buf = try!(self.bufs.checkout(len)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "read larger than the pooled buffers")));
+        let file = file.clone();
+
+        self.pool.submit(token, move || {
+            let res = read_at(&file, &mut buf, offset, len);
+            FileOp::Read(res.map(|_| buf))
+        })
+    }
+
+    /// Writes the bytes of `buf` to `file` at `offset`.
+    pub fn write_at(&self, token: Token, file: &AsyncFile, offset: u64, buf: PooledBuf) -> io::Result<()> {
+        let file = file.clone();
+        let mut buf = buf;
+
+        self.pool.submit(token, move || {
+            FileOp::Written(write_at(&file, &mut buf, offset))
+        })
+    }
+}
+
+impl fmt::Debug for FileIo {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "FileIo {{ pool: {:?} }}", self.pool)
+    }
+}
+
+// Reads until `len` bytes are read or the end of the file is reached
+fn read_at(file: &AsyncFile, buf: &mut PooledBuf, mut offset: u64, len: usize) -> io::Result<()> {
+    let fd = file.get_ref().as_raw_fd();
+    let mut read = 0;
+
+    while read < len {
+        let res = {
+            let dst = buf.mut_bytes();
+            let want = ::std::cmp::min(len - read, dst.len());
+            sys::pread(fd, &mut dst[..want], offset)
+        };
+
+        match res {
+            Ok(0) => break,
+            Ok(cnt) => {
+                MutBuf::advance(buf, cnt);
+                read += cnt;
+                offset += cnt as u64;
+            }
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
+fn write_at(file: &AsyncFile, buf: &mut PooledBuf, mut offset: u64) -> io::Result<usize> {
+    let fd = file.get_ref().as_raw_fd();
+    let mut written = 0;
+
+    while Buf::has_remaining(buf) {
+        match sys::pwrite(fd, Buf::bytes(buf), offset) {
+            Ok(0) => return Err(io::Error::new(ErrorKind::WriteZero, "failed to write whole buffer")),
+            Ok(cnt) => {
+                Buf::advance(buf, cnt);
+                written += cnt;
+                offset += cnt as u64;
+            }
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(written)
+}
@@ -0,0 +1,168 @@
+//! Frames prefixed with their length, as a big-endian `u32`.
+//!
+//! `FrameReader` and `FrameWriter` keep the state of a partially read or
+//! written frame across readiness events, so that a handler only has to
+//! call them again when the handle is ready.
+
+use {io, TryRead, TryWrite, WriteAll};
+use io::NonBlock;
+use std::collections::VecDeque;
+use std::io::ErrorKind::{Interrupted, InvalidInput, Other};
+
+const HEADER_LEN: usize = 4;
+
+/// Reads length-prefixed frames from a non-blocking source.
+#[derive(Debug)]
+pub struct FrameReader {
+    max_len: usize,
+    header: [u8; HEADER_LEN],
+    header_pos: usize,
+    // The frame being read, once its header is complete
+    body: Option<(Vec<u8>, usize)>,
+}
+
+impl FrameReader {
+    /// Reads frames of up to `max_len` bytes, not counting the length
+    /// prefix.
+    pub fn new(max_len: usize) -> FrameReader {
+        FrameReader {
+            max_len: max_len,
+            header: [0; HEADER_LEN],
+            header_pos: 0,
+            body: None,
+        }
+    }
+
+    /// Reads from `src` until a frame is complete or `src` would block.
+    /// Returns `Ok(Some(Some(frame)))` with the complete frame,
+    /// `Ok(Some(None))` if `src` reached EOF between frames, and `Ok(None)`
+    /// if `src` would block first. Call it again until it would block, as
+    /// several frames may be available.
+    ///
+    /// A frame longer than the maximum fails with `ErrorKind::InvalidInput`
+    /// and EOF in the middle of a frame with `ErrorKind::Other`, after
+    /// which the stream cannot be read any further.
+    pub fn read_from<R: TryRead>(&mut self, src: &mut R) -> NonBlock<Option<Vec<u8>>> {
+        while self.body.is_none() {
+            let res = src.try_read(&mut self.header[self.header_pos..]);
+
+            match res {
+                Ok(Some(0)) if self.header_pos == 0 => return Ok(Some(None)),
+                Ok(Some(0)) => return Err(io::Error::new(Other, "unexpected EOF")),
+                Ok(Some(cnt)) => self.header_pos += cnt,
+                Ok(None) => return Ok(None),
+                Err(ref e) if e.kind() == Interrupted => {}
+                Err(e) => return Err(e),
+            }
+
+            if self.header_pos == HEADER_LEN {
+                let len = decode_len(&self.header);
+
+                if len > self.max_len {
+                    return Err(io::Error::new(InvalidInput, "frame exceeds the maximum length"));
+                }
+
+                self.body = Some((vec![0; len], 0));
+            }
+        }
+
+        loop {
+            let res = {
+                let &mut (ref mut body, pos) = self.body.as_mut().unwrap();
+
+                if pos == body.len() {
+                    break;
+                }
+
+                src.try_read(&mut body[pos..])
+            };
+
+            match res {
+                Ok(Some(0)) => return Err(io::Error::new(Other, "unexpected EOF")),
+                Ok(Some(cnt)) => self.body.as_mut().unwrap().1 += cnt,
+                Ok(None) => return Ok(None),
+                Err(ref e) if e.kind() == Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.header_pos = 0;
+        Ok(Some(self.body.take().map(|(body, _)| body)))
+    }
+
+    /// Returns true if a frame has been partially read.
+    pub fn is_partial(&self) -> bool {
+        self.header_pos > 0
+    }
+}
+
+/// Writes length-prefixed frames to a non-blocking sink.
+#[derive(Debug)]
+pub struct FrameWriter {
+    max_len: usize,
+    frames: VecDeque<(WriteAll<[u8; HEADER_LEN]>, WriteAll<Vec<u8>>)>,
+}
+
+impl FrameWriter {
+    /// Writes frames of up to `max_len` bytes, not counting the length
+    /// prefix.
+    pub fn new(max_len: usize) -> FrameWriter {
+        assert!(max_len <= ::std::u32::MAX as usize, "frame length must fit in a u32");
+
+        FrameWriter {
+            max_len: max_len,
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// Queues `frame` to be written. A frame longer than the maximum fails
+    /// with `ErrorKind::InvalidInput`.
+    pub fn push(&mut self, frame: Vec<u8>) -> io::Result<()> {
+        if frame.len() > self.max_len {
+            return Err(io::Error::new(InvalidInput, "frame exceeds the maximum length"));
+        }
+
+        let header = encode_len(frame.len());
+        self.frames.push_back((WriteAll::new(header), WriteAll::new(frame)));
+        Ok(())
+    }
+
+    /// Writes the queued frames to `dst` until all of them are written or
+    /// `dst` would block. Returns `Ok(Some(()))` once every frame has been
+    /// written, `Ok(None)` if `dst` would block first.
+    pub fn write_to<W: TryWrite>(&mut self, dst: &mut W) -> NonBlock<()> {
+        loop {
+            match self.frames.front_mut() {
+                Some(&mut (ref mut header, ref mut body)) => {
+                    if try!(header.write_to(dst)).is_none() {
+                        return Ok(None);
+                    }
+
+                    if try!(body.write_to(dst)).is_none() {
+                        return Ok(None);
+                    }
+                }
+                None => return Ok(Some(())),
+            }
+
+            self.frames.pop_front();
+        }
+    }
+
+    /// Returns the number of frames not entirely written.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+fn decode_len(header: &[u8; HEADER_LEN]) -> usize {
+    ((header[0] as usize) << 24) | ((header[1] as usize) << 16) | ((header[2] as usize) << 8) | header[3] as usize
+}
+
+fn encode_len(len: usize) -> [u8; HEADER_LEN] {
+    [(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8]
+}
@@ -0,0 +1,147 @@
+use {Error, EventLoop, Evented, Handler, Timeout, TimerResult, Token};
+use std::{fmt, mem};
+use std::collections::HashSet;
+
+/// The timeouts scheduled by `GracefulShutdown`, which the handler's timeout
+/// type must be convertible from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Graceful {
+    /// The drain deadline passed with connections still live. Pass it to
+    /// `GracefulShutdown::expire` and close the connections it returns.
+    Deadline,
+    /// Every connection is closed, the event loop can be shut down.
+    Complete,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Running,
+    Draining,
+    Complete,
+}
+
+/// Coordinates stopping a server: stop accepting, let the live connections
+/// finish, and force them closed after a deadline.
+///
+/// Report each connection with `opened` and `closed`. `begin` closes the
+/// listeners and arms the drain deadline. Once the last connection is
+/// closed, `Graceful::Complete` is delivered to `Handler::timeout`. If the
+/// deadline passes first, `Graceful::Deadline` is delivered instead, for
+/// the handler to close the connections left with `expire`, which then
+/// completes the shutdown.
+pub struct GracefulShutdown {
+    live: HashSet<Token>,
+    state: State,
+    deadline: Option<Timeout>,
+}
+
+impl GracefulShutdown {
+    pub fn new() -> GracefulShutdown {
+        GracefulShutdown {
+            live: HashSet::new(),
+            state: State::Running,
+            deadline: None,
+        }
+    }
+
+    /// Tracks the connection `token`.
+    pub fn opened(&mut self, token: Token) {
+        self.live.insert(token);
+    }
+
+    /// Stops tracking the connection `token`, completing the shutdown if it
+    /// was the last one while draining.
+    pub fn closed<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token) -> TimerResult<()>
+        where H::Timeout: From<Graceful>
+    {
+        if !self.live.remove(&token) || self.state != State::Draining || !self.live.is_empty() {
+            return Ok(());
+        }
+
+        self.complete(event_loop)
+    }
+
+    /// Starts the shutdown: deregisters and closes `listeners`, and arms the
+    /// drain deadline, `drain_ms` from now. Completes right away if no
+    /// connection is live. Does nothing if the shutdown already started.
+    pub fn begin<H, L>(&mut self, event_loop: &mut EventLoop<H>, listeners: Vec<L>, drain_ms: u64) -> Result<(), Error>
+        where H: Handler, H::Timeout: From<Graceful>, L: Evented
+    {
+        if self.state != State::Running {
+            return Ok(());
+        }
+
+        for listener in listeners {
+            try!(event_loop.deregister(&listener));
+        }
+
+        self.state = State::Draining;
+
+        if self.live.is_empty() {
+            try!(self.complete(event_loop));
+        } else {
+            self.deadline = Some(try!(event_loop.timeout_ms(From::from(Graceful::Deadline), drain_ms)));
+        }
+
+        Ok(())
+    }
+
+    /// Handles `Graceful::Deadline`: returns the connections still live,
+    /// which the handler must close, and completes the shutdown.
+    pub fn expire<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> TimerResult<Vec<Token>>
+        where H::Timeout: From<Graceful>
+    {
+        self.deadline = None;
+
+        if self.state != State::Draining {
+            return Ok(vec![]);
+        }
+
+        let live = mem::replace(&mut self.live, HashSet::new()).into_iter().collect();
+
+        try!(self.complete(event_loop));
+        Ok(live)
+    }
+
+    /// Returns true once `begin` has been called.
+    pub fn is_shutting_down(&self) -> bool {
+        self.state != State::Running
+    }
+
+    /// Returns true once `Graceful::Complete` has been scheduled.
+    pub fn is_complete(&self) -> bool {
+        self.state == State::Complete
+    }
+
+    /// Returns the tokens of the live connections.
+    pub fn live(&self) -> Vec<Token> {
+        self.live.iter().cloned().collect()
+    }
+
+    /// Returns the number of live connections.
+    pub fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty()
+    }
+
+    fn complete<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> TimerResult<()>
+        where H::Timeout: From<Graceful>
+    {
+        if let Some(timeout) = self.deadline.take() {
+            event_loop.clear_timeout(timeout);
+        }
+
+        try!(event_loop.timeout_ms(From::from(Graceful::Complete), 0));
+        self.state = State::Complete;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for GracefulShutdown {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "GracefulShutdown {{ state: {:?}, live: {} }}", self.state, self.live.len())
+    }
+}
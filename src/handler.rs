@@ -1,4 +1,20 @@
-use {EventLoop, EventSet, Token};
+use {io, EventLoop, EventSet, Token};
+use std::io::ErrorKind;
+use signal::Signal;
+
+/// A handler callback, as reported to `Handler::slow_callback` and
+/// `Handler::panicked`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Callback {
+    /// `Handler::ready` for the given token and readiness.
+    Ready(Token, EventSet),
+    /// `Handler::error` for the given token and readiness.
+    Error(Token, EventSet),
+    Notify,
+    Timeout,
+    Signal(Signal),
+    Tick,
+}
 
 #[allow(unused_variables)]
 pub trait Handler {
@@ -14,10 +30,26 @@ pub trait Handler {
     /// `events` will have `writable` set.
     ///
     /// This function will only be invoked a single time per socket per event
-    /// loop tick.
+    /// loop tick, and never with an empty `events`. Events polled for a
+    /// token whose handle an earlier callback of the same tick deregistered
+    /// or dropped are dropped too.
     fn ready(&mut self, event_loop: &mut EventLoop<Self>, token: Token, events: EventSet) {
     }
 
+    /// Invoked instead of `ready` when the socket represented by `token`
+    /// reports error readiness and the selector was able to capture the
+    /// pending socket error (`SO_ERROR`). `events` still contains the full
+    /// set of readiness, including `error`. Only invoked when
+    /// `EventLoopConfig::capture_errors` is set.
+    ///
+    /// Capturing the error clears it on the socket, so a subsequent
+    /// `take_socket_error` will return `Ok(())`.
+    ///
+    /// The default implementation discards the error and forwards to `ready`.
+    fn error(&mut self, event_loop: &mut EventLoop<Self>, token: Token, events: EventSet, err: io::Error) {
+        self.ready(event_loop, token, events);
+    }
+
     /// Invoked when a message has been received via the event loop's channel.
     fn notify(&mut self, event_loop: &mut EventLoop<Self>, msg: Self::Message) {
     }
@@ -26,7 +58,47 @@ pub trait Handler {
     fn timeout(&mut self, event_loop: &mut EventLoop<Self>, timeout: Self::Timeout) {
     }
 
+    /// Invoked when the process received one of the signals registered with
+    /// [EventLoop::register_signals](struct.EventLoop.html#method.register_signals).
+    fn signal(&mut self, event_loop: &mut EventLoop<Self>, signal: Signal) {
+    }
+
+    /// Invoked once the IO handle of a dropped `RegistrationGuard` has been
+    /// deregistered, so that `token` can be freed.
+    fn deregistered(&mut self, event_loop: &mut EventLoop<Self>, token: Token) {
+    }
+
     /// Invoked when `EventLoop` has been interrupted by a signal interrupt.
+    ///
+    /// Interrupted polls are resumed internally by default, so this is only
+    /// invoked when `EventLoopConfig::retry_interrupted` is unset.
     fn interrupted(&mut self, event_loop: &mut EventLoop<Self>) {
     }
+
+    /// Invoked once at the end of every event loop iteration, after all IO
+    /// events, notifications and timeouts for the iteration have been
+    /// dispatched and before the event loop polls for new events. This is
+    /// a good place to flush buffered writes or record batched metrics.
+    fn tick(&mut self, event_loop: &mut EventLoop<Self>) {
+    }
+
+    /// Invoked right after a callback that ran for longer than
+    /// `EventLoopConfig::slow_callback_ms`, with the time it took in
+    /// nanoseconds.
+    ///
+    /// The default implementation logs a warning.
+    fn slow_callback(&mut self, event_loop: &mut EventLoop<Self>, callback: Callback, elapsed_ns: u64) {
+        warn!("slow handler callback; callback={:?}; elapsed_ms={}", callback, elapsed_ns / 1_000_000);
+    }
+
+    /// Invoked when `callback` panicked, with `EventLoopConfig::catch_panics`
+    /// set. `msg` is the message the callback panicked with. For `Ready` and
+    /// `Error` callbacks, the offending token is part of `callback`.
+    ///
+    /// The default implementation shuts down the event loop, so that `run`
+    /// returns an error describing the panic.
+    fn panicked(&mut self, event_loop: &mut EventLoop<Self>, callback: Callback, msg: String) {
+        let msg = format!("handler panicked; callback={:?}; msg={}", callback, msg);
+        event_loop.shutdown_with(io::Error::new(ErrorKind::Other, msg));
+    }
 }
@@ -0,0 +1,169 @@
+use {EventLoop, Handler, Timeout, TimerResult, Token};
+use std::{cmp, fmt};
+use std::collections::HashMap;
+
+/// The timeouts scheduled by `IdleTimeouts`, which the handler's timeout
+/// type must be convertible from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Idle {
+    /// The wheel is due to advance, pass it to `IdleTimeouts::tick`.
+    Tick,
+    /// Nothing happened on the token for the idle timeout. The token is no
+    /// longer tracked.
+    Expired(Token),
+}
+
+/// Detects idle connections, for example to close them.
+///
+/// Call `touch` with the token of a connection whenever there is activity
+/// on it. A connection left untouched for the idle timeout is reported to
+/// `Handler::timeout` as `Idle::Expired`. Tokens are kept on a single
+/// coarse wheel advanced by one event loop timeout per `resolution_ms`, so
+/// touching a token is cheap and does not schedule anything. Expirations
+/// are late by up to `resolution_ms`.
+///
+/// The wheel advances once `start` scheduled the first `Idle::Tick`, and
+/// each tick passed to `tick` schedules the next one.
+pub struct IdleTimeouts {
+    idle_ticks: u64,
+    resolution_ms: u64,
+    // Number of ticks since the wheel started
+    cursor: u64,
+    entries: HashMap<Token, Entry>,
+    wheel: Vec<Vec<Token>>,
+    timeout: Option<Timeout>,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    // Tick at which the token expires
+    deadline: u64,
+    // Tick of the slot holding the token. Tokens are only moved when their
+    // slot comes up, the other slots may still hold stale copies.
+    queued: u64,
+}
+
+impl IdleTimeouts {
+    /// Returns a wheel expiring tokens after `idle_ms`, rounded up to
+    /// `resolution_ms`.
+    pub fn new(idle_ms: u64, resolution_ms: u64) -> IdleTimeouts {
+        let resolution_ms = cmp::max(resolution_ms, 1);
+        let idle_ticks = cmp::max((idle_ms + resolution_ms - 1) / resolution_ms, 1);
+
+        IdleTimeouts {
+            idle_ticks: idle_ticks,
+            resolution_ms: resolution_ms,
+            cursor: 0,
+            entries: HashMap::new(),
+            wheel: (0..idle_ticks + 1).map(|_| Vec::new()).collect(),
+            timeout: None,
+        }
+    }
+
+    /// Starts advancing the wheel.
+    pub fn start<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> TimerResult<()>
+        where H::Timeout: From<Idle>
+    {
+        if self.timeout.is_none() {
+            self.timeout = Some(try!(event_loop.timeout_ms(From::from(Idle::Tick), self.resolution_ms)));
+        }
+
+        Ok(())
+    }
+
+    /// Stops advancing the wheel. The tokens stay tracked, and the time
+    /// spent stopped does not count towards their idle timeout.
+    pub fn stop<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) {
+        if let Some(timeout) = self.timeout.take() {
+            event_loop.clear_timeout(timeout);
+        }
+    }
+
+    /// Records activity on `token`, tracking it if it is not already.
+    pub fn touch(&mut self, token: Token) {
+        let deadline = self.cursor + self.idle_ticks;
+
+        if let Some(entry) = self.entries.get_mut(&token) {
+            entry.deadline = deadline;
+            return;
+        }
+
+        self.entries.insert(token, Entry { deadline: deadline, queued: deadline });
+        self.queue(token, deadline);
+    }
+
+    /// Stops tracking `token`, returning false if it was not tracked.
+    pub fn remove(&mut self, token: Token) -> bool {
+        self.entries.remove(&token).is_some()
+    }
+
+    pub fn contains(&self, token: Token) -> bool {
+        self.entries.contains_key(&token)
+    }
+
+    /// Returns the number of tokens tracked.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Advances the wheel on `Idle::Tick`, scheduling an `Idle::Expired`
+    /// timeout for every token idle for too long, and the next tick.
+    ///
+    /// When the timer is full, the tokens that could not be reported stay
+    /// tracked and expire on the next tick.
+    pub fn tick<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> TimerResult<()>
+        where H::Timeout: From<Idle>
+    {
+        self.timeout = None;
+        self.cursor += 1;
+
+        let cursor = self.cursor;
+        let slot = (cursor % self.wheel.len() as u64) as usize;
+        let tokens = ::std::mem::replace(&mut self.wheel[slot], Vec::new());
+        let mut ret = Ok(());
+
+        for token in tokens {
+            let entry = match self.entries.get(&token) {
+                Some(&entry) if entry.queued == cursor => entry,
+                // Removed, or a stale copy
+                _ => continue,
+            };
+
+            if entry.deadline > cursor {
+                self.entries.get_mut(&token).unwrap().queued = entry.deadline;
+                self.queue(token, entry.deadline);
+                continue;
+            }
+
+            match event_loop.timeout_ms(From::from(Idle::Expired(token)), 0) {
+                Ok(_) => {
+                    self.entries.remove(&token);
+                }
+                Err(err) => {
+                    self.entries.get_mut(&token).unwrap().queued = cursor + 1;
+                    self.queue(token, cursor + 1);
+                    ret = Err(err);
+                }
+            }
+        }
+
+        try!(self.start(event_loop));
+        ret
+    }
+
+    fn queue(&mut self, token: Token, tick: u64) {
+        let slot = (tick % self.wheel.len() as u64) as usize;
+        self.wheel[slot].push(token);
+    }
+}
+
+impl fmt::Debug for IdleTimeouts {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "IdleTimeouts {{ idle_ticks: {}, resolution_ms: {}, tracked: {} }}",
+               self.idle_ticks, self.resolution_ms, self.entries.len())
+    }
+}
@@ -1,9 +1,38 @@
 use {EventSet, Selector, PollOpt, Token};
-use buf::{Buf, MutBuf};
+use buf::{Buf, MutBuf, IoVec, IoVecMut, VecBuf, VecMutBuf};
+use std::{cmp, error, fmt, ops};
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
 
 // Re-export the io::Result / Error types for convenience
 pub use std::io::{Read, Write, Result, Error};
 
+/// The result of an operation on a non-blocking handle: `Ok(Some(v))` once
+/// the operation completed, `Ok(None)` if it would have blocked, instead of
+/// an error of kind `ErrorKind::WouldBlock`.
+///
+/// Reads and writes (`TryRead`, `TryWrite`), accepting connections,
+/// sending and receiving datagrams and the `ReadExact` / `WriteAll` helpers
+/// all follow this convention. A connect that is still in progress is
+/// reported by `ConnectState`, and a full event loop channel hands the
+/// message back in `NotifyError::Full`.
+pub type NonBlock<T> = Result<Option<T>>;
+
+/// Converts the result of an operation on a non-blocking handle to a
+/// `NonBlock`, for operations that fail with `ErrorKind::WouldBlock`.
+pub trait MapNonBlock<T> {
+    fn map_non_block(self) -> NonBlock<T>;
+}
+
+impl<T> MapNonBlock<T> for Result<T> {
+    fn map_non_block(self) -> NonBlock<T> {
+        match self {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => to_non_block(e),
+        }
+    }
+}
+
 /// A value that may be registered with an `EventLoop`
 pub trait Evented {
     #[doc(hidden)]
@@ -16,66 +45,756 @@ pub trait Evented {
     fn deregister(&self, selector: &mut Selector) -> Result<()>;
 }
 
+/// The outcome of draining a non-blocking source or sink with
+/// `try_read_buf_all` or `try_write_buf_all`.
+///
+/// When using edge-triggered registrations, no further readiness will be
+/// delivered for the handle until `would_block` has been observed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Drain {
+    /// The number of bytes transferred.
+    pub count: usize,
+    /// True when the operation stopped because it would have blocked, i.e.
+    /// the readiness has been fully consumed.
+    pub would_block: bool,
+    /// True when the source reached EOF. Always false for writes.
+    pub eof: bool,
+}
+
+/// Non-blocking reads, implemented for every `Read` type, including the
+/// sockets and pipes of this crate and shared references to them.
+///
+/// A read that would block returns `Ok(None)` instead of failing with
+/// `ErrorKind::WouldBlock`, while `Ok(Some(0))` means EOF.
 pub trait TryRead {
-    fn try_read_buf<B: MutBuf>(&mut self, buf: &mut B) -> Result<Option<usize>>
+    /// Reads into `buf` until the source would block, reaches EOF, or `buf`
+    /// is full. This is the read loop edge-triggered registrations require.
+    ///
+    /// If an error is encountered after some bytes have been read, the error
+    /// is returned; `buf` has still been advanced past the bytes read.
+    fn try_read_buf_all<B: MutBuf>(&mut self, buf: &mut B) -> Result<Drain>
+        where Self : Sized
+    {
+        use std::io::ErrorKind::Interrupted;
+
+        let mut drain = Drain { count: 0, would_block: false, eof: false };
+
+        while buf.has_remaining() {
+            match self.try_read_buf(buf) {
+                Ok(Some(0)) => {
+                    drain.eof = true;
+                    break;
+                }
+                Ok(Some(cnt)) => drain.count += cnt,
+                Ok(None) => {
+                    drain.would_block = true;
+                    break;
+                }
+                Err(ref e) if e.kind() == Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(drain)
+    }
+
+    /// Appends everything the source has available to `buf`, growing it as
+    /// needed, until the source would block or reaches EOF. The returned
+    /// `Drain` tells both cases apart.
+    ///
+    /// If an error is encountered after some bytes have been read, the error
+    /// is returned; the bytes read have still been appended to `buf`.
+    fn try_read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<Drain>
+        where Self : Sized
+    {
+        use std::io::ErrorKind::Interrupted;
+        use std::iter;
+
+        let mut drain = Drain { count: 0, would_block: false, eof: false };
+
+        loop {
+            let len = buf.len();
+
+            if len == buf.capacity() {
+                buf.reserve(cmp::max(len, 32));
+            }
+
+            // Expose the spare capacity, truncated back to the bytes read
+            let cap = buf.capacity();
+            buf.extend(iter::repeat(0).take(cap - len));
+
+            let res = self.try_read(&mut buf[len..]);
+
+            match res {
+                Ok(Some(cnt)) => buf.truncate(len + cnt),
+                _ => buf.truncate(len),
+            }
+
+            match res {
+                Ok(Some(0)) => {
+                    drain.eof = true;
+                    break;
+                }
+                Ok(Some(cnt)) => drain.count += cnt,
+                Ok(None) => {
+                    drain.would_block = true;
+                    break;
+                }
+                Err(ref e) if e.kind() == Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(drain)
+    }
+
+    /// Reads into `buf` and advances it past the bytes read.
+    ///
+    /// The free space of a buffer may be split in several windows, such as
+    /// the end and the start of a `RingBuf` that wrapped around. Reading
+    /// continues into the next window as long as the previous one was
+    /// filled, so that a single call fills all of the free space if the
+    /// source has enough data. Once some bytes have been read, a read that
+    /// would block or fails ends the call with the bytes read so far, the
+    /// error is then reported by the next call.
+    fn try_read_buf<B: MutBuf>(&mut self, buf: &mut B) -> NonBlock<usize>
         where Self : Sized
     {
-        // Reads the length of the slice supplied by buf.mut_bytes into the buffer
         // This is not guaranteed to consume an entire datagram or segment.
         // If your protocol is msg based (instead of continuous stream) you should
         // ensure that your buffer is large enough to hold an entire segment (1532 bytes if not jumbo
         // frames)
-        let res = self.try_read(buf.mut_bytes());
+        let mut total = 0;
 
-        if let Ok(Some(cnt)) = res {
-            buf.advance(cnt);
-        }
+        loop {
+            let (res, window) = {
+                let dst = buf.mut_bytes();
+                let window = dst.len();
+                (self.try_read(dst), window)
+            };
 
-        res
+            match res {
+                Ok(Some(cnt)) => {
+                    buf.advance(cnt);
+                    total += cnt;
+
+                    // EOF, short read, or no space left
+                    if cnt == 0 || cnt < window || !buf.has_remaining() {
+                        return Ok(Some(total));
+                    }
+                }
+                Ok(None) | Err(_) if total > 0 => return Ok(Some(total)),
+                res => return res,
+            }
+        }
     }
 
-    fn try_read(&mut self, buf: &mut [u8]) -> Result<Option<usize>>;
+    /// Reads into `buf`, returning `Ok(None)` if the source would block.
+    fn try_read(&mut self, buf: &mut [u8]) -> NonBlock<usize>;
+
+    /// Returns an adapter reading at most `limit` bytes from this source,
+    /// see `TryTake`.
+    fn try_take(self, limit: usize) -> TryTake<Self>
+        where Self : Sized
+    {
+        TryTake { inner: self, limit: limit }
+    }
 }
 
+/// Non-blocking writes, implemented for every `Write` type, including the
+/// sockets and pipes of this crate and shared references to them.
+///
+/// A write that would block returns `Ok(None)` instead of failing with
+/// `ErrorKind::WouldBlock`.
 pub trait TryWrite {
-    fn try_write_buf<B: Buf>(&mut self, buf: &mut B) -> Result<Option<usize>>
+    /// Writes from `buf` until it is empty or the sink would block. This is
+    /// the write loop edge-triggered registrations require.
+    ///
+    /// If an error is encountered after some bytes have been written, the
+    /// error is returned; `buf` has still been advanced past the bytes
+    /// written.
+    fn try_write_buf_all<B: Buf>(&mut self, buf: &mut B) -> Result<Drain>
         where Self : Sized
     {
-        let res = self.try_write(buf.bytes());
+        use std::io::ErrorKind::{Interrupted, WriteZero};
 
-        if let Ok(Some(cnt)) = res {
-            buf.advance(cnt);
+        let mut drain = Drain { count: 0, would_block: false, eof: false };
+
+        while buf.has_remaining() {
+            match self.try_write_buf(buf) {
+                Ok(Some(0)) => {
+                    return Err(Error::new(WriteZero, "failed to write whole buffer"));
+                }
+                Ok(Some(cnt)) => drain.count += cnt,
+                Ok(None) => {
+                    drain.would_block = true;
+                    break;
+                }
+                Err(ref e) if e.kind() == Interrupted => {}
+                Err(e) => return Err(e),
+            }
         }
 
-        res
+        Ok(drain)
+    }
+
+    /// Writes from `buf` and advances it past the bytes written.
+    ///
+    /// As with `TryRead::try_read_buf`, writing continues with the next
+    /// window of a buffer whose contents are split, such as a `RingBuf`
+    /// that wrapped around, as long as the previous window was written
+    /// entirely.
+    fn try_write_buf<B: Buf>(&mut self, buf: &mut B) -> NonBlock<usize>
+        where Self : Sized
+    {
+        let mut total = 0;
+
+        loop {
+            let (res, window) = {
+                let src = buf.bytes();
+                (self.try_write(src), src.len())
+            };
+
+            match res {
+                Ok(Some(cnt)) => {
+                    buf.advance(cnt);
+                    total += cnt;
+
+                    if cnt == 0 || cnt < window || !buf.has_remaining() {
+                        return Ok(Some(total));
+                    }
+                }
+                Ok(None) | Err(_) if total > 0 => return Ok(Some(total)),
+                res => return res,
+            }
+        }
     }
 
-    fn try_write(&mut self, buf: &[u8]) -> Result<Option<usize>>;
+    /// Writes from `buf`, returning `Ok(None)` if the sink would block.
+    fn try_write(&mut self, buf: &[u8]) -> NonBlock<usize>;
 }
 
 impl<T: Read> TryRead for T {
-    fn try_read(&mut self, dst: &mut [u8]) -> Result<Option<usize>> {
+    fn try_read(&mut self, dst: &mut [u8]) -> NonBlock<usize> {
         self.read(dst)
-            .map(|cnt| Some(cnt))
-            .or_else(to_non_block)
+            .map_non_block()
     }
 }
 
 impl<T: Write> TryWrite for T {
-    fn try_write(&mut self, src: &[u8]) -> Result<Option<usize>> {
+    fn try_write(&mut self, src: &[u8]) -> NonBlock<usize> {
         self.write(src)
-            .map(|cnt| Some(cnt))
-            .or_else(to_non_block)
+            .map_non_block()
+    }
+}
+
+/// Non-blocking sends with flags, for sockets.
+///
+/// As with `TryWrite`, a send that would block returns `Ok(None)`.
+pub trait TrySend : TryWrite {
+    /// Sends from `buf` until it is empty or the socket would block,
+    /// hinting that more bytes follow if `more` is set.
+    ///
+    /// Every window of `buf` but its last is sent with the hint, so that a
+    /// buffer whose contents are split still goes out in full segments.
+    fn try_send_buf_all<B: Buf>(&mut self, buf: &mut B, more: bool) -> Result<Drain>
+        where Self : Sized
+    {
+        use std::io::ErrorKind::{Interrupted, WriteZero};
+
+        let mut drain = Drain { count: 0, would_block: false, eof: false };
+
+        while buf.has_remaining() {
+            let res = {
+                let src = buf.bytes();
+                let more = more || src.len() < buf.remaining();
+
+                self.try_send(src, if more { SendFlags::more() } else { SendFlags::none() })
+            };
+
+            match res {
+                Ok(Some(0)) => {
+                    return Err(Error::new(WriteZero, "failed to write whole buffer"));
+                }
+                Ok(Some(cnt)) => {
+                    buf.advance(cnt);
+                    drain.count += cnt;
+                }
+                Ok(None) => {
+                    drain.would_block = true;
+                    break;
+                }
+                Err(ref e) if e.kind() == Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(drain)
+    }
+
+    /// Sends from `buf` with `flags`, returning `Ok(None)` if the socket
+    /// would block.
+    fn try_send(&mut self, buf: &[u8], flags: SendFlags) -> NonBlock<usize>;
+}
+
+/// The flags of `TrySend::try_send`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SendFlags(u32);
+
+const MORE: u32 = 0b1;
+
+impl SendFlags {
+    #[inline]
+    pub fn none() -> SendFlags {
+        SendFlags(0)
+    }
+
+    /// More bytes follow: the kernel may hold back a partial segment to
+    /// send it along with the next send, as `MSG_MORE` does. Only Linux
+    /// honors it, elsewhere it is ignored.
+    #[inline]
+    pub fn more() -> SendFlags {
+        SendFlags(MORE)
+    }
+
+    #[inline]
+    pub fn is_more(&self) -> bool {
+        self.contains(SendFlags::more())
+    }
+
+    #[inline]
+    pub fn contains(&self, other: SendFlags) -> bool {
+        (*self & other) == other
+    }
+}
+
+impl ops::BitOr for SendFlags {
+    type Output = SendFlags;
+
+    #[inline]
+    fn bitor(self, other: SendFlags) -> SendFlags {
+        SendFlags(self.0 | other.0)
+    }
+}
+
+impl ops::BitAnd for SendFlags {
+    type Output = SendFlags;
+
+    #[inline]
+    fn bitand(self, other: SendFlags) -> SendFlags {
+        SendFlags(self.0 & other.0)
+    }
+}
+
+/// A source limited to a number of bytes, created with `TryRead::try_take`.
+///
+/// Once `limit` bytes have been read, reads report EOF, so that a
+/// length-delimited frame can be read without reading into the next one.
+/// The limit is decremented by every read.
+#[derive(Debug)]
+pub struct TryTake<R> {
+    inner: R,
+    limit: usize,
+}
+
+impl<R> TryTake<R> {
+    /// Returns the number of bytes left before the limit is reached.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: TryRead> TryRead for TryTake<R> {
+    fn try_read(&mut self, buf: &mut [u8]) -> NonBlock<usize> {
+        if self.limit == 0 {
+            return Ok(Some(0));
+        }
+
+        let len = cmp::min(buf.len(), self.limit);
+        let res = self.inner.try_read(&mut buf[..len]);
+
+        if let Ok(Some(cnt)) = res {
+            self.limit -= cnt;
+        }
+
+        res
+    }
+}
+
+/// Non-blocking vectored reads, implemented for every type exposing a raw
+/// fd, including the sockets and pipes of this crate.
+///
+/// As with `TryRead`, a read that would block returns `Ok(None)` and
+/// `Ok(Some(0))` means EOF.
+pub trait TryReadV {
+    /// Reads into `bufs` in order with a single `readv(2)`, filling each
+    /// slice before moving to the next one. `VecMutBuf::mut_bytes_vec`
+    /// exposes the free space of a buffer as such slices.
+    fn try_readv(&mut self, bufs: &mut [IoVecMut]) -> NonBlock<usize>;
+
+    /// Reads into the free space of `buf` with a single `readv(2)` and
+    /// advances it past the bytes read. Unlike `TryRead::try_read_buf`,
+    /// filling a `RingBuf` whose free space wraps around takes one system
+    /// call. Up to `MAX_READ_VECS` regions of free space are read into.
+    fn try_read_buf_vec<B: VecMutBuf>(&mut self, buf: &mut B) -> NonBlock<usize>
+        where Self : Sized
+    {
+        let res = {
+            let mut iovs = [IoVecMut::empty(), IoVecMut::empty(), IoVecMut::empty(), IoVecMut::empty()];
+            let cnt = buf.mut_bytes_vec(&mut iovs);
+
+            self.try_readv(&mut iovs[..cnt])
+        };
+
+        if let Ok(Some(cnt)) = res {
+            MutBuf::advance(buf, cnt);
+        }
+
+        res
+    }
+}
+
+/// Maximum number of regions read into by `TryReadV::try_read_buf_vec`.
+pub const MAX_READ_VECS: usize = 4;
+
+/// Maximum number of regions written from by `TryWriteV::try_write_buf_vec`.
+pub const MAX_WRITE_VECS: usize = 16;
+
+/// Non-blocking vectored writes, implemented for every type exposing a raw
+/// fd, including the sockets and pipes of this crate.
+///
+/// As with `TryWrite`, a write that would block returns `Ok(None)`.
+pub trait TryWriteV {
+    /// Writes from `bufs` in order with a single `writev(2)`.
+    /// `VecBuf::bytes_vec` exposes the bytes of a buffer as such slices.
+    fn try_writev(&mut self, bufs: &[IoVec]) -> NonBlock<usize>;
+
+    /// Writes the bytes of `buf` with a single `writev(2)` and advances it
+    /// past the bytes written, draining a `RingBuf` that wrapped around or a
+    /// `Chain` of buffers in one system call. Up to `MAX_WRITE_VECS` regions
+    /// are written.
+    fn try_write_buf_vec<B: VecBuf>(&mut self, buf: &mut B) -> NonBlock<usize>
+        where Self : Sized
+    {
+        let res = {
+            let mut iovs = [IoVec::empty(); MAX_WRITE_VECS];
+            let cnt = buf.bytes_vec(&mut iovs);
+
+            self.try_writev(&iovs[..cnt])
+        };
+
+        if let Ok(Some(cnt)) = res {
+            Buf::advance(buf, cnt);
+        }
+
+        res
+    }
+
+    /// Writes a sequence of slices with a single `writev(2)`, without first
+    /// copying them into one buffer. Up to `MAX_WRITE_VECS` slices are
+    /// written, the remaining ones are ignored.
+    ///
+    /// Returns how far the sequence was written, so that the next call can
+    /// resume from the first slice not written entirely.
+    fn try_write_slices<'a, I>(&mut self, slices: I) -> NonBlock<SlicesWritten>
+        where I: IntoIterator<Item=&'a [u8]>,
+              Self : Sized
+    {
+        let mut iovs = [IoVec::empty(); MAX_WRITE_VECS];
+        let mut cnt = 0;
+
+        for slice in slices.into_iter().take(MAX_WRITE_VECS) {
+            iovs[cnt] = IoVec::new(slice);
+            cnt += 1;
+        }
+
+        let count = match try!(self.try_writev(&iovs[..cnt])) {
+            Some(count) => count,
+            None => return Ok(None),
+        };
+
+        let mut written = SlicesWritten { count: count, slices: 0, offset: 0 };
+        let mut left = count;
+
+        for iov in &iovs[..cnt] {
+            if left < iov.len() {
+                written.offset = left;
+                break;
+            }
+
+            left -= iov.len();
+            written.slices += 1;
+        }
+
+        Ok(Some(written))
+    }
+}
+
+/// How far a sequence of slices was written by
+/// `TryWriteV::try_write_slices`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SlicesWritten {
+    /// The number of bytes written.
+    pub count: usize,
+    /// The number of slices written entirely.
+    pub slices: usize,
+    /// The number of bytes written from the first slice not written
+    /// entirely.
+    pub offset: usize,
+}
+
+#[cfg(unix)]
+impl<T: AsRawFd> TryReadV for T {
+    fn try_readv(&mut self, bufs: &mut [IoVecMut]) -> NonBlock<usize> {
+        ::sys::readv(self.as_raw_fd(), bufs)
+            .map_non_block()
+    }
+}
+
+#[cfg(unix)]
+impl<T: AsRawFd> TryWriteV for T {
+    fn try_writev(&mut self, bufs: &[IoVec]) -> NonBlock<usize> {
+        ::sys::writev(self.as_raw_fd(), bufs)
+            .map_non_block()
+    }
+}
+
+/*
+ *
+ * ===== ReadExact / WriteAll =====
+ *
+ */
+
+/// Fills a buffer from a non-blocking source across readiness events.
+///
+/// Each call to `read_from` reads as much as is available, remembers how
+/// far the buffer has been filled, and reports whether it is full. This is
+/// the state kept by protocols reading fixed-size headers or frames.
+#[derive(Debug)]
+pub struct ReadExact<T> {
+    buf: T,
+    pos: usize,
+    len: usize,
+}
+
+impl<T: AsMut<[u8]>> ReadExact<T> {
+    /// Reads exactly as many bytes as `buf` holds.
+    pub fn new(mut buf: T) -> ReadExact<T> {
+        let len = buf.as_mut().len();
+        ReadExact { buf: buf, pos: 0, len: len }
+    }
+
+    /// Reads from `src` until the buffer is full or `src` would block.
+    /// Returns `Ok(Some(()))` once the buffer is full, `Ok(None)` if `src`
+    /// would block first.
+    ///
+    /// Reaching EOF before the buffer is full fails with `ErrorKind::Other`.
+    /// The bytes read so far are kept and reading can resume after an error.
+    pub fn read_from<R: TryRead>(&mut self, src: &mut R) -> NonBlock<()> {
+        use std::io::ErrorKind::{Interrupted, Other};
+
+        while self.pos < self.len {
+            let res = src.try_read(&mut self.buf.as_mut()[self.pos..]);
+
+            match res {
+                Ok(Some(0)) => return Err(Error::new(Other, "unexpected EOF")),
+                Ok(Some(cnt)) => self.pos += cnt,
+                Ok(None) => return Ok(None),
+                Err(ref e) if e.kind() == Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Some(()))
+    }
+
+    /// Returns `true` once the buffer is full.
+    pub fn is_complete(&self) -> bool {
+        self.pos == self.len
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the buffer, of which the first `position` bytes were read.
+    pub fn into_inner(self) -> T {
+        self.buf
     }
 }
 
+/// Writes a buffer to a non-blocking sink across readiness events.
+///
+/// Each call to `write_to` writes as much as the sink accepts, remembers how
+/// much of the buffer has been written, and reports whether all of it was.
+#[derive(Debug)]
+pub struct WriteAll<T> {
+    buf: T,
+    pos: usize,
+}
+
+impl<T: AsRef<[u8]>> WriteAll<T> {
+    /// Writes all of `buf`.
+    pub fn new(buf: T) -> WriteAll<T> {
+        WriteAll { buf: buf, pos: 0 }
+    }
+
+    /// Writes to `dst` until the whole buffer is written or `dst` would
+    /// block. Returns `Ok(Some(()))` once the whole buffer has been written,
+    /// `Ok(None)` if `dst` would block first.
+    ///
+    /// A sink accepting no bytes fails with `ErrorKind::WriteZero`. Writing
+    /// can resume after an error.
+    pub fn write_to<W: TryWrite>(&mut self, dst: &mut W) -> NonBlock<()> {
+        use std::io::ErrorKind::{Interrupted, WriteZero};
+
+        while !self.is_complete() {
+            let res = dst.try_write(&self.buf.as_ref()[self.pos..]);
+
+            match res {
+                Ok(Some(0)) => return Err(Error::new(WriteZero, "failed to write whole buffer")),
+                Ok(Some(cnt)) => self.pos += cnt,
+                Ok(None) => return Ok(None),
+                Err(ref e) if e.kind() == Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Some(()))
+    }
+
+    /// Returns `true` once the whole buffer has been written.
+    pub fn is_complete(&self) -> bool {
+        self.pos == self.buf.as_ref().len()
+    }
+
+    /// Returns the number of bytes written so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn into_inner(self) -> T {
+        self.buf
+    }
+}
+
+/*
+ *
+ * ===== Unsupported =====
+ *
+ */
+
+/// The error wrapped in the `io::Error` returned when an operation or option
+/// is not available on the current platform or selector, such as
+/// `set_busy_poll` outside of Linux. Callers that can do without the
+/// operation can tell it apart from genuine failures with `is_unsupported`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Unsupported {
+    desc: &'static str,
+}
+
+impl Unsupported {
+    /// Returns true if `err` reports an unsupported operation.
+    pub fn is_unsupported(err: &Error) -> bool {
+        err.get_ref().map(|e| e.is::<Unsupported>()).unwrap_or(false)
+    }
+
+    /// Returns the message describing the unsupported operation.
+    pub fn desc(&self) -> &'static str {
+        self.desc
+    }
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.desc)
+    }
+}
+
+impl error::Error for Unsupported {
+    fn description(&self) -> &str {
+        self.desc
+    }
+}
+
+/// Returns the error for an unsupported operation, `desc` being the full
+/// message, e.g. "SO_BUSY_POLL is not supported on this platform".
+pub fn unsupported(desc: &'static str) -> Error {
+    Error::new(::std::io::ErrorKind::Other, Unsupported { desc: desc })
+}
+
+/*
+ *
+ * ===== FdLimit =====
+ *
+ */
+
+/// The error wrapped in the `io::Error` returned by `accept` when the process
+/// or the system ran out of file descriptors (`EMFILE` or `ENFILE`).
+///
+/// The pending connection stays queued and the listener stays readable, so
+/// accepting again right away fails the same way and spins the event loop.
+/// Either close a connection with an `FdReserve`, or `EventLoop::pause` the
+/// listener until descriptors are freed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FdLimit {
+    errno: i32,
+}
+
+impl FdLimit {
+    /// Returns true if `err` reports running out of file descriptors.
+    pub fn is_fd_limit(err: &Error) -> bool {
+        err.get_ref().map(|e| e.is::<FdLimit>()).unwrap_or(false)
+    }
+
+    /// Returns the OS error code, `EMFILE` or `ENFILE`.
+    pub fn raw_os_error(&self) -> i32 {
+        self.errno
+    }
+}
+
+impl fmt::Display for FdLimit {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", Error::from_raw_os_error(self.errno))
+    }
+}
+
+impl error::Error for FdLimit {
+    fn description(&self) -> &str {
+        "too many open files"
+    }
+}
+
+/// Returns the error for running out of file descriptors, `errno` being the
+/// OS error code.
+pub fn fd_limit(errno: i32) -> Error {
+    Error::new(::std::io::ErrorKind::Other, FdLimit { errno: errno })
+}
+
 /*
  *
  * ===== Helpers =====
  *
  */
 
-pub fn to_non_block<T>(err: Error) -> Result<Option<T>> {
+pub fn to_non_block<T>(err: Error) -> NonBlock<T> {
     use std::io::ErrorKind::WouldBlock;
 
     if let WouldBlock = err.kind() {
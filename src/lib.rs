@@ -78,10 +78,14 @@
 #![deny(warnings)]
 
 extern crate bytes;
+#[cfg(feature = "bytes04")]
+extern crate bytes04;
 extern crate nix;
 extern crate clock_ticks;
 extern crate slab;
 extern crate libc;
+#[cfg(feature = "fd-tracking")]
+extern crate backtrace;
 
 #[macro_use]
 extern crate log;
@@ -89,67 +93,257 @@ extern crate log;
 #[cfg(test)]
 extern crate env_logger;
 
+pub mod buf;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "fd-tracking")]
+pub mod fd_tracking;
 pub mod util;
+#[cfg(unix)]
+pub mod signal;
 
+mod broadcast;
+mod clock;
+#[cfg(unix)]
+mod composite;
+mod connection;
+mod connector;
+mod deadline;
+mod diagnostics;
+mod error;
 mod event;
 mod event_loop;
+#[cfg(unix)]
+mod file;
+mod frame;
+mod graceful;
 mod handler;
+mod idle;
 mod io;
+mod listener;
+mod metrics;
 mod net;
 mod notify;
+mod offload;
 mod poll;
+mod pool;
+mod rate_limit;
+mod registrar;
+#[cfg(unix)]
+mod relay;
+mod resolver;
+mod selector;
 mod sys;
+mod test_selector;
 mod timer;
 mod token;
+mod token_map;
+mod trace;
+mod transport;
+#[cfg(unix)]
+mod watcher;
+mod write_queue;
 
 pub use buf::{
     Buf,
     MutBuf,
 };
+pub use broadcast::{
+    Broadcast,
+    Progress,
+};
+pub use clock::{
+    Clock,
+    MockClock,
+    SystemClock,
+};
+#[cfg(unix)]
+pub use composite::{
+    Composite,
+};
+pub use connection::{
+    Connection,
+    ConnectionState,
+    Protocol,
+};
+pub use connector::{
+    Connecting,
+    ConnectPolicy,
+    Connector,
+    Dialed,
+};
+pub use deadline::{
+    DeadlineDue,
+    DeadlineMap,
+};
+pub use diagnostics::{
+    Diagnostics,
+    NoDiagnostics,
+};
+pub use error::{
+    Error,
+};
 pub use event::{
     PollOpt,
+    PollOptIter,
     EventSet,
+    EventSetIter,
+    IoEvent,
 };
 pub use event_loop::{
+    channel,
     EventLoop,
     EventLoopConfig,
+    IdleStrategy,
+    Receiver,
     Sender,
 };
+#[cfg(unix)]
+pub use file::{
+    AsyncFile,
+    FileIo,
+    FileOp,
+};
+pub use frame::{
+    FrameReader,
+    FrameWriter,
+};
+pub use graceful::{
+    Graceful,
+    GracefulShutdown,
+};
 pub use handler::{
+    Callback,
     Handler,
 };
+pub use idle::{
+    Idle,
+    IdleTimeouts,
+};
 pub use io::{
     TryRead,
     TryWrite,
+    TryReadV,
+    TrySend,
+    TryTake,
+    TryWriteV,
     Evented,
+    Drain,
+    FdLimit,
+    MapNonBlock,
+    NonBlock,
+    ReadExact,
+    SendFlags,
+    SlicesWritten,
+    Unsupported,
+    WriteAll,
+    MAX_READ_VECS,
+    MAX_WRITE_VECS,
+};
+pub use listener::{
+    PausableListener,
 };
 pub use net::{
     tcp,
     udp,
+    AcceptBatch,
+    AcceptStop,
+    ConnectState,
+    FdReserve,
     IpAddr,
     Ipv4Addr,
     Ipv6Addr,
 };
 #[cfg(unix)]
-pub use net::unix;
+pub use net::{interface, unix};
 
+pub use metrics::{
+    Metrics,
+};
 pub use notify::{
     NotifyError,
+    Priority,
+};
+pub use offload::{
+    Completed,
+    Offload,
 };
 pub use poll::{
     Poll
 };
+pub use pool::{
+    Dispatch,
+    WorkerLoad,
+    WorkerPool,
+};
+pub use rate_limit::{
+    Rate,
+    RateLimiter,
+    Refilled,
+};
+pub use registrar::{
+    Completion,
+    Registrar,
+    RegistrationGuard,
+};
+#[cfg(unix)]
+pub use relay::{
+    Relay,
+    Termination,
+};
+pub use resolver::{
+    Resolved,
+    Resolver,
+    ThreadResolver,
+};
+pub use test_selector::{
+    TestSelector,
+};
 pub use timer::{
+    Scheduler,
     Timeout,
     TimerError,
     TimerResult
 };
 pub use token::{
     Token,
+    MAX_TOKEN,
+};
+pub use token_map::{
+    TokenMap,
+    TokenMapIter,
+    TokenMapIterMut,
+    TokenMapUsage,
+};
+#[cfg(feature = "trace")]
+pub use trace::{
+    Trace,
+};
+pub use transport::{
+    Transport,
+};
+pub use util::{
+    TokenSlab,
+    TokenSlabDrain,
+    TokenSlabIter,
+    TokenSlabIterMut,
+};
+#[cfg(unix)]
+pub use watcher::{
+    FdWatcher,
+    WatchRequests,
+};
+pub use write_queue::{
+    Watermark,
+    WriteQueue,
+};
+pub use selector::{
+    Backend,
+    Selector,
+    SelectorKind,
 };
 pub use sys::{
     Io,
-    Selector,
 };
 
 pub mod prelude {
@@ -159,16 +353,3 @@ pub mod prelude {
         TryWrite,
     };
 }
-
-// Re-export bytes
-pub mod buf {
-    pub use bytes::{
-        Buf,
-        MutBuf,
-        ByteBuf,
-        MutByteBuf,
-        RingBuf,
-        SliceBuf,
-        MutSliceBuf,
-    };
-}
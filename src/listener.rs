@@ -0,0 +1,155 @@
+use {io, EventLoop, Evented, EventSet, Handler, PollOpt, Token};
+use std::fmt;
+
+/// A listener that stops accepting under overload without being closed.
+///
+/// While paused, the listener stays registered under its token with no
+/// interest, so the kernel keeps queueing connections up to the backlog
+/// and `resume` restores the interest and options it was registered with.
+///
+/// Besides `pause` and `resume`, the listener pauses itself when the number
+/// of pending connections reaches the high-water mark set with
+/// `set_watermarks`, and resumes once it drops to the low-water mark.
+/// Report each accepted connection with `opened`, and each connection done
+/// with `closed`. A manual `pause` is only undone by `resume`.
+pub struct PausableListener<L> {
+    listener: L,
+    token: Token,
+    registered: bool,
+    manual: bool,
+    overloaded: bool,
+    paused: bool,
+    pending: usize,
+    // High and low-water marks
+    watermarks: Option<(usize, usize)>,
+}
+
+impl<L: Evented> PausableListener<L> {
+    pub fn new(listener: L, token: Token) -> PausableListener<L> {
+        PausableListener {
+            listener: listener,
+            token: token,
+            registered: false,
+            manual: false,
+            overloaded: false,
+            paused: false,
+            pending: 0,
+            watermarks: None,
+        }
+    }
+
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    pub fn get_ref(&self) -> &L {
+        &self.listener
+    }
+
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.listener
+    }
+
+    /// Registers the listener as readable with `opts`.
+    pub fn register<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, opts: PollOpt) -> io::Result<()> {
+        try!(event_loop.register_opt(&self.listener, self.token, EventSet::readable(), opts));
+
+        self.registered = true;
+        self.paused = false;
+        self.update(event_loop)
+    }
+
+    pub fn deregister<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        self.registered = false;
+        self.paused = false;
+        event_loop.deregister(&self.listener)
+    }
+
+    /// Enables automatic pausing once `high` connections are pending, and
+    /// resuming once `low` are.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `low` is greater than or equal to `high`.
+    pub fn set_watermarks<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, high: usize, low: usize) -> io::Result<()> {
+        assert!(low < high, "low-water mark must be below the high-water mark");
+
+        self.watermarks = Some((high, low));
+        self.update(event_loop)
+    }
+
+    /// Disables automatic pausing.
+    pub fn clear_watermarks<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        self.watermarks = None;
+        self.update(event_loop)
+    }
+
+    /// Stops accepting until `resume` is called.
+    pub fn pause<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        self.manual = true;
+        self.update(event_loop)
+    }
+
+    /// Undoes `pause`. The listener stays paused while over the high-water
+    /// mark.
+    pub fn resume<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        self.manual = false;
+        self.update(event_loop)
+    }
+
+    /// Returns true if readiness is not reported for the listener.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Returns the number of pending connections, opened and not closed.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Records that a connection was accepted.
+    pub fn opened<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        self.pending += 1;
+        self.update(event_loop)
+    }
+
+    /// Records that an accepted connection is done.
+    pub fn closed<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        self.pending = self.pending.saturating_sub(1);
+        self.update(event_loop)
+    }
+
+    // Pauses or resumes the listener to match the manual state and the
+    // watermarks
+    fn update<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        match self.watermarks {
+            Some((high, _)) if self.pending >= high => self.overloaded = true,
+            Some((_, low)) if self.pending <= low => self.overloaded = false,
+            // Between the marks, stay as is
+            Some(..) => {}
+            None => self.overloaded = false,
+        }
+
+        let paused = self.registered && (self.manual || self.overloaded);
+
+        if paused == self.paused {
+            return Ok(());
+        }
+
+        if paused {
+            try!(event_loop.pause(&self.listener, self.token));
+        } else {
+            try!(event_loop.rearm(&self.listener, self.token));
+        }
+
+        self.paused = paused;
+        Ok(())
+    }
+}
+
+impl<L: fmt::Debug> fmt::Debug for PausableListener<L> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "PausableListener {{ listener: {:?}, token: {:?}, paused: {}, pending: {} }}",
+               self.listener, self.token, self.paused, self.pending)
+    }
+}
@@ -0,0 +1,41 @@
+/// A snapshot of the event loop's metrics, see `EventLoop::metrics`.
+///
+/// Counters accumulate from the creation of the event loop, or from the last
+/// call to `EventLoop::reset_metrics`. Metrics are only collected when
+/// `EventLoopConfig::metrics` is set.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Number of ticks of the event loop.
+    pub ticks: u64,
+    /// Number of IO events dispatched to the handler.
+    pub io_events: u64,
+    /// Largest number of IO events dispatched in a single tick.
+    pub max_io_events_per_tick: usize,
+    /// Time spent blocked polling for IO events, in nanoseconds.
+    pub poll_wait_ns: u64,
+    /// Time spent dispatching IO events, messages and timeouts, in
+    /// nanoseconds.
+    pub dispatch_ns: u64,
+    /// Number of timeouts fired.
+    pub timeouts_fired: u64,
+    /// Number of messages delivered to the handler.
+    pub messages: u64,
+    /// Number of times the buffers the event loop reuses from one tick to
+    /// the next were reallocated: the events and the deferred messages.
+    /// Once they fit the load, a tick allocates nothing and this stops
+    /// increasing.
+    pub buffer_reallocations: u64,
+    /// Number of waits for IO events that blocked in the selector, after
+    /// spinning without finding any, if spinning.
+    pub idle_blocks: u64,
+    /// Number of waits for IO events that found events while spinning or
+    /// yielding, without blocking, see `IdleStrategy`.
+    pub idle_spin_hits: u64,
+
+    // == Gauges, sampled when the snapshot is taken ==
+
+    /// Number of timeouts currently scheduled.
+    pub timeouts_pending: usize,
+    /// Number of messages waiting in the notify queue.
+    pub notify_queue_depth: usize,
+}
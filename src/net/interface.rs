@@ -0,0 +1,12 @@
+//! Network interfaces of the host.
+//!
+//! The interface index is what the IPv6 multicast and scope APIs take, see
+//! `UdpSocket::join_multicast_on`, and the name is what
+//! `TcpSocket::bind_device` takes.
+
+pub use sys::interface::{
+    Interface,
+    index,
+    interface,
+    interfaces,
+};
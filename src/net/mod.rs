@@ -1,17 +1,95 @@
 //! Networking primitives
 //!
+use io;
+use std::io::ErrorKind;
 use std::net::AddrParseError;
 use std::str::FromStr;
 
 pub mod tcp;
 pub mod udp;
 
+#[cfg(unix)]
+pub mod interface;
 #[cfg(unix)]
 pub mod unix;
 
+#[cfg(unix)]
+pub use sys::FdReserve;
+
+/// The outcome of starting a non-blocking connect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectState {
+    /// The connection was established right away, which is common with
+    /// local addresses.
+    Connected,
+    /// The connection is being established. Wait for the stream to become
+    /// writable, then call `finish_connect` to learn whether it succeeded.
+    InProgress,
+}
+
+impl ConnectState {
+    pub fn is_connected(&self) -> bool {
+        *self == ConnectState::Connected
+    }
+}
+
+/// Why `accept_batch` stopped accepting.
+#[derive(Debug)]
+pub enum AcceptStop {
+    /// The batch limit was reached, more connections may be pending.
+    Limit,
+    /// The backlog is drained.
+    WouldBlock,
+    /// The process or the system ran out of file descriptors, see
+    /// `FdLimit`. Stop accepting until descriptors are freed, or shed the
+    /// pending connections with `accept_reserved`.
+    FdLimit(io::Error),
+    /// Any other error.
+    Error(io::Error),
+}
+
+/// The connections accepted by `accept_batch`, and why it stopped.
+#[derive(Debug)]
+pub struct AcceptBatch<S> {
+    pub accepted: Vec<S>,
+    pub stopped: AcceptStop,
+}
+
+// Accepts with `accept` until `limit` connections are accepted or it fails.
+// Connections aborted by the peer before being accepted are skipped.
+fn accept_batch<S, F>(limit: usize, mut accept: F) -> AcceptBatch<S>
+    where F: FnMut() -> io::NonBlock<S>
+{
+    let mut accepted = Vec::new();
+
+    while accepted.len() < limit {
+        let stopped = match accept() {
+            Ok(Some(sock)) => {
+                accepted.push(sock);
+                continue;
+            }
+            Ok(None) => AcceptStop::WouldBlock,
+            Err(ref err) if err.kind() == ErrorKind::ConnectionAborted => continue,
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => {
+                if io::FdLimit::is_fd_limit(&err) {
+                    AcceptStop::FdLimit(err)
+                } else {
+                    AcceptStop::Error(err)
+                }
+            }
+        };
+
+        return AcceptBatch { accepted: accepted, stopped: stopped };
+    }
+
+    AcceptBatch { accepted: accepted, stopped: AcceptStop::Limit }
+}
+
 /// An IP address, either a IPv4 or IPv6 address.
 ///
 /// Once `std::net::IpAddr` is stable, this will go away.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum IpAddr {
     V4(Ipv4Addr),
     V6(Ipv6Addr),
@@ -1,4 +1,5 @@
-use {io, sys, Evented, EventSet, PollOpt, Selector, Token};
+use {io, net, sys, Evented, EventSet, MapNonBlock, PollOpt, Selector, SendFlags, Token, Transport, TrySend};
+use net::ConnectState;
 use std::io::{Read, Write};
 use std::net::SocketAddr;
 
@@ -24,9 +25,15 @@ impl TcpSocket {
         sys::TcpSocket::v6().map(From::from)
     }
 
-    pub fn connect(self, addr: &SocketAddr) -> io::Result<(TcpStream, bool)> {
-        let complete = try!(self.sys.connect(addr));
-        Ok((From::from(self.sys), complete))
+    /// Starts connecting the socket to `addr`, returning the stream along
+    /// with whether the connection is already established.
+    ///
+    /// When it is `ConnectState::InProgress`, register the stream for
+    /// writable events and call `TcpStream::finish_connect` once it is
+    /// writable.
+    pub fn connect(self, addr: &SocketAddr) -> io::Result<(TcpStream, ConnectState)> {
+        let state = try!(self.sys.connect(addr));
+        Ok((From::from(self.sys), state))
     }
 
     pub fn bind(&self, addr: &SocketAddr) -> io::Result<()> {
@@ -51,6 +58,21 @@ impl TcpSocket {
             .map(From::from)
     }
 
+    /// Switches the socket between non-blocking and blocking mode, for
+    /// example to hand a connection off to a thread doing blocking IO. Mio
+    /// sockets are non-blocking, switch them back before registering them
+    /// with an event loop again.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.sys.set_nonblocking(nonblocking)
+    }
+
+    /// Lets programs executed by the process inherit the socket. Mio creates
+    /// every descriptor close-on-exec, atomically where the platform allows
+    /// it, so that sockets do not leak into child processes.
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.sys.set_inheritable(inheritable)
+    }
+
     /*
      *
      * ===== Socket Options =====
@@ -69,6 +91,21 @@ impl TcpSocket {
         self.sys.set_nodelay(nodelay)
     }
 
+    /// Sets the `SO_BUSY_POLL` socket option: when receiving, the kernel
+    /// busy polls the device queue for up to `usecs` microseconds before
+    /// sleeping. Only supported on Linux.
+    pub fn set_busy_poll(&self, usecs: u32) -> io::Result<()> {
+        self.sys.set_busy_poll(usecs)
+    }
+
+    /// Restricts the socket to the network interface named `interface`,
+    /// see `interface::interfaces`, or removes the restriction if it is
+    /// empty. Sets `SO_BINDTODEVICE`, which is only supported on Linux and
+    /// usually requires `CAP_NET_RAW`.
+    pub fn bind_device(&self, interface: &str) -> io::Result<()> {
+        self.sys.bind_device(interface)
+    }
+
     pub fn set_keepalive(&self, seconds: Option<u32>) -> io::Result<()> {
         self.sys.set_keepalive(seconds)
     }
@@ -115,6 +152,16 @@ pub enum Shutdown {
     Both,
 }
 
+impl From<::std::net::Shutdown> for Shutdown {
+    fn from(how: ::std::net::Shutdown) -> Shutdown {
+        match how {
+            ::std::net::Shutdown::Read => Shutdown::Read,
+            ::std::net::Shutdown::Write => Shutdown::Write,
+            ::std::net::Shutdown::Both => Shutdown::Both,
+        }
+    }
+}
+
 impl TcpStream {
     pub fn connect(addr: &SocketAddr) -> io::Result<TcpStream> {
         let sock = try!(match *addr {
@@ -126,10 +173,30 @@ impl TcpStream {
             .map(|(stream, _)| stream)
     }
 
+    /// Takes over a connected std `TcpStream`, for example one connected
+    /// with a timeout or handed over by a blocking library, switching it to
+    /// non-blocking mode.
+    pub fn from_stream(stream: ::std::net::TcpStream) -> io::Result<TcpStream> {
+        sys::TcpSocket::from_std(stream)
+            .map(From::from)
+    }
+
+    /// Hands the connection over to a std `TcpStream`, switched back to
+    /// blocking mode. The stream is deregistered, as if it was dropped.
+    ///
+    /// Handles created with `try_clone` share the blocking mode and are
+    /// switched too.
+    pub fn into_stream(self) -> io::Result<::std::net::TcpStream> {
+        self.sys.into_std()
+    }
+
+    /// Returns the address of the peer, cached after the first call.
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
         self.sys.peer_addr()
     }
 
+    /// Returns the local address, cached after the first call once the
+    /// stream is connected.
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         self.sys.local_addr()
     }
@@ -138,6 +205,15 @@ impl TcpStream {
         self.sys.try_clone()
             .map(From::from)
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.sys.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.sys.set_inheritable(inheritable)
+    }
+
     pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
         self.sys.shutdown(how)
     }
@@ -146,6 +222,13 @@ impl TcpStream {
         self.sys.set_nodelay(nodelay)
     }
 
+    /// Sets the `SO_BUSY_POLL` socket option: when receiving, the kernel
+    /// busy polls the device queue for up to `usecs` microseconds before
+    /// sleeping. Only supported on Linux.
+    pub fn set_busy_poll(&self, usecs: u32) -> io::Result<()> {
+        self.sys.set_busy_poll(usecs)
+    }
+
     pub fn set_keepalive(&self, seconds: Option<u32>) -> io::Result<()> {
         self.sys.set_keepalive(seconds)
     }
@@ -153,6 +236,19 @@ impl TcpStream {
     pub fn take_socket_error(&self) -> io::Result<()> {
         self.sys.take_socket_error()
     }
+
+    /// Completes a connect that returned `ConnectState::InProgress`, once the
+    /// stream is writable. Returns the error the connection failed with,
+    /// taken from `SO_ERROR`, such as `ErrorKind::ConnectionRefused`.
+    ///
+    /// Returns `ConnectState::InProgress` when the writable event was
+    /// spurious and the connection is still being established: keep waiting
+    /// for the next writable event, no need to connect again. A failed
+    /// connect reported to `Handler::error` has its `SO_ERROR` consumed
+    /// already.
+    pub fn finish_connect(&self) -> io::Result<ConnectState> {
+        self.sys.finish_connect()
+    }
 }
 
 impl Read for TcpStream {
@@ -171,6 +267,36 @@ impl Write for TcpStream {
     }
 }
 
+impl<'a> Read for &'a TcpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.sys).read(buf)
+    }
+}
+
+impl<'a> Write for &'a TcpStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.sys).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.sys).flush()
+    }
+}
+
+impl TrySend for TcpStream {
+    fn try_send(&mut self, buf: &[u8], flags: SendFlags) -> io::NonBlock<usize> {
+        self.sys.send(buf, flags.is_more())
+            .map_non_block()
+    }
+}
+
+impl<'a> TrySend for &'a TcpStream {
+    fn try_send(&mut self, buf: &[u8], flags: SendFlags) -> io::NonBlock<usize> {
+        self.sys.send(buf, flags.is_more())
+            .map_non_block()
+    }
+}
+
 impl Evented for TcpStream {
     fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
         self.sys.register(selector, token, interest, opts)
@@ -185,6 +311,12 @@ impl Evented for TcpStream {
     }
 }
 
+impl Transport for TcpStream {
+    fn finish_connect(&self) -> io::Result<ConnectState> {
+        TcpStream::finish_connect(self)
+    }
+}
+
 impl From<sys::TcpSocket> for TcpStream {
     fn from(sys: sys::TcpSocket) -> TcpStream {
         TcpStream { sys: sys }
@@ -220,17 +352,58 @@ impl TcpListener {
         sock.listen(1024)
     }
 
+    /// Takes over a bound std `TcpListener`, switching it to non-blocking
+    /// mode.
+    pub fn from_listener(listener: ::std::net::TcpListener) -> io::Result<TcpListener> {
+        sys::TcpSocket::from_std(listener)
+            .map(From::from)
+    }
+
+    /// Hands the socket over to a std `TcpListener`, switched back to
+    /// blocking mode. The listener is deregistered, as if it was dropped.
+    pub fn into_listener(self) -> io::Result<::std::net::TcpListener> {
+        self.sys.into_std()
+    }
+
     /// Accepts a new `TcpStream`.
     ///
     /// Returns a `Ok(None)` when the socket `WOULDBLOCK`, this means the stream will be ready at
     /// a later point.
-    pub fn accept(&self) -> io::Result<Option<TcpStream>> {
+    pub fn accept(&self) -> io::NonBlock<TcpStream> {
         self.sys.accept()
             .map(|opt| {
                 opt.map(|sys| TcpStream { sys: sys })
             })
     }
 
+    /// Accepts up to `limit` connections, until the backlog is drained or
+    /// accepting fails, and returns them along with why it stopped.
+    /// Connections aborted before being accepted are skipped.
+    ///
+    /// A level-triggered listener is reported readable again while
+    /// connections are pending, so bounding the batch per event does not
+    /// leave them behind. An edge-triggered one is not, and must keep
+    /// accepting until `AcceptStop::WouldBlock`.
+    pub fn accept_batch(&self, limit: usize) -> net::AcceptBatch<TcpStream> {
+        net::accept_batch(limit, || self.accept())
+    }
+
+    /// Accepts a new `TcpStream`, shedding the pending connection with
+    /// `reserve` when the process ran out of file descriptors.
+    ///
+    /// The `FdLimit` error is still returned after the connection is closed,
+    /// so that the caller can stop accepting until descriptors are freed.
+    #[cfg(unix)]
+    pub fn accept_reserved(&self, reserve: &mut net::FdReserve) -> io::NonBlock<TcpStream> {
+        self.accept().or_else(|err| {
+            if io::FdLimit::is_fd_limit(&err) {
+                try!(reserve.reject(self));
+            }
+
+            Err(err)
+        })
+    }
+
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         self.sys.local_addr()
     }
@@ -239,6 +412,14 @@ impl TcpListener {
         self.sys.try_clone()
             .map(From::from)
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.sys.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.sys.set_inheritable(inheritable)
+    }
 }
 
 impl From<sys::TcpSocket> for TcpListener {
@@ -268,7 +449,7 @@ impl Evented for TcpListener {
  */
 
 #[cfg(unix)]
-use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
 
 #[cfg(unix)]
 impl AsRawFd for TcpSocket {
@@ -284,6 +465,13 @@ impl FromRawFd for TcpSocket {
     }
 }
 
+#[cfg(unix)]
+impl IntoRawFd for TcpSocket {
+    fn into_raw_fd(self) -> RawFd {
+        self.sys.into_raw_fd()
+    }
+}
+
 #[cfg(unix)]
 impl AsRawFd for TcpStream {
     fn as_raw_fd(&self) -> RawFd {
@@ -298,6 +486,13 @@ impl FromRawFd for TcpStream {
     }
 }
 
+#[cfg(unix)]
+impl IntoRawFd for TcpStream {
+    fn into_raw_fd(self) -> RawFd {
+        self.sys.into_raw_fd()
+    }
+}
+
 #[cfg(unix)]
 impl AsRawFd for TcpListener {
     fn as_raw_fd(&self) -> RawFd {
@@ -311,3 +506,10 @@ impl FromRawFd for TcpListener {
         TcpListener { sys: FromRawFd::from_raw_fd(fd) }
     }
 }
+
+#[cfg(unix)]
+impl IntoRawFd for TcpListener {
+    fn into_raw_fd(self) -> RawFd {
+        self.sys.into_raw_fd()
+    }
+}
@@ -1,5 +1,6 @@
 use {io, sys, Evented, EventSet, IpAddr, PollOpt, Selector, Token};
 use buf::{Buf, MutBuf};
+use net::interface::Interface;
 use std::net::SocketAddr;
 
 #[derive(Debug)]
@@ -33,6 +34,18 @@ impl UdpSocket {
         Ok(sock)
     }
 
+    /// Takes over a std `UdpSocket`, switching it to non-blocking mode.
+    pub fn from_socket(socket: ::std::net::UdpSocket) -> io::Result<UdpSocket> {
+        sys::UdpSocket::from_std(socket)
+            .map(From::from)
+    }
+
+    /// Hands the socket over to a std `UdpSocket`, switched back to blocking
+    /// mode. The socket is deregistered, as if it was dropped.
+    pub fn into_socket(self) -> io::Result<::std::net::UdpSocket> {
+        self.sys.into_std()
+    }
+
     pub fn bind(&self, addr: &SocketAddr) -> io::Result<()> {
         self.sys.bind(addr)
     }
@@ -46,14 +59,29 @@ impl UdpSocket {
             .map(From::from)
     }
 
-    pub fn send_to<B: Buf>(&self, buf: &mut B, target: &SocketAddr) -> io::Result<Option<()>> {
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.sys.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.sys.set_inheritable(inheritable)
+    }
+
+    pub fn send_to<B: Buf>(&self, buf: &mut B, target: &SocketAddr) -> io::NonBlock<()> {
         self.sys.send_to(buf, target)
     }
 
-    pub fn recv_from<B: MutBuf>(&self, buf: &mut B) -> io::Result<Option<SocketAddr>> {
+    pub fn recv_from<B: MutBuf>(&self, buf: &mut B) -> io::NonBlock<SocketAddr> {
         self.sys.recv_from(buf)
     }
 
+    /// Sets the `SO_BUSY_POLL` socket option: when receiving, the kernel
+    /// busy polls the device queue for up to `usecs` microseconds before
+    /// sleeping. Only supported on Linux.
+    pub fn set_busy_poll(&self, usecs: u32) -> io::Result<()> {
+        self.sys.set_busy_poll(usecs)
+    }
+
     pub fn set_broadcast(&self, on: bool) -> io::Result<()> {
         self.sys.set_broadcast(on)
     }
@@ -63,11 +91,30 @@ impl UdpSocket {
     }
 
     pub fn join_multicast(&self, multi: &IpAddr) -> io::Result<()> {
-        self.sys.join_multicast(multi)
+        self.sys.join_multicast(multi, None)
     }
 
     pub fn leave_multicast(&self, multi: &IpAddr) -> io::Result<()> {
-        self.sys.leave_multicast(multi)
+        self.sys.leave_multicast(multi, None)
+    }
+
+    /// Joins the multicast group `multi` on `interface` rather than on the
+    /// interface chosen by the kernel. IPv4 groups are joined through the
+    /// first IPv4 address of the interface, and fail if it has none.
+    pub fn join_multicast_on(&self, multi: &IpAddr, interface: &Interface) -> io::Result<()> {
+        self.sys.join_multicast(multi, Some(interface))
+    }
+
+    /// Leaves a multicast group joined with `join_multicast_on`.
+    pub fn leave_multicast_on(&self, multi: &IpAddr, interface: &Interface) -> io::Result<()> {
+        self.sys.leave_multicast(multi, Some(interface))
+    }
+
+    /// Restricts the socket to the network interface named `interface`, or
+    /// removes the restriction if it is empty. See
+    /// `TcpSocket::bind_device`.
+    pub fn bind_device(&self, interface: &str) -> io::Result<()> {
+        self.sys.bind_device(interface)
     }
 
     pub fn set_multicast_time_to_live(&self, ttl: i32) -> io::Result<()> {
@@ -102,7 +149,7 @@ impl From<sys::UdpSocket> for UdpSocket {
  */
 
 #[cfg(unix)]
-use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
 
 #[cfg(unix)]
 impl AsRawFd for UdpSocket {
@@ -117,3 +164,10 @@ impl FromRawFd for UdpSocket {
         UdpSocket { sys: FromRawFd::from_raw_fd(fd) }
     }
 }
+
+#[cfg(unix)]
+impl IntoRawFd for UdpSocket {
+    fn into_raw_fd(self) -> RawFd {
+        self.sys.into_raw_fd()
+    }
+}
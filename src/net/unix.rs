@@ -1,6 +1,10 @@
-use {io, sys, Evented, EventSet, Io, PollOpt, Selector, Token};
+use {io, net, selector, sys, Error, Evented, EventSet, Io, PollOpt, Selector, Token, Transport};
+use net::ConnectState;
+use std::{mem, ptr};
 use std::io::{Read, Write};
 use std::path::Path;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
 
 #[derive(Debug)]
 pub struct UnixSocket {
@@ -15,9 +19,9 @@ impl UnixSocket {
     }
 
     /// Connect the socket to the specified address
-    pub fn connect<P: AsRef<Path> + ?Sized>(self, addr: &P) -> io::Result<(UnixStream, bool)> {
-        let complete = try!(self.sys.connect(addr));
-        Ok((From::from(self.sys), complete))
+    pub fn connect<P: AsRef<Path> + ?Sized>(self, addr: &P) -> io::Result<(UnixStream, ConnectState)> {
+        let state = try!(self.sys.connect(addr));
+        Ok((From::from(self.sys), state))
     }
 
     /// Bind the socket to the specified address
@@ -35,6 +39,14 @@ impl UnixSocket {
         self.sys.try_clone()
             .map(From::from)
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.sys.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.sys.set_inheritable(inheritable)
+    }
 }
 
 impl Evented for UnixSocket {
@@ -79,6 +91,20 @@ impl UnixStream {
         self.sys.try_clone()
             .map(From::from)
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.sys.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.sys.set_inheritable(inheritable)
+    }
+
+    /// Completes a connect that returned `ConnectState::InProgress`, once the
+    /// stream is writable. See `TcpStream::finish_connect`.
+    pub fn finish_connect(&self) -> io::Result<ConnectState> {
+        self.sys.finish_connect()
+    }
 }
 
 impl Read for UnixStream {
@@ -97,6 +123,22 @@ impl Write for UnixStream {
     }
 }
 
+impl<'a> Read for &'a UnixStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.sys).read(buf)
+    }
+}
+
+impl<'a> Write for &'a UnixStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.sys).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.sys).flush()
+    }
+}
+
 impl Evented for UnixStream {
     fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
         self.sys.register(selector, token, interest, opts)
@@ -111,6 +153,12 @@ impl Evented for UnixStream {
     }
 }
 
+impl Transport for UnixStream {
+    fn finish_connect(&self) -> io::Result<ConnectState> {
+        UnixStream::finish_connect(self)
+    }
+}
+
 impl From<sys::UnixSocket> for UnixStream {
     fn from(sys: sys::UnixSocket) -> UnixStream {
         UnixStream { sys: sys }
@@ -136,15 +184,42 @@ impl UnixListener {
         })
     }
 
-    pub fn accept(&self) -> io::Result<Option<UnixStream>> {
+    pub fn accept(&self) -> io::NonBlock<UnixStream> {
         self.sys.accept()
             .map(|opt| opt.map(From::from))
     }
 
+    /// Accepts up to `limit` connections, see
+    /// `TcpListener::accept_batch`.
+    pub fn accept_batch(&self, limit: usize) -> net::AcceptBatch<UnixStream> {
+        net::accept_batch(limit, || self.accept())
+    }
+
+    /// Accepts a new `UnixStream`, shedding the pending connection with
+    /// `reserve` when the process ran out of file descriptors. See
+    /// `TcpListener::accept_reserved`.
+    pub fn accept_reserved(&self, reserve: &mut net::FdReserve) -> io::NonBlock<UnixStream> {
+        self.accept().or_else(|err| {
+            if io::FdLimit::is_fd_limit(&err) {
+                try!(reserve.reject(self));
+            }
+
+            Err(err)
+        })
+    }
+
     pub fn try_clone(&self) -> io::Result<UnixListener> {
         self.sys.try_clone()
             .map(From::from)
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.sys.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.sys.set_inheritable(inheritable)
+    }
 }
 
 impl Evented for UnixListener {
@@ -183,12 +258,32 @@ pub struct PipeReader {
     io: Io,
 }
 
+impl PipeReader {
+    pub fn try_clone(&self) -> io::Result<PipeReader> {
+        self.io.try_clone().map(From::from)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.io.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.io.set_inheritable(inheritable)
+    }
+}
+
 impl Read for PipeReader {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.io.read(buf)
     }
 }
 
+impl<'a> Read for &'a PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.io).read(buf)
+    }
+}
+
 impl Evented for PipeReader {
     fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
         self.io.register(selector, token, interest, opts)
@@ -214,6 +309,20 @@ pub struct PipeWriter {
     io: Io,
 }
 
+impl PipeWriter {
+    pub fn try_clone(&self) -> io::Result<PipeWriter> {
+        self.io.try_clone().map(From::from)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.io.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.io.set_inheritable(inheritable)
+    }
+}
+
 impl Write for PipeWriter {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.io.write(buf)
@@ -224,6 +333,16 @@ impl Write for PipeWriter {
     }
 }
 
+impl<'a> Write for &'a PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.io).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.io).flush()
+    }
+}
+
 impl Evented for PipeWriter {
     fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
         self.io.register(selector, token, interest, opts)
@@ -244,13 +363,140 @@ impl From<Io> for PipeWriter {
     }
 }
 
+/*
+ *
+ * ===== EventedFd =====
+ *
+ */
+
+/// Registers an fd owned by other code, such as the connection of a
+/// database client library, with an `EventLoop`.
+///
+/// The fd should be in non-blocking mode: readiness is only a hint, a read
+/// may still block after a spurious event. With edge-triggered
+/// registrations, readiness is only reported again once an operation
+/// returned `WouldBlock`. Switching the fd to non-blocking mode is left to
+/// the owner, mio does not change its flags.
+///
+/// The fd must be deregistered before it is closed.
+#[derive(Copy, Clone, Debug)]
+pub struct EventedFd<'a>(pub &'a RawFd);
+
+impl<'a> Evented for EventedFd<'a> {
+    fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        selector.register(*self.0, token, interest, opts)
+    }
+
+    fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        selector.reregister(*self.0, token, interest, opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+        selector.deregister(*self.0)
+    }
+}
+
+/// Takes ownership of a handle exposing an fd, such as a `File` or the
+/// client of a C library, to register it with an `EventLoop`.
+///
+/// Like the sockets of this crate, the handle can only be registered with
+/// one selector at a time and is deregistered when dropped. The same
+/// non-blocking expectations as `EventedFd` apply.
+#[derive(Debug)]
+pub struct EventedIo<T: AsRawFd> {
+    inner: T,
+    // Id of the selector the fd is registered with, 0 if none
+    owner: AtomicUsize,
+}
+
+impl<T: AsRawFd> EventedIo<T> {
+    pub fn new(inner: T) -> EventedIo<T> {
+        EventedIo {
+            inner: inner,
+            owner: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Returns the handle, deregistered as if it was dropped.
+    pub fn into_inner(self) -> T {
+        self.release();
+
+        let inner = unsafe { ptr::read(&self.inner) };
+        mem::forget(self);
+        inner
+    }
+
+    fn release(&self) {
+        let owner = self.owner.load(Relaxed);
+
+        if owner != 0 {
+            selector::release(owner, self.inner.as_raw_fd());
+        }
+    }
+
+    fn check_owner(&self, selector: &Selector) -> io::Result<()> {
+        let owner = self.owner.load(Relaxed);
+
+        if owner != 0 && owner != selector.id() {
+            return Err(From::from(Error::RegisteredElsewhere));
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: AsRawFd> Evented for EventedIo<T> {
+    fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        if !selector.is_transfer() {
+            try!(self.check_owner(selector));
+        }
+
+        try!(selector.register(self.inner.as_raw_fd(), token, interest, opts));
+        self.owner.store(selector.id(), Relaxed);
+        Ok(())
+    }
+
+    fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        try!(self.check_owner(selector));
+        selector.reregister(self.inner.as_raw_fd(), token, interest, opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+        try!(self.check_owner(selector));
+        try!(selector.deregister(self.inner.as_raw_fd()));
+        self.owner.store(0, Relaxed);
+        Ok(())
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for EventedIo<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<T: AsRawFd> Drop for EventedIo<T> {
+    fn drop(&mut self) {
+        // The handle closes the fd once dropped, if it owns it
+        self.release();
+    }
+}
+
 /*
  *
  * ===== Conversions =====
  *
  */
 
-use std::os::unix::io::{RawFd, AsRawFd, FromRawFd};
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd};
 
 impl AsRawFd for UnixSocket {
     fn as_raw_fd(&self) -> RawFd {
@@ -264,6 +510,12 @@ impl FromRawFd for UnixSocket {
     }
 }
 
+impl IntoRawFd for UnixSocket {
+    fn into_raw_fd(self) -> RawFd {
+        self.sys.into_raw_fd()
+    }
+}
+
 impl AsRawFd for UnixStream {
     fn as_raw_fd(&self) -> RawFd {
         self.sys.as_raw_fd()
@@ -276,6 +528,12 @@ impl FromRawFd for UnixStream {
     }
 }
 
+impl IntoRawFd for UnixStream {
+    fn into_raw_fd(self) -> RawFd {
+        self.sys.into_raw_fd()
+    }
+}
+
 impl AsRawFd for UnixListener {
     fn as_raw_fd(&self) -> RawFd {
         self.sys.as_raw_fd()
@@ -288,6 +546,12 @@ impl FromRawFd for UnixListener {
     }
 }
 
+impl IntoRawFd for UnixListener {
+    fn into_raw_fd(self) -> RawFd {
+        self.sys.into_raw_fd()
+    }
+}
+
 impl AsRawFd for PipeReader {
     fn as_raw_fd(&self) -> RawFd {
         self.io.as_raw_fd()
@@ -300,6 +564,12 @@ impl FromRawFd for PipeReader {
     }
 }
 
+impl IntoRawFd for PipeReader {
+    fn into_raw_fd(self) -> RawFd {
+        self.io.into_raw_fd()
+    }
+}
+
 impl AsRawFd for PipeWriter {
     fn as_raw_fd(&self) -> RawFd {
         self.io.as_raw_fd()
@@ -311,3 +581,9 @@ impl FromRawFd for PipeWriter {
         PipeWriter { io: FromRawFd::from_raw_fd(fd) }
     }
 }
+
+impl IntoRawFd for PipeWriter {
+    fn into_raw_fd(self) -> RawFd {
+        self.io.into_raw_fd()
+    }
+}
@@ -2,7 +2,7 @@ use {sys, Evented, EventSet, PollOpt, Selector, Token};
 use util::BoundedQueue;
 use std::{fmt, cmp, io};
 use std::sync::Arc;
-use std::sync::atomic::AtomicIsize;
+use std::sync::atomic::{AtomicIsize, AtomicUsize};
 use std::sync::atomic::Ordering::Relaxed;
 
 const SLEEP: isize = -1;
@@ -10,7 +10,8 @@ const CLOSED: isize = -2;
 
 /// Send notifications to the event loop, waking it up if necessary. If the
 /// event loop is not currently sleeping, avoid using an OS wake-up strategy
-/// (eventfd, pipe, ...). Backed by a pre-allocated lock free MPMC queue.
+/// (eventfd, pipe, ...). Backed by pre-allocated lock free MPMC queues, one
+/// per priority lane.
 ///
 /// TODO: Use more efficient wake-up strategy if available
 pub struct Notify<M: Send> {
@@ -19,9 +20,9 @@ pub struct Notify<M: Send> {
 
 impl<M: Send> Notify<M> {
     #[inline]
-    pub fn with_capacity(capacity: usize) -> io::Result<Notify<M>> {
+    pub fn with_capacity(capacity: usize, high_capacity: usize) -> io::Result<Notify<M>> {
         Ok(Notify {
-            inner: Arc::new(try!(NotifyInner::with_capacity(capacity)))
+            inner: Arc::new(try!(NotifyInner::with_capacity(capacity, high_capacity)))
         })
     }
 
@@ -30,9 +31,36 @@ impl<M: Send> Notify<M> {
         self.inner.check(max, will_sleep)
     }
 
+    /// Returns the number of messages that have been sent but not yet
+    /// claimed by `check`.
     #[inline]
-    pub fn notify(&self, value: M) -> Result<(), NotifyError<M>> {
-        self.inner.notify(value)
+    pub fn pending(&self) -> usize {
+        let cur = self.inner.state.load(Relaxed);
+
+        if cur > 0 { cur as usize } else { 0 }
+    }
+
+    /// Returns the number of messages rejected with `NotifyError::Full`
+    /// since the last call.
+    #[inline]
+    pub fn take_rejected(&self) -> usize {
+        self.inner.rejected.swap(0, Relaxed)
+    }
+
+    #[inline]
+    pub fn prepare_sleep(&self) -> bool {
+        self.inner.prepare_sleep()
+    }
+
+    /// Returns a handle that wakes up the event loop without sending a
+    /// message.
+    pub fn waker(&self) -> Waker {
+        Waker { awaken: self.inner.awaken.clone() }
+    }
+
+    #[inline]
+    pub fn notify(&self, value: M, priority: Priority) -> Result<(), NotifyError<M>> {
+        self.inner.notify(value, priority)
     }
 
     #[inline]
@@ -68,18 +96,35 @@ impl<M> fmt::Debug for Notify<M> {
 unsafe impl<M: Send> Sync for Notify<M> { }
 unsafe impl<M: Send> Send for Notify<M> { }
 
+/// The lane a message is sent on. Messages sent with `High` priority are
+/// delivered before any `Normal` message still in the queue.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Priority {
+    /// The default lane, sized by `EventLoopConfig::notify_capacity`.
+    Normal,
+    /// A separate lane for control messages, sized by
+    /// `EventLoopConfig::notify_high_capacity`.
+    High,
+}
+
 struct NotifyInner<M> {
+    // Number of messages pending across both lanes
     state: AtomicIsize,
     queue: BoundedQueue<M>,
-    awaken: sys::Awakener
+    high: BoundedQueue<M>,
+    // Number of messages rejected because their lane was full
+    rejected: AtomicUsize,
+    awaken: Arc<sys::Awakener>
 }
 
 impl<M: Send> NotifyInner<M> {
-    fn with_capacity(capacity: usize) -> io::Result<NotifyInner<M>> {
+    fn with_capacity(capacity: usize, high_capacity: usize) -> io::Result<NotifyInner<M>> {
         Ok(NotifyInner {
             state: AtomicIsize::new(0),
             queue: BoundedQueue::with_capacity(capacity),
-            awaken: try!(sys::Awakener::new())
+            high: BoundedQueue::with_capacity(high_capacity),
+            rejected: AtomicUsize::new(0),
+            awaken: Arc::new(try!(sys::Awakener::new()))
         })
     }
 
@@ -125,11 +170,31 @@ impl<M: Send> NotifyInner<M> {
         }
     }
 
+    // Transition to the sleeping state, so that the next notification wakes
+    // the awakener, unless there are pending messages. Returns true if the
+    // event loop may sleep.
+    fn prepare_sleep(&self) -> bool {
+        let cur = self.state.load(Relaxed);
+
+        if cur > 0 {
+            return false;
+        }
+
+        if cur == SLEEP {
+            return true;
+        }
+
+        // Only fails if a message was concurrently pushed
+        self.state.compare_and_swap(cur, SLEEP, Relaxed) == cur
+    }
+
+    // The high priority lane is always drained first. Messages are counted
+    // after being pushed, so every claimed message is in one of the lanes.
     fn poll(&self) -> Option<M> {
-        self.queue.pop()
+        self.high.pop().or_else(|| self.queue.pop())
     }
 
-    fn notify(&self, value: M) -> Result<(), NotifyError<M>> {
+    fn notify(&self, value: M, priority: Priority) -> Result<(), NotifyError<M>> {
         let mut cur = self.state.load(Relaxed);
 
         if cur == CLOSED {
@@ -137,8 +202,14 @@ impl<M: Send> NotifyInner<M> {
             return Err(NotifyError::Closed(Some(value)));
         }
 
+        let queue = match priority {
+            Priority::Normal => &self.queue,
+            Priority::High => &self.high,
+        };
+
         // First, push the message onto the queue
-        if let Err(value) = self.queue.push(value) {
+        if let Err(value) = queue.push(value) {
+            self.rejected.fetch_add(1, Relaxed);
             return Err(NotifyError::Full(value));
         }
 
@@ -150,7 +221,7 @@ impl<M: Send> NotifyInner<M> {
                 CLOSED => {
                     // The receiving end has hung up, and we cannot reliably get our message back
                     // We poll 1 message from the queue to make sure that no message is stuck
-                    let _ = self.queue.pop();
+                    let _ = queue.pop();
                     return Err(NotifyError::Closed(None));
                 }
                 SLEEP => { 1 }
@@ -177,7 +248,7 @@ impl<M: Send> NotifyInner<M> {
 
     fn close(&self) {
         self.state.swap(CLOSED, Relaxed);
-        while let Some(m) = self.queue.pop() {
+        while let Some(m) = self.poll() {
             drop(m);
         }
     }
@@ -201,6 +272,21 @@ impl<M: Send> Evented for Notify<M> {
     }
 }
 
+/// Wakes up the event loop, causing it to tick.
+pub struct Waker {
+    awaken: Arc<sys::Awakener>,
+}
+
+impl Waker {
+    pub fn wakeup(&self) -> io::Result<()> {
+        self.awaken.wakeup()
+    }
+}
+
+// Writing to the awakener is thread safe, see `Awakener::wakeup`
+unsafe impl Sync for Waker { }
+unsafe impl Send for Waker { }
+
 pub enum NotifyError<T> {
     Io(io::Error),
     Full(T),
@@ -0,0 +1,125 @@
+use {NotifyError, Sender, Token};
+use std::{fmt, io, panic, thread};
+use std::panic::AssertUnwindSafe;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// The outcome of a task run by an `Offload` pool, delivered to the handler
+/// as a message.
+pub struct Completed<T> {
+    /// The token the task was submitted with.
+    pub token: Token,
+    /// The value returned by the task, or the payload it panicked with.
+    pub result: thread::Result<T>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for Completed<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.result {
+            Ok(ref v) => write!(fmt, "Completed {{ token: {:?}, result: Ok({:?}) }}", self.token, v),
+            Err(..) => write!(fmt, "Completed {{ token: {:?}, result: Err(..) }}", self.token),
+        }
+    }
+}
+
+struct Job<T> {
+    // Boxed `FnMut` calling the `FnOnce` task, which cannot be called boxed
+    task: Box<FnMut() -> T + Send>,
+    token: Token,
+}
+
+/// A pool of threads running blocking or CPU-heavy tasks off the event loop
+/// thread, such as compression or calls into blocking libraries.
+///
+/// `submit` queues a closure tagged with a token. Its return value is
+/// delivered over the notify channel of an event loop as a `Completed`,
+/// which the handler's message type must be convertible from. A task that
+/// panics is delivered with the panic payload, the thread keeps running
+/// tasks. When the notify queue is full, the pool thread waits for the
+/// event loop to make room. Results are dropped once the event loop is
+/// gone.
+///
+/// Dropping the pool stops its threads once they are done with the tasks
+/// already submitted, without waiting for them.
+pub struct Offload<T> {
+    jobs: mpsc::Sender<Job<T>>,
+    threads: usize,
+}
+
+impl<T: Send + 'static> Offload<T> {
+    /// Spawns `threads` threads delivering results with `sender`, usually
+    /// obtained with `EventLoop::channel`.
+    pub fn new<M>(threads: usize, sender: Sender<M>) -> io::Result<Offload<T>>
+        where M: From<Completed<T>> + Send + 'static
+    {
+        assert!(threads > 0, "offload pool requires at least one thread");
+
+        let (tx, rx) = mpsc::channel();
+        let rx = Arc::new(Mutex::new(rx));
+
+        for _ in 0..threads {
+            let rx = rx.clone();
+            let sender = sender.clone();
+
+            try!(thread::Builder::new()
+                .name("mio-offload".to_string())
+                .spawn(move || run(rx, sender)));
+        }
+
+        Ok(Offload {
+            jobs: tx,
+            threads: threads,
+        })
+    }
+
+    /// Queues `task` to run on a pool thread, its result delivered tagged
+    /// with `token`.
+    pub fn submit<F>(&self, token: Token, task: F) -> io::Result<()>
+        where F: FnOnce() -> T + Send + 'static
+    {
+        let mut task = Some(task);
+
+        let job = Job {
+            task: Box::new(move || (task.take().unwrap())()),
+            token: token,
+        };
+
+        self.jobs.send(job)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "offload threads are gone"))
+    }
+}
+
+impl<T> fmt::Debug for Offload<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Offload {{ threads: {} }}", self.threads)
+    }
+}
+
+fn run<T, M>(jobs: Arc<Mutex<mpsc::Receiver<Job<T>>>>, sender: Sender<M>)
+    where M: From<Completed<T>> + Send
+{
+    loop {
+        // The lock is released before running the task, other threads can
+        // pick up the next job
+        let mut job = match jobs.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| (job.task)()));
+        let mut msg: M = From::from(Completed { token: job.token, result: result });
+
+        loop {
+            match sender.send(msg) {
+                Ok(()) => break,
+                Err(NotifyError::Full(m)) => {
+                    msg = m;
+                    thread::sleep(Duration::from_millis(1));
+                }
+                // Lost with the wakeup, the next results may get through
+                Err(NotifyError::Io(..)) => break,
+                Err(NotifyError::Closed(..)) => return,
+            }
+        }
+    }
+}
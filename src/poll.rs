@@ -1,20 +1,64 @@
-use {sys, Evented, Token};
+use {Error, Evented, Selector, Token};
 use event::{EventSet, IoEvent, PollOpt};
+use selector::{Backend, Events, SelectorKind};
+use signal::Signal;
+use test_selector::{self, TestSelector};
 use std::{fmt, io};
-
-pub use sys::{Events};
+use std::collections::{HashMap, HashSet};
 
 pub struct Poll {
-    selector: sys::Selector,
-    events: sys::Events,
+    selector: Selector,
+    events: Events,
+    handles: Box<Handles>,
+    // Number of handles currently registered
+    registered: usize,
+    // Readiness injected by a `TestSelector`, reported after the OS events
+    test: Option<TestSelector>,
+}
+
+// What `Poll` tracks of the registered handles, boxed to keep the event loop
+// small
+struct Handles {
+    // The interest and options most recently registered for each token, used
+    // to re-arm oneshot registrations and to skip re-registrations that
+    // change nothing, and whether they are in effect rather than paused.
+    // Not consulted when dispatching.
+    registrations: HashMap<Token, (EventSet, PollOpt, bool)>,
+    // Tokens registered or deregistered since the last poll, whose polled
+    // events are for a previous handle and must not be dispatched. The
+    // selectors hand back tokens directly, and this is empty unless
+    // handlers register or deregister handles, so dispatching usually
+    // needs no lookup.
+    stale: HashSet<Token>,
 }
 
 impl Poll {
     pub fn new() -> io::Result<Poll> {
-        Ok(Poll {
-            selector: try!(sys::Selector::new()),
-            events: sys::Events::new(),
-        })
+        Ok(Poll::with_selector(try!(Selector::new())))
+    }
+
+    /// Returns a poll using the readiness API chosen by `kind`.
+    pub fn with_kind(kind: SelectorKind) -> io::Result<Poll> {
+        Ok(Poll::with_selector(try!(Selector::with_kind(kind))))
+    }
+
+    /// Returns a poll backed by a custom `Backend` instead of the native OS
+    /// selector.
+    pub fn with_backend(backend: Box<Backend>) -> Poll {
+        Poll::with_selector(Selector::with_backend(backend))
+    }
+
+    fn with_selector(selector: Selector) -> Poll {
+        Poll {
+            selector: selector,
+            events: Events::new(),
+            handles: Box::new(Handles {
+                registrations: HashMap::new(),
+                stale: HashSet::new(),
+            }),
+            registered: 0,
+            test: None,
+        }
     }
 
     pub fn register<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()>
@@ -22,23 +66,217 @@ impl Poll {
     {
         trace!("registering with poller");
 
+        try!(validate(token, interest, opts, true));
+
+        // The fd may be a reused one of a handle dropped while registered
+        self.forget_dropped();
+
         // Register interests for this socket
         try!(io.register(&mut self.selector, token, interest, opts));
 
+        self.track(token, interest, opts);
         Ok(())
     }
 
+    /// Registers one of the event loop's own handles, under a reserved
+    /// token.
+    pub fn register_reserved<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()>
+        where E: Evented
+    {
+        debug_assert!(token.is_reserved());
+
+        try!(io.register(&mut self.selector, token, interest, opts));
+
+        self.track(token, interest, opts);
+        Ok(())
+    }
+
+    /// Re-registers `io`, unless `token` is registered with `interest` and
+    /// `opts` already, see `EventLoop::reregister`.
     pub fn reregister<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()>
         where E: Evented
+    {
+        if self.is_unchanged(token, interest, opts) {
+            trace!("registration unchanged; token={:?}", token);
+            return Ok(());
+        }
+
+        self.reregister_force(io, token, interest, opts)
+    }
+
+    /// Re-registers `io` even if `token` is registered with `interest` and
+    /// `opts` already.
+    pub fn reregister_force<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()>
+        where E: Evented
     {
         trace!("registering with poller");
 
+        try!(validate(token, interest, opts, false));
+
         // Register interests for this socket
         try!(io.reregister(&mut self.selector, token, interest, opts));
 
+        self.reregistered(token, interest, opts);
+        Ok(())
+    }
+
+    /// Registers `io` even if it is registered with another poll, which
+    /// stops tracking it as registered there.
+    pub fn transfer<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()>
+        where E: Evented
+    {
+        trace!("transferring to poller");
+
+        try!(validate(token, interest, opts, true));
+        self.forget_dropped();
+
+        self.selector.set_transfer(true);
+        let res = io.register(&mut self.selector, token, interest, opts);
+        self.selector.set_transfer(false);
+
+        try!(res);
+
+        self.track(token, interest, opts);
+        Ok(())
+    }
+
+    /// Registers `io`, queueing the change until the next poll (or an
+    /// explicit `flush_deferred`) rather than applying it immediately.
+    pub fn register_deferred<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()>
+        where E: Evented
+    {
+        trace!("registering with poller (deferred)");
+
+        try!(validate(token, interest, opts, true));
+        self.forget_dropped();
+
+        self.selector.set_deferred(true);
+        let res = io.register(&mut self.selector, token, interest, opts);
+        self.selector.set_deferred(false);
+
+        try!(res);
+
+        self.track(token, interest, opts);
+        Ok(())
+    }
+
+    /// Re-registers `io`, queueing the change until the next poll (or an
+    /// explicit `flush_deferred`) rather than applying it immediately.
+    pub fn reregister_deferred<E: ?Sized>(&mut self, io: &E, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()>
+        where E: Evented
+    {
+        if self.is_unchanged(token, interest, opts) {
+            trace!("registration unchanged; token={:?}", token);
+            return Ok(());
+        }
+
+        trace!("registering with poller (deferred)");
+
+        try!(validate(token, interest, opts, false));
+
+        self.selector.set_deferred(true);
+        let res = io.reregister(&mut self.selector, token, interest, opts);
+        self.selector.set_deferred(false);
+
+        try!(res);
+
+        self.reregistered(token, interest, opts);
+        Ok(())
+    }
+
+    /// Applies all queued registration changes. The handles whose
+    /// registration fails stop being tracked as registered.
+    pub fn flush_deferred(&mut self) -> io::Result<()> {
+        let res = self.selector.flush_deferred();
+        self.forget_failed();
+        res
+    }
+
+    // Stops tracking the handles whose deferred registration failed once
+    // applied, the only ones deregistered by the selector on its own. The
+    // kernel never saw them, so no events were polled for them.
+    fn forget_failed(&mut self) {
+        for token in self.selector.take_deregistered() {
+            self.handles.registrations.remove(&token);
+            self.registered = self.registered.saturating_sub(1);
+        }
+    }
+
+    /// Re-registers `io` using the interest and options most recently
+    /// registered with `token`.
+    pub fn rearm<E: ?Sized>(&mut self, io: &E, token: Token) -> io::Result<()>
+        where E: Evented
+    {
+        let (interest, opts) = match self.handles.registrations.get(&token) {
+            Some(&(interest, opts, _)) => (interest, opts),
+            None => return Err(From::from(Error::NotRegistered(token))),
+        };
+
+        trace!("re-arming with poller; token={:?}", token);
+
+        try!(io.reregister(&mut self.selector, token, interest, opts));
+
+        self.reregistered(token, interest, opts);
         Ok(())
     }
 
+    /// Stops reporting readiness for `io` without forgetting the interest
+    /// and options registered with `token`, which `rearm` restores.
+    pub fn pause<E: ?Sized>(&mut self, io: &E, token: Token) -> io::Result<()>
+        where E: Evented
+    {
+        let opts = match self.handles.registrations.get(&token) {
+            Some(&(_, opts, _)) => opts,
+            None => return Err(From::from(Error::NotRegistered(token))),
+        };
+
+        trace!("pausing with poller; token={:?}", token);
+
+        try!(io.reregister(&mut self.selector, token, EventSet::none(), opts));
+
+        if let Some(registration) = self.handles.registrations.get_mut(&token) {
+            registration.2 = false;
+        }
+
+        Ok(())
+    }
+
+    // Records a new handle registered under `token`, which makes the events
+    // already polled for the token stale
+    fn track(&mut self, token: Token, interest: EventSet, opts: PollOpt) {
+        self.handles.registrations.insert(token, (interest, opts, true));
+        self.handles.stale.insert(token);
+        self.registered += 1;
+    }
+
+    // Updates the interest and options of `token`, the handle is the same
+    fn reregistered(&mut self, token: Token, interest: EventSet, opts: PollOpt) {
+        self.handles.registrations.insert(token, (interest, opts, true));
+    }
+
+    // True if re-registering `token` would change nothing. A oneshot
+    // registration is disarmed once its events are polled, which is not
+    // tracked, so it is always re-registered.
+    fn is_unchanged(&self, token: Token, interest: EventSet, opts: PollOpt) -> bool {
+        if opts.is_oneshot() {
+            return false;
+        }
+
+        match self.handles.registrations.get(&token) {
+            Some(&(i, o, armed)) => armed && i == interest && o == opts,
+            None => false,
+        }
+    }
+
+    /// Returns true if `token` was deregistered or registered again after
+    /// the last poll, in which case the events polled for it refer to a
+    /// handle that is no longer registered under the token and must not be
+    /// dispatched.
+    #[inline]
+    pub fn is_stale(&self, token: Token) -> bool {
+        !self.handles.stale.is_empty() && self.handles.stale.contains(&token)
+    }
+
     pub fn deregister<E: ?Sized>(&mut self, io: &E) -> io::Result<()>
         where E: Evented
     {
@@ -47,17 +285,191 @@ impl Poll {
         // Deregister interests for this socket
         try!(io.deregister(&mut self.selector));
 
+        for token in self.selector.take_deregistered() {
+            self.handles.registrations.remove(&token);
+            self.handles.stale.insert(token);
+        }
+
+        // kqueue does not report deregistering an unregistered handle
+        self.registered = self.registered.saturating_sub(1);
         Ok(())
     }
 
-    pub fn poll(&mut self, timeout_ms: usize) -> io::Result<usize> {
-        try!(self.selector.select(&mut self.events, timeout_ms));
+    /// Deregisters the handles dropped while registered. Their tokens stop
+    /// being counted as registered, and the events already polled for them
+    /// are stale.
+    pub fn forget_dropped(&mut self) {
+        if !self.selector.forget_dropped() {
+            return;
+        }
+
+        for token in self.selector.take_deregistered() {
+            self.handles.registrations.remove(&token);
+            self.handles.stale.insert(token);
+            self.registered = self.registered.saturating_sub(1);
+        }
+    }
+
+    /// Returns the number of handles currently registered. Handles dropped
+    /// while registered are no longer counted once forgotten, before the
+    /// next poll.
+    pub fn registered(&self) -> usize {
+        self.registered
+    }
+
+    /// When set (the default), `poll` resumes waiting when interrupted by a
+    /// signal instead of failing with `ErrorKind::Interrupted`.
+    pub fn set_retry_interrupted(&mut self, retry: bool) {
+        self.selector.set_retry_interrupted(retry);
+    }
+
+    /// When set, the pending socket error of every handle reporting an error
+    /// is captured along with the event, see `IoEvent::error`. Off by
+    /// default, as capturing the error clears it on the socket.
+    pub fn set_capture_errors(&mut self, capture: bool) {
+        self.selector.set_capture_errors(capture);
+    }
+
+    /// Bounds the number of events returned by a single `poll`, see
+    /// `EventLoopConfig::events_capacity_min`. Has no effect with a custom
+    /// backend.
+    pub fn set_events_capacity(&mut self, min: usize, max: usize) {
+        self.events.set_capacity(min, max);
+    }
+
+    /// Returns the maximum number of events the next `poll` may return.
+    pub fn events_capacity(&self) -> usize {
+        self.events.capacity()
+    }
+
+    /// Returns a handle injecting readiness into this poll. Once a test
+    /// selector has been requested, polls never block; they report the
+    /// injected readiness after the events of the registered handles.
+    pub fn test_selector(&mut self) -> TestSelector {
+        if self.test.is_none() {
+            self.test = Some(test_selector::new());
+        }
+
+        self.test.clone().unwrap()
+    }
+
+    pub fn poll(&mut self, mut timeout_ms: usize) -> io::Result<usize> {
+        if self.test.is_some() {
+            timeout_ms = 0;
+        }
+
+        self.forget_dropped();
+
+        let res = self.selector.select(&mut self.events, timeout_ms);
+        self.forget_failed();
+        try!(res);
+
+        self.handles.stale.clear();
+
+        if let Some(ref test) = self.test {
+            test_selector::drain_into(test, self.events.extra_mut());
+        }
+
+        self.events.coalesce();
         Ok(self.events.len())
     }
 
+    /// Blocks exactly `signals` while polling, see
+    /// `EventLoopConfig::poll_sigmask`.
+    pub fn set_sigmask(&mut self, signals: &[Signal]) -> io::Result<()> {
+        self.selector.set_sigmask(signals)
+    }
+
+    /// Returns true if timeouts can be driven by kernel timers, with
+    /// `set_timer`.
+    pub fn has_timers(&self) -> bool {
+        self.selector.has_timers()
+    }
+
+    /// Arms the oneshot kernel timer `id` to fire in `delay_ms`. The timer
+    /// wakes up the poll, and `id` is then returned by `pop_timer`.
+    pub fn set_timer(&mut self, id: usize, delay_ms: u64) -> io::Result<()> {
+        self.selector.set_timer(id, delay_ms)
+    }
+
+    /// Disarms the kernel timer `id`. If it fired during the last poll, it is
+    /// not returned by `pop_timer`.
+    pub fn clear_timer(&mut self, id: usize) -> io::Result<()> {
+        self.selector.clear_timer(id, &mut self.events)
+    }
+
+    /// Pops the id of a kernel timer that fired.
+    pub fn pop_timer(&mut self) -> Option<usize> {
+        self.events.pop_timer()
+    }
+
     pub fn event(&self, idx: usize) -> IoEvent {
         self.events.get(idx)
     }
+
+    /// Returns the number of times the events buffers were reallocated,
+    /// which stops once they fit the load.
+    pub fn reallocations(&self) -> u64 {
+        self.events.reallocations()
+    }
+}
+
+// Rejects registrations under a reserved token, and those that would never
+// report events. A re-registration may have no interest, to stop reporting
+// readiness for a while.
+fn validate(token: Token, interest: EventSet, opts: PollOpt, registering: bool) -> io::Result<()> {
+    if token.is_reserved() {
+        return Err(From::from(Error::ReservedToken(token)));
+    }
+
+    if opts.is_edge() == opts.is_level() {
+        let desc = if opts.is_edge() {
+            "edge and level triggered options are exclusive"
+        } else {
+            "options must include either edge or level triggered"
+        };
+
+        return Err(From::from(Error::InvalidInterest(desc)));
+    }
+
+    let os = EventSet::readable() | EventSet::writable() | EventSet::hup();
+
+    if registering && (interest & os) == EventSet::none() {
+        return Err(From::from(Error::InvalidInterest("interest must include readable, writable or hup")));
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
+
+#[cfg(unix)]
+impl AsRawFd for Poll {
+    fn as_raw_fd(&self) -> RawFd {
+        self.selector.as_raw_fd()
+    }
+}
+
+/// A `Poll` can itself be registered with another selector, becoming readable
+/// when it has pending events. This allows a parent event loop to drive child
+/// event loops.
+///
+/// The implementation is on `&Poll` so that it does not shadow the inherent
+/// registration methods: `poll.register(&&child, token, ...)`.
+#[cfg(unix)]
+impl<'a> Evented for &'a Poll {
+    fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        selector.register(self.as_raw_fd(), token, interest, opts)
+    }
+
+    fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        selector.reregister(self.as_raw_fd(), token, interest, opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+        selector.deregister(self.as_raw_fd())
+    }
 }
 
 impl fmt::Debug for Poll {
@@ -0,0 +1,240 @@
+use {Handler, EventLoop, EventLoopConfig, NotifyError, Sender};
+use std::{fmt, io, thread};
+use std::sync::{mpsc, Arc};
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+/// How a `WorkerPool` picks the worker a message is handed off to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Dispatch {
+    /// Cycle through the workers in order.
+    RoundRobin,
+    /// Pick the worker with the lowest load, as tracked by `WorkerLoad`.
+    LeastLoaded,
+}
+
+/// The load of a worker, shared between the pool and the worker's handler.
+///
+/// The pool increments the load every time a message is handed off to the
+/// worker. The handler should call `release` once the work associated with
+/// the message is done (for example, when a handed off connection is
+/// closed). The load is only used by `Dispatch::LeastLoaded`.
+#[derive(Clone, Debug)]
+pub struct WorkerLoad {
+    count: Arc<AtomicUsize>,
+}
+
+impl WorkerLoad {
+    fn new() -> WorkerLoad {
+        WorkerLoad { count: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// Returns the current load of the worker.
+    pub fn get(&self) -> usize {
+        self.count.load(Relaxed)
+    }
+
+    /// Decrements the load of the worker.
+    pub fn release(&self) {
+        self.count.fetch_sub(1, Relaxed);
+    }
+
+    fn acquire(&self) {
+        self.count.fetch_add(1, Relaxed);
+    }
+}
+
+/// A pool of threads, each running its own `EventLoop`.
+///
+/// Work, typically accepted connections, is handed off to the workers over
+/// their notify channels. A common setup is an acceptor event loop that
+/// accepts `TcpStream`s and hands them off with `handoff`, the worker handler
+/// then registering the stream with its own event loop in `Handler::notify`.
+///
+/// A worker stops when its handler shuts down its event loop, when its event
+/// loop fails, or when the pool is shut down or dropped. The pool stop is
+/// only noticed once the worker's current tick completes, so it can take up
+/// to `EventLoopConfig::io_poll_timeout_ms` to take effect.
+pub struct WorkerPool<M: Send> {
+    workers: Vec<Worker<M>>,
+    dispatch: Dispatch,
+    next: usize,
+    running: Arc<AtomicBool>,
+}
+
+struct Worker<M: Send> {
+    sender: Sender<M>,
+    load: WorkerLoad,
+    thread: Option<thread::JoinHandle<io::Result<()>>>,
+}
+
+impl<M: Send + 'static> WorkerPool<M> {
+    /// Spawns `size` worker threads. Each thread creates an event loop using
+    /// `config` and a handler by calling `factory` with the index of the
+    /// worker and its load.
+    ///
+    /// The factory runs on the worker thread, so for a thread per core
+    /// setup it can pin the worker with
+    /// `util::set_cpu_affinity(&[idx])`. `EventLoopConfig::cpu_affinity`
+    /// applies to every worker instead.
+    ///
+    /// Returns an error if any of the event loops could not be created, in
+    /// which case the workers that were started are stopped.
+    pub fn spawn<H, F>(size: usize, config: EventLoopConfig, dispatch: Dispatch, factory: F) -> io::Result<WorkerPool<M>>
+        where H: Handler<Message = M> + 'static,
+              F: Fn(usize, WorkerLoad) -> H + Send + Sync + 'static
+    {
+        assert!(size > 0, "worker pool requires at least one worker");
+
+        let factory = Arc::new(factory);
+
+        let mut pool = WorkerPool {
+            workers: Vec::with_capacity(size),
+            dispatch: dispatch,
+            next: 0,
+            running: Arc::new(AtomicBool::new(true)),
+        };
+
+        for idx in 0..size {
+            let (tx, rx) = mpsc::channel();
+            let load = WorkerLoad::new();
+
+            let factory = factory.clone();
+            let config = config.clone();
+            let running = pool.running.clone();
+            let worker_load = load.clone();
+
+            let thread = thread::spawn(move || {
+                let mut event_loop: EventLoop<H> = match EventLoop::configured(config) {
+                    Ok(event_loop) => event_loop,
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        return Ok(());
+                    }
+                };
+
+                let mut handler = factory(idx, worker_load);
+                let _ = tx.send(Ok(event_loop.channel()));
+
+                while running.load(SeqCst) && event_loop.is_running() {
+                    try!(event_loop.run_once(&mut handler));
+                }
+
+                Ok(())
+            });
+
+            match rx.recv() {
+                Ok(Ok(sender)) => {
+                    pool.workers.push(Worker {
+                        sender: sender,
+                        load: load,
+                        thread: Some(thread),
+                    });
+                }
+                Ok(Err(e)) => return Err(e),
+                Err(_) => return Err(io::Error::new(io::ErrorKind::Other, "worker thread panicked")),
+            }
+        }
+
+        Ok(pool)
+    }
+
+    /// Returns the number of workers in the pool.
+    pub fn len(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Returns the load of the given worker.
+    pub fn load(&self, idx: usize) -> usize {
+        self.workers[idx].load.get()
+    }
+
+    /// Hands `msg` off to a worker, picked according to the pool's dispatch
+    /// strategy. Returns the index of the worker the message was sent to.
+    pub fn handoff(&mut self, msg: M) -> Result<usize, NotifyError<M>> {
+        let idx = self.pick();
+        try!(self.send_to(idx, msg));
+        Ok(idx)
+    }
+
+    /// Hands `msg` off to the given worker.
+    pub fn send_to(&mut self, idx: usize, msg: M) -> Result<(), NotifyError<M>> {
+        let worker = &self.workers[idx];
+
+        try!(worker.sender.send(msg));
+        worker.load.acquire();
+
+        Ok(())
+    }
+
+    /// Returns a sender for the given worker's event loop.
+    pub fn sender(&self, idx: usize) -> Sender<M> {
+        self.workers[idx].sender.clone()
+    }
+
+    /// Stops all the workers and waits for their threads to exit. Returns the
+    /// first error a worker's event loop failed with.
+    pub fn shutdown(mut self) -> io::Result<()> {
+        self.stop()
+    }
+
+    fn pick(&mut self) -> usize {
+        match self.dispatch {
+            Dispatch::RoundRobin => {
+                let idx = self.next % self.workers.len();
+                self.next = self.next.wrapping_add(1);
+                idx
+            }
+            Dispatch::LeastLoaded => {
+                let mut idx = 0;
+
+                for i in 1..self.workers.len() {
+                    if self.workers[i].load.get() < self.workers[idx].load.get() {
+                        idx = i;
+                    }
+                }
+
+                idx
+            }
+        }
+    }
+
+    fn stop(&mut self) -> io::Result<()> {
+        let mut ret = Ok(());
+
+        self.running.store(false, SeqCst);
+
+        for worker in self.workers.iter_mut() {
+            if let Some(thread) = worker.thread.take() {
+                let res = match thread.join() {
+                    Ok(res) => res,
+                    Err(_) => Err(io::Error::new(io::ErrorKind::Other, "worker thread panicked")),
+                };
+
+                if ret.is_ok() {
+                    ret = res;
+                }
+            }
+        }
+
+        ret
+    }
+}
+
+impl<M: Send> fmt::Debug for WorkerPool<M> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "WorkerPool {{ workers: {}, dispatch: {:?} }}", self.workers.len(), self.dispatch)
+    }
+}
+
+impl<M: Send> Drop for WorkerPool<M> {
+    fn drop(&mut self) {
+        self.running.store(false, SeqCst);
+
+        for worker in self.workers.iter_mut() {
+            if let Some(thread) = worker.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
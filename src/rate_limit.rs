@@ -0,0 +1,205 @@
+use {EventLoop, Handler, Timeout, TimerResult, Token};
+use std::{cmp, fmt};
+use std::collections::HashMap;
+
+/// A rate, in bytes per second, with the number of bytes that may be spent
+/// at once after being idle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rate {
+    pub bytes_per_sec: u64,
+    pub burst: u64,
+}
+
+impl Rate {
+    /// # Panics
+    ///
+    /// This method will panic if `bytes_per_sec` is 0.
+    pub fn new(bytes_per_sec: u64, burst: u64) -> Rate {
+        assert!(bytes_per_sec > 0, "rate must be positive");
+
+        Rate {
+            bytes_per_sec: bytes_per_sec,
+            burst: burst,
+        }
+    }
+}
+
+/// The timeout scheduled by `RateLimiter::throttle`, which the handler's
+/// timeout type must be convertible from. The budget of the token refilled,
+/// pass it to `RateLimiter::refilled` and enable its interest again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Refilled(pub Token);
+
+/// Token buckets capping the bytes read or written per token, and in total.
+///
+/// Before reading or writing, ask `available` how many bytes the token may
+/// transfer now, and report the bytes transferred with `consume`. When the
+/// budget is exhausted, disable the interest of the token and call
+/// `throttle`, which schedules a `Refilled` timeout for when the buckets
+/// hold enough bytes again.
+///
+/// Buckets start full. The per-token bucket is created on first use, drop it
+/// with `remove` once the token is done.
+pub struct RateLimiter {
+    per_token: Option<Rate>,
+    global: Option<Bucket>,
+    buckets: HashMap<Token, Bucket>,
+    throttled: HashMap<Token, Timeout>,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    rate: Rate,
+    // In thousandths of a byte, so that refilling for a few milliseconds at
+    // a low rate does not round down to nothing
+    credit: u64,
+    last_ms: u64,
+}
+
+impl Bucket {
+    fn new(rate: Rate, now_ms: u64) -> Bucket {
+        Bucket {
+            rate: rate,
+            credit: rate.burst.saturating_mul(1_000),
+            last_ms: now_ms,
+        }
+    }
+
+    fn refill(&mut self, now_ms: u64) {
+        let elapsed = now_ms.saturating_sub(self.last_ms);
+        let max = self.rate.burst.saturating_mul(1_000);
+
+        self.credit = cmp::min(self.credit.saturating_add(elapsed.saturating_mul(self.rate.bytes_per_sec)), max);
+        self.last_ms = cmp::max(self.last_ms, now_ms);
+    }
+
+    fn available(&self) -> u64 {
+        self.credit / 1_000
+    }
+
+    fn consume(&mut self, bytes: u64) {
+        self.credit = self.credit.saturating_sub(bytes.saturating_mul(1_000));
+    }
+
+    // Milliseconds until the bucket holds `bytes`, capped to its burst
+    fn delay_ms(&self, bytes: u64) -> u64 {
+        let want = cmp::min(bytes, self.rate.burst).saturating_mul(1_000);
+
+        if want <= self.credit {
+            return 0;
+        }
+
+        let missing = want - self.credit;
+        (missing + self.rate.bytes_per_sec - 1) / self.rate.bytes_per_sec
+    }
+}
+
+impl RateLimiter {
+    /// Returns a limiter capping each token to `per_token`, and all tokens
+    /// together to `global`. `None` leaves the corresponding rate unlimited.
+    pub fn new(per_token: Option<Rate>, global: Option<Rate>) -> RateLimiter {
+        RateLimiter {
+            per_token: per_token,
+            global: global.map(|rate| Bucket::new(rate, 0)),
+            buckets: HashMap::new(),
+            throttled: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of bytes `token` may transfer now, `usize::MAX`
+    /// when unlimited.
+    pub fn available<H: Handler>(&mut self, event_loop: &EventLoop<H>, token: Token) -> usize {
+        let now = event_loop.now_ms();
+        let mut ret = ::std::u64::MAX;
+
+        if let Some(bucket) = self.bucket(token, now) {
+            ret = bucket.available();
+        }
+
+        if let Some(ref mut global) = self.global {
+            global.refill(now);
+            ret = cmp::min(ret, global.available());
+        }
+
+        cmp::min(ret, ::std::usize::MAX as u64) as usize
+    }
+
+    /// Records that `token` transferred `bytes`.
+    pub fn consume<H: Handler>(&mut self, event_loop: &EventLoop<H>, token: Token, bytes: usize) {
+        let now = event_loop.now_ms();
+
+        if let Some(bucket) = self.bucket(token, now) {
+            bucket.consume(bytes as u64);
+        }
+
+        if let Some(ref mut global) = self.global {
+            global.refill(now);
+            global.consume(bytes as u64);
+        }
+    }
+
+    /// Schedules a `Refilled` timeout for when `token` may transfer `bytes`,
+    /// or as many bytes as its buckets hold when full. Does nothing if the
+    /// token is already throttled.
+    pub fn throttle<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, bytes: usize) -> TimerResult<()>
+        where H::Timeout: From<Refilled>
+    {
+        if self.throttled.contains_key(&token) {
+            return Ok(());
+        }
+
+        let now = event_loop.now_ms();
+        let mut delay = 0;
+
+        if let Some(bucket) = self.bucket(token, now) {
+            delay = bucket.delay_ms(bytes as u64);
+        }
+
+        if let Some(ref mut global) = self.global {
+            global.refill(now);
+            delay = cmp::max(delay, global.delay_ms(bytes as u64));
+        }
+
+        let timeout = try!(event_loop.timeout_ms(From::from(Refilled(token)), delay));
+
+        self.throttled.insert(token, timeout);
+        Ok(())
+    }
+
+    /// Records that the `Refilled` timeout of `token` fired.
+    pub fn refilled(&mut self, token: Token) {
+        self.throttled.remove(&token);
+    }
+
+    pub fn is_throttled(&self, token: Token) -> bool {
+        self.throttled.contains_key(&token)
+    }
+
+    /// Drops the bucket of `token`, and cancels its `Refilled` timeout.
+    pub fn remove<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token) {
+        self.buckets.remove(&token);
+
+        if let Some(timeout) = self.throttled.remove(&token) {
+            event_loop.clear_timeout(timeout);
+        }
+    }
+
+    fn bucket(&mut self, token: Token, now_ms: u64) -> Option<&mut Bucket> {
+        let rate = match self.per_token {
+            Some(rate) => rate,
+            None => return None,
+        };
+
+        let bucket = self.buckets.entry(token).or_insert_with(|| Bucket::new(rate, now_ms));
+
+        bucket.refill(now_ms);
+        Some(bucket)
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "RateLimiter {{ per_token: {:?}, global: {:?}, throttled: {} }}",
+               self.per_token, self.global.map(|bucket| bucket.rate), self.throttled.len())
+    }
+}
@@ -0,0 +1,346 @@
+use {io, Error, Evented, EventSet, Poll, PollOpt, Selector, Token};
+use notify::Waker;
+use std::{fmt, ops};
+use std::io::ErrorKind;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Registers IO handles with an event loop from any thread.
+///
+/// Operations are queued and applied by the event loop thread the next time
+/// it ticks, waking it up if it is sleeping. Each operation returns a
+/// `Completion` that resolves once the operation has been applied.
+///
+/// The registrar only references the handle's fd, the handle must stay open
+/// until the operation completes.
+///
+/// A registrar can also make the event loop dispatch readiness for a token
+/// without any IO taking place, see `set_readiness`.
+#[derive(Clone)]
+pub struct Registrar {
+    shared: Arc<Shared>,
+}
+
+/// The pending result of an operation queued with a `Registrar`.
+pub struct Completion {
+    inner: Arc<(Mutex<Option<io::Result<()>>>, Condvar)>,
+}
+
+impl Completion {
+    fn new() -> Completion {
+        Completion { inner: Arc::new((Mutex::new(None), Condvar::new())) }
+    }
+
+    /// Returns true if the operation has been applied.
+    pub fn is_complete(&self) -> bool {
+        self.inner.0.lock().unwrap().is_some()
+    }
+
+    /// Blocks until the operation has been applied by the event loop, and
+    /// returns its result.
+    pub fn wait(self) -> io::Result<()> {
+        let &(ref lock, ref cvar) = &*self.inner;
+        let mut res = lock.lock().unwrap();
+
+        loop {
+            if let Some(res) = res.take() {
+                return res;
+            }
+
+            res = cvar.wait(res).unwrap();
+        }
+    }
+
+    fn complete(&self, res: io::Result<()>) {
+        let &(ref lock, ref cvar) = &*self.inner;
+
+        *lock.lock().unwrap() = Some(res);
+        cvar.notify_all();
+    }
+}
+
+impl fmt::Debug for Completion {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Completion {{ complete: {} }}", self.is_complete())
+    }
+}
+
+impl Registrar {
+    /// Queues the registration of `io` with the event loop. See
+    /// `EventLoop::register_opt`.
+    pub fn register<E: ?Sized>(&self, io: &E, token: Token, interest: EventSet, opts: PollOpt) -> Completion
+        where E: AsRawFd
+    {
+        self.push(Op::Register(io.as_raw_fd(), token, interest, opts))
+    }
+
+    /// Queues a change to the registration of `io`. See
+    /// `EventLoop::reregister`.
+    pub fn reregister<E: ?Sized>(&self, io: &E, token: Token, interest: EventSet, opts: PollOpt) -> Completion
+        where E: AsRawFd
+    {
+        self.push(Op::Reregister(io.as_raw_fd(), token, interest, opts))
+    }
+
+    /// Queues the deregistration of `io`.
+    pub fn deregister<E: ?Sized>(&self, io: &E) -> Completion
+        where E: AsRawFd
+    {
+        self.push(Op::Deregister(io.as_raw_fd()))
+    }
+
+    /// Marks `token` as ready in userspace: on its next tick, the event loop
+    /// invokes `Handler::ready` with `events`, as if an IO handle registered
+    /// under `token` had become ready. The token does not need to be
+    /// registered. Readiness set several times for the same token before the
+    /// event loop picks it up is merged into a single event. Reserved
+    /// tokens fail with `Error::ReservedToken`.
+    pub fn set_readiness(&self, token: Token, events: EventSet) -> Completion {
+        if token.is_reserved() {
+            let completion = Completion::new();
+            completion.complete(Err(From::from(Error::ReservedToken(token))));
+            return completion;
+        }
+
+        self.push(Op::Ready(token, events))
+    }
+
+    /// Same as `set_readiness(token, EventSet::readable())`. Convenient to
+    /// resume a connection once a worker thread finished a job for it.
+    pub fn wake(&self, token: Token) -> Completion {
+        self.set_readiness(token, EventSet::readable())
+    }
+
+    fn push(&self, op: Op) -> Completion {
+        let completion = Completion::new();
+
+        {
+            let mut queue = self.shared.queue.lock().unwrap();
+
+            if queue.closed {
+                completion.complete(Err(io::Error::new(ErrorKind::Other, "event loop has been dropped")));
+                return completion;
+            }
+
+            queue.ops.push((op, Completion { inner: completion.inner.clone() }));
+            self.shared.pending.store(true, SeqCst);
+        }
+
+        if let Err(e) = self.shared.waker.wakeup() {
+            // The operation will still be applied on the next tick
+            warn!("failed to wake up event loop; err={:?}", e);
+        }
+
+        completion
+    }
+}
+
+/// An IO handle registered with `EventLoop::register_guarded`, deregistered
+/// when the guard is dropped.
+///
+/// Dropping the guard hands the handle back to the event loop, which
+/// deregisters and closes it on its next tick, then invokes
+/// `Handler::deregistered` with the token so it can be freed. The handle is
+/// kept open until then, so its fd cannot be reused by a new registration in
+/// the meantime. Use `EventLoop::release` to deregister immediately instead.
+pub struct RegistrationGuard<E: Evented + Send + 'static> {
+    io: Option<E>,
+    token: Token,
+    registrar: Registrar,
+}
+
+impl<E: Evented + Send + 'static> RegistrationGuard<E> {
+    pub fn new(io: E, token: Token, registrar: Registrar) -> RegistrationGuard<E> {
+        RegistrationGuard {
+            io: Some(io),
+            token: token,
+            registrar: registrar,
+        }
+    }
+
+    /// Returns the token the handle is registered with.
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    /// Returns the handle, leaving the caller responsible for deregistering
+    /// it.
+    pub fn into_inner(mut self) -> E {
+        self.io.take().unwrap()
+    }
+}
+
+impl<E: Evented + Send + 'static> ops::Deref for RegistrationGuard<E> {
+    type Target = E;
+
+    fn deref(&self) -> &E {
+        self.io.as_ref().unwrap()
+    }
+}
+
+impl<E: Evented + Send + 'static> ops::DerefMut for RegistrationGuard<E> {
+    fn deref_mut(&mut self) -> &mut E {
+        self.io.as_mut().unwrap()
+    }
+}
+
+impl<E: Evented + Send + 'static> fmt::Debug for RegistrationGuard<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "RegistrationGuard {{ token: {:?} }}", self.token)
+    }
+}
+
+impl<E: Evented + Send + 'static> Drop for RegistrationGuard<E> {
+    fn drop(&mut self) {
+        if let Some(io) = self.io.take() {
+            self.registrar.push(Op::Release(Box::new(io), self.token));
+        }
+    }
+}
+
+impl fmt::Debug for Registrar {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Registrar {{ ... }}")
+    }
+}
+
+/// The event loop side of the registrar, applying queued operations.
+pub struct Registrations {
+    shared: Arc<Shared>,
+}
+
+impl Registrations {
+    pub fn new(waker: Waker) -> Registrations {
+        Registrations {
+            shared: Arc::new(Shared {
+                queue: Mutex::new(Queue { ops: vec![], closed: false }),
+                pending: AtomicBool::new(false),
+                waker: waker,
+            }),
+        }
+    }
+
+    pub fn registrar(&self) -> Registrar {
+        Registrar { shared: self.shared.clone() }
+    }
+
+    /// Returns true if there are no queued operations.
+    pub fn is_empty(&self) -> bool {
+        !self.shared.pending.load(SeqCst)
+    }
+
+    /// Applies all queued operations to `poll`. Returns what remains to be
+    /// reported to the handler.
+    pub fn apply(&self, poll: &mut Poll) -> Vec<Applied> {
+        let mut applied = vec![];
+
+        if !self.shared.pending.load(SeqCst) {
+            return applied;
+        }
+
+        let ops = {
+            let mut queue = self.shared.queue.lock().unwrap();
+            self.shared.pending.store(false, SeqCst);
+            ::std::mem::replace(&mut queue.ops, vec![])
+        };
+
+        for (op, completion) in ops {
+            let res = match op {
+                Op::Register(fd, token, interest, opts) => poll.register(&Fd(fd), token, interest, opts),
+                Op::Reregister(fd, token, interest, opts) => poll.reregister(&Fd(fd), token, interest, opts),
+                Op::Deregister(fd) => poll.deregister(&Fd(fd)),
+                Op::Release(io, token) => {
+                    applied.push(Applied::Released(token));
+                    poll.deregister(&*io)
+                }
+                Op::Ready(token, events) => {
+                    merge_ready(&mut applied, token, events);
+                    Ok(())
+                }
+            };
+
+            completion.complete(res);
+        }
+
+        applied
+    }
+}
+
+impl fmt::Debug for Registrations {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Registrations {{ ... }}")
+    }
+}
+
+impl Drop for Registrations {
+    fn drop(&mut self) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.closed = true;
+
+        for (_, completion) in queue.ops.drain(..) {
+            completion.complete(Err(io::Error::new(ErrorKind::Other, "event loop has been dropped")));
+        }
+    }
+}
+
+/// The outcome of an applied operation that the handler must be told about.
+pub enum Applied {
+    /// A dropped `RegistrationGuard` has been deregistered.
+    Released(Token),
+    /// Readiness was set for the token with `Registrar::set_readiness`.
+    Ready(Token, EventSet),
+}
+
+fn merge_ready(applied: &mut Vec<Applied>, token: Token, events: EventSet) {
+    for a in applied.iter_mut() {
+        if let Applied::Ready(t, ref mut evts) = *a {
+            if t == token {
+                *evts = *evts | events;
+                return;
+            }
+        }
+    }
+
+    applied.push(Applied::Ready(token, events));
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    // Set when there are queued operations, to avoid taking the lock every tick
+    pending: AtomicBool,
+    waker: Waker,
+}
+
+struct Queue {
+    ops: Vec<(Op, Completion)>,
+    closed: bool,
+}
+
+enum Op {
+    Register(RawFd, Token, EventSet, PollOpt),
+    Reregister(RawFd, Token, EventSet, PollOpt),
+    Deregister(RawFd),
+    // Deregister and close the handle of a dropped guard
+    Release(Box<Evented + Send>, Token),
+    // Userspace readiness to dispatch to the handler
+    Ready(Token, EventSet),
+}
+
+// A borrowed fd, registered on behalf of the handle it belongs to
+struct Fd(RawFd);
+
+impl Evented for Fd {
+    fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        selector.register(self.0, token, interest, opts)
+    }
+
+    fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        selector.reregister(self.0, token, interest, opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+        selector.deregister(self.0)
+    }
+}
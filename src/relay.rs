@@ -0,0 +1,392 @@
+use {io, EventLoop, Evented, EventSet, Handler, PollOpt, Token, TryRead, TryWrite};
+use buf::{Buf, RingBuf};
+use std::fmt;
+use std::os::unix::io::AsRawFd;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use sys::{self, Io};
+
+/// Why a `Relay` ended. Both ends are deregistered by then.
+#[derive(Debug)]
+pub enum Termination {
+    /// The client closed its side, after everything it sent was forwarded
+    /// to the upstream.
+    ClientClosed,
+    /// The upstream closed its side, after everything it sent was
+    /// forwarded to the client.
+    UpstreamClosed,
+    /// Reading from or writing to the client failed.
+    ClientError(io::Error),
+    /// Reading from or writing to the upstream failed.
+    UpstreamError(io::Error),
+}
+
+/// Copies bytes both ways between a client and an upstream connection, as
+/// proxies do.
+///
+/// Both ends are registered level-triggered under their own token. Pass
+/// the events of either token to `ready`, which reads from the ready end
+/// into the buffer towards the other end, writes that buffer out, and
+/// updates the interest of both ends: an end stops being read while the
+/// buffer towards the other end is full, so a slow reader slows the writer
+/// down. Hangups are only asked for while an end is read: once it reached
+/// EOF, or while it is paused, a level-triggered hangup would be reported
+/// on every poll.
+///
+/// On Linux, bytes are moved with `splice(2)` through a pipe per direction
+/// rather than copied through user space, falling back to buffers if the
+/// pipes cannot be created. Both ends must then be sockets or pipes.
+///
+/// The relay ends once either end is closed and the bytes it sent are
+/// forwarded, or on the first error. Half-closed connections are not kept
+/// open.
+pub struct Relay<A, B> {
+    client: End<A>,
+    upstream: End<B>,
+    // Bytes from the client to the upstream, and back
+    to_upstream: Channel,
+    to_client: Channel,
+    done: bool,
+}
+
+struct End<T> {
+    io: T,
+    token: Token,
+    eof: bool,
+    // The interest the end is registered with, if registered
+    registered: Option<EventSet>,
+}
+
+impl<A, B> Relay<A, B>
+    where A: Evented + TryRead + TryWrite + AsRawFd,
+          B: Evented + TryRead + TryWrite + AsRawFd
+{
+    /// Relays between `client` and `upstream`, buffering up to `capacity`
+    /// bytes each way, rounded up to a power of two. With `splice(2)`, at
+    /// most 64KiB are buffered each way.
+    pub fn new(client: A, client_token: Token, upstream: B, upstream_token: Token, capacity: usize) -> Relay<A, B> {
+        Relay {
+            client: End::new(client, client_token),
+            upstream: End::new(upstream, upstream_token),
+            to_upstream: Channel::new(capacity),
+            to_client: Channel::new(capacity),
+            done: false,
+        }
+    }
+
+    /// Returns true if bytes are moved with `splice(2)`.
+    pub fn is_splicing(&self) -> bool {
+        self.to_upstream.is_splice() && self.to_client.is_splice()
+    }
+
+    pub fn client(&self) -> &A {
+        &self.client.io
+    }
+
+    pub fn upstream(&self) -> &B {
+        &self.upstream.io
+    }
+
+    /// Returns the number of bytes read from the client and not yet
+    /// written to the upstream, and the other way around.
+    pub fn pending(&self) -> (usize, usize) {
+        (self.to_upstream.pending(), self.to_client.pending())
+    }
+
+    /// Registers both ends with `event_loop`.
+    pub fn register<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        let interest = self.client_interest();
+        try!(event_loop.register_opt(&self.client.io, self.client.token, interest, PollOpt::level()));
+        self.client.registered = Some(interest);
+
+        let interest = self.upstream_interest();
+        try!(event_loop.register_opt(&self.upstream.io, self.upstream.token, interest, PollOpt::level()));
+        self.upstream.registered = Some(interest);
+
+        Ok(())
+    }
+
+    /// Moves bytes on readiness for either token. Returns why the relay
+    /// ended, once it did.
+    pub fn ready<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, events: EventSet) -> Option<Termination> {
+        if self.done {
+            return None;
+        }
+
+        let res = if token == self.client.token {
+            self.step_client(events)
+        } else if token == self.upstream.token {
+            self.step_upstream(events)
+        } else {
+            return None;
+        };
+
+        let termination = match res {
+            Err(termination) => Some(termination),
+            Ok(()) => self.closed(),
+        };
+
+        if termination.is_some() {
+            self.finish(event_loop);
+            return termination;
+        }
+
+        if let Err(err) = self.update(event_loop) {
+            self.finish(event_loop);
+            return Some(err);
+        }
+
+        None
+    }
+
+    /// Returns both ends, with the bytes still buffered lost.
+    pub fn into_inner(self) -> (A, B) {
+        (self.client.io, self.upstream.io)
+    }
+
+    fn step_client(&mut self, events: EventSet) -> Result<(), Termination> {
+        if events.is_writable() {
+            try!(self.to_client.drain(&mut self.client.io).map_err(Termination::ClientError));
+        }
+
+        if events.is_readable() || events.is_hup() {
+            try!(self.forward_from_client());
+        }
+
+        if events.is_error() {
+            return Err(Termination::ClientError(io::Error::new(::std::io::ErrorKind::Other, "client socket error")));
+        }
+
+        Ok(())
+    }
+
+    fn step_upstream(&mut self, events: EventSet) -> Result<(), Termination> {
+        if events.is_writable() {
+            try!(self.to_upstream.drain(&mut self.upstream.io).map_err(Termination::UpstreamError));
+        }
+
+        if events.is_readable() || events.is_hup() {
+            try!(self.forward_from_upstream());
+        }
+
+        if events.is_error() {
+            return Err(Termination::UpstreamError(io::Error::new(::std::io::ErrorKind::Other, "upstream socket error")));
+        }
+
+        Ok(())
+    }
+
+    fn forward_from_client(&mut self) -> Result<(), Termination> {
+        if !self.client.eof && !self.to_upstream.is_full() {
+            let eof = try!(self.to_upstream.fill(&mut self.client.io).map_err(Termination::ClientError));
+            self.client.eof = eof;
+        }
+
+        // The upstream is usually writable already
+        try!(self.to_upstream.drain(&mut self.upstream.io).map_err(Termination::UpstreamError));
+        Ok(())
+    }
+
+    fn forward_from_upstream(&mut self) -> Result<(), Termination> {
+        if !self.upstream.eof && !self.to_client.is_full() {
+            let eof = try!(self.to_client.fill(&mut self.upstream.io).map_err(Termination::UpstreamError));
+            self.upstream.eof = eof;
+        }
+
+        try!(self.to_client.drain(&mut self.client.io).map_err(Termination::ClientError));
+        Ok(())
+    }
+
+    fn closed(&self) -> Option<Termination> {
+        if self.client.eof && self.to_upstream.pending() == 0 {
+            return Some(Termination::ClientClosed);
+        }
+
+        if self.upstream.eof && self.to_client.pending() == 0 {
+            return Some(Termination::UpstreamClosed);
+        }
+
+        None
+    }
+
+    fn client_interest(&self) -> EventSet {
+        interest(self.client.eof || self.to_upstream.is_full(), self.to_client.pending() > 0)
+    }
+
+    fn upstream_interest(&self) -> EventSet {
+        interest(self.upstream.eof || self.to_client.is_full(), self.to_upstream.pending() > 0)
+    }
+
+    fn update<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> Result<(), Termination> {
+        let interest = self.client_interest();
+
+        if self.client.registered.is_some() && self.client.registered != Some(interest) {
+            try!(event_loop.reregister(&self.client.io, self.client.token, interest, PollOpt::level())
+                .map_err(Termination::ClientError));
+            self.client.registered = Some(interest);
+        }
+
+        let interest = self.upstream_interest();
+
+        if self.upstream.registered.is_some() && self.upstream.registered != Some(interest) {
+            try!(event_loop.reregister(&self.upstream.io, self.upstream.token, interest, PollOpt::level())
+                .map_err(Termination::UpstreamError));
+            self.upstream.registered = Some(interest);
+        }
+
+        Ok(())
+    }
+
+    fn finish<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) {
+        self.done = true;
+
+        if self.client.registered.take().is_some() {
+            let _ = event_loop.deregister(&self.client.io);
+        }
+
+        if self.upstream.registered.take().is_some() {
+            let _ = event_loop.deregister(&self.upstream.io);
+        }
+    }
+}
+
+impl<A: fmt::Debug, B: fmt::Debug> fmt::Debug for Relay<A, B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Relay {{ client: {:?}, upstream: {:?}, pending: ({}, {}) }}",
+               self.client.io, self.upstream.io, self.to_upstream.pending(), self.to_client.pending())
+    }
+}
+
+impl<T> End<T> {
+    fn new(io: T, token: Token) -> End<T> {
+        End {
+            io: io,
+            token: token,
+            eof: false,
+            registered: None,
+        }
+    }
+}
+
+fn interest(paused: bool, writable: bool) -> EventSet {
+    let mut interest = EventSet::none();
+
+    // A hangup is read as EOF, as long as the end is read
+    if !paused {
+        interest = interest | EventSet::readable() | EventSet::hup();
+    }
+
+    if writable {
+        interest = interest | EventSet::writable();
+    }
+
+    interest
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const PIPE_SIZE: usize = 65_536;
+
+// The bytes in flight in one direction
+enum Channel {
+    Buffer(RingBuf),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Splice {
+        rd: Io,
+        wr: Io,
+        pending: usize,
+        capacity: usize,
+    },
+}
+
+impl Channel {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn new(capacity: usize) -> Channel {
+        match sys::pipe() {
+            // The pipe itself buffers the bytes, so it cannot hold more
+            // than its default size
+            Ok((rd, wr)) => Channel::Splice { rd: rd, wr: wr, pending: 0, capacity: ::std::cmp::min(capacity, PIPE_SIZE) },
+            Err(err) => {
+                debug!("failed to create relay pipe, buffering instead; err={:?}", err);
+                Channel::Buffer(RingBuf::new(capacity))
+            }
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn new(capacity: usize) -> Channel {
+        Channel::Buffer(RingBuf::new(capacity))
+    }
+
+    fn is_splice(&self) -> bool {
+        match *self {
+            Channel::Buffer(..) => false,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Channel::Splice { .. } => true,
+        }
+    }
+
+    fn pending(&self) -> usize {
+        match *self {
+            Channel::Buffer(ref buf) => Buf::remaining(buf),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Channel::Splice { pending, .. } => pending,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        match *self {
+            Channel::Buffer(ref buf) => buf.is_full(),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Channel::Splice { pending, capacity, .. } => pending >= capacity,
+        }
+    }
+
+    // Reads from `src` until the channel is full or `src` would block,
+    // returning true at EOF
+    fn fill<R: TryRead + AsRawFd>(&mut self, src: &mut R) -> io::Result<bool> {
+        match *self {
+            Channel::Buffer(ref mut buf) => {
+                let drain = try!(src.try_read_buf_all(buf));
+                Ok(drain.eof)
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Channel::Splice { ref wr, ref mut pending, capacity, .. } => {
+                while *pending < capacity {
+                    match try!(sys::splice(src.as_raw_fd(), wr.as_raw_fd(), capacity - *pending)) {
+                        Some(0) => return Ok(true),
+                        Some(cnt) => *pending += cnt,
+                        // `src` has nothing to read, or the pipe is full
+                        None => break,
+                    }
+                }
+
+                Ok(false)
+            }
+        }
+    }
+
+    // Writes the bytes in flight to `dst` until it would block
+    fn drain<W: TryWrite + AsRawFd>(&mut self, dst: &mut W) -> io::Result<()> {
+        match *self {
+            Channel::Buffer(ref mut buf) => {
+                if Buf::has_remaining(buf) {
+                    try!(dst.try_write_buf_all(buf));
+                }
+
+                Ok(())
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Channel::Splice { ref rd, ref mut pending, .. } => {
+                while *pending > 0 {
+                    match try!(sys::splice(rd.as_raw_fd(), dst.as_raw_fd(), *pending)) {
+                        Some(0) => break,
+                        Some(cnt) => *pending -= cnt,
+                        None => break,
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use {NotifyError, Sender, Token};
+use std::{fmt, io, thread};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// The outcome of resolving a host name, delivered to the handler as a
+/// message.
+#[derive(Debug)]
+pub struct Resolved {
+    /// The token the resolution was started with.
+    pub token: Token,
+    /// The addresses of the host, in the order returned by the resolver.
+    pub result: io::Result<Vec<SocketAddr>>,
+}
+
+/// Resolves host names without blocking the event loop.
+///
+/// `resolve` only starts the resolution, the result is delivered later as a
+/// `Resolved` tagged with the token. `ThreadResolver` offloads the blocking
+/// system resolver to threads. Asynchronous resolver libraries can be
+/// plugged in by implementing this trait, registering their fds with the
+/// event loop and delivering the results from `Handler::ready`.
+pub trait Resolver {
+    /// Starts resolving `host`, the addresses returned carrying `port`.
+    fn resolve(&mut self, host: &str, port: u16, token: Token) -> io::Result<()>;
+}
+
+struct Job {
+    host: String,
+    port: u16,
+    token: Token,
+}
+
+/// A `Resolver` running `getaddrinfo(3)` on a pool of threads, and
+/// delivering the results over the notify channel of an event loop.
+///
+/// The handler's message type must be convertible from `Resolved`. When the
+/// notify queue is full, the resolver thread waits for the event loop to
+/// make room. Results are dropped once the event loop is gone.
+///
+/// Dropping the resolver stops its threads once they are done with the
+/// resolutions already started, without waiting for them.
+pub struct ThreadResolver {
+    jobs: mpsc::Sender<Job>,
+    threads: usize,
+}
+
+impl ThreadResolver {
+    /// Spawns `threads` resolver threads delivering results with `sender`,
+    /// usually obtained with `EventLoop::channel`.
+    pub fn new<M>(threads: usize, sender: Sender<M>) -> io::Result<ThreadResolver>
+        where M: From<Resolved> + Send + 'static
+    {
+        assert!(threads > 0, "resolver requires at least one thread");
+
+        let (tx, rx) = mpsc::channel();
+        let rx = Arc::new(Mutex::new(rx));
+
+        for _ in 0..threads {
+            let rx = rx.clone();
+            let sender = sender.clone();
+
+            try!(thread::Builder::new()
+                .name("mio-resolver".to_string())
+                .spawn(move || run(rx, sender)));
+        }
+
+        Ok(ThreadResolver {
+            jobs: tx,
+            threads: threads,
+        })
+    }
+}
+
+impl Resolver for ThreadResolver {
+    fn resolve(&mut self, host: &str, port: u16, token: Token) -> io::Result<()> {
+        let job = Job {
+            host: host.to_string(),
+            port: port,
+            token: token,
+        };
+
+        self.jobs.send(job)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "resolver threads are gone"))
+    }
+}
+
+impl fmt::Debug for ThreadResolver {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "ThreadResolver {{ threads: {} }}", self.threads)
+    }
+}
+
+fn run<M>(jobs: Arc<Mutex<mpsc::Receiver<Job>>>, sender: Sender<M>)
+    where M: From<Resolved> + Send
+{
+    loop {
+        // The lock is released before resolving, other threads can pick up
+        // the next job
+        let job = match jobs.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+
+        let result = (&job.host[..], job.port).to_socket_addrs()
+            .map(|addrs| addrs.collect());
+
+        let mut msg: M = From::from(Resolved { token: job.token, result: result });
+
+        loop {
+            match sender.send(msg) {
+                Ok(()) => break,
+                Err(NotifyError::Full(m)) => {
+                    msg = m;
+                    thread::sleep(Duration::from_millis(1));
+                }
+                // Lost with the wakeup, the next results may get through
+                Err(NotifyError::Io(..)) => break,
+                Err(NotifyError::Closed(..)) => return,
+            }
+        }
+    }
+}
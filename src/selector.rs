@@ -0,0 +1,852 @@
+use {io, sys, Error, EventSet, PollOpt, Token};
+use event::IoEvent;
+use signal::Signal;
+use clock_ticks::precise_time_ns;
+use std::{cmp, fmt, mem};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::{Mutex, Once, ONCE_INIT};
+use std::sync::atomic::{AtomicBool, AtomicUsize, ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT};
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+// Ids start at 1, 0 marks handles that are not registered
+static NEXT_SELECTOR_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// Set when a handle was dropped while registered, until the selector it was
+// registered with forgets it
+static DROPPED_PENDING: AtomicBool = ATOMIC_BOOL_INIT;
+
+/// The operations of an OS readiness API the event loop relies on, for
+/// platforms that mio does not support natively.
+///
+/// A backend is handed to `Poll::with_backend` or
+/// `EventLoop::with_backend`, the event loop then calls it with the fds of
+/// every `Evented` handle that is registered.
+pub trait Backend: Send {
+    /// Starts watching `fd` for the readiness in `interest`, reported under
+    /// `token`.
+    fn register(&mut self, fd: RawFd, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()>;
+
+    /// Changes the registration of an already registered `fd`.
+    fn reregister(&mut self, fd: RawFd, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()>;
+
+    /// Stops watching `fd`.
+    fn deregister(&mut self, fd: RawFd) -> io::Result<()>;
+
+    /// Waits for at most `timeout_ms` for registered fds to become ready,
+    /// pushing one event per ready fd to `events`, which is empty when
+    /// called. A wait interrupted by a signal must fail with
+    /// `ErrorKind::Interrupted`.
+    fn select(&mut self, events: &mut Vec<IoEvent>, timeout_ms: usize) -> io::Result<()>;
+
+    /// Returns an fd that is readable when events are pending, used when
+    /// embedding the event loop in another one. Defaults to -1, for
+    /// backends that do not have one.
+    fn as_raw_fd(&self) -> RawFd {
+        -1
+    }
+}
+
+/// Which OS readiness API an event loop polls with, see
+/// `EventLoopConfig::selector`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectorKind {
+    /// The native selector, falling back to `poll(2)` if it cannot be
+    /// created, such as in sandboxes denying `epoll_create`.
+    Auto,
+    /// The native selector (epoll, kqueue...), failing if it cannot be
+    /// created.
+    Native,
+    /// A `poll(2)` based selector, available on every unix platform. Scans
+    /// all registered fds on every poll, and edge-triggered registrations
+    /// behave as level-triggered ones.
+    Poll,
+}
+
+/// The selector `Evented` handles register their fds with. Backed by the
+/// native OS selector (epoll, kqueue...) unless a custom `Backend` is
+/// supplied.
+pub struct Selector {
+    inner: Inner,
+    id: usize,
+    // Lets handles registered with another selector be registered anyway
+    transfer: bool,
+    fds: Box<Fds>,
+}
+
+// The fds registered with a `Selector`, boxed to keep the event loop small
+struct Fds {
+    // The token each registered fd is registered with. The only index of the
+    // registrations, the OS selectors hand back tokens directly.
+    tokens: HashMap<RawFd, Token>,
+    // Tokens of the fds deregistered since the last `take_deregistered`
+    deregistered: Vec<Token>,
+}
+
+enum Inner {
+    Native(sys::Selector),
+    // The backend, and whether interrupted waits are retried
+    Custom(Box<Backend>, bool),
+}
+
+impl Selector {
+    pub fn new() -> io::Result<Selector> {
+        Ok(Selector::with_inner(Inner::Native(try!(sys::Selector::new()))))
+    }
+
+    /// Returns a selector using the readiness API chosen by `kind`.
+    pub fn with_kind(kind: SelectorKind) -> io::Result<Selector> {
+        match kind {
+            SelectorKind::Native => Selector::new(),
+            SelectorKind::Poll => Ok(Selector::with_backend(Box::new(try!(sys::PollBackend::new())))),
+            SelectorKind::Auto => {
+                match Selector::new() {
+                    Ok(selector) => Ok(selector),
+                    Err(e) => {
+                        warn!("native selector unavailable, falling back to poll(2); err={:?}", e);
+                        Selector::with_kind(SelectorKind::Poll)
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn with_backend(backend: Box<Backend>) -> Selector {
+        Selector::with_inner(Inner::Custom(backend, true))
+    }
+
+    fn with_inner(inner: Inner) -> Selector {
+        let id = NEXT_SELECTOR_ID.fetch_add(1, Relaxed) + 1;
+
+        let native_fd = match inner {
+            Inner::Native(ref sel) => sel.as_raw_fd(),
+            Inner::Custom(..) => -1,
+        };
+
+        dropped().lock().unwrap().insert(id, (native_fd, Vec::new()));
+
+        Selector {
+            inner: inner,
+            id: id,
+            transfer: false,
+            fds: Box::new(Fds {
+                tokens: HashMap::new(),
+                deregistered: Vec::new(),
+            }),
+        }
+    }
+
+    /// Identifies the selector among all the selectors of the process, so
+    /// that handles can tell which one they are registered with.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// When set, handles registered with another selector may be registered
+    /// with this one, see `EventLoop::transfer`.
+    pub fn set_transfer(&mut self, transfer: bool) {
+        self.transfer = transfer;
+    }
+
+    pub fn is_transfer(&self) -> bool {
+        self.transfer
+    }
+
+    /// When set (the default), a wait interrupted by a signal is resumed for
+    /// the remaining time instead of failing with `ErrorKind::Interrupted`.
+    pub fn set_retry_interrupted(&mut self, retry: bool) {
+        match self.inner {
+            Inner::Native(ref mut sel) => sel.set_retry_interrupted(retry),
+            Inner::Custom(_, ref mut r) => *r = retry,
+        }
+    }
+
+    /// When set, the pending socket error of every handle reporting an error
+    /// is captured along with the event, see
+    /// `EventLoopConfig::capture_errors`. Custom backends report errors as
+    /// they see fit.
+    pub fn set_capture_errors(&mut self, capture: bool) {
+        if let Inner::Native(ref mut sel) = self.inner {
+            sel.set_capture_errors(capture);
+        }
+    }
+
+    /// When set, registration changes are queued rather than applied
+    /// immediately. Custom backends always apply changes immediately.
+    pub fn set_deferred(&mut self, deferred: bool) {
+        if let Inner::Native(ref mut sel) = self.inner {
+            sel.set_deferred(deferred);
+        }
+    }
+
+    /// Apply all queued registration changes.
+    /// The fds whose add fails are deregistered, their tokens are then
+    /// returned by `take_deregistered`.
+    pub fn flush_deferred(&mut self) -> io::Result<()> {
+        match self.inner {
+            Inner::Native(ref mut sel) => {
+                let res = sel.flush_deferred();
+                forget_failed(sel, &mut self.fds);
+                res
+            }
+            Inner::Custom(..) => Ok(()),
+        }
+    }
+
+    /// Wait for events from the OS
+    pub fn select(&mut self, evts: &mut Events, timeout_ms: usize) -> io::Result<()> {
+        evts.custom.clear();
+        evts.merge.events.clear();
+        evts.coalesced = false;
+
+        match self.inner {
+            Inner::Native(ref mut sel) => {
+                evts.resize();
+
+                // Deferred changes are applied before waiting
+                let res = sel.select(&mut evts.native, timeout_ms);
+                forget_failed(sel, &mut self.fds);
+                try!(res);
+
+                capture_errors(sel, &mut evts.native, &self.fds.tokens);
+                Ok(())
+            }
+            Inner::Custom(ref mut backend, retry) => {
+                let start = precise_time_ns();
+                let mut wait_ms = timeout_ms;
+
+                loop {
+                    match backend.select(&mut evts.custom, wait_ms) {
+                        Err(ref e) if retry && e.kind() == ErrorKind::Interrupted => {
+                            evts.custom.clear();
+
+                            let elapsed = ((precise_time_ns() - start) / 1_000_000) as usize;
+                            wait_ms = if elapsed >= timeout_ms { 0 } else { timeout_ms - elapsed };
+                        }
+                        res => return res,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Register event interests for the given IO handle with the OS.
+    /// Registering an fd that is already registered fails with
+    /// `Error::TokenInUse`, whatever the backend: kqueue and event ports
+    /// would replace the registration instead.
+    pub fn register(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        if let Some(&prev) = self.fds.tokens.get(&fd) {
+            return Err(From::from(Error::TokenInUse(Some(prev))));
+        }
+
+        try!(match self.inner {
+            Inner::Native(ref mut sel) => sel.register(fd, token, interests, opts),
+            Inner::Custom(ref mut backend, _) => backend.register(fd, token, interests, opts),
+        });
+
+        self.fds.tokens.insert(fd, token);
+        Ok(())
+    }
+
+    /// Register event interests for the given IO handle with the OS
+    pub fn reregister(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        try!(match self.inner {
+            Inner::Native(ref mut sel) => sel.reregister(fd, token, interests, opts),
+            Inner::Custom(ref mut backend, _) => backend.reregister(fd, token, interests, opts),
+        });
+
+        self.fds.tokens.insert(fd, token);
+        Ok(())
+    }
+
+    /// Deregister event interests for the given IO handle with the OS
+    pub fn deregister(&mut self, fd: RawFd) -> io::Result<()> {
+        try!(match self.inner {
+            Inner::Native(ref mut sel) => sel.deregister(fd),
+            Inner::Custom(ref mut backend, _) => backend.deregister(fd),
+        });
+
+        if let Some(token) = self.fds.tokens.remove(&fd) {
+            self.fds.deregistered.push(token);
+        }
+
+        Ok(())
+    }
+
+    /// Deregisters the fds of the handles dropped while registered with this
+    /// selector, see `release`. Their tokens are then returned by
+    /// `take_deregistered`. Returns false if there were none.
+    pub fn forget_dropped(&mut self) -> bool {
+        if !DROPPED_PENDING.load(SeqCst) {
+            return false;
+        }
+
+        let fds = {
+            let mut dropped = dropped().lock().unwrap();
+
+            let fds = match dropped.get_mut(&self.id) {
+                Some(entry) => mem::replace(&mut entry.1, Vec::new()),
+                None => Vec::new(),
+            };
+
+            if dropped.values().all(|entry| entry.1.is_empty()) {
+                DROPPED_PENDING.store(false, SeqCst);
+            }
+
+            fds
+        };
+
+        if fds.is_empty() {
+            return false;
+        }
+
+        for fd in fds {
+            trace!("forgetting dropped fd; fd={}", fd);
+
+            match self.inner {
+                Inner::Native(ref mut sel) => sel.forget(fd),
+                Inner::Custom(ref mut backend, _) => {
+                    let _ = backend.deregister(fd);
+                }
+            }
+
+            if let Some(token) = self.fds.tokens.remove(&fd) {
+                self.fds.deregistered.push(token);
+            }
+        }
+
+        true
+    }
+
+    /// Returns the tokens of the fds deregistered since the last call.
+    pub fn take_deregistered(&mut self) -> ::std::vec::Drain<Token> {
+        self.fds.deregistered.drain(..)
+    }
+
+    /// Blocks exactly `signals` while waiting for events, swapping the
+    /// signal mask atomically with the wait. Only the native epoll selector
+    /// supports it.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_sigmask(&mut self, signals: &[Signal]) -> io::Result<()> {
+        match self.inner {
+            Inner::Native(ref mut sel) => sel.set_sigmask(signals),
+            Inner::Custom(..) => Err(sigmask_unsupported()),
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub fn set_sigmask(&mut self, _signals: &[Signal]) -> io::Result<()> {
+        Err(sigmask_unsupported())
+    }
+
+    /// Returns true if timeouts can be driven by kernel timers, see
+    /// `set_timer`. Only the native kqueue selector supports them.
+    #[cfg(any(target_os = "macos",
+              target_os = "ios",
+              target_os = "watchos",
+              target_os = "freebsd",
+              target_os = "dragonfly",
+              target_os = "netbsd",
+              target_os = "openbsd"))]
+    pub fn has_timers(&self) -> bool {
+        match self.inner {
+            Inner::Native(..) => true,
+            Inner::Custom(..) => false,
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos",
+                  target_os = "ios",
+                  target_os = "watchos",
+                  target_os = "freebsd",
+                  target_os = "dragonfly",
+                  target_os = "netbsd",
+                  target_os = "openbsd")))]
+    pub fn has_timers(&self) -> bool {
+        false
+    }
+
+    /// Arms the oneshot kernel timer `id`, its id is returned by
+    /// `Events::pop_timer` once it fires.
+    #[cfg(any(target_os = "macos",
+              target_os = "ios",
+              target_os = "watchos",
+              target_os = "freebsd",
+              target_os = "dragonfly",
+              target_os = "netbsd",
+              target_os = "openbsd"))]
+    pub fn set_timer(&mut self, id: usize, delay_ms: u64) -> io::Result<()> {
+        match self.inner {
+            Inner::Native(ref mut sel) => sel.set_timer(id, delay_ms),
+            Inner::Custom(..) => Err(timers_unsupported()),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos",
+                  target_os = "ios",
+                  target_os = "watchos",
+                  target_os = "freebsd",
+                  target_os = "dragonfly",
+                  target_os = "netbsd",
+                  target_os = "openbsd")))]
+    pub fn set_timer(&mut self, _id: usize, _delay_ms: u64) -> io::Result<()> {
+        Err(timers_unsupported())
+    }
+
+    /// Disarms the kernel timer `id`, also forgetting it if it already fired
+    /// without having been popped from `evts`.
+    #[cfg(any(target_os = "macos",
+              target_os = "ios",
+              target_os = "watchos",
+              target_os = "freebsd",
+              target_os = "dragonfly",
+              target_os = "netbsd",
+              target_os = "openbsd"))]
+    pub fn clear_timer(&mut self, id: usize, evts: &mut Events) -> io::Result<()> {
+        match self.inner {
+            Inner::Native(ref mut sel) => sel.clear_timer(id, &mut evts.native),
+            Inner::Custom(..) => Err(timers_unsupported()),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos",
+                  target_os = "ios",
+                  target_os = "watchos",
+                  target_os = "freebsd",
+                  target_os = "dragonfly",
+                  target_os = "netbsd",
+                  target_os = "openbsd")))]
+    pub fn clear_timer(&mut self, _id: usize, _evts: &mut Events) -> io::Result<()> {
+        Err(timers_unsupported())
+    }
+
+    /// Registers the `EVFILT_USER` event `id` under `token`, returning the
+    /// kqueue fd to trigger it with. Fails if the selector has no user
+    /// events, callers then fall back to a pipe.
+    #[cfg(any(target_os = "macos",
+              target_os = "ios",
+              target_os = "watchos",
+              target_os = "freebsd",
+              target_os = "dragonfly",
+              target_os = "netbsd",
+              target_os = "openbsd"))]
+    pub fn register_user(&mut self, id: usize, token: Token) -> io::Result<RawFd> {
+        match self.inner {
+            Inner::Native(ref mut sel) => {
+                try!(sel.register_user(id, token));
+                Ok(sel.as_raw_fd())
+            }
+            Inner::Custom(..) => Err(user_events_unsupported()),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos",
+                  target_os = "ios",
+                  target_os = "watchos",
+                  target_os = "freebsd",
+                  target_os = "dragonfly",
+                  target_os = "netbsd",
+                  target_os = "openbsd")))]
+    pub fn register_user(&mut self, _id: usize, _token: Token) -> io::Result<RawFd> {
+        Err(user_events_unsupported())
+    }
+
+    #[cfg(any(target_os = "macos",
+              target_os = "ios",
+              target_os = "watchos",
+              target_os = "freebsd",
+              target_os = "dragonfly",
+              target_os = "netbsd",
+              target_os = "openbsd"))]
+    pub fn deregister_user(&mut self, id: usize) -> io::Result<()> {
+        match self.inner {
+            Inner::Native(ref mut sel) => sel.deregister_user(id),
+            Inner::Custom(..) => Err(user_events_unsupported()),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos",
+                  target_os = "ios",
+                  target_os = "watchos",
+                  target_os = "freebsd",
+                  target_os = "dragonfly",
+                  target_os = "netbsd",
+                  target_os = "openbsd")))]
+    pub fn deregister_user(&mut self, _id: usize) -> io::Result<()> {
+        Err(user_events_unsupported())
+    }
+}
+
+fn timers_unsupported() -> io::Error {
+    io::unsupported("kernel timers are not supported by this selector")
+}
+
+fn user_events_unsupported() -> io::Error {
+    io::unsupported("user events are not supported by this selector")
+}
+
+fn sigmask_unsupported() -> io::Error {
+    io::unsupported("a signal mask during the wait is not supported by this selector")
+}
+
+// epoll events only carry the token, the fds of the events reporting an
+// error are looked up in the registrations. The other selectors fetch the
+// errors while polling.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn capture_errors(sel: &sys::Selector, evts: &mut sys::Events, fds: &HashMap<RawFd, Token>) {
+    sel.capture_errors(evts, fds);
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn capture_errors(_sel: &sys::Selector, _evts: &mut sys::Events, _fds: &HashMap<RawFd, Token>) {
+}
+
+// Deregisters the fds whose deferred add failed. Only epoll defers changes.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn forget_failed(sel: &mut sys::Selector, fds: &mut Fds) {
+    for fd in sel.take_failed() {
+        if let Some(token) = fds.tokens.remove(&fd) {
+            fds.deregistered.push(token);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn forget_failed(_sel: &mut sys::Selector, _fds: &mut Fds) {
+}
+
+impl Drop for Selector {
+    fn drop(&mut self) {
+        dropped().lock().unwrap().remove(&self.id);
+    }
+}
+
+impl AsRawFd for Selector {
+    fn as_raw_fd(&self) -> RawFd {
+        match self.inner {
+            Inner::Native(ref sel) => sel.as_raw_fd(),
+            Inner::Custom(ref backend, _) => backend.as_raw_fd(),
+        }
+    }
+}
+
+impl fmt::Debug for Selector {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.inner {
+            Inner::Native(ref sel) => write!(fmt, "Selector {{ native: {:?} }}", sel),
+            Inner::Custom(..) => write!(fmt, "Selector {{ custom }}"),
+        }
+    }
+}
+
+/// Called by a handle registered with the selector `id` right before its
+/// `fd` is closed. The fd is removed from the native selector if closing it
+/// might not be enough, and deregistered from the selector before its next
+/// select, so that the event loop never sees events for dropped handles.
+pub fn release(id: usize, fd: RawFd) {
+    let mut dropped = dropped().lock().unwrap();
+
+    if let Some(entry) = dropped.get_mut(&id) {
+        if entry.0 >= 0 {
+            sys::unregister_closing(entry.0, fd);
+        }
+
+        entry.1.push(fd);
+        DROPPED_PENDING.store(true, SeqCst);
+    }
+}
+
+// For each live selector, its native fd (-1 if none) and the fds of the
+// handles dropped while registered with it
+type Dropped = Mutex<HashMap<usize, (RawFd, Vec<RawFd>)>>;
+
+fn dropped() -> &'static Dropped {
+    static INIT: Once = ONCE_INIT;
+    static mut DROPPED: *const Dropped = 0 as *const Dropped;
+
+    unsafe {
+        INIT.call_once(|| {
+            let dropped: Box<Dropped> = Box::new(Mutex::new(HashMap::new()));
+            DROPPED = mem::transmute(dropped);
+        });
+
+        &*DROPPED
+    }
+}
+
+// Consecutive full selects after which the events buffer is doubled
+const GROW_AFTER: u32 = 2;
+
+// Consecutive selects using at most a quarter of the events buffer after
+// which it is halved
+const SHRINK_AFTER: u32 = 256;
+
+/// The events returned by the last `Selector::select`.
+pub struct Events {
+    native: sys::Events,
+    // Events of a custom backend, plus those injected by a `TestSelector`
+    custom: Vec<IoEvent>,
+    // Set when the capacity of the native buffer adapts to the load
+    sizing: Option<Box<Sizing>>,
+    // Set by `coalesce` when some token was reported more than once
+    coalesced: bool,
+    merge: Box<Merge>,
+    // Number of times the buffers were reallocated, and their capacity
+    // after the last select
+    reallocations: u64,
+    capacity: usize,
+}
+
+// The events merged per token by `coalesce`, used instead of `native` and
+// `custom` once coalesced
+struct Merge {
+    events: Vec<IoEvent>,
+    // The token of each event and its index, sorted so that the events of a
+    // token are next to each other
+    order: Vec<(usize, usize)>,
+}
+
+struct Sizing {
+    min: usize,
+    max: usize,
+    full: u32,
+    low: u32,
+}
+
+impl Events {
+    pub fn new() -> Events {
+        Events {
+            native: sys::Events::new(),
+            custom: Vec::new(),
+            sizing: None,
+            coalesced: false,
+            merge: Box::new(Merge {
+                events: Vec::new(),
+                order: Vec::new(),
+            }),
+            reallocations: 0,
+            capacity: 0,
+        }
+    }
+
+    /// Lets the number of events a select may return vary between `min` and
+    /// `max`. The buffer is doubled when selects keep filling it up, and
+    /// halved after a sustained period using no more than a quarter of it.
+    /// With `min == max`, the capacity is fixed.
+    pub fn set_capacity(&mut self, min: usize, max: usize) {
+        let min = cmp::max(min, 1);
+        let max = cmp::max(max, min);
+
+        if self.native.capacity() != min {
+            self.native.set_capacity(min);
+        }
+
+        self.sizing = if min < max {
+            Some(Box::new(Sizing { min: min, max: max, full: 0, low: 0 }))
+        } else {
+            None
+        };
+    }
+
+    /// The number of events the next select may return.
+    pub fn capacity(&self) -> usize {
+        self.native.capacity()
+    }
+
+    // Adapts the buffer to the usage of the previous select. Called before
+    // selecting, so that the events are not discarded before being read.
+    fn resize(&mut self) {
+        let sizing = match self.sizing {
+            Some(ref mut sizing) => sizing,
+            None => return,
+        };
+
+        let cap = self.native.capacity();
+
+        if self.native.is_full() {
+            sizing.full += 1;
+            sizing.low = 0;
+
+            if sizing.full >= GROW_AFTER && cap < sizing.max {
+                sizing.full = 0;
+                self.native.set_capacity(cmp::min(cap * 2, sizing.max));
+                self.reallocations += 1;
+            }
+        } else if self.native.len() <= cap / 4 {
+            sizing.full = 0;
+            sizing.low += 1;
+
+            if sizing.low >= SHRINK_AFTER && cap > sizing.min {
+                sizing.low = 0;
+                self.native.set_capacity(cmp::max(cap / 2, sizing.min));
+                self.reallocations += 1;
+            }
+        } else {
+            sizing.full = 0;
+            sizing.low = 0;
+        }
+    }
+
+    /// Events added after those returned by the selector, until the next
+    /// `Selector::select`.
+    pub fn extra_mut(&mut self) -> &mut Vec<IoEvent> {
+        &mut self.custom
+    }
+
+    /// Pops the id of a kernel timer that fired, see `Selector::set_timer`.
+    #[cfg(any(target_os = "macos",
+              target_os = "ios",
+              target_os = "watchos",
+              target_os = "freebsd",
+              target_os = "dragonfly",
+              target_os = "netbsd",
+              target_os = "openbsd"))]
+    pub fn pop_timer(&mut self) -> Option<usize> {
+        self.native.pop_timer()
+    }
+
+    #[cfg(not(any(target_os = "macos",
+                  target_os = "ios",
+                  target_os = "watchos",
+                  target_os = "freebsd",
+                  target_os = "dragonfly",
+                  target_os = "netbsd",
+                  target_os = "openbsd")))]
+    pub fn pop_timer(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// Merges the events reported for the same token, so that a handle is
+    /// dispatched once per select with all its readiness. The native
+    /// selectors report a handle at most once per select, kqueue merging
+    /// the filters of a handle itself, but the events of a custom backend
+    /// and those added with `extra_mut` may repeat a token. Call it once
+    /// the events are all in.
+    pub fn coalesce(&mut self) {
+        self.merge.events.clear();
+        self.merge.order.clear();
+        self.coalesced = false;
+
+        if !self.custom.is_empty() {
+            let len = self.native.len() + self.custom.len();
+            self.merge_tokens(len);
+        }
+
+        // The buffers are reused from one select to the next, and only
+        // reallocated while the load grows
+        let capacity = self.custom.capacity() + self.merge.events.capacity() + self.merge.order.capacity();
+
+        if capacity > self.capacity {
+            self.reallocations += 1;
+        }
+
+        self.capacity = capacity;
+    }
+
+    /// Returns the number of times the buffers holding the events were
+    /// reallocated.
+    pub fn reallocations(&self) -> u64 {
+        self.reallocations
+    }
+
+    // Fills `merge.events` with one event per token, in the order each token
+    // was first reported, if some token repeats
+    fn merge_tokens(&mut self, len: usize) {
+        for idx in 0..len {
+            let token = self.get_raw(idx).token;
+            self.merge.order.push((token.as_usize(), idx));
+        }
+
+        self.merge.order.sort();
+
+        if !self.merge.order.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return;
+        }
+
+        // Merges each run of a token into its first event, then replaces the
+        // run by the index of that event and of the merged one
+        let mut runs = 0;
+        let mut i = 0;
+
+        while i < len {
+            let (token, first) = self.merge.order[i];
+            let mut evt = self.get_raw(first);
+
+            i += 1;
+
+            while i < len && self.merge.order[i].0 == token {
+                merge(&mut evt, self.get_raw(self.merge.order[i].1));
+                i += 1;
+            }
+
+            self.merge.order[runs] = (first, runs);
+            self.merge.events.push(evt);
+            runs += 1;
+        }
+
+        self.merge.order.truncate(runs);
+        self.merge.order.sort();
+
+        for k in 0..runs {
+            let evt = self.merge.events[self.merge.order[k].1];
+            self.merge.events.push(evt);
+        }
+
+        self.merge.events.drain(..runs);
+        self.coalesced = true;
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        if self.coalesced {
+            return self.merge.events.len();
+        }
+
+        self.native.len() + self.custom.len()
+    }
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> IoEvent {
+        if self.coalesced {
+            return self.merge.events[idx];
+        }
+
+        self.get_raw(idx)
+    }
+
+    #[inline]
+    fn get_raw(&self, idx: usize) -> IoEvent {
+        let len = self.native.len();
+
+        if idx < len {
+            normalize(self.native.get(idx))
+        } else {
+            self.custom[idx - len]
+        }
+    }
+}
+
+// Adds the readiness of `other` to `evt`, keeping the first socket error
+// reported
+fn merge(evt: &mut IoEvent, other: IoEvent) {
+    evt.kind.insert(other.kind);
+
+    if evt.error().is_none() {
+        if let Some(code) = other.error() {
+            evt.set_error(code);
+        }
+    }
+}
+
+// Backends disagree on what a peer close reports: epoll may report a bare
+// `EPOLLHUP`, kqueue sets `EV_EOF` on whichever filter fired. A hangup is
+// always reported as readable as well, so that reading sees the end of
+// stream whatever the platform.
+fn normalize(mut evt: IoEvent) -> IoEvent {
+    if evt.kind.is_hup() {
+        evt.kind.insert(EventSet::readable());
+    }
+
+    evt
+}
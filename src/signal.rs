@@ -0,0 +1,18 @@
+//! Signals that can be delivered through the event loop, see
+//! `EventLoop::register_signals` and `Handler::signal`.
+
+pub use sys::signal::{
+    Signal,
+    SIGHUP,
+    SIGINT,
+    SIGQUIT,
+    SIGPIPE,
+    SIGALRM,
+    SIGTERM,
+    SIGCHLD,
+    SIGCONT,
+    SIGTSTP,
+    SIGWINCH,
+    SIGUSR1,
+    SIGUSR2,
+};
@@ -2,13 +2,28 @@
 pub use self::unix::{
     Awakener,
     Events,
+    FdReserve,
     Io,
+    PollBackend,
     Selector,
+    Signals,
     TcpSocket,
     UdpSocket,
     UnixSocket,
+    interface,
     pipe,
+    pread,
+    pwrite,
+    readv,
+    set_cpu_affinity,
+    set_timer_slack,
+    signal,
+    unregister_closing,
+    writev,
 };
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::unix::splice;
+
 #[cfg(unix)]
 mod unix;
@@ -0,0 +1,82 @@
+use io;
+use libc;
+use std::mem;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+use std::io::ErrorKind;
+
+/// Restricts the calling thread to the given CPUs, typically to pin an event
+/// loop thread to a core. Supported on Linux and FreeBSD, fails with an
+/// `Unsupported` error on other platforms.
+#[cfg(target_os = "linux")]
+pub fn set_cpu_affinity(cpus: &[usize]) -> io::Result<()> {
+    // glibc's cpu_set_t, room for 1024 CPUs
+    let set = try!(cpu_set::<[u64; 16]>(cpus, [0; 16]));
+
+    let res = unsafe {
+        sched_setaffinity(0, mem::size_of_val(&set) as libc::size_t, set.as_ptr() as *const libc::c_void)
+    };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Restricts the calling thread to the given CPUs.
+#[cfg(target_os = "freebsd")]
+pub fn set_cpu_affinity(cpus: &[usize]) -> io::Result<()> {
+    const CPU_LEVEL_WHICH: libc::c_int = 3;
+    const CPU_WHICH_TID: libc::c_int = 1;
+
+    // cpuset_t, room for 256 CPUs
+    let set = try!(cpu_set::<[u64; 4]>(cpus, [0; 4]));
+
+    // An id of -1 refers to the calling thread
+    let res = unsafe {
+        cpuset_setaffinity(CPU_LEVEL_WHICH, CPU_WHICH_TID, -1,
+                           mem::size_of_val(&set) as libc::size_t, set.as_ptr() as *const libc::c_void)
+    };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// CPU affinity is not supported on this platform, always fails with an
+/// `Unsupported` error.
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+pub fn set_cpu_affinity(_cpus: &[usize]) -> io::Result<()> {
+    Err(io::unsupported("CPU affinity is not supported on this platform"))
+}
+
+// Sets the bit of every CPU in `set`
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn cpu_set<S: AsMut<[u64]>>(cpus: &[usize], mut set: S) -> io::Result<S> {
+    {
+        let bits = set.as_mut();
+
+        for &cpu in cpus {
+            if cpu >= bits.len() * 64 {
+                return Err(io::Error::new(ErrorKind::InvalidInput, "CPU index out of range"));
+            }
+
+            bits[cpu / 64] |= 1 << (cpu % 64);
+        }
+    }
+
+    Ok(set)
+}
+
+#[cfg(target_os = "linux")]
+extern {
+    fn sched_setaffinity(pid: libc::pid_t, size: libc::size_t, mask: *const libc::c_void) -> libc::c_int;
+}
+
+#[cfg(target_os = "freebsd")]
+extern {
+    fn cpuset_setaffinity(level: libc::c_int, which: libc::c_int, id: i64,
+                          size: libc::size_t, mask: *const libc::c_void) -> libc::c_int;
+}
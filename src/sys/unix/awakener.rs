@@ -1,6 +1,130 @@
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "watchos",
+          target_os = "freebsd",
+          target_os = "dragonfly",
+          target_os = "netbsd",
+          target_os = "openbsd"))]
+pub use self::user::Awakener;
+
+#[cfg(not(any(target_os = "macos",
+              target_os = "ios",
+              target_os = "watchos",
+              target_os = "freebsd",
+              target_os = "dragonfly",
+              target_os = "netbsd",
+              target_os = "openbsd")))]
 pub use self::pipe::Awakener;
 
-/// Default *nix awakener implementation
+/// kqueue awakener, triggering an `EVFILT_USER` event instead of writing to
+/// a pipe. This saves the two fds of the pipe, and a read to drain it after
+/// every wakeup. The pipe is only created when registered with a selector
+/// that has no user events, such as a custom `Backend`.
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "watchos",
+          target_os = "freebsd",
+          target_os = "dragonfly",
+          target_os = "netbsd",
+          target_os = "openbsd"))]
+mod user {
+    use {io, Evented, EventSet, PollOpt, Selector, Token};
+    use sys::unix::kqueue;
+    use std::cell::UnsafeCell;
+    use std::sync::atomic::{AtomicIsize, AtomicUsize};
+    use std::sync::atomic::Ordering::SeqCst;
+
+    // Ident of the user event, each event loop has its own kqueue
+    const IDENT: usize = 0;
+
+    pub struct Awakener {
+        // The kqueue the user event is registered with, -1 when not
+        // registered
+        kq: AtomicIsize,
+        // Token the user event is reported under
+        token: AtomicUsize,
+        // Fallback for selectors without user events. Only set while
+        // registering, before the awakener is shared with other threads.
+        pipe: UnsafeCell<Option<super::pipe::Awakener>>,
+    }
+
+    impl Awakener {
+        pub fn new() -> io::Result<Awakener> {
+            Ok(Awakener {
+                kq: AtomicIsize::new(-1),
+                token: AtomicUsize::new(0),
+                pipe: UnsafeCell::new(None),
+            })
+        }
+
+        pub fn wakeup(&self) -> io::Result<()> {
+            let kq = self.kq.load(SeqCst);
+
+            if kq >= 0 {
+                return kqueue::trigger_user(kq as i32, IDENT, Token(self.token.load(SeqCst)));
+            }
+
+            match *self.pipe() {
+                Some(ref pipe) => pipe.wakeup(),
+                // Not registered yet, there is nobody to wake up
+                None => Ok(()),
+            }
+        }
+
+        pub fn cleanup(&self) {
+            // The user event is registered with EV_CLEAR, it resets itself
+            // once reported
+            if let Some(ref pipe) = *self.pipe() {
+                pipe.cleanup();
+            }
+        }
+
+        fn pipe(&self) -> &Option<super::pipe::Awakener> {
+            unsafe { &*self.pipe.get() }
+        }
+    }
+
+    impl Evented for Awakener {
+        fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+            match selector.register_user(IDENT, token) {
+                Ok(kq) => {
+                    self.token.store(token.as_usize(), SeqCst);
+                    self.kq.store(kq as isize, SeqCst);
+                    Ok(())
+                }
+                Err(_) => {
+                    let pipe = try!(super::pipe::Awakener::new());
+                    try!(pipe.register(selector, token, interest, opts));
+
+                    unsafe { *self.pipe.get() = Some(pipe); }
+                    Ok(())
+                }
+            }
+        }
+
+        fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+            match *self.pipe() {
+                Some(ref pipe) => pipe.reregister(selector, token, interest, opts),
+                None => {
+                    self.token.store(token.as_usize(), SeqCst);
+                    selector.register_user(IDENT, token).map(|_| ())
+                }
+            }
+        }
+
+        fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+            match *self.pipe() {
+                Some(ref pipe) => pipe.deregister(selector),
+                None => {
+                    self.kq.store(-1, SeqCst);
+                    selector.deregister_user(IDENT)
+                }
+            }
+        }
+    }
+}
+
+/// Default *nix awakener implementation, writing to a pipe
 mod pipe {
     use {io, Evented, EventSet, PollOpt, Selector, Token, TryRead, TryWrite};
     use unix::{self, PipeReader, PipeWriter};
@@ -1,66 +1,247 @@
-use {io, EventSet, PollOpt, Token};
+use {io, Error, EventSet, PollOpt, Token};
 use event::IoEvent;
 use nix::sys::epoll::*;
+use nix::sys::signal::sigset_t;
 use nix::unistd::close;
-use std::os::unix::io::RawFd;
+use signal::Signal;
+use sys::unix::nix;
+use std::{fmt, mem, vec};
+use std::collections::HashMap;
+use std::os::unix::io::{AsRawFd, RawFd};
 
 #[derive(Debug)]
 pub struct Selector {
-    epfd: RawFd
+    epfd: RawFd,
+    deferred: Box<Deferred>,
+    retry_interrupted: bool,
+    // Fetch `SO_ERROR` for events reporting an error, see
+    // `set_capture_errors`
+    capture_errors: bool,
+    // Signal mask applied while waiting, see `set_sigmask`
+    sigmask: Option<Box<SigMask>>,
 }
 
 impl Selector {
     pub fn new() -> io::Result<Selector> {
-        let epfd = try!(epoll_create().map_err(super::from_nix_error));
+        let epfd = try!(epoll_create_cloexec());
+
+        Ok(Selector {
+            epfd: epfd,
+            deferred: Box::new(Deferred {
+                enabled: false,
+                pending: HashMap::new(),
+                failed: Vec::new(),
+            }),
+            retry_interrupted: true,
+            capture_errors: false,
+            sigmask: None,
+        })
+    }
+
+    /// When set (the default), a wait interrupted by a signal is resumed for
+    /// the remaining time instead of failing with `ErrorKind::Interrupted`.
+    pub fn set_retry_interrupted(&mut self, retry: bool) {
+        self.retry_interrupted = retry;
+    }
+
+    /// When set, the pending `SO_ERROR` of every fd reporting an error is
+    /// fetched by `capture_errors`. Fetching the error clears it on the
+    /// socket, so this is off by default.
+    pub fn set_capture_errors(&mut self, capture: bool) {
+        self.capture_errors = capture;
+    }
+
+    /// Blocks exactly `signals` while waiting for events, with
+    /// `epoll_pwait`. The mask of the thread is swapped atomically with the
+    /// wait, so a signal unblocked by `signals` either interrupts the wait or
+    /// is delivered once the thread mask is restored.
+    pub fn set_sigmask(&mut self, signals: &[Signal]) -> io::Result<()> {
+        let mut set: sigset_t = unsafe { mem::zeroed() };
+
+        unsafe { sigemptyset(&mut set); }
+
+        for &signal in signals {
+            if unsafe { sigaddset(&mut set, signal) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        self.sigmask = Some(Box::new(SigMask(set)));
+        Ok(())
+    }
 
-        Ok(Selector { epfd: epfd })
+    /// When set, registration changes are queued rather than applied
+    /// immediately.
+    pub fn set_deferred(&mut self, deferred: bool) {
+        self.deferred.enabled = deferred;
+    }
+
+    /// Apply all queued registration changes, returning the first error
+    /// encountered. Every change is attempted regardless of errors, the fds
+    /// whose add failed are then returned by `take_failed`.
+    pub fn flush_deferred(&mut self) -> io::Result<()> {
+        let mut ret = Ok(());
+
+        for (fd, (add, token, interests, opts)) in self.deferred.pending.drain() {
+            let op = if add { EpollOp::EpollCtlAdd } else { EpollOp::EpollCtlMod };
+            let info = EpollEvent {
+                events: ioevent_to_epoll(interests, opts),
+                data: token.as_usize() as u64
+            };
+
+            match epoll_ctl(self.epfd, op, fd, &info) {
+                Ok(_) => {}
+                Err(e) => {
+                    if add {
+                        self.deferred.failed.push(fd);
+                    }
+
+                    if ret.is_ok() {
+                        ret = Err(if add { add_error(e) } else { super::from_nix_error(e) });
+                    }
+                }
+            }
+        }
+
+        ret
+    }
+
+    /// Returns the fds whose deferred add failed since the last call, they
+    /// are not registered.
+    pub fn take_failed(&mut self) -> vec::Drain<RawFd> {
+        self.deferred.failed.drain(..)
+    }
+
+    fn defer(&mut self, fd: RawFd, add: bool, token: Token, interests: EventSet, opts: PollOpt) {
+        // A pending add stays an add even if it is modified again before the
+        // changes are flushed.
+        let add = add || self.deferred.pending.get(&fd).map(|&(add, _, _, _)| add).unwrap_or(false);
+        self.deferred.pending.insert(fd, (add, token, interests, opts));
     }
 
     /// Wait for events from the OS
     pub fn select(&mut self, evts: &mut Events, timeout_ms: usize) -> io::Result<()> {
+        use clock_ticks::precise_time_ns;
         use std::{isize, slice};
 
         let timeout_ms = if timeout_ms >= isize::MAX as usize {
-            isize::MAX
+            isize::MAX as usize
         } else {
-            timeout_ms as isize
+            timeout_ms
         };
 
+        // Deferred changes must be visible to this poll. There is no caller
+        // to report errors to at this point, so they are only logged.
+        if let Err(e) = self.flush_deferred() {
+            warn!("failed to apply deferred registration; err={:?}", e);
+        }
+
         let dst = unsafe {
             slice::from_raw_parts_mut(
                 evts.events.as_mut_ptr(),
                 evts.events.capacity())
         };
 
+        let start = precise_time_ns();
+        let mut wait_ms = timeout_ms;
+
         // Wait for epoll events for at most timeout_ms milliseconds
-        let cnt = try!(epoll_wait(self.epfd, dst, timeout_ms)
-                           .map_err(super::from_nix_error));
+        let cnt;
+
+        loop {
+            let res = match self.sigmask {
+                Some(ref mask) => epoll_pwait(self.epfd, dst, wait_ms, mask),
+                None => epoll_wait(self.epfd, dst, wait_ms as isize),
+            };
+
+            match res {
+                Ok(n) => {
+                    cnt = n;
+                    break;
+                }
+                Err(nix::Error::Sys(nix::EINTR)) if self.retry_interrupted => {
+                    wait_ms = super::remaining_ms(start, timeout_ms);
+                }
+                Err(e) => return Err(super::from_nix_error(e)),
+            }
+        }
 
         unsafe { evts.events.set_len(cnt); }
 
+        evts.errors.clear();
+
         Ok(())
     }
 
+    /// Fetches the pending `SO_ERROR` for every event flagged with EPOLLERR
+    /// so that it can be handed to the handler along with the readiness, if
+    /// `set_capture_errors` is set. epoll only hands back the token, the fd
+    /// is looked up in `fds`, the fds registered with the selector.
+    pub fn capture_errors(&self, evts: &mut Events, fds: &HashMap<RawFd, Token>) {
+        if !self.capture_errors {
+            return;
+        }
+
+        for idx in 0..evts.events.len() {
+            let evt = evts.events[idx];
+            let kind = evt.events;
+
+            if !kind.contains(EPOLLERR) {
+                continue;
+            }
+
+            let token = Token(evt.data as usize);
+
+            // Errors are rare, scanning the registrations when one is reported
+            // is cheaper than indexing every registration by token as well
+            if let Some((&fd, _)) = fds.iter().find(|&(_, &t)| t == token) {
+                match nix::getsockopt(fd, nix::sockopt::SocketError) {
+                    Ok(code) if code != 0 => evts.errors.push((idx, code)),
+                    _ => {}
+                }
+            }
+        }
+    }
+
     /// Register event interests for the given IO handle with the OS
     pub fn register(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        if self.deferred.enabled {
+            self.defer(fd, true, token, interests, opts);
+            return Ok(());
+        }
+
         let info = EpollEvent {
             events: ioevent_to_epoll(interests, opts),
             data: token.as_usize() as u64
         };
 
-        epoll_ctl(self.epfd, EpollOp::EpollCtlAdd, fd, &info)
-            .map_err(super::from_nix_error)
+        // An immediate change supersedes anything queued for the fd
+        self.deferred.pending.remove(&fd);
+
+        try!(epoll_ctl(self.epfd, EpollOp::EpollCtlAdd, fd, &info)
+                 .map_err(add_error));
+
+        Ok(())
     }
 
     /// Register event interests for the given IO handle with the OS
     pub fn reregister(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        if self.deferred.enabled {
+            self.defer(fd, false, token, interests, opts);
+            return Ok(());
+        }
+
         let info = EpollEvent {
             events: ioevent_to_epoll(interests, opts),
             data: token.as_usize() as u64
         };
 
-        epoll_ctl(self.epfd, EpollOp::EpollCtlMod, fd, &info)
-            .map_err(super::from_nix_error)
+        self.deferred.pending.remove(&fd);
+
+        try!(epoll_ctl(self.epfd, EpollOp::EpollCtlMod, fd, &info)
+                 .map_err(super::from_nix_error));
+
+        Ok(())
     }
 
     /// Deregister event interests for the given IO handle with the OS
@@ -73,8 +254,118 @@ impl Selector {
             data: 0
         };
 
-        epoll_ctl(self.epfd, EpollOp::EpollCtlDel, fd, &info)
-            .map_err(super::from_nix_error)
+        // An add still queued was never applied, the fd is not in the epoll
+        // set
+        if let Some((true, _, _, _)) = self.deferred.pending.remove(&fd) {
+            return Ok(());
+        }
+
+        try!(epoll_ctl(self.epfd, EpollOp::EpollCtlDel, fd, &info)
+                 .map_err(super::from_nix_error));
+
+        Ok(())
+    }
+
+    /// Forgets a closed fd, already removed from the epoll set by
+    /// `unregister_closing`.
+    pub fn forget(&mut self, fd: RawFd) {
+        self.deferred.pending.remove(&fd);
+    }
+}
+
+// Registration changes queued while deferring, see `set_deferred`
+#[derive(Debug)]
+struct Deferred {
+    enabled: bool,
+    // Coalesced per fd and applied in a single pass before the next
+    // epoll_wait. The flag is true for adds.
+    pending: HashMap<RawFd, (bool, Token, EventSet, PollOpt)>,
+    // The fds whose add failed when flushed, see `take_failed`
+    failed: Vec<RawFd>,
+}
+
+// A signal mask for `epoll_pwait`
+struct SigMask(sigset_t);
+
+impl fmt::Debug for SigMask {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "SigMask")
+    }
+}
+
+fn epoll_pwait(epfd: RawFd, events: &mut [EpollEvent], timeout_ms: usize, mask: &SigMask) -> Result<usize, nix::Error> {
+    use nix::errno::Errno;
+
+    // As with epoll_wait, `isize::MAX` truncates to -1 and waits forever
+    let res = unsafe {
+        ffi_epoll_pwait(epfd, events.as_mut_ptr(), events.len() as nix::c_int,
+                        timeout_ms as nix::c_int, &mask.0)
+    };
+
+    if res < 0 {
+        return Err(nix::Error::Sys(Errno::last()));
+    }
+
+    Ok(res as usize)
+}
+
+// epoll_create1 sets close-on-exec atomically. Kernels older than 2.6.27
+// lack it, the flag is then set right after epoll_create.
+fn epoll_create_cloexec() -> io::Result<RawFd> {
+    let epfd = unsafe { ffi_epoll_create1(EPOLL_CLOEXEC) };
+
+    if epfd >= 0 {
+        return Ok(epfd);
+    }
+
+    let err = io::Error::last_os_error();
+
+    if err.raw_os_error() != Some(ENOSYS) {
+        return Err(err);
+    }
+
+    let epfd = try!(epoll_create().map_err(super::from_nix_error));
+
+    if let Err(e) = super::set_cloexec(epfd, true) {
+        let _ = close(epfd);
+        return Err(e);
+    }
+
+    Ok(epfd)
+}
+
+const EPOLL_CLOEXEC: nix::c_int = 0o2000000;
+const ENOSYS: i32 = 38;
+
+extern {
+    #[link_name = "epoll_create1"]
+    fn ffi_epoll_create1(flags: nix::c_int) -> nix::c_int;
+    #[link_name = "epoll_pwait"]
+    fn ffi_epoll_pwait(epfd: nix::c_int, events: *mut EpollEvent, max_events: nix::c_int,
+                       timeout: nix::c_int, sigmask: *const sigset_t) -> nix::c_int;
+    fn sigemptyset(set: *mut sigset_t) -> nix::c_int;
+    fn sigaddset(set: *mut sigset_t, signum: nix::c_int) -> nix::c_int;
+}
+
+/// Removes `fd` from the epoll set `epfd`, right before it is closed. epoll
+/// watches the file rather than the fd, so the registration would otherwise
+/// outlive the fd as long as a duplicate of it is open. Safe to call from
+/// any thread.
+pub fn unregister_closing(epfd: RawFd, fd: RawFd) {
+    let info = EpollEvent {
+        events: EpollEventKind::empty(),
+        data: 0
+    };
+
+    let _ = epoll_ctl(epfd, EpollOp::EpollCtlDel, fd, &info);
+}
+
+// Translate an EEXIST from an add into the error reporting that the fd is
+// registered already.
+fn add_error(err: nix::Error) -> io::Error {
+    match err {
+        nix::Error::Sys(nix::EEXIST) => From::from(Error::TokenInUse(None)),
+        _ => super::from_nix_error(err),
     }
 }
 
@@ -108,6 +399,12 @@ fn ioevent_to_epoll(interest: EventSet, opts: PollOpt) -> EpollEventKind {
     kind
 }
 
+impl AsRawFd for Selector {
+    fn as_raw_fd(&self) -> RawFd {
+        self.epfd
+    }
+}
+
 impl Drop for Selector {
     fn drop(&mut self) {
         let _ = close(self.epfd);
@@ -116,12 +413,15 @@ impl Drop for Selector {
 
 pub struct Events {
     events: Vec<EpollEvent>,
+    // (index, errno) pairs for the events that reported a socket error
+    errors: Vec<(usize, i32)>,
 }
 
 impl Events {
     pub fn new() -> Events {
         Events {
             events: Vec::with_capacity(1024),
+            errors: Vec::new(),
         }
     }
 
@@ -130,6 +430,24 @@ impl Events {
         self.events.len()
     }
 
+    /// Whether the last select filled the buffer.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.events.len() == self.events.capacity()
+    }
+
+    /// The maximum number of events returned by a select.
+    pub fn capacity(&self) -> usize {
+        self.events.capacity()
+    }
+
+    /// Replaces the buffer with one holding up to `cap` events, discarding
+    /// the current events.
+    pub fn set_capacity(&mut self, cap: usize) {
+        self.events = Vec::with_capacity(cap);
+        self.errors.clear();
+    }
+
     #[inline]
     pub fn get(&self, idx: usize) -> IoEvent {
         let epoll = self.events[idx].events;
@@ -153,7 +471,14 @@ impl Events {
         }
 
         let token = self.events[idx].data;
+        let mut evt = IoEvent::new(kind, Token(token as usize));
+
+        if kind.is_error() {
+            if let Some(&(_, code)) = self.errors.iter().find(|&&(i, _)| i == idx) {
+                evt.set_error(code);
+            }
+        }
 
-        IoEvent::new(kind, Token(token as usize))
+        evt
     }
 }
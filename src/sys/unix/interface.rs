@@ -0,0 +1,179 @@
+use {io, IpAddr, Ipv4Addr, Ipv6Addr};
+use libc;
+use std::{mem, ptr};
+use std::ffi::{CStr, CString};
+use std::io::ErrorKind;
+
+const IFF_UP: u32 = 0x1;
+const IFF_BROADCAST: u32 = 0x2;
+const IFF_LOOPBACK: u32 = 0x8;
+const IFF_RUNNING: u32 = 0x40;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const IFF_MULTICAST: u32 = 0x1000;
+
+#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+const IFF_MULTICAST: u32 = 0x800;
+
+#[cfg(not(any(target_os = "linux",
+              target_os = "android",
+              target_os = "solaris",
+              target_os = "illumos")))]
+const IFF_MULTICAST: u32 = 0x8000;
+
+/// A network interface of the host, as listed by `interfaces`.
+#[derive(Clone, Debug)]
+pub struct Interface {
+    name: String,
+    index: u32,
+    flags: u32,
+    addrs: Vec<IpAddr>,
+}
+
+impl Interface {
+    /// Returns the name of the interface, such as `eth0` or `lo0`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the index of the interface, to pass to the IPv6 multicast
+    /// and scope APIs.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the IPv4 and IPv6 addresses assigned to the interface.
+    pub fn addrs(&self) -> &[IpAddr] {
+        &self.addrs
+    }
+
+    /// Returns the first IPv4 address assigned to the interface, which
+    /// identifies it in the IPv4 multicast APIs.
+    pub fn ipv4_addr(&self) -> Option<Ipv4Addr> {
+        self.addrs.iter()
+            .filter_map(|addr| match *addr {
+                IpAddr::V4(addr) => Some(addr),
+                IpAddr::V6(..) => None,
+            })
+            .next()
+    }
+
+    /// Returns the raw `IFF_*` flags of the interface.
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.flags & IFF_UP != 0
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.flags & IFF_RUNNING != 0
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.flags & IFF_LOOPBACK != 0
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.flags & IFF_BROADCAST != 0
+    }
+
+    pub fn is_multicast(&self) -> bool {
+        self.flags & IFF_MULTICAST != 0
+    }
+}
+
+/// Lists the network interfaces of the host with `getifaddrs(3)`, in the
+/// order it returns them.
+pub fn interfaces() -> io::Result<Vec<Interface>> {
+    let mut head: *mut libc::ifaddrs = ptr::null_mut();
+
+    if unsafe { libc::getifaddrs(&mut head) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let ret = unsafe { collect(head) };
+
+    unsafe { libc::freeifaddrs(head); }
+    Ok(ret)
+}
+
+/// Returns the interface named `name`.
+pub fn interface(name: &str) -> io::Result<Interface> {
+    let all = try!(interfaces());
+
+    all.into_iter()
+        .find(|iface| iface.name == name)
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no such network interface"))
+}
+
+/// Returns the index of the interface named `name`, with
+/// `if_nametoindex(3)`.
+pub fn index(name: &str) -> io::Result<u32> {
+    let name = try!(CString::new(name)
+        .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "interface name contains a nul byte")));
+
+    match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+        0 => Err(io::Error::new(ErrorKind::NotFound, "no such network interface")),
+        index => Ok(index as u32),
+    }
+}
+
+// getifaddrs(3) returns one entry per address, which are merged per
+// interface
+unsafe fn collect(mut cur: *mut libc::ifaddrs) -> Vec<Interface> {
+    let mut ret: Vec<Interface> = Vec::new();
+
+    while !cur.is_null() {
+        let entry = &*cur;
+        cur = entry.ifa_next;
+
+        let name = CStr::from_ptr(entry.ifa_name).to_string_lossy().into_owned();
+        let addr = to_ip_addr(entry.ifa_addr);
+
+        if let Some(iface) = ret.iter_mut().find(|iface| iface.name == name) {
+            iface.addrs.extend(addr);
+            continue;
+        }
+
+        // Removed since getifaddrs(3) returned
+        let idx = match index(&name) {
+            Ok(idx) => idx,
+            Err(..) => continue,
+        };
+
+        ret.push(Interface {
+            name: name,
+            index: idx,
+            flags: entry.ifa_flags as u32,
+            addrs: addr.into_iter().collect(),
+        });
+    }
+
+    ret
+}
+
+unsafe fn to_ip_addr(addr: *const libc::sockaddr) -> Option<IpAddr> {
+    if addr.is_null() {
+        return None;
+    }
+
+    match (*addr).sa_family as libc::c_int {
+        libc::AF_INET => {
+            let addr = &*(addr as *const libc::sockaddr_in);
+            let octets: [u8; 4] = mem::transmute(addr.sin_addr.s_addr);
+
+            Some(IpAddr::V4(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3])))
+        }
+        libc::AF_INET6 => {
+            let addr = &*(addr as *const libc::sockaddr_in6);
+            let s = addr.sin6_addr.s6_addr;
+
+            Some(IpAddr::V6(Ipv6Addr::new(
+                u16::from_be(s[0]), u16::from_be(s[1]), u16::from_be(s[2]), u16::from_be(s[3]),
+                u16::from_be(s[4]), u16::from_be(s[5]), u16::from_be(s[6]), u16::from_be(s[7]))))
+        }
+        _ => None,
+    }
+}
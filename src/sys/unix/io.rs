@@ -1,6 +1,20 @@
-use {io, Evented, EventSet, PollOpt, Selector, Token};
+use {io, selector, Error, Evented, EventSet, PollOpt, Selector, Token};
+use buf::{IoVec, IoVecMut};
+use libc;
+use std::{cmp, fmt, mem};
 use std::io::{Read, Write};
-use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+
+#[cfg(feature = "fd-tracking")]
+use fd_tracking::{opened as track_open, closed as track_close};
+
+#[cfg(not(feature = "fd-tracking"))]
+fn track_open(_: RawFd) {}
+
+#[cfg(not(feature = "fd-tracking"))]
+fn track_close(_: RawFd) {}
 
 /*
  *
@@ -8,20 +22,76 @@ use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
  *
  */
 
-#[derive(Debug)]
 pub struct Io {
     fd: RawFd,
+    // Id of the selector the fd is registered with, 0 if none. An fd
+    // registered with two selectors would have its events split between
+    // them.
+    owner: AtomicUsize,
 }
 
 impl Io {
     pub fn from_raw_fd(fd: RawFd) -> Io {
-        Io { fd: fd }
+        track_open(fd);
+
+        Io {
+            fd: fd,
+            owner: AtomicUsize::new(0),
+        }
+    }
+
+    /// Creates a new handle to the same file description, with its own
+    /// close-on-exec fd. Both handles share the file offset and status
+    /// flags, such as `O_NONBLOCK`.
+    pub fn try_clone(&self) -> io::Result<Io> {
+        super::net::dup(self)
+    }
+
+    /// Switches the fd between non-blocking and blocking mode, for example
+    /// to hand it off to code doing blocking IO.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        super::net::set_nonblock(self, nonblocking)
+    }
+
+    /// Clears or sets the close-on-exec flag, so that the fd is or is not
+    /// inherited by programs the process executes.
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        super::set_cloexec(self.fd, !inheritable)
+    }
+
+    /// Performs an `ioctl(2)` mio does not wrap, such as an interface query
+    /// or a device control, on the fd. The fd stays owned by the handle.
+    ///
+    /// # Safety
+    ///
+    /// `arg` must be what `request` expects, usually a pointer to memory of
+    /// the size and layout the request reads or writes.
+    pub unsafe fn ioctl(&self, request: libc::c_ulong, arg: *mut libc::c_void) -> io::Result<libc::c_int> {
+        let res = ffi::ioctl(self.fd, request, arg);
+
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(res)
+    }
+
+    /// Returns the number of bytes that can be read without blocking, with
+    /// the `FIONREAD` ioctl.
+    pub fn bytes_readable(&self) -> io::Result<usize> {
+        let mut cnt: libc::c_int = 0;
+
+        try!(unsafe {
+            self.ioctl(ffi::FIONREAD, &mut cnt as *mut libc::c_int as *mut libc::c_void)
+        });
+
+        Ok(cnt as usize)
     }
 }
 
 impl From<RawFd> for Io {
     fn from(fd: RawFd) -> Io {
-        Io { fd: fd }
+        Io::from_raw_fd(fd)
     }
 }
 
@@ -37,21 +107,80 @@ impl AsRawFd for Io {
     }
 }
 
+/// Gives up ownership of the fd without closing it. A registered handle is
+/// deregistered first, as if it was dropped.
+impl IntoRawFd for Io {
+    fn into_raw_fd(self) -> RawFd {
+        let fd = self.fd;
+
+        self.release();
+        mem::forget(self);
+        fd
+    }
+}
+
 impl Evented for Io {
     fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
-        selector.register(self.fd, token, interest, opts)
+        if !selector.is_transfer() {
+            try!(self.check_owner(selector));
+        }
+
+        try!(selector.register(self.fd, token, interest, opts));
+        self.owner.store(selector.id(), Relaxed);
+        Ok(())
     }
 
     fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        try!(self.check_owner(selector));
         selector.reregister(self.fd, token, interest, opts)
     }
 
     fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
-        selector.deregister(self.fd)
+        try!(self.check_owner(selector));
+        try!(selector.deregister(self.fd));
+        self.owner.store(0, Relaxed);
+        Ok(())
+    }
+}
+
+impl Io {
+    // Dropping a registered handle deregisters it, whatever the selector
+    fn release(&self) {
+        let owner = self.owner.load(Relaxed);
+
+        if owner != 0 {
+            selector::release(owner, self.fd);
+        }
+
+        track_close(self.fd);
+    }
+
+    fn check_owner(&self, selector: &Selector) -> io::Result<()> {
+        let owner = self.owner.load(Relaxed);
+
+        if owner != 0 && owner != selector.id() {
+            return Err(From::from(Error::RegisteredElsewhere));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Io {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Io {{ fd: {} }}", self.fd)
     }
 }
 
 impl Read for Io {
+    fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(dst)
+    }
+}
+
+// Reads and writes only go through the fd, so they do not need exclusive
+// access, as with `std::net::TcpStream`
+impl<'a> Read for &'a Io {
     fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
         use nix::unistd::read;
 
@@ -61,6 +190,16 @@ impl Read for Io {
 }
 
 impl Write for Io {
+    fn write(&mut self, src: &[u8]) -> io::Result<usize> {
+        (&*self).write(src)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> Write for &'a Io {
     fn write(&mut self, src: &[u8]) -> io::Result<usize> {
         use nix::unistd::write;
 
@@ -76,6 +215,130 @@ impl Write for Io {
 impl Drop for Io {
     fn drop(&mut self) {
         use nix::unistd::close;
+
+        self.release();
         let _ = close(self.as_raw_fd());
     }
 }
+
+/*
+ *
+ * ===== Vectored IO =====
+ *
+ */
+
+// Lowest `IOV_MAX` of the supported platforms, longer slices are truncated
+const IOV_MAX: usize = 1024;
+
+/// Reads into `bufs` in order with a single `readv(2)`.
+pub fn readv(fd: RawFd, bufs: &mut [IoVecMut]) -> io::Result<usize> {
+    let cnt = cmp::min(bufs.len(), IOV_MAX) as libc::c_int;
+    let res = unsafe { ffi::readv(fd, bufs.as_ptr(), cnt) };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(res as usize)
+}
+
+/// Writes from `bufs` in order with a single `writev(2)`.
+pub fn writev(fd: RawFd, bufs: &[IoVec]) -> io::Result<usize> {
+    let cnt = cmp::min(bufs.len(), IOV_MAX) as libc::c_int;
+    let res = unsafe { ffi::writev(fd, bufs.as_ptr(), cnt) };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(res as usize)
+}
+
+/*
+ *
+ * ===== Positioned IO =====
+ *
+ */
+
+/// Reads into `buf` from `offset` with `pread(2)`, leaving the file offset
+/// unchanged.
+pub fn pread(fd: RawFd, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    let res = unsafe {
+        libc::pread(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len() as libc::size_t, offset as libc::off_t)
+    };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(res as usize)
+}
+
+/// Writes `buf` at `offset` with `pwrite(2)`, leaving the file offset
+/// unchanged.
+pub fn pwrite(fd: RawFd, buf: &[u8], offset: u64) -> io::Result<usize> {
+    let res = unsafe {
+        libc::pwrite(fd, buf.as_ptr() as *const libc::c_void, buf.len() as libc::size_t, offset as libc::off_t)
+    };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(res as usize)
+}
+
+/*
+ *
+ * ===== Splice =====
+ *
+ */
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SPLICE_F_MOVE: libc::c_uint = 0x1;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SPLICE_F_NONBLOCK: libc::c_uint = 0x2;
+
+/// Moves up to `len` bytes from `fd_in` to `fd_out` with `splice(2)`,
+/// without copying them through user space. One of the fds must be a pipe.
+/// Returns `Ok(None)` if either fd would block.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn splice(fd_in: RawFd, fd_out: RawFd, len: usize) -> io::NonBlock<usize> {
+    use io::MapNonBlock;
+
+    let res = unsafe {
+        ffi::splice(fd_in, ::std::ptr::null_mut(), fd_out, ::std::ptr::null_mut(),
+                    len as libc::size_t, SPLICE_F_MOVE | SPLICE_F_NONBLOCK)
+    };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error()).map_non_block();
+    }
+
+    Ok(Some(res as usize))
+}
+
+mod ffi {
+    use buf::{IoVec, IoVecMut};
+    use libc;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub const FIONREAD: libc::c_ulong = 0x541b;
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub const FIONREAD: libc::c_ulong = 0x4004667f;
+
+    // `IoVec` and `IoVecMut` have the layout of a `struct iovec`
+    extern {
+        pub fn readv(fd: libc::c_int, iov: *const IoVecMut, iovcnt: libc::c_int) -> libc::ssize_t;
+        pub fn writev(fd: libc::c_int, iov: *const IoVec, iovcnt: libc::c_int) -> libc::ssize_t;
+        pub fn ioctl(fd: libc::c_int, request: libc::c_ulong, ...) -> libc::c_int;
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    extern {
+        pub fn splice(fd_in: libc::c_int, off_in: *mut libc::off_t, fd_out: libc::c_int, off_out: *mut libc::off_t,
+                      len: libc::size_t, flags: libc::c_uint) -> libc::ssize_t;
+    }
+}
@@ -1,45 +1,100 @@
 use {io, EventSet, PollOpt, Token};
 use event::IoEvent;
-use nix::sys::event::{EventFilter, EventFlag, FilterFlag, KEvent, kqueue, kevent};
-use nix::sys::event::{EV_ADD, EV_CLEAR, EV_DELETE, EV_DISABLE, EV_ENABLE, EV_EOF, EV_ONESHOT};
+use self::ffi::{Filter, Flags, KEvent, kqueue, kevent};
+use self::ffi::{EVFILT_READ, EVFILT_WRITE, EVFILT_TIMER};
+use self::ffi::{EV_ADD, EV_CLEAR, EV_DELETE, EV_DISABLE, EV_ENABLE, EV_EOF, EV_ERROR, EV_ONESHOT, NOTE_TRIGGER};
 use std::{fmt, slice};
-use std::os::unix::io::RawFd;
+use std::io::ErrorKind;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct Selector {
     kq: RawFd,
-    changes: Events
+    changes: Events,
+    retry_interrupted: bool,
+    capture_errors: bool,
 }
 
 impl Selector {
     pub fn new() -> io::Result<Selector> {
         Ok(Selector {
-            kq: try!(kqueue().map_err(super::from_nix_error)),
-            changes: Events::new()
+            kq: try!(kqueue()),
+            changes: Events::new(),
+            retry_interrupted: true,
+            capture_errors: false,
         })
     }
 
+    /// When set (the default), a wait interrupted by a signal is resumed for
+    /// the remaining time instead of failing with `ErrorKind::Interrupted`.
+    pub fn set_retry_interrupted(&mut self, retry: bool) {
+        self.retry_interrupted = retry;
+    }
+
+    /// When set, the error kqueue reports along with `EV_EOF` is attached
+    /// to the event. Off by default, as on the other selectors.
+    pub fn set_capture_errors(&mut self, capture: bool) {
+        self.capture_errors = capture;
+    }
+
     pub fn select(&mut self, evts: &mut Events, timeout_ms: usize) -> io::Result<()> {
-        let cnt = try!(kevent(self.kq, self.changes.as_slice(), evts.as_mut_slice(), timeout_ms)
-                                  .map_err(super::from_nix_error));
+        use clock_ticks::precise_time_ns;
 
-        self.changes.sys_events.clear();
+        let start = precise_time_ns();
+        let mut wait_ms = timeout_ms;
+
+        let cnt;
+
+        loop {
+            let res = kevent(self.kq, self.changes.as_slice(), evts.as_mut_slice(), wait_ms);
+
+            // The changelist has been applied even if the wait was interrupted
+            self.changes.sys_events.clear();
+
+            match res {
+                Ok(n) => {
+                    cnt = n;
+                    break;
+                }
+                Err(ref e) if e.kind() == ErrorKind::Interrupted && self.retry_interrupted => {
+                    wait_ms = super::remaining_ms(start, timeout_ms);
+                }
+                Err(e) => return Err(e),
+            }
+        }
 
         unsafe {
             evts.sys_events.set_len(cnt);
         }
 
-        evts.coalesce();
+        evts.coalesce(self.capture_errors);
 
         Ok(())
     }
 
+    /// kqueue always queues registration changes in the changelist and
+    /// submits them with the next call to kevent, so there is nothing to do.
+    pub fn set_deferred(&mut self, _deferred: bool) {
+    }
+
+    /// Submit all queued registration changes.
+    pub fn flush_deferred(&mut self) -> io::Result<()> {
+        if self.changes.sys_events.is_empty() {
+            return Ok(());
+        }
+
+        try!(kevent(self.kq, self.changes.as_slice(), &mut [], 0));
+
+        self.changes.sys_events.clear();
+        Ok(())
+    }
+
     pub fn register(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
         trace!("registering; token={:?}; interests={:?}", token, interests);
 
-        try!(self.ev_register(fd, token.as_usize(), EventFilter::EVFILT_READ, interests.contains(EventSet::readable()), opts));
-        try!(self.ev_register(fd, token.as_usize(), EventFilter::EVFILT_WRITE, interests.contains(EventSet::writable()), opts));
+        try!(self.ev_register(fd, token.as_usize(), EVFILT_READ, interests.contains(EventSet::readable()), opts));
+        try!(self.ev_register(fd, token.as_usize(), EVFILT_WRITE, interests.contains(EventSet::writable()), opts));
 
         Ok(())
     }
@@ -51,13 +106,62 @@ impl Selector {
     }
 
     pub fn deregister(&mut self, fd: RawFd) -> io::Result<()> {
-        try!(self.ev_push(fd, 0, EventFilter::EVFILT_READ, EV_DELETE));
-        try!(self.ev_push(fd, 0, EventFilter::EVFILT_WRITE, EV_DELETE));
+        try!(self.ev_push(fd, 0, EVFILT_READ, EV_DELETE));
+        try!(self.ev_push(fd, 0, EVFILT_WRITE, EV_DELETE));
+
+        Ok(())
+    }
+
+    /// Forgets a closed fd. The kernel drops the filters of closed fds.
+    pub fn forget(&mut self, _fd: RawFd) {
+    }
+
+    /// Registers the `EVFILT_USER` event `id`, reported as readable under
+    /// `token` once triggered with `trigger_user`. The registration is
+    /// submitted right away, so that other threads can trigger the event as
+    /// soon as this returns.
+    pub fn register_user(&mut self, id: usize, token: Token) -> io::Result<()> {
+        let change = try!(user_event(id, token, EV_ADD | EV_CLEAR, 0));
+
+        try!(kevent(self.kq, &[change], &mut [], 0));
+
+        Ok(())
+    }
+
+    pub fn deregister_user(&mut self, id: usize) -> io::Result<()> {
+        let change = try!(user_event(id, Token(0), EV_DELETE, 0));
+
+        try!(kevent(self.kq, &[change], &mut [], 0));
+
+        Ok(())
+    }
+
+    /// Arms the oneshot kernel timer `id` to fire in `delay_ms`. Arming a
+    /// timer that is already armed resets it.
+    pub fn set_timer(&mut self, id: usize, delay_ms: u64) -> io::Result<()> {
+        try!(self.maybe_flush_changes());
+
+        self.changes.sys_events.push(
+            KEvent {
+                ident: id as ::libc::uintptr_t,
+                filter: EVFILT_TIMER,
+                flags: EV_ADD | EV_ONESHOT,
+                fflags: 0,
+                data: delay_ms as ffi::Data,
+                udata: id
+            });
 
         Ok(())
     }
 
-    fn ev_register(&mut self, fd: RawFd, token: usize, filter: EventFilter, enable: bool, opts: PollOpt) -> io::Result<()> {
+    /// Disarms the kernel timer `id`, and forgets it if it fired during the
+    /// last select without having been popped yet.
+    pub fn clear_timer(&mut self, id: usize, evts: &mut Events) -> io::Result<()> {
+        evts.timers.retain(|&t| t != id);
+        self.ev_push(id as RawFd, 0, EVFILT_TIMER, EV_DELETE)
+    }
+
+    fn ev_register(&mut self, fd: RawFd, token: usize, filter: Filter, enable: bool, opts: PollOpt) -> io::Result<()> {
         let mut flags = EV_ADD;
 
         if enable {
@@ -77,7 +181,7 @@ impl Selector {
         self.ev_push(fd, token, filter, flags)
     }
 
-    fn ev_push(&mut self, fd: RawFd, token: usize, filter: EventFilter, flags: EventFlag) -> io::Result<()> {
+    fn ev_push(&mut self, fd: RawFd, token: usize, filter: Filter, flags: Flags) -> io::Result<()> {
         try!(self.maybe_flush_changes());
 
         self.changes.sys_events.push(
@@ -85,7 +189,7 @@ impl Selector {
                 ident: fd as ::libc::uintptr_t,
                 filter: filter,
                 flags: flags,
-                fflags: FilterFlag::empty(),
+                fflags: 0,
                 data: 0,
                 udata: token
             });
@@ -95,8 +199,7 @@ impl Selector {
 
     fn maybe_flush_changes(&mut self) -> io::Result<()> {
         if self.changes.is_full() {
-            try!(kevent(self.kq, self.changes.as_slice(), &mut [], 0)
-                    .map_err(super::from_nix_error));
+            try!(kevent(self.kq, self.changes.as_slice(), &mut [], 0));
 
             self.changes.sys_events.clear();
         }
@@ -105,10 +208,47 @@ impl Selector {
     }
 }
 
+/// Triggers the `EVFILT_USER` event `id` registered with `kq`, see
+/// `Selector::register_user`. Safe to call from any thread.
+pub fn trigger_user(kq: RawFd, id: usize, token: Token) -> io::Result<()> {
+    // The udata of the knote is replaced by the one of the trigger
+    let change = try!(user_event(id, token, 0, NOTE_TRIGGER));
+
+    try!(kevent(kq, &[change], &mut [], 0));
+
+    Ok(())
+}
+
+// Fails on platforms without `EVFILT_USER`, where the awakener falls back to
+// a pipe
+fn user_event(id: usize, token: Token, flags: Flags, fflags: u32) -> io::Result<KEvent> {
+    let filter = match ffi::evfilt_user() {
+        Some(filter) => filter,
+        None => return Err(io::unsupported("EVFILT_USER is not supported on this platform")),
+    };
+
+    Ok(KEvent {
+        ident: id as ::libc::uintptr_t,
+        filter: filter,
+        flags: flags,
+        fflags: fflags,
+        data: 0,
+        udata: token.as_usize()
+    })
+}
+
+impl AsRawFd for Selector {
+    fn as_raw_fd(&self) -> RawFd {
+        self.kq
+    }
+}
+
 pub struct Events {
     sys_events: Vec<KEvent>,
     events: Vec<IoEvent>,
     event_map: HashMap<Token, usize>,
+    // Ids of the kernel timers that fired, not yet popped
+    timers: Vec<usize>,
 }
 
 impl Events {
@@ -116,7 +256,8 @@ impl Events {
         Events {
             sys_events: Vec::with_capacity(1024),
             events: Vec::with_capacity(1024),
-            event_map: HashMap::with_capacity(1024)
+            event_map: HashMap::with_capacity(1024),
+            timers: Vec::new(),
         }
     }
 
@@ -125,15 +266,43 @@ impl Events {
         self.events.len()
     }
 
+    /// The maximum number of kevents returned by a select.
+    pub fn capacity(&self) -> usize {
+        self.sys_events.capacity()
+    }
+
+    /// Replaces the buffer with one holding up to `cap` kevents, discarding
+    /// the current events.
+    pub fn set_capacity(&mut self, cap: usize) {
+        self.sys_events = Vec::with_capacity(cap);
+        self.events.clear();
+        self.event_map.clear();
+    }
+
     pub fn get(&self, idx: usize) -> IoEvent {
         self.events[idx]
     }
 
-    pub fn coalesce(&mut self) {
+    /// Pops the id of a kernel timer that fired.
+    pub fn pop_timer(&mut self) -> Option<usize> {
+        self.timers.pop()
+    }
+
+    pub fn coalesce(&mut self, capture_errors: bool) {
         self.events.clear();
         self.event_map.clear();
 
         for e in self.sys_events.iter() {
+            if e.filter == EVFILT_TIMER {
+                // Deleting a timer that already fired reports an error for
+                // the change, which is not a timer firing
+                if e.flags & EV_ERROR == 0 {
+                    self.timers.push(e.udata);
+                }
+
+                continue;
+            }
+
             let token = Token(e.udata as usize);
             let len = self.events.len();
 
@@ -146,26 +315,31 @@ impl Events {
 
             }
 
-            if e.filter == EventFilter::EVFILT_READ {
+            if e.filter == EVFILT_READ || Some(e.filter) == ffi::evfilt_user() {
                 self.events[idx].kind.insert(EventSet::readable());
-            } else if e.filter == EventFilter::EVFILT_WRITE {
+            } else if e.filter == EVFILT_WRITE {
                 self.events[idx].kind.insert(EventSet::writable());
             }
 
-            if e.flags.contains(EV_EOF) {
+            if e.flags & EV_EOF != 0 {
                 self.events[idx].kind.insert(EventSet::hup());
 
                 // When the read end of the socket is closed, EV_EOF is set on
                 // flags, and fflags contains the error if there is one.
-                if !e.fflags.is_empty() {
+                if e.fflags != 0 {
                     self.events[idx].kind.insert(EventSet::error());
+
+                    if capture_errors {
+                        self.events[idx].set_error(e.fflags as i32);
+                    }
                 }
             }
         }
     }
 
+    /// Whether the last select filled the buffer.
     #[inline]
-    fn is_full(&self) -> bool {
+    pub fn is_full(&self) -> bool {
         self.sys_events.len() == self.sys_events.capacity()
     }
 
@@ -189,3 +363,161 @@ impl fmt::Debug for Events {
         write!(fmt, "Events {{ len: {} }}", self.sys_events.len())
     }
 }
+
+/*
+ *
+ * ===== FFI =====
+ *
+ */
+
+// The layout of `struct kevent` and the filter numbers differ between the
+// BSDs, so they are declared here rather than relying on nix, which only
+// knows about the Darwin / FreeBSD layout.
+mod ffi {
+    use io;
+    use libc::{self, c_int, uintptr_t};
+    use std::{isize, ptr};
+    use std::os::unix::io::RawFd;
+
+    #[cfg(not(target_os = "netbsd"))]
+    pub type Filter = i16;
+    #[cfg(target_os = "netbsd")]
+    pub type Filter = u32;
+
+    #[cfg(not(target_os = "netbsd"))]
+    pub type Flags = u16;
+    #[cfg(target_os = "netbsd")]
+    pub type Flags = u32;
+
+    #[cfg(not(any(target_os = "netbsd", target_os = "openbsd")))]
+    pub type Data = isize;
+    #[cfg(any(target_os = "netbsd", target_os = "openbsd"))]
+    pub type Data = i64;
+
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    pub struct KEvent {
+        pub ident: uintptr_t,
+        pub filter: Filter,
+        pub flags: Flags,
+        pub fflags: u32,
+        pub data: Data,
+        // A `void *` (`intptr_t` on NetBSD), holds the token
+        pub udata: usize,
+    }
+
+    #[cfg(not(target_os = "netbsd"))]
+    mod filters {
+        use super::Filter;
+
+        pub const EVFILT_READ: Filter = -1;
+        pub const EVFILT_WRITE: Filter = -2;
+        pub const EVFILT_TIMER: Filter = -7;
+    }
+
+    #[cfg(target_os = "netbsd")]
+    mod filters {
+        use super::Filter;
+
+        pub const EVFILT_READ: Filter = 0;
+        pub const EVFILT_WRITE: Filter = 1;
+        pub const EVFILT_TIMER: Filter = 6;
+    }
+
+    pub use self::filters::*;
+
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "watchos"))]
+    pub fn evfilt_user() -> Option<Filter> {
+        Some(-10)
+    }
+
+    #[cfg(target_os = "freebsd")]
+    pub fn evfilt_user() -> Option<Filter> {
+        Some(-11)
+    }
+
+    #[cfg(target_os = "dragonfly")]
+    pub fn evfilt_user() -> Option<Filter> {
+        Some(-9)
+    }
+
+    #[cfg(target_os = "netbsd")]
+    pub fn evfilt_user() -> Option<Filter> {
+        Some(8)
+    }
+
+    #[cfg(target_os = "openbsd")]
+    pub fn evfilt_user() -> Option<Filter> {
+        None
+    }
+
+    pub const EV_ADD: Flags = 0x0001;
+    pub const EV_DELETE: Flags = 0x0002;
+    pub const EV_ENABLE: Flags = 0x0004;
+    pub const EV_DISABLE: Flags = 0x0008;
+    pub const EV_ONESHOT: Flags = 0x0010;
+    pub const EV_CLEAR: Flags = 0x0020;
+    pub const EV_ERROR: Flags = 0x4000;
+    pub const EV_EOF: Flags = 0x8000;
+
+    pub const NOTE_TRIGGER: u32 = 0x01000000;
+
+    #[repr(C)]
+    struct Timespec {
+        tv_sec: libc::time_t,
+        tv_nsec: libc::c_long,
+    }
+
+    pub fn kqueue() -> io::Result<RawFd> {
+        let kq = unsafe { sys_kqueue() };
+
+        if kq < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // Forked children do not inherit kqueues, but the fd would survive
+        // an exec of this process
+        if let Err(e) = ::sys::unix::set_cloexec(kq, true) {
+            unsafe { libc::close(kq); }
+            return Err(e);
+        }
+
+        Ok(kq)
+    }
+
+    /// Submits `changes` and waits at most `timeout_ms` for `events`.
+    pub fn kevent(kq: RawFd, changes: &[KEvent], events: &mut [KEvent], timeout_ms: usize) -> io::Result<usize> {
+        let ts = Timespec {
+            tv_sec: (timeout_ms / 1_000) as libc::time_t,
+            tv_nsec: ((timeout_ms % 1_000) * 1_000_000) as libc::c_long,
+        };
+
+        // Wait forever rather than overflowing the timespec
+        let timeout = if timeout_ms >= isize::MAX as usize { ptr::null() } else { &ts as *const Timespec };
+
+        let res = unsafe {
+            sys_kevent(kq,
+                       changes.as_ptr(), changes.len() as c_int,
+                       events.as_mut_ptr(), events.len() as c_int,
+                       timeout)
+        };
+
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(res as usize)
+    }
+
+    extern {
+        #[link_name = "kqueue"]
+        fn sys_kqueue() -> c_int;
+
+        #[cfg_attr(target_os = "netbsd", link_name = "__kevent50")]
+        #[cfg_attr(not(target_os = "netbsd"), link_name = "kevent")]
+        fn sys_kevent(kq: c_int,
+                      changelist: *const KEvent, nchanges: c_int,
+                      eventlist: *mut KEvent, nevents: c_int,
+                      timeout: *const Timespec) -> c_int;
+    }
+}
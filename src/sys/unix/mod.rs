@@ -1,30 +1,116 @@
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 mod epoll;
 
-#[cfg(target_os = "linux")]
-pub use self::epoll::{Events, Selector};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::epoll::{Events, Selector, unregister_closing};
 
-#[cfg(any(target_os = "macos", target_os = "ios"))]
+/// Removes `fd` from the native selector `selector_fd` right before it is
+/// closed. kqueue and event ports drop the registrations of closed fds, and
+/// the `poll(2)` and `select(2)` selectors forget them before their next
+/// select, see `Selector::forget`.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn unregister_closing(_selector_fd: ::std::os::unix::io::RawFd, _fd: ::std::os::unix::io::RawFd) {
+}
+
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "watchos",
+          target_os = "freebsd",
+          target_os = "dragonfly",
+          target_os = "netbsd",
+          target_os = "openbsd"))]
 mod kqueue;
 
-#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "watchos",
+          target_os = "freebsd",
+          target_os = "dragonfly",
+          target_os = "netbsd",
+          target_os = "openbsd"))]
 pub use self::kqueue::{Events, Selector};
 
+#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+mod port;
+
+#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+pub use self::port::{Events, Selector};
+
+// Also backs `SelectorKind::Poll` on every platform, where parts of it go
+// unused
+#[allow(dead_code)]
+mod poll;
+
+#[cfg(not(any(target_os = "linux",
+              target_os = "android",
+              target_os = "macos",
+              target_os = "ios",
+              target_os = "watchos",
+              target_os = "freebsd",
+              target_os = "dragonfly",
+              target_os = "netbsd",
+              target_os = "openbsd",
+              target_os = "solaris",
+              target_os = "illumos",
+              feature = "select")))]
+pub use self::poll::{Events, Selector};
+
+#[cfg(all(feature = "select",
+          not(any(target_os = "linux",
+                  target_os = "android",
+                  target_os = "macos",
+                  target_os = "ios",
+                  target_os = "watchos",
+                  target_os = "freebsd",
+                  target_os = "dragonfly",
+                  target_os = "netbsd",
+                  target_os = "openbsd",
+                  target_os = "solaris",
+                  target_os = "illumos"))))]
+mod select;
+
+#[cfg(all(feature = "select",
+          not(any(target_os = "linux",
+                  target_os = "android",
+                  target_os = "macos",
+                  target_os = "ios",
+                  target_os = "watchos",
+                  target_os = "freebsd",
+                  target_os = "dragonfly",
+                  target_os = "netbsd",
+                  target_os = "openbsd",
+                  target_os = "solaris",
+                  target_os = "illumos"))))]
+pub use self::select::{Events, Selector};
+
+mod affinity;
 mod awakener;
+pub mod interface;
 mod io;
 mod net;
+mod reserve;
+pub mod signal;
+mod slack;
 mod socket;
 mod tcp;
 mod udp;
 mod uds;
 
+pub use self::affinity::set_cpu_affinity;
+pub use self::poll::PollBackend;
 pub use self::awakener::Awakener;
-pub use self::io::Io;
+pub use self::io::{Io, pread, pwrite, readv, writev};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::io::splice;
+pub use self::reserve::FdReserve;
+pub use self::signal::Signals;
+pub use self::slack::set_timer_slack;
 pub use self::socket::Socket;
 pub use self::tcp::TcpSocket;
 pub use self::udp::UdpSocket;
 pub use self::uds::UnixSocket;
 
+#[cfg(not(target_os = "android"))]
 pub fn pipe() -> ::io::Result<(Io, Io)> {
     use nix::fcntl::{O_NONBLOCK, O_CLOEXEC};
     use nix::unistd::pipe2;
@@ -35,17 +121,84 @@ pub fn pipe() -> ::io::Result<(Io, Io)> {
     Ok((Io::from_raw_fd(rd), Io::from_raw_fd(wr)))
 }
 
+// Bionic only provides pipe2 from API level 9, create the pipe and set the
+// flags separately instead
+#[cfg(target_os = "android")]
+pub fn pipe() -> ::io::Result<(Io, Io)> {
+    use nix::unistd::pipe;
+    use std::os::unix::io::AsRawFd;
+
+    let (rd, wr) = try!(pipe().map_err(from_nix_error));
+
+    // Dropping the `Io`s closes both ends on failure
+    let (rd, wr) = (Io::from_raw_fd(rd), Io::from_raw_fd(wr));
+
+    try!(set_cloexec_nonblock(rd.as_raw_fd(), true));
+    try!(set_cloexec_nonblock(wr.as_raw_fd(), true));
+
+    Ok((rd, wr))
+}
+
+// Fallback for the `SOCK_CLOEXEC` / `O_NONBLOCK` flags of accept4 and pipe2,
+// on platforms where those calls are missing.
+#[cfg(target_os = "android")]
+fn set_cloexec_nonblock(fd: ::std::os::unix::io::RawFd, nonblock: bool) -> ::io::Result<()> {
+    use nix::fcntl::{fcntl, O_NONBLOCK};
+    use nix::fcntl::FcntlArg::{F_GETFL, F_SETFL};
+    use nix::fcntl::OFlag;
+
+    try!(set_cloexec(fd, true));
+
+    if nonblock {
+        let flags = try!(fcntl(fd, F_GETFL).map_err(from_nix_error));
+        let flags = OFlag::from_bits_truncate(flags) | O_NONBLOCK;
+
+        try!(fcntl(fd, F_SETFL(flags)).map_err(from_nix_error));
+    }
+
+    Ok(())
+}
+
+/// Sets or clears the close-on-exec flag of `fd`. Every fd mio creates has
+/// the flag set, atomically where the platform allows it.
+pub fn set_cloexec(fd: ::std::os::unix::io::RawFd, cloexec: bool) -> ::io::Result<()> {
+    use nix::fcntl::{fcntl, FdFlag, FD_CLOEXEC};
+    use nix::fcntl::FcntlArg::{F_GETFD, F_SETFD};
+
+    let flags = try!(fcntl(fd, F_GETFD).map_err(from_nix_error));
+    let mut flags = FdFlag::from_bits_truncate(flags);
+
+    if cloexec {
+        flags.insert(FD_CLOEXEC);
+    } else {
+        flags.remove(FD_CLOEXEC);
+    }
+
+    try!(fcntl(fd, F_SETFD(flags)).map_err(from_nix_error));
+    Ok(())
+}
+
 pub fn from_nix_error(err: ::nix::Error) -> ::io::Error {
     ::io::Error::from_raw_os_error(err.errno() as i32)
 }
 
+// Milliseconds left of a `timeout_ms` wait that started at `start_ns`, used
+// to resume a wait interrupted by a signal.
+fn remaining_ms(start_ns: u64, timeout_ms: usize) -> usize {
+    use clock_ticks::precise_time_ns;
+
+    let elapsed = ((precise_time_ns() - start_ns) / 1_000_000) as usize;
+
+    if elapsed >= timeout_ms { 0 } else { timeout_ms - elapsed }
+}
+
 mod nix {
     pub use nix::{
         c_int,
         Error,
     };
-    pub use nix::errno::{EINPROGRESS, EAGAIN};
-    pub use nix::fcntl::{fcntl, FcntlArg, O_NONBLOCK};
+    pub use nix::errno::{EINPROGRESS, EAGAIN, EEXIST, EINTR, EMFILE, ENFILE, ENOTCONN};
+    pub use nix::fcntl::{fcntl, FcntlArg, OFlag, O_NONBLOCK};
     pub use nix::sys::socket::{
         sockopt,
         AddressFamily,
@@ -56,8 +209,10 @@ mod nix {
         Ipv4Addr,
         Ipv6Addr,
         MSG_DONTWAIT,
+        MSG_PEEK,
         SOCK_NONBLOCK,
         SOCK_CLOEXEC,
+        accept,
         accept4,
         bind,
         connect,
@@ -68,6 +223,7 @@ mod nix {
         ipv6_mreq,
         linger,
         listen,
+        recv,
         recvfrom,
         sendto,
         setsockopt,
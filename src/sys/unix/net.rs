@@ -1,7 +1,9 @@
 use {io};
+use libc;
 use sys::unix::{nix, Io};
 use std::net::SocketAddr;
 use std::os::unix::io::{AsRawFd, RawFd};
+pub use net::ConnectState;
 pub use net::tcp::Shutdown;
 
 pub fn socket(family: nix::AddressFamily, ty: nix::SockType, nonblock: bool) -> io::Result<RawFd> {
@@ -11,16 +13,29 @@ pub fn socket(family: nix::AddressFamily, ty: nix::SockType, nonblock: bool) ->
         nix::SOCK_CLOEXEC
     };
 
-    nix::socket(family, ty, opts)
-        .map_err(super::from_nix_error)
+    let fd = try!(nix::socket(family, ty, opts)
+        .map_err(super::from_nix_error));
+
+    try!(close_on_error(fd, set_nosigpipe(fd)));
+
+    Ok(fd)
+}
+
+// Closes `fd` if setting it up failed
+fn close_on_error(fd: RawFd, res: io::Result<()>) -> io::Result<()> {
+    if res.is_err() {
+        let _ = ::nix::unistd::close(fd);
+    }
+
+    res
 }
 
-pub fn connect(io: &Io, addr: &nix::SockAddr) -> io::Result<bool> {
+pub fn connect(io: &Io, addr: &nix::SockAddr) -> io::Result<ConnectState> {
     match nix::connect(io.as_raw_fd(), addr) {
-        Ok(_) => Ok(true),
+        Ok(_) => Ok(ConnectState::Connected),
         Err(e) => {
             match e {
-                nix::Error::Sys(nix::EINPROGRESS) => Ok(false),
+                nix::Error::Sys(nix::EINPROGRESS) => Ok(ConnectState::InProgress),
                 _ => Err(super::from_nix_error(e))
             }
         }
@@ -37,6 +52,7 @@ pub fn listen(io: &Io, backlog: usize) -> io::Result<()> {
         .map_err(super::from_nix_error)
 }
 
+#[cfg(not(target_os = "android"))]
 pub fn accept(io: &Io, nonblock: bool) -> io::Result<RawFd> {
     let opts = if nonblock {
         nix::SOCK_NONBLOCK | nix::SOCK_CLOEXEC
@@ -44,8 +60,34 @@ pub fn accept(io: &Io, nonblock: bool) -> io::Result<RawFd> {
         nix::SOCK_CLOEXEC
     };
 
-    nix::accept4(io.as_raw_fd(), opts)
-        .map_err(super::from_nix_error)
+    let fd = try!(nix::accept4(io.as_raw_fd(), opts)
+        .map_err(accept_error));
+
+    try!(close_on_error(fd, set_nosigpipe(fd)));
+
+    Ok(fd)
+}
+
+// Bionic only provides accept4 from API level 21, accept and set the flags
+// separately instead
+#[cfg(target_os = "android")]
+pub fn accept(io: &Io, nonblock: bool) -> io::Result<RawFd> {
+    let fd = try!(nix::accept(io.as_raw_fd())
+        .map_err(accept_error));
+
+    try!(close_on_error(fd, super::set_cloexec_nonblock(fd, nonblock)));
+
+    Ok(fd)
+}
+
+// Running out of fds is reported as `FdLimit`, as retrying does not help
+fn accept_error(err: nix::Error) -> io::Error {
+    match err {
+        nix::Error::Sys(nix::EMFILE) | nix::Error::Sys(nix::ENFILE) => {
+            io::fd_limit(err.errno() as i32)
+        }
+        _ => super::from_nix_error(err),
+    }
 }
 
 pub fn shutdown(io: &Io, how: Shutdown) -> io::Result<()> {
@@ -68,6 +110,20 @@ pub fn take_socket_error(io: &Io) -> io::Result<()> {
     }
 }
 
+// A writable event does not always mean that the connect completed: the
+// socket may be reported writable spuriously, for example after a
+// re-registration. `SO_ERROR` tells about failures, and the socket has a peer
+// only once the connection is established.
+pub fn finish_connect(io: &Io) -> io::Result<ConnectState> {
+    try!(take_socket_error(io));
+
+    match nix::getpeername(io.as_raw_fd()) {
+        Ok(_) => Ok(ConnectState::Connected),
+        Err(nix::Error::Sys(nix::ENOTCONN)) => Ok(ConnectState::InProgress),
+        Err(e) => Err(super::from_nix_error(e)),
+    }
+}
+
 pub fn set_nodelay(io: &Io, delay: bool) -> io::Result<()> {
     nix::setsockopt(io.as_raw_fd(), nix::sockopt::TcpNoDelay, &delay)
         .map_err(super::from_nix_error)
@@ -85,20 +141,118 @@ pub fn set_tcp_keepalive(io: &Io, seconds: u32) -> io::Result<()> {
         .map_err(super::from_nix_error)
 }
 
+// nix has no `TCP_KEEPALIVE` mapping for watchOS
+#[cfg(target_os = "watchos")]
+pub fn set_tcp_keepalive(io: &Io, seconds: u32) -> io::Result<()> {
+    const TCP_KEEPALIVE: libc::c_int = 0x10;
+
+    setsockopt_int(io, libc::IPPROTO_TCP, TCP_KEEPALIVE, seconds as libc::c_int)
+}
+
 #[cfg(any(target_os = "freebsd",
           target_os = "dragonfly",
-          target_os = "linux"))]
+          target_os = "linux",
+          target_os = "android"))]
 pub fn set_tcp_keepalive(io: &Io, seconds: u32) -> io::Result<()> {
     nix::setsockopt(io.as_raw_fd(), nix::sockopt::TcpKeepIdle, &seconds)
         .map_err(super::from_nix_error)
 }
 
+// nix has no `TCP_KEEPIDLE` mapping for NetBSD
+#[cfg(target_os = "netbsd")]
+pub fn set_tcp_keepalive(io: &Io, seconds: u32) -> io::Result<()> {
+    const TCP_KEEPIDLE: libc::c_int = 3;
+
+    setsockopt_int(io, libc::IPPROTO_TCP, TCP_KEEPIDLE, seconds as libc::c_int)
+}
+
+// OpenBSD only has system wide keepalive timers
 #[cfg(not(any(target_os = "freebsd",
               target_os = "dragonfly",
               target_os = "linux",
+              target_os = "android",
               target_os = "macos",
-              target_os = "ios")))]
-pub fn set_tcp_keepalive(io: &Io, _seconds: u32) -> io::Result<()> {
+              target_os = "ios",
+              target_os = "watchos",
+              target_os = "netbsd")))]
+pub fn set_tcp_keepalive(_io: &Io, _seconds: u32) -> io::Result<()> {
+    Err(io::unsupported("setting the TCP keepalive idle time is not supported on this platform"))
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_busy_poll(io: &Io, usecs: u32) -> io::Result<()> {
+    const SO_BUSY_POLL: libc::c_int = 46;
+
+    setsockopt_int(io, libc::SOL_SOCKET, SO_BUSY_POLL, usecs as libc::c_int)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_busy_poll(_io: &Io, _usecs: u32) -> io::Result<()> {
+    Err(io::unsupported("SO_BUSY_POLL is not supported on this platform"))
+}
+
+// An empty name removes the binding
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn bind_device(io: &Io, interface: &str) -> io::Result<()> {
+    const SO_BINDTODEVICE: libc::c_int = 25;
+
+    let res = unsafe {
+        libc::setsockopt(io.as_raw_fd(), libc::SOL_SOCKET, SO_BINDTODEVICE,
+                         interface.as_ptr() as *const libc::c_void,
+                         interface.len() as libc::socklen_t)
+    };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn bind_device(_io: &Io, _interface: &str) -> io::Result<()> {
+    Err(io::unsupported("SO_BINDTODEVICE is not supported on this platform"))
+}
+
+// Darwin raises SIGPIPE when writing to a socket closed by the peer, which
+// kills the process by default, and apps cannot always install a handler.
+// Writes fail with EPIPE instead once set.
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "watchos"))]
+fn set_nosigpipe(fd: RawFd) -> io::Result<()> {
+    const SO_NOSIGPIPE: libc::c_int = 0x1022;
+
+    setsockopt_fd(fd, libc::SOL_SOCKET, SO_NOSIGPIPE, 1)
+}
+
+#[cfg(not(any(target_os = "macos",
+              target_os = "ios",
+              target_os = "watchos")))]
+fn set_nosigpipe(_fd: RawFd) -> io::Result<()> {
+    Ok(())
+}
+
+// For options nix has no mapping for on the current platform
+#[allow(dead_code)]
+fn setsockopt_int(io: &Io, level: libc::c_int, opt: libc::c_int, val: libc::c_int) -> io::Result<()> {
+    setsockopt_fd(io.as_raw_fd(), level, opt, val)
+}
+
+#[allow(dead_code)]
+fn setsockopt_fd(fd: RawFd, level: libc::c_int, opt: libc::c_int, val: libc::c_int) -> io::Result<()> {
+    use std::mem;
+
+    let res = unsafe {
+        libc::setsockopt(fd, level, opt,
+                         &val as *const libc::c_int as *const libc::c_void,
+                         mem::size_of::<libc::c_int>() as libc::socklen_t)
+    };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
     Ok(())
 }
 
@@ -116,6 +270,29 @@ pub fn sendto(io: &Io, buf: &[u8], target: &nix::SockAddr) -> io::Result<usize>
         .map_err(super::from_nix_error)
 }
 
+// TCP & UDS. With `more`, the kernel may hold back a partial segment to
+// coalesce it with the next send. Only Linux has `MSG_MORE`, elsewhere the
+// hint is dropped.
+pub fn send(io: &Io, buf: &[u8], more: bool) -> io::Result<usize> {
+    let flags = if more { MSG_MORE } else { 0 };
+
+    let res = unsafe {
+        libc::send(io.as_raw_fd(), buf.as_ptr() as *const libc::c_void, buf.len() as libc::size_t, flags)
+    };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(res as usize)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MSG_MORE: libc::c_int = 0x8000;
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const MSG_MORE: libc::c_int = 0;
+
 pub fn getpeername(io: &Io) -> io::Result<nix::SockAddr> {
     nix::getpeername(io.as_raw_fd())
         .map_err(super::from_nix_error)
@@ -126,9 +303,27 @@ pub fn getsockname(io: &Io) -> io::Result<nix::SockAddr> {
         .map_err(super::from_nix_error)
 }
 
+pub fn set_nonblock(io: &Io, nonblock: bool) -> io::Result<()> {
+    let fd = io.as_raw_fd();
+    let flags = try!(nix::fcntl(fd, nix::FcntlArg::F_GETFL).map_err(super::from_nix_error));
+    let mut flags = nix::OFlag::from_bits_truncate(flags);
+
+    if nonblock {
+        flags.insert(nix::O_NONBLOCK);
+    } else {
+        flags.remove(nix::O_NONBLOCK);
+    }
+
+    nix::fcntl(fd, nix::FcntlArg::F_SETFL(flags))
+        .map_err(super::from_nix_error)
+        .map(|_| ())
+}
+
+// The duplicate is close-on-exec, as are the fds mio creates, regardless of
+// the flags of the original fd
 #[inline]
 pub fn dup(io: &Io) -> io::Result<Io> {
-    nix::dup(io.as_raw_fd())
+    nix::fcntl(io.as_raw_fd(), nix::FcntlArg::F_DUPFD_CLOEXEC(0))
         .map_err(super::from_nix_error)
         .map(|fd| Io::from_raw_fd(fd))
 }
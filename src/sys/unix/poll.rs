@@ -0,0 +1,389 @@
+use {io, Error, EventSet, PollOpt, Token};
+use event::IoEvent;
+use selector::Backend;
+use libc;
+use sys::unix::nix;
+use std::{cmp, fmt};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+/// Portable selector backed by `poll(2)`, used on platforms without epoll,
+/// kqueue or event ports.
+///
+/// Every select hands the whole set of registered fds to the kernel and
+/// scans it for readiness, so its cost grows linearly with the number of
+/// registered handles. It is meant to make mio usable on such platforms,
+/// not to handle thousands of connections.
+///
+/// `poll(2)` only reports the current state of an fd, so edge-triggered
+/// registrations behave as level-triggered ones: an fd is reported by every
+/// select for as long as it stays ready. Oneshot registrations are disabled
+/// once reported, until they are re-registered. There is no fd to wait on
+/// for embedding the event loop in another one.
+pub struct Selector {
+    // Passed to poll(2) as is. Disabled oneshot registrations have a negative
+    // fd, which poll(2) ignores.
+    fds: Vec<PollFd>,
+    // Registration matching each entry of `fds`
+    registrations: Vec<(Token, PollOpt)>,
+    // Index of each registered fd in `fds`
+    index: HashMap<RawFd, usize>,
+    // Where the next select starts scanning `fds`, so that fds at the end
+    // are not starved when the events buffer fills up
+    next: usize,
+    retry_interrupted: bool,
+    capture_errors: bool,
+}
+
+impl Selector {
+    pub fn new() -> io::Result<Selector> {
+        Ok(Selector {
+            fds: vec![],
+            registrations: vec![],
+            index: HashMap::new(),
+            next: 0,
+            retry_interrupted: true,
+            capture_errors: false,
+        })
+    }
+
+    /// When set (the default), a wait interrupted by a signal is resumed for
+    /// the remaining time instead of failing with `ErrorKind::Interrupted`.
+    pub fn set_retry_interrupted(&mut self, retry: bool) {
+        self.retry_interrupted = retry;
+    }
+
+    /// When set, the pending `SO_ERROR` of every fd reporting an error is
+    /// fetched along with the event. Fetching the error clears it on the
+    /// socket, so this is off by default.
+    pub fn set_capture_errors(&mut self, capture: bool) {
+        self.capture_errors = capture;
+    }
+
+    /// Registrations only live in userspace until the next select, so there
+    /// is nothing to defer.
+    pub fn set_deferred(&mut self, _deferred: bool) {
+    }
+
+    pub fn flush_deferred(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn select(&mut self, evts: &mut Events, timeout_ms: usize) -> io::Result<()> {
+        use clock_ticks::precise_time_ns;
+
+        let start = precise_time_ns();
+        let mut wait_ms = timeout_ms;
+
+        evts.events.clear();
+
+        loop {
+            // A negative timeout waits forever
+            let timeout = if wait_ms >= libc::c_int::max_value() as usize { -1 } else { wait_ms as libc::c_int };
+
+            let res = unsafe { poll(self.fds.as_mut_ptr(), self.fds.len() as nfds_t, timeout) };
+
+            if res >= 0 {
+                break;
+            }
+
+            let err = io::Error::last_os_error();
+
+            match err.raw_os_error() {
+                Some(EINTR) if self.retry_interrupted => {
+                    wait_ms = super::remaining_ms(start, timeout_ms);
+                }
+                _ => return Err(err),
+            }
+        }
+
+        let len = self.fds.len();
+
+        for i in 0..len {
+            if evts.is_full() {
+                // Resume from here on the next select
+                self.next = (self.next + i) % len;
+                return Ok(());
+            }
+
+            let idx = (self.next + i) % len;
+            let pollfd = self.fds[idx];
+
+            if pollfd.fd < 0 || pollfd.revents == 0 {
+                continue;
+            }
+
+            let (token, opts) = self.registrations[idx];
+            let mut evt = IoEvent::new(poll_to_ioevent(pollfd.revents), token);
+
+            if self.capture_errors && pollfd.revents & POLLERR != 0 {
+                match nix::getsockopt(pollfd.fd, nix::sockopt::SocketError) {
+                    Ok(code) if code != 0 => evt.set_error(code),
+                    _ => {}
+                }
+            }
+
+            if opts.is_oneshot() {
+                self.fds[idx].fd = disabled(pollfd.fd);
+            }
+
+            evts.events.push(evt);
+        }
+
+        Ok(())
+    }
+
+    /// Register event interests for the given IO handle. Registering an fd
+    /// that is already registered fails with `ErrorKind::AlreadyExists`.
+    pub fn register(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        trace!("registering; token={:?}; interests={:?}", token, interests);
+
+        if let Some(&idx) = self.index.get(&fd) {
+            return Err(From::from(Error::TokenInUse(Some(self.registrations[idx].0))));
+        }
+
+        self.index.insert(fd, self.fds.len());
+        self.fds.push(PollFd { fd: fd, events: interests_to_poll(interests), revents: 0 });
+        self.registrations.push((token, opts));
+
+        Ok(())
+    }
+
+    /// Changes the registration of `fd`, which also re-enables a oneshot
+    /// registration.
+    pub fn reregister(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        let idx = try!(self.lookup(fd));
+
+        self.fds[idx] = PollFd { fd: fd, events: interests_to_poll(interests), revents: 0 };
+        self.registrations[idx] = (token, opts);
+
+        Ok(())
+    }
+
+    pub fn deregister(&mut self, fd: RawFd) -> io::Result<()> {
+        let idx = try!(self.lookup(fd));
+
+        self.index.remove(&fd);
+        self.fds.swap_remove(idx);
+        self.registrations.swap_remove(idx);
+
+        // Fix up the index of the entry moved into the freed slot
+        if idx < self.fds.len() {
+            let moved = self.fds[idx].fd;
+            self.index.insert(enabled(moved), idx);
+        }
+
+        Ok(())
+    }
+
+    /// Forgets a closed fd, which would otherwise keep being polled.
+    pub fn forget(&mut self, fd: RawFd) {
+        let _ = self.deregister(fd);
+    }
+
+    fn lookup(&self, fd: RawFd) -> io::Result<usize> {
+        match self.index.get(&fd) {
+            Some(&idx) => Ok(idx),
+            None => Err(io::Error::new(ErrorKind::NotFound, format!("fd {} is not registered", fd))),
+        }
+    }
+}
+
+// poll(2) ignores negative fds. The mapping is its own inverse and keeps fd 0
+// distinct from -1.
+#[inline]
+fn disabled(fd: RawFd) -> RawFd {
+    -fd - 1
+}
+
+#[inline]
+fn enabled(fd: RawFd) -> RawFd {
+    if fd < 0 { -fd - 1 } else { fd }
+}
+
+fn interests_to_poll(interests: EventSet) -> libc::c_short {
+    let mut events = 0;
+
+    if interests.is_readable() {
+        events |= POLLIN;
+    }
+
+    if interests.is_writable() {
+        events |= POLLOUT;
+    }
+
+    events
+}
+
+fn poll_to_ioevent(revents: libc::c_short) -> EventSet {
+    let mut kind = EventSet::none();
+
+    if revents & POLLIN != 0 {
+        kind = kind | EventSet::readable();
+    }
+
+    if revents & POLLOUT != 0 {
+        kind = kind | EventSet::writable();
+    }
+
+    if revents & (POLLERR | POLLNVAL) != 0 {
+        kind = kind | EventSet::error();
+    }
+
+    if revents & POLLHUP != 0 {
+        kind = kind | EventSet::hup();
+    }
+
+    kind
+}
+
+impl AsRawFd for Selector {
+    /// `poll(2)` has no fd to wait on, always returns -1.
+    fn as_raw_fd(&self) -> RawFd {
+        -1
+    }
+}
+
+impl fmt::Debug for Selector {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Selector {{ registered: {} }}", self.fds.len())
+    }
+}
+
+pub struct Events {
+    events: Vec<IoEvent>,
+}
+
+impl Events {
+    pub fn new() -> Events {
+        Events { events: Vec::with_capacity(1024) }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether the last select filled the buffer.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.events.len() == self.events.capacity()
+    }
+
+    /// The maximum number of events returned by a select.
+    pub fn capacity(&self) -> usize {
+        self.events.capacity()
+    }
+
+    /// Replaces the buffer with one holding up to `cap` events, discarding
+    /// the current events.
+    pub fn set_capacity(&mut self, cap: usize) {
+        self.events = Vec::with_capacity(cmp::max(cap, 1));
+    }
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> IoEvent {
+        self.events[idx]
+    }
+}
+
+impl fmt::Debug for Events {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Events {{ len: {} }}", self.events.len())
+    }
+}
+
+/// Runs the `poll(2)` selector as a `Backend`, in place of the native
+/// selector, see `SelectorKind`.
+pub struct PollBackend {
+    selector: Selector,
+    events: Events,
+}
+
+impl PollBackend {
+    pub fn new() -> io::Result<PollBackend> {
+        let mut selector = try!(Selector::new());
+
+        // Interrupted waits are retried by the `Selector` wrapping backends
+        selector.set_retry_interrupted(false);
+
+        Ok(PollBackend {
+            selector: selector,
+            events: Events::new(),
+        })
+    }
+}
+
+impl Backend for PollBackend {
+    fn register(&mut self, fd: RawFd, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        self.selector.register(fd, token, interest, opts)
+    }
+
+    fn reregister(&mut self, fd: RawFd, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        self.selector.reregister(fd, token, interest, opts)
+    }
+
+    fn deregister(&mut self, fd: RawFd) -> io::Result<()> {
+        self.selector.deregister(fd)
+    }
+
+    fn select(&mut self, events: &mut Vec<IoEvent>, timeout_ms: usize) -> io::Result<()> {
+        try!(self.selector.select(&mut self.events, timeout_ms));
+        events.extend(self.events.events.iter().cloned());
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PollBackend {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "PollBackend {{ registered: {} }}", self.selector.fds.len())
+    }
+}
+
+/*
+ *
+ * ===== FFI =====
+ *
+ */
+
+#[cfg(not(target_os = "aix"))]
+mod flags {
+    use libc::c_short;
+
+    pub const POLLIN: c_short = 0x0001;
+    pub const POLLOUT: c_short = 0x0004;
+    pub const POLLERR: c_short = 0x0008;
+    pub const POLLHUP: c_short = 0x0010;
+    pub const POLLNVAL: c_short = 0x0020;
+}
+
+#[cfg(target_os = "aix")]
+mod flags {
+    use libc::c_short;
+
+    pub const POLLIN: c_short = 0x0001;
+    pub const POLLOUT: c_short = 0x0002;
+    pub const POLLERR: c_short = 0x4000;
+    pub const POLLHUP: c_short = 0x2000;
+    pub const POLLNVAL: c_short = 0x8000u16 as c_short;
+}
+
+use self::flags::*;
+
+const EINTR: i32 = 4;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct PollFd {
+    fd: libc::c_int,
+    events: libc::c_short,
+    revents: libc::c_short,
+}
+
+#[allow(non_camel_case_types)]
+type nfds_t = libc::c_ulong;
+
+extern {
+    fn poll(fds: *mut PollFd, nfds: nfds_t, timeout: libc::c_int) -> libc::c_int;
+}
@@ -0,0 +1,363 @@
+use {io, EventSet, PollOpt, Token};
+use event::IoEvent;
+use libc;
+use nix::unistd::close;
+use sys::unix::nix;
+use std::{fmt, isize, ptr};
+use std::collections::HashMap;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+/// Selector backed by Solaris / illumos event ports.
+///
+/// The kernel dissociates an fd from the port every time it reports an event
+/// for it. Level-triggered registrations are emulated by associating the fd
+/// again right after its event has been retrieved, so that it is reported by
+/// the next select for as long as it stays ready. Event ports have no notion
+/// of edge-triggered readiness, edge registrations behave the same way.
+/// Oneshot registrations are left dissociated until they are re-registered.
+pub struct Selector {
+    port: RawFd,
+    // Registration of each fd, used to associate it again after an event
+    registrations: HashMap<RawFd, (Token, EventSet, PollOpt)>,
+    retry_interrupted: bool,
+    capture_errors: bool,
+}
+
+impl Selector {
+    pub fn new() -> io::Result<Selector> {
+        let port = unsafe { port_create() };
+
+        if port < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if let Err(e) = super::set_cloexec(port, true) {
+            let _ = close(port);
+            return Err(e);
+        }
+
+        Ok(Selector {
+            port: port,
+            registrations: HashMap::new(),
+            retry_interrupted: true,
+            capture_errors: false,
+        })
+    }
+
+    /// When set (the default), a wait interrupted by a signal is resumed for
+    /// the remaining time instead of failing with `ErrorKind::Interrupted`.
+    pub fn set_retry_interrupted(&mut self, retry: bool) {
+        self.retry_interrupted = retry;
+    }
+
+    /// When set, the pending `SO_ERROR` of every fd reporting an error is
+    /// fetched along with the event. Fetching the error clears it on the
+    /// socket, so this is off by default.
+    pub fn set_capture_errors(&mut self, capture: bool) {
+        self.capture_errors = capture;
+    }
+
+    /// Associations are cheap and always applied immediately, so there is
+    /// nothing to defer.
+    pub fn set_deferred(&mut self, _deferred: bool) {
+    }
+
+    pub fn flush_deferred(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn select(&mut self, evts: &mut Events, timeout_ms: usize) -> io::Result<()> {
+        use clock_ticks::precise_time_ns;
+
+        let start = precise_time_ns();
+        let mut wait_ms = timeout_ms;
+
+        let cnt;
+
+        loop {
+            // Block until at least one event is available
+            let mut nget: libc::c_uint = 1;
+
+            let mut ts = Timespec {
+                tv_sec: (wait_ms / 1_000) as libc::time_t,
+                tv_nsec: ((wait_ms % 1_000) * 1_000_000) as libc::c_long,
+            };
+
+            // Wait forever rather than overflowing the timespec
+            let timeout = if wait_ms >= isize::MAX as usize { ptr::null_mut() } else { &mut ts as *mut Timespec };
+
+            let res = unsafe {
+                port_getn(self.port,
+                          evts.events.as_mut_ptr(),
+                          evts.events.capacity() as libc::c_uint,
+                          &mut nget,
+                          timeout)
+            };
+
+            if res == 0 {
+                cnt = nget as usize;
+                break;
+            }
+
+            let err = io::Error::last_os_error();
+
+            match err.raw_os_error() {
+                // The timeout expired, `nget` holds the events retrieved
+                // until then
+                Some(ETIME) => {
+                    cnt = nget as usize;
+                    break;
+                }
+                Some(EINTR) if self.retry_interrupted => {
+                    wait_ms = super::remaining_ms(start, timeout_ms);
+                }
+                _ => return Err(err),
+            }
+        }
+
+        unsafe { evts.events.set_len(cnt); }
+
+        evts.errors.clear();
+
+        for idx in 0..cnt {
+            let evt = evts.events[idx];
+            let fd = evt.portev_object as RawFd;
+
+            if self.capture_errors && evt.portev_events & POLLERR != 0 {
+                match nix::getsockopt(fd, nix::sockopt::SocketError) {
+                    Ok(code) if code != 0 => evts.errors.push((idx, code)),
+                    _ => {}
+                }
+            }
+
+            if let Err(e) = self.reassociate(fd) {
+                // Typically an fd closed without being deregistered
+                warn!("failed to associate fd again; fd={}; err={:?}", fd, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Associates `fd` again after an event has been retrieved for it, unless
+    // it was registered as oneshot
+    fn reassociate(&mut self, fd: RawFd) -> io::Result<()> {
+        let (token, interests, opts) = match self.registrations.get(&fd) {
+            Some(&registration) => registration,
+            // Deregistered since the event was queued
+            None => return Ok(()),
+        };
+
+        if opts.is_oneshot() {
+            return Ok(());
+        }
+
+        self.associate(fd, token, interests)
+    }
+
+    fn associate(&self, fd: RawFd, token: Token, interests: EventSet) -> io::Result<()> {
+        let res = unsafe {
+            port_associate(self.port,
+                           PORT_SOURCE_FD,
+                           fd as libc::uintptr_t,
+                           interests_to_poll(interests),
+                           token.as_usize() as *mut libc::c_void)
+        };
+
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Register event interests for the given IO handle with the OS
+    pub fn register(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        trace!("registering; token={:?}; interests={:?}", token, interests);
+
+        try!(self.associate(fd, token, interests));
+
+        self.registrations.insert(fd, (token, interests, opts));
+        Ok(())
+    }
+
+    /// Associating an fd again updates its events and user data, which also
+    /// re-arms oneshot registrations.
+    pub fn reregister(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        self.register(fd, token, interests, opts)
+    }
+
+    pub fn deregister(&mut self, fd: RawFd) -> io::Result<()> {
+        self.registrations.remove(&fd);
+
+        let res = unsafe { port_dissociate(self.port, PORT_SOURCE_FD, fd as libc::uintptr_t) };
+
+        if res < 0 {
+            let err = io::Error::last_os_error();
+
+            // A oneshot fd, or one whose event was just retrieved, is no
+            // longer associated with the port
+            if err.raw_os_error() != Some(ENOENT) {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forgets a closed fd. Closing an fd dissociates it from the port.
+    pub fn forget(&mut self, fd: RawFd) {
+        self.registrations.remove(&fd);
+    }
+}
+
+fn interests_to_poll(interests: EventSet) -> libc::c_int {
+    let mut events = 0;
+
+    if interests.is_readable() {
+        events |= POLLIN;
+    }
+
+    if interests.is_writable() {
+        events |= POLLOUT;
+    }
+
+    events
+}
+
+impl AsRawFd for Selector {
+    fn as_raw_fd(&self) -> RawFd {
+        self.port
+    }
+}
+
+impl fmt::Debug for Selector {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Selector {{ port: {} }}", self.port)
+    }
+}
+
+impl Drop for Selector {
+    fn drop(&mut self) {
+        let _ = close(self.port);
+    }
+}
+
+pub struct Events {
+    events: Vec<PortEvent>,
+    // (index, errno) pairs for the events that reported a socket error
+    errors: Vec<(usize, i32)>,
+}
+
+impl Events {
+    pub fn new() -> Events {
+        Events {
+            events: Vec::with_capacity(1024),
+            errors: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether the last select filled the buffer.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.events.len() == self.events.capacity()
+    }
+
+    /// The maximum number of events returned by a select.
+    pub fn capacity(&self) -> usize {
+        self.events.capacity()
+    }
+
+    /// Replaces the buffer with one holding up to `cap` events, discarding
+    /// the current events.
+    pub fn set_capacity(&mut self, cap: usize) {
+        self.events = Vec::with_capacity(cap);
+        self.errors.clear();
+    }
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> IoEvent {
+        let evt = self.events[idx];
+        let mut kind = EventSet::none();
+
+        if evt.portev_events & POLLIN != 0 {
+            kind = kind | EventSet::readable();
+        }
+
+        if evt.portev_events & POLLOUT != 0 {
+            kind = kind | EventSet::writable();
+        }
+
+        if evt.portev_events & POLLERR != 0 {
+            kind = kind | EventSet::error();
+        }
+
+        if evt.portev_events & POLLHUP != 0 {
+            kind = kind | EventSet::hup();
+        }
+
+        let mut ret = IoEvent::new(kind, Token(evt.portev_user as usize));
+
+        if kind.is_error() {
+            if let Some(&(_, code)) = self.errors.iter().find(|&&(i, _)| i == idx) {
+                ret.set_error(code);
+            }
+        }
+
+        ret
+    }
+}
+
+impl fmt::Debug for Events {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Events {{ len: {} }}", self.events.len())
+    }
+}
+
+/*
+ *
+ * ===== FFI =====
+ *
+ */
+
+const PORT_SOURCE_FD: libc::c_int = 4;
+
+const POLLIN: libc::c_int = 0x0001;
+const POLLOUT: libc::c_int = 0x0004;
+const POLLERR: libc::c_int = 0x0008;
+const POLLHUP: libc::c_int = 0x0010;
+
+const ENOENT: i32 = 2;
+const EINTR: i32 = 4;
+const ETIME: i32 = 62;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct PortEvent {
+    portev_events: libc::c_int,
+    portev_source: libc::c_ushort,
+    portev_pad: libc::c_ushort,
+    portev_object: libc::uintptr_t,
+    // A `void *` in C, holds the token
+    portev_user: libc::uintptr_t,
+}
+
+#[repr(C)]
+struct Timespec {
+    tv_sec: libc::time_t,
+    tv_nsec: libc::c_long,
+}
+
+extern {
+    fn port_create() -> libc::c_int;
+    fn port_associate(port: libc::c_int, source: libc::c_int, object: libc::uintptr_t,
+                      events: libc::c_int, user: *mut libc::c_void) -> libc::c_int;
+    fn port_dissociate(port: libc::c_int, source: libc::c_int, object: libc::uintptr_t) -> libc::c_int;
+    fn port_getn(port: libc::c_int, list: *mut PortEvent, max: libc::c_uint,
+                 nget: *mut libc::c_uint, timeout: *mut Timespec) -> libc::c_int;
+}
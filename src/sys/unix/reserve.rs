@@ -0,0 +1,58 @@
+use {io, Io};
+use sys::unix::nix;
+use std::os::unix::io::AsRawFd;
+
+/// A file descriptor held in reserve, to shed connections once the process
+/// runs out of descriptors.
+///
+/// When `accept` fails with `FdLimit`, the connection it could not accept
+/// stays queued and keeps the listener readable. `reject` closes the reserved
+/// descriptor, which makes room to accept the connection, closes the
+/// connection right away, and reserves a descriptor again. The client sees
+/// the connection closed instead of hanging until it times out.
+pub struct FdReserve {
+    spare: Option<Io>,
+}
+
+impl FdReserve {
+    /// Reserves a descriptor by opening `/dev/null`.
+    pub fn new() -> io::Result<FdReserve> {
+        Ok(FdReserve { spare: Some(try!(open_spare())) })
+    }
+
+    /// Returns true if a descriptor is currently reserved. A `reject` that
+    /// could not reserve a descriptor again leaves the reserve empty until
+    /// the next `reject`.
+    pub fn is_reserved(&self) -> bool {
+        self.spare.is_some()
+    }
+
+    /// Accepts and immediately closes one pending connection of `listener`,
+    /// using the reserved descriptor. Returns `false` if no connection was
+    /// pending.
+    pub fn reject<L: AsRawFd>(&mut self, listener: &L) -> io::Result<bool> {
+        // Dropping the spare closes it
+        self.spare = None;
+
+        let res = nix::accept4(listener.as_raw_fd(), nix::SOCK_NONBLOCK | nix::SOCK_CLOEXEC)
+            .map(Io::from_raw_fd)
+            .map_err(super::from_nix_error);
+
+        self.spare = open_spare().ok();
+
+        match res {
+            Ok(_) => Ok(true),
+            Err(e) => io::to_non_block(e).map(|_: Option<()>| false),
+        }
+    }
+}
+
+fn open_spare() -> io::Result<Io> {
+    use nix::fcntl::{open, O_CLOEXEC, O_RDONLY};
+    use nix::sys::stat::Mode;
+    use std::path::Path;
+
+    open(Path::new("/dev/null"), O_RDONLY | O_CLOEXEC, Mode::empty())
+        .map(Io::from_raw_fd)
+        .map_err(super::from_nix_error)
+}
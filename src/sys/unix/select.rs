@@ -0,0 +1,355 @@
+use {io, Error, EventSet, PollOpt, Token};
+use event::IoEvent;
+use sys::unix::nix;
+use libc;
+use std::{cmp, fmt, mem, ptr};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+/// Last resort selector backed by `select(2)`, enabled with the `select`
+/// cargo feature for libcs that provide neither `poll(2)` nor a native
+/// selector.
+///
+/// Only fds lower than `FD_SETSIZE` can be registered, registering any other
+/// fd fails with `ErrorKind::InvalidInput`. As with the `poll(2)` selector,
+/// every select scans all registered fds, edge-triggered registrations
+/// behave as level-triggered ones and oneshot registrations are disabled
+/// once reported, until they are re-registered. Sockets with a pending error
+/// are reported as readable and writable, without `EventSet::error()`.
+/// `EventSet::hup()` is only detected on sockets, by peeking at them once
+/// they are readable.
+pub struct Selector {
+    // Registered fds, with their registration and whether they are enabled
+    registrations: Vec<(RawFd, Token, EventSet, PollOpt, bool)>,
+    // Index of each registered fd in `registrations`
+    index: HashMap<RawFd, usize>,
+    // Where the next select starts scanning `registrations`, so that fds at
+    // the end are not starved when the events buffer fills up
+    next: usize,
+    retry_interrupted: bool,
+}
+
+impl Selector {
+    pub fn new() -> io::Result<Selector> {
+        Ok(Selector {
+            registrations: vec![],
+            index: HashMap::new(),
+            next: 0,
+            retry_interrupted: true,
+        })
+    }
+
+    /// When set (the default), a wait interrupted by a signal is resumed for
+    /// the remaining time instead of failing with `ErrorKind::Interrupted`.
+    pub fn set_retry_interrupted(&mut self, retry: bool) {
+        self.retry_interrupted = retry;
+    }
+
+    /// Errors are not reported by select(2), so there is nothing to capture.
+    pub fn set_capture_errors(&mut self, _capture: bool) {
+    }
+
+    /// Registrations only live in userspace until the next select, so there
+    /// is nothing to defer.
+    pub fn set_deferred(&mut self, _deferred: bool) {
+    }
+
+    pub fn flush_deferred(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn select(&mut self, evts: &mut Events, timeout_ms: usize) -> io::Result<()> {
+        use clock_ticks::precise_time_ns;
+
+        let start = precise_time_ns();
+        let mut wait_ms = timeout_ms;
+
+        evts.events.clear();
+
+        let mut rd = FdSet::new();
+        let mut wr = FdSet::new();
+
+        loop {
+            let mut nfds = 0;
+
+            rd.clear();
+            wr.clear();
+
+            for &(fd, _, interests, _, enabled) in &self.registrations {
+                if !enabled {
+                    continue;
+                }
+
+                if interests.is_readable() {
+                    rd.insert(fd);
+                }
+
+                if interests.is_writable() {
+                    wr.insert(fd);
+                }
+
+                nfds = cmp::max(nfds, fd + 1);
+            }
+
+            let mut tv = Timeval {
+                tv_sec: (wait_ms / 1_000) as libc::time_t,
+                tv_usec: ((wait_ms % 1_000) * 1_000) as libc::c_long,
+            };
+
+            // Wait forever rather than overflowing the timeval
+            let timeout = if wait_ms >= libc::c_int::max_value() as usize { ptr::null_mut() } else { &mut tv as *mut Timeval };
+
+            let res = unsafe { select(nfds, &mut rd, &mut wr, ptr::null_mut(), timeout) };
+
+            if res >= 0 {
+                break;
+            }
+
+            let err = io::Error::last_os_error();
+
+            match err.raw_os_error() {
+                Some(EINTR) if self.retry_interrupted => {
+                    wait_ms = super::remaining_ms(start, timeout_ms);
+                }
+                _ => return Err(err),
+            }
+        }
+
+        let len = self.registrations.len();
+
+        for i in 0..len {
+            if evts.is_full() {
+                // Resume from here on the next select
+                self.next = (self.next + i) % len;
+                return Ok(());
+            }
+
+            let idx = (self.next + i) % len;
+            let (fd, token, interests, opts, enabled) = self.registrations[idx];
+
+            if !enabled {
+                continue;
+            }
+
+            let mut kind = EventSet::none();
+
+            if rd.contains(fd) {
+                kind = kind | EventSet::readable();
+
+                // select(2) has no hangup condition, check whether the peer
+                // closed its end when the fd is reported readable
+                if interests.is_hup() && peer_closed(fd) {
+                    kind = kind | EventSet::hup();
+                }
+            }
+
+            if wr.contains(fd) {
+                kind = kind | EventSet::writable();
+            }
+
+            if kind == EventSet::none() {
+                continue;
+            }
+
+            if opts.is_oneshot() {
+                self.registrations[idx].4 = false;
+            }
+
+            evts.events.push(IoEvent::new(kind, token));
+        }
+
+        Ok(())
+    }
+
+    /// Register event interests for the given IO handle. Registering an fd
+    /// that is already registered fails with `ErrorKind::AlreadyExists`.
+    pub fn register(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        trace!("registering; token={:?}; interests={:?}", token, interests);
+
+        try!(check_fd(fd));
+
+        if let Some(&idx) = self.index.get(&fd) {
+            return Err(From::from(Error::TokenInUse(Some(self.registrations[idx].1))));
+        }
+
+        self.index.insert(fd, self.registrations.len());
+        self.registrations.push((fd, token, interests, opts, true));
+
+        Ok(())
+    }
+
+    /// Changes the registration of `fd`, which also re-enables a oneshot
+    /// registration.
+    pub fn reregister(&mut self, fd: RawFd, token: Token, interests: EventSet, opts: PollOpt) -> io::Result<()> {
+        let idx = try!(self.lookup(fd));
+
+        self.registrations[idx] = (fd, token, interests, opts, true);
+
+        Ok(())
+    }
+
+    pub fn deregister(&mut self, fd: RawFd) -> io::Result<()> {
+        let idx = try!(self.lookup(fd));
+
+        self.index.remove(&fd);
+        self.registrations.swap_remove(idx);
+
+        // Fix up the index of the entry moved into the freed slot
+        if idx < self.registrations.len() {
+            self.index.insert(self.registrations[idx].0, idx);
+        }
+
+        Ok(())
+    }
+
+    /// Forgets a closed fd, which would otherwise keep being polled.
+    pub fn forget(&mut self, fd: RawFd) {
+        let _ = self.deregister(fd);
+    }
+
+    fn lookup(&self, fd: RawFd) -> io::Result<usize> {
+        match self.index.get(&fd) {
+            Some(&idx) => Ok(idx),
+            None => Err(io::Error::new(ErrorKind::NotFound, format!("fd {} is not registered", fd))),
+        }
+    }
+}
+
+// select(2) cannot watch fds beyond the size of an `fd_set`
+fn check_fd(fd: RawFd) -> io::Result<()> {
+    if fd < 0 || fd as usize >= FD_SETSIZE {
+        let msg = format!("fd {} exceeds FD_SETSIZE ({}) and cannot be watched with select(2)", fd, FD_SETSIZE);
+        return Err(io::Error::new(ErrorKind::InvalidInput, msg));
+    }
+
+    Ok(())
+}
+
+impl AsRawFd for Selector {
+    /// `select(2)` has no fd to wait on, always returns -1.
+    fn as_raw_fd(&self) -> RawFd {
+        -1
+    }
+}
+
+impl fmt::Debug for Selector {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Selector {{ registered: {} }}", self.registrations.len())
+    }
+}
+
+pub struct Events {
+    events: Vec<IoEvent>,
+}
+
+impl Events {
+    pub fn new() -> Events {
+        Events { events: Vec::with_capacity(1024) }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether the last select filled the buffer.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.events.len() == self.events.capacity()
+    }
+
+    /// The maximum number of events returned by a select.
+    pub fn capacity(&self) -> usize {
+        self.events.capacity()
+    }
+
+    /// Replaces the buffer with one holding up to `cap` events, discarding
+    /// the current events.
+    pub fn set_capacity(&mut self, cap: usize) {
+        self.events = Vec::with_capacity(cmp::max(cap, 1));
+    }
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> IoEvent {
+        self.events[idx]
+    }
+}
+
+impl fmt::Debug for Events {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Events {{ len: {} }}", self.events.len())
+    }
+}
+
+// Whether `fd` is a socket whose peer closed its end, which reads then
+// report as end of stream. Always false for other fds.
+fn peer_closed(fd: RawFd) -> bool {
+    let mut buf = [0; 1];
+
+    match nix::recv(fd, &mut buf, nix::MSG_PEEK | nix::MSG_DONTWAIT) {
+        Ok(0) => true,
+        _ => false,
+    }
+}
+
+/*
+ *
+ * ===== FFI =====
+ *
+ */
+
+const FD_SETSIZE: usize = 1024;
+
+// Words of an `fd_set`, an array of longs
+#[cfg(target_pointer_width = "64")]
+const FD_SET_WORDS: usize = FD_SETSIZE / 64;
+
+#[cfg(target_pointer_width = "32")]
+const FD_SET_WORDS: usize = FD_SETSIZE / 32;
+
+const EINTR: i32 = 4;
+
+// Bits per word of an `fd_set`
+#[inline]
+fn word_bits() -> usize {
+    mem::size_of::<libc::c_ulong>() * 8
+}
+
+#[repr(C)]
+struct FdSet {
+    bits: [libc::c_ulong; FD_SET_WORDS],
+}
+
+impl FdSet {
+    fn new() -> FdSet {
+        FdSet { bits: [0; FD_SET_WORDS] }
+    }
+
+    fn clear(&mut self) {
+        for word in self.bits.iter_mut() {
+            *word = 0;
+        }
+    }
+
+    fn insert(&mut self, fd: RawFd) {
+        let fd = fd as usize;
+        self.bits[fd / word_bits()] |= 1 << (fd % word_bits());
+    }
+
+    fn contains(&self, fd: RawFd) -> bool {
+        let fd = fd as usize;
+        self.bits[fd / word_bits()] & (1 << (fd % word_bits())) != 0
+    }
+}
+
+#[repr(C)]
+struct Timeval {
+    tv_sec: libc::time_t,
+    tv_usec: libc::c_long,
+}
+
+extern {
+    fn select(nfds: libc::c_int, readfds: *mut FdSet, writefds: *mut FdSet,
+              errorfds: *mut FdSet, timeout: *mut Timeval) -> libc::c_int;
+}
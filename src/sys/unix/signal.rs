@@ -0,0 +1,172 @@
+use {io, Evented, EventSet, PollOpt, Selector, Token};
+use sys::unix::{pipe, Io};
+use libc;
+use std::io::{ErrorKind, Read};
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicIsize, ATOMIC_ISIZE_INIT};
+use std::sync::atomic::Ordering::SeqCst;
+
+pub type Signal = libc::c_int;
+
+// Defined here rather than re-exported from nix, whose signal types change
+// between versions
+pub const SIGHUP: Signal = 1;
+pub const SIGINT: Signal = 2;
+pub const SIGQUIT: Signal = 3;
+pub const SIGPIPE: Signal = 13;
+pub const SIGALRM: Signal = 14;
+pub const SIGTERM: Signal = 15;
+
+pub use self::consts::{
+    SIGCHLD,
+    SIGCONT,
+    SIGTSTP,
+    SIGWINCH,
+    SIGUSR1,
+    SIGUSR2,
+};
+
+#[cfg(any(target_os = "android",
+          all(target_os = "linux",
+              not(any(target_arch = "mips", target_arch = "mipsel")))))]
+mod consts {
+    use super::Signal;
+
+    pub const SIGUSR1: Signal = 10;
+    pub const SIGUSR2: Signal = 12;
+    pub const SIGCHLD: Signal = 17;
+    pub const SIGCONT: Signal = 18;
+    pub const SIGTSTP: Signal = 20;
+    pub const SIGWINCH: Signal = 28;
+}
+
+#[cfg(any(target_os = "solaris",
+          target_os = "illumos",
+          all(target_os = "linux",
+              any(target_arch = "mips", target_arch = "mipsel"))))]
+mod consts {
+    use super::Signal;
+
+    pub const SIGUSR1: Signal = 16;
+    pub const SIGUSR2: Signal = 17;
+    pub const SIGCHLD: Signal = 18;
+    pub const SIGWINCH: Signal = 20;
+    pub const SIGTSTP: Signal = 24;
+    pub const SIGCONT: Signal = 25;
+}
+
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "watchos",
+          target_os = "freebsd",
+          target_os = "dragonfly",
+          target_os = "netbsd",
+          target_os = "openbsd"))]
+mod consts {
+    use super::Signal;
+
+    pub const SIGTSTP: Signal = 18;
+    pub const SIGCONT: Signal = 19;
+    pub const SIGCHLD: Signal = 20;
+    pub const SIGWINCH: Signal = 28;
+    pub const SIGUSR1: Signal = 30;
+    pub const SIGUSR2: Signal = 31;
+}
+
+// Write end of the self-pipe of the event loop currently handling signals,
+// plus one. Zero when no event loop handles signals.
+static SIGNAL_FD: AtomicIsize = ATOMIC_ISIZE_INIT;
+
+extern fn on_signal(signal: libc::c_int) {
+    let fd = SIGNAL_FD.load(SeqCst);
+
+    if fd > 0 {
+        let byte = signal as u8;
+
+        // write(2) is async-signal-safe. If the pipe is full, the signal is
+        // dropped, which is fine as there is already a wakeup pending.
+        unsafe {
+            libc::write((fd - 1) as libc::c_int, &byte as *const u8 as *const libc::c_void, 1);
+        }
+    }
+}
+
+/// Delivers signals through a self-pipe, readable when signals are pending.
+///
+/// Only a single `Signals` may exist in the process at a time.
+#[derive(Debug)]
+pub struct Signals {
+    reader: Io,
+    // Written to by the signal handler, only kept open here
+    #[allow(dead_code)]
+    writer: Io,
+    // Signals with the handler installed, and their previous handler
+    installed: Vec<(Signal, libc::sighandler_t)>,
+}
+
+impl Signals {
+    pub fn new() -> io::Result<Signals> {
+        let (reader, writer) = try!(pipe());
+        let fd = writer.as_raw_fd() as isize + 1;
+
+        if SIGNAL_FD.compare_and_swap(0, fd, SeqCst) != 0 {
+            return Err(io::Error::new(ErrorKind::AlreadyExists, "signals are already handled by another event loop"));
+        }
+
+        Ok(Signals {
+            reader: reader,
+            writer: writer,
+            installed: vec![],
+        })
+    }
+
+    /// Installs the signal handler for `signal`, if not already installed.
+    pub fn add(&mut self, signal: Signal) -> io::Result<()> {
+        if self.installed.iter().any(|&(s, _)| s == signal) {
+            return Ok(());
+        }
+
+        let prev = unsafe { libc::funcs::posix01::signal::signal(signal, on_signal as extern fn(libc::c_int) as libc::sighandler_t) };
+
+        if prev == !0 {
+            // SIG_ERR
+            return Err(io::Error::last_os_error());
+        }
+
+        self.installed.push((signal, prev));
+        Ok(())
+    }
+
+    /// Reads pending signals into `dst`, returning the number of signals
+    /// read.
+    pub fn read(&mut self, dst: &mut [u8]) -> usize {
+        match self.reader.read(dst) {
+            Ok(n) => n,
+            Err(_) => 0,
+        }
+    }
+}
+
+impl Evented for Signals {
+    fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        self.reader.register(selector, token, interest, opts)
+    }
+
+    fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        self.reader.reregister(selector, token, interest, opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+        self.reader.deregister(selector)
+    }
+}
+
+impl Drop for Signals {
+    fn drop(&mut self) {
+        for &(signal, prev) in self.installed.iter() {
+            unsafe { libc::funcs::posix01::signal::signal(signal, prev); }
+        }
+
+        SIGNAL_FD.store(0, SeqCst);
+    }
+}
@@ -0,0 +1,30 @@
+use io;
+#[cfg(target_os = "linux")]
+use libc;
+
+/// Lets the kernel delay the timers of the calling thread, such as the
+/// timeout of a poll, by up to `ns` nanoseconds to coalesce their wakeups
+/// with others. Only supported on Linux, elsewhere nothing is done.
+#[cfg(target_os = "linux")]
+pub fn set_timer_slack(ns: u64) -> io::Result<()> {
+    const PR_SET_TIMERSLACK: libc::c_int = 29;
+
+    let res = unsafe { prctl(PR_SET_TIMERSLACK, ns as libc::c_ulong, 0, 0, 0) };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_timer_slack(_ns: u64) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+extern {
+    fn prctl(option: libc::c_int, arg2: libc::c_ulong, arg3: libc::c_ulong,
+             arg4: libc::c_ulong, arg5: libc::c_ulong) -> libc::c_int;
+}
@@ -1,12 +1,24 @@
 use {io, Evented, EventSet, Io, PollOpt, Selector, Token};
+use io::MapNonBlock;
 use sys::unix::{net, nix, Socket};
 use std::io::{Read, Write};
 use std::net::SocketAddr;
-use std::os::unix::io::{RawFd, FromRawFd, AsRawFd};
+use std::os::unix::io::{RawFd, FromRawFd, AsRawFd, IntoRawFd};
+use std::sync::Mutex;
 
 #[derive(Debug)]
 pub struct TcpSocket {
     io: Io,
+    addrs: Mutex<Addrs>,
+}
+
+// The addresses of the socket, cached once they cannot change anymore
+#[derive(Clone, Copy, Debug, Default)]
+struct Addrs {
+    local: Option<SocketAddr>,
+    peer: Option<SocketAddr>,
+    // Connected or listening, the local address is final
+    fixed: bool,
 }
 
 impl TcpSocket {
@@ -25,37 +37,105 @@ impl TcpSocket {
             .map(|fd| From::from(Io::from_raw_fd(fd)))
     }
 
-    pub fn connect(&self, addr: &SocketAddr) -> io::Result<bool> {
-        net::connect(&self.io, &net::to_nix_addr(addr))
+    pub fn connect(&self, addr: &SocketAddr) -> io::Result<net::ConnectState> {
+        self.invalidate_addrs();
+
+        let state = try!(net::connect(&self.io, &net::to_nix_addr(addr)));
+
+        if state == net::ConnectState::Connected {
+            self.addrs.lock().unwrap().fixed = true;
+        }
+
+        Ok(state)
     }
 
     pub fn bind(&self, addr: &SocketAddr) -> io::Result<()> {
+        self.invalidate_addrs();
         net::bind(&self.io, &net::to_nix_addr(addr))
     }
 
     pub fn listen(&self, backlog: usize) -> io::Result<()> {
-        net::listen(&self.io, backlog)
+        try!(net::listen(&self.io, backlog));
+        self.addrs.lock().unwrap().fixed = true;
+        Ok(())
     }
 
-    pub fn accept(&self) -> io::Result<Option<TcpSocket>> {
+    pub fn accept(&self) -> io::NonBlock<TcpSocket> {
         net::accept(&self.io, true)
-            .map(|fd| Some(From::from(Io::from_raw_fd(fd))))
-            .or_else(io::to_non_block)
+            .map(|fd| {
+                let sock: TcpSocket = From::from(Io::from_raw_fd(fd));
+                sock.addrs.lock().unwrap().fixed = true;
+                sock
+            })
+            .map_non_block()
     }
 
+    /// Returns the address of the peer. It is cached once known, so only
+    /// the first call makes a syscall.
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
-        net::getpeername(&self.io)
-            .map(net::to_std_addr)
+        if let Some(addr) = self.addrs.lock().unwrap().peer {
+            return Ok(addr);
+        }
+
+        let addr = try!(net::getpeername(&self.io).map(net::to_std_addr));
+
+        let mut addrs = self.addrs.lock().unwrap();
+        addrs.peer = Some(addr);
+        addrs.fixed = true;
+
+        Ok(addr)
     }
 
+    /// Returns the local address. It is cached once the socket is connected
+    /// or listening, before which binding or connecting may still change
+    /// it.
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
-        net::getsockname(&self.io)
-            .map(net::to_std_addr)
+        if let Some(addr) = self.addrs.lock().unwrap().local {
+            return Ok(addr);
+        }
+
+        let addr = try!(net::getsockname(&self.io).map(net::to_std_addr));
+
+        let mut addrs = self.addrs.lock().unwrap();
+
+        if addrs.fixed {
+            addrs.local = Some(addr);
+        }
+
+        Ok(addr)
     }
 
+    /// Returns a new handle to the socket. The handles cache addresses
+    /// independently, the clone starting with those of `self`.
     pub fn try_clone(&self) -> io::Result<TcpSocket> {
-        net::dup(&self.io)
-            .map(From::from)
+        let io = try!(net::dup(&self.io));
+        let addrs = *self.addrs.lock().unwrap();
+
+        Ok(TcpSocket {
+            io: io,
+            addrs: Mutex::new(addrs),
+        })
+    }
+
+    /// Takes over the fd of a std socket, switched to non-blocking mode.
+    pub fn from_std<T: IntoRawFd>(sock: T) -> io::Result<TcpSocket> {
+        let sock: TcpSocket = From::from(Io::from_raw_fd(sock.into_raw_fd()));
+        try!(sock.set_nonblocking(true));
+        Ok(sock)
+    }
+
+    /// Hands the fd over to a std socket, switched back to blocking mode.
+    pub fn into_std<T: FromRawFd>(self) -> io::Result<T> {
+        try!(self.set_nonblocking(false));
+        Ok(unsafe { FromRawFd::from_raw_fd(self.io.into_raw_fd()) })
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.io.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.io.set_inheritable(inheritable)
     }
 
     pub fn shutdown(&self, how: net::Shutdown) -> io::Result<()> {
@@ -76,10 +156,32 @@ impl TcpSocket {
         net::take_socket_error(&self.io)
     }
 
+    pub fn finish_connect(&self) -> io::Result<net::ConnectState> {
+        let state = try!(net::finish_connect(&self.io));
+
+        if state == net::ConnectState::Connected {
+            self.addrs.lock().unwrap().fixed = true;
+        }
+
+        Ok(state)
+    }
+
     pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
         net::set_nodelay(&self.io, nodelay)
     }
 
+    pub fn send(&self, buf: &[u8], more: bool) -> io::Result<usize> {
+        net::send(&self.io, buf, more)
+    }
+
+    pub fn set_busy_poll(&self, usecs: u32) -> io::Result<()> {
+        net::set_busy_poll(&self.io, usecs)
+    }
+
+    pub fn bind_device(&self, interface: &str) -> io::Result<()> {
+        net::bind_device(&self.io, interface)
+    }
+
     pub fn set_keepalive(&self, seconds: Option<u32>) -> io::Result<()> {
         match seconds {
             Some(sec) => {
@@ -91,6 +193,10 @@ impl TcpSocket {
             }
         }
     }
+
+    fn invalidate_addrs(&self) {
+        *self.addrs.lock().unwrap() = Addrs::default();
+    }
 }
 
 impl Read for TcpSocket {
@@ -109,6 +215,22 @@ impl Write for TcpSocket {
     }
 }
 
+impl<'a> Read for &'a TcpSocket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.io).read(buf)
+    }
+}
+
+impl<'a> Write for &'a TcpSocket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.io).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.io).flush()
+    }
+}
+
 impl Evented for TcpSocket {
     fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
         self.io.register(selector, token, interest, opts)
@@ -128,13 +250,22 @@ impl Socket for TcpSocket {
 
 impl From<Io> for TcpSocket {
     fn from(io: Io) -> TcpSocket {
-        TcpSocket { io: io }
+        TcpSocket {
+            io: io,
+            addrs: Mutex::new(Addrs::default()),
+        }
     }
 }
 
 impl FromRawFd for TcpSocket {
     unsafe fn from_raw_fd(fd: RawFd) -> TcpSocket {
-        TcpSocket { io: Io::from_raw_fd(fd) }
+        From::from(Io::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for TcpSocket {
+    fn into_raw_fd(self) -> RawFd {
+        self.io.into_raw_fd()
     }
 }
 
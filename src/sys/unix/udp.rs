@@ -1,8 +1,11 @@
-use {io, Evented, EventSet, Io, IpAddr, PollOpt, Selector, Token};
+use {io, Evented, EventSet, Io, IpAddr, Ipv4Addr, Ipv6Addr, PollOpt, Selector, Token};
+use io::MapNonBlock;
 use buf::{Buf, MutBuf};
 use sys::unix::{net, nix, Socket};
+use sys::unix::interface::Interface;
+use std::io::ErrorKind;
 use std::net::SocketAddr;
-use std::os::unix::io::{RawFd, AsRawFd, FromRawFd};
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd};
 
 #[derive(Debug)]
 pub struct UdpSocket {
@@ -36,22 +39,44 @@ impl UdpSocket {
             .map(From::from)
     }
 
-    pub fn send_to<B: Buf>(&self, buf: &mut B, target: &SocketAddr) -> io::Result<Option<()>> {
+    /// Takes over the fd of a std socket, switched to non-blocking mode.
+    pub fn from_std<T: IntoRawFd>(sock: T) -> io::Result<UdpSocket> {
+        let sock = UdpSocket { io: Io::from_raw_fd(sock.into_raw_fd()) };
+        try!(sock.set_nonblocking(true));
+        Ok(sock)
+    }
+
+    /// Hands the fd over to a std socket, switched back to blocking mode.
+    pub fn into_std<T: FromRawFd>(self) -> io::Result<T> {
+        try!(self.set_nonblocking(false));
+        Ok(unsafe { FromRawFd::from_raw_fd(self.io.into_raw_fd()) })
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.io.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.io.set_inheritable(inheritable)
+    }
+
+    pub fn send_to<B: Buf>(&self, buf: &mut B, target: &SocketAddr) -> io::NonBlock<()> {
         net::sendto(&self.io, buf.bytes(), &net::to_nix_addr(target))
-            .map(|cnt| {
-                buf.advance(cnt);
-                Some(())
-            })
-            .or_else(io::to_non_block)
+            .map(|cnt| buf.advance(cnt))
+            .map_non_block()
     }
 
-    pub fn recv_from<B: MutBuf>(&self, buf: &mut B) -> io::Result<Option<SocketAddr>> {
+    pub fn recv_from<B: MutBuf>(&self, buf: &mut B) -> io::NonBlock<SocketAddr> {
         net::recvfrom(&self.io, buf.mut_bytes())
             .map(|(cnt, addr)| {
                 buf.advance(cnt);
-                Some(net::to_std_addr(addr))
+                net::to_std_addr(addr)
             })
-            .or_else(io::to_non_block)
+            .map_non_block()
+    }
+
+    pub fn set_busy_poll(&self, usecs: u32) -> io::Result<()> {
+        net::set_busy_poll(&self.io, usecs)
     }
 
     pub fn set_broadcast(&self, on: bool) -> io::Result<()> {
@@ -64,11 +89,11 @@ impl UdpSocket {
             .map_err(super::from_nix_error)
     }
 
-    pub fn join_multicast(&self, multi: &IpAddr) -> io::Result<()> {
+    pub fn join_multicast(&self, multi: &IpAddr, interface: Option<&Interface>) -> io::Result<()> {
         match *multi {
             IpAddr::V4(ref addr) => {
                 // Create the request
-                let req = nix::ip_mreq::new(nix::Ipv4Addr::from_std(addr), None);
+                let req = try!(ip_mreq(addr, interface));
 
                 // Set the socket option
                 nix::setsockopt(self.as_raw_fd(), nix::sockopt::IpAddMembership, &req)
@@ -76,7 +101,7 @@ impl UdpSocket {
             }
             IpAddr::V6(ref addr) => {
                 // Create the request
-                let req = nix::ipv6_mreq::new(nix::Ipv6Addr::from_std(addr));
+                let req = ipv6_mreq(addr, interface);
 
                 // Set the socket option
                 nix::setsockopt(self.as_raw_fd(), nix::sockopt::Ipv6AddMembership, &req)
@@ -85,11 +110,11 @@ impl UdpSocket {
         }
     }
 
-    pub fn leave_multicast(&self, multi: &IpAddr) -> io::Result<()> {
+    pub fn leave_multicast(&self, multi: &IpAddr, interface: Option<&Interface>) -> io::Result<()> {
         match *multi {
             IpAddr::V4(ref addr) => {
                 // Create the request
-                let req = nix::ip_mreq::new(nix::Ipv4Addr::from_std(addr), None);
+                let req = try!(ip_mreq(addr, interface));
 
                 // Set the socket option
                 nix::setsockopt(self.as_raw_fd(), nix::sockopt::IpDropMembership, &req)
@@ -97,7 +122,7 @@ impl UdpSocket {
             }
             IpAddr::V6(ref addr) => {
                 // Create the request
-                let req = nix::ipv6_mreq::new(nix::Ipv6Addr::from_std(addr));
+                let req = ipv6_mreq(addr, interface);
 
                 // Set the socket option
                 nix::setsockopt(self.as_raw_fd(), nix::sockopt::Ipv6DropMembership, &req)
@@ -106,6 +131,10 @@ impl UdpSocket {
         }
     }
 
+    pub fn bind_device(&self, interface: &str) -> io::Result<()> {
+        net::bind_device(&self.io, interface)
+    }
+
     pub fn set_multicast_time_to_live(&self, ttl: i32) -> io::Result<()> {
         let v = if ttl < 0 {
             0
@@ -149,8 +178,40 @@ impl FromRawFd for UdpSocket {
     }
 }
 
+impl IntoRawFd for UdpSocket {
+    fn into_raw_fd(self) -> RawFd {
+        self.io.into_raw_fd()
+    }
+}
+
 impl AsRawFd for UdpSocket {
     fn as_raw_fd(&self) -> RawFd {
         self.io.as_raw_fd()
     }
 }
+
+// IPv4 identifies the interface by one of its addresses
+fn ip_mreq(multi: &Ipv4Addr, interface: Option<&Interface>) -> io::Result<nix::ip_mreq> {
+    let local = match interface {
+        Some(interface) => {
+            let addr = try!(interface.ipv4_addr()
+                .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "interface has no IPv4 address")));
+
+            Some(nix::Ipv4Addr::from_std(&addr))
+        }
+        None => None,
+    };
+
+    Ok(nix::ip_mreq::new(nix::Ipv4Addr::from_std(multi), local))
+}
+
+// IPv6 identifies the interface by its index, 0 letting the kernel choose
+fn ipv6_mreq(multi: &Ipv6Addr, interface: Option<&Interface>) -> nix::ipv6_mreq {
+    let mut req = nix::ipv6_mreq::new(nix::Ipv6Addr::from_std(multi));
+
+    if let Some(interface) = interface {
+        req.ipv6mr_interface = interface.index();
+    }
+
+    req
+}
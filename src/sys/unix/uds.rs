@@ -1,8 +1,9 @@
 use {io, Evented, EventSet, Io, PollOpt, Selector, Token};
+use io::MapNonBlock;
 use sys::unix::{net, nix, Socket};
 use std::io::{Read, Write};
 use std::path::Path;
-use std::os::unix::io::{RawFd, AsRawFd, FromRawFd};
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd};
 
 #[derive(Debug)]
 pub struct UnixSocket {
@@ -21,7 +22,7 @@ impl UnixSocket {
     }
 
     /// Connect the socket to the specified address
-    pub fn connect<P: AsRef<Path> + ?Sized>(&self, addr: &P) -> io::Result<bool> {
+    pub fn connect<P: AsRef<Path> + ?Sized>(&self, addr: &P) -> io::Result<net::ConnectState> {
         net::connect(&self.io, &try!(to_nix_addr(addr)))
     }
 
@@ -30,10 +31,10 @@ impl UnixSocket {
         net::listen(&self.io, backlog)
     }
 
-    pub fn accept(&self) -> io::Result<Option<UnixSocket>> {
+    pub fn accept(&self) -> io::NonBlock<UnixSocket> {
         net::accept(&self.io, true)
-            .map(|fd| Some(From::from(Io::from_raw_fd(fd))))
-            .or_else(io::to_non_block)
+            .map(|fd| From::from(Io::from_raw_fd(fd)))
+            .map_non_block()
     }
 
     /// Bind the socket to the specified address
@@ -45,6 +46,18 @@ impl UnixSocket {
         net::dup(&self.io)
             .map(From::from)
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.io.set_nonblocking(nonblocking)
+    }
+
+    pub fn set_inheritable(&self, inheritable: bool) -> io::Result<()> {
+        self.io.set_inheritable(inheritable)
+    }
+
+    pub fn finish_connect(&self) -> io::Result<net::ConnectState> {
+        net::finish_connect(&self.io)
+    }
 }
 
 impl Read for UnixSocket {
@@ -63,6 +76,22 @@ impl Write for UnixSocket {
     }
 }
 
+impl<'a> Read for &'a UnixSocket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.io).read(buf)
+    }
+}
+
+impl<'a> Write for &'a UnixSocket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.io).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.io).flush()
+    }
+}
+
 impl Evented for UnixSocket {
     fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
         self.io.register(selector, token, interest, opts)
@@ -92,6 +121,12 @@ impl FromRawFd for UnixSocket {
     }
 }
 
+impl IntoRawFd for UnixSocket {
+    fn into_raw_fd(self) -> RawFd {
+        self.io.into_raw_fd()
+    }
+}
+
 impl AsRawFd for UnixSocket {
     fn as_raw_fd(&self) -> RawFd {
         self.io.as_raw_fd()
@@ -0,0 +1,75 @@
+use {EventSet, Token};
+use event::IoEvent;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Injects readiness into an event loop, for unit testing handlers without
+/// real sockets or real timing. See `EventLoop::test_selector`.
+///
+/// Readiness set with `set_ready` is reported by the next poll, and then
+/// forgotten, as with edge-triggered registrations. Readiness set several
+/// times for the same token before the poll is merged into a single event.
+#[derive(Clone)]
+pub struct TestSelector {
+    ready: Arc<Mutex<Vec<IoEvent>>>,
+}
+
+impl TestSelector {
+    /// Reports `events` for `token` on the next poll. The token does not
+    /// need to be registered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `token` is reserved for the event loop, see `MAX_TOKEN`.
+    pub fn set_ready(&self, token: Token, events: EventSet) {
+        assert!(!token.is_reserved(), "token is reserved by the event loop; token={:?}", token);
+
+        let mut ready = self.ready.lock().unwrap();
+
+        for evt in ready.iter_mut() {
+            if evt.token == token {
+                evt.kind = evt.kind | events;
+                return;
+            }
+        }
+
+        ready.push(IoEvent::new(events, token));
+    }
+
+    /// Reports `events` for `token` on the next poll, along with the socket
+    /// error `code`, so that `Handler::error` is invoked instead of
+    /// `Handler::ready`.
+    pub fn set_error(&self, token: Token, events: EventSet, code: i32) {
+        let mut evt = IoEvent::new(events | EventSet::error(), token);
+        evt.set_error(code);
+
+        let mut ready = self.ready.lock().unwrap();
+        ready.retain(|e| e.token != token);
+        ready.push(evt);
+    }
+
+    /// Forgets the readiness set for `token` that has not been polled yet.
+    pub fn clear(&self, token: Token) {
+        self.ready.lock().unwrap().retain(|e| e.token != token);
+    }
+
+    /// Returns the number of tokens with readiness waiting to be polled.
+    pub fn pending(&self) -> usize {
+        self.ready.lock().unwrap().len()
+    }
+}
+
+pub fn new() -> TestSelector {
+    TestSelector { ready: Arc::new(Mutex::new(vec![])) }
+}
+
+// Moves the injected readiness into `dst`, called by `Poll::poll`
+pub fn drain_into(selector: &TestSelector, dst: &mut Vec<IoEvent>) {
+    dst.extend(selector.ready.lock().unwrap().drain(..));
+}
+
+impl fmt::Debug for TestSelector {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "TestSelector {{ pending: {} }}", self.pending())
+    }
+}
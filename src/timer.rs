@@ -1,13 +1,28 @@
+use clock::Clock;
+use notify::{NotifyError, Waker};
 use token::Token;
-use util::Slab;
-use clock_ticks::precise_time_ns;
-use std::{usize, iter};
+use util::{BoundedQueue, Slab};
+use std::{fmt, u64, usize, iter};
+use std::time::Duration;
 use std::cmp::max;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
 
 use self::TimerErrorKind::TimerOverflow;
 
 const EMPTY: Token = Token(usize::MAX);
-const NS_PER_MS: u64 = 1_000_000;
+
+// Tick of the entries driven by a kernel timer rather than the wheel
+const DETACHED: u64 = u64::MAX;
+
+// Latest tick a wheel timeout can be due at. Deadlines past it are clamped,
+// they are too far away to ever fire.
+const MAX_TICK: u64 = DETACHED - 1;
+
+// Cancelled entries are only swept out of the wheel once there are at least
+// this many, and they make up half of the entries
+const COMPACT_MIN: usize = 64;
 
 // Implements coarse-grained timeouts using an algorithm based on hashed timing
 // wheels by Varghese & Lauck.
@@ -16,7 +31,6 @@ const NS_PER_MS: u64 = 1_000_000;
 // * Handle the case when the timer falls more than an entire wheel behind. There
 //   is no point to loop multiple times around the wheel in one go.
 // * New type for tick, now() -> Tick
-#[derive(Debug)]
 pub struct Timer<T> {
     // Size of each tick in milliseconds
     tick_ms: u64,
@@ -24,15 +38,23 @@ pub struct Timer<T> {
     entries: Slab<Entry<T>>,
     // Timeout wheel. Each tick, the timer will look at the next slot for
     // timeouts that match the current tick.
-    wheel: Vec<Token>,
+    wheel: Box<[Token]>,
     // Tick 0's time in milliseconds
     start: u64,
     // The current tick
     tick: u64,
     // The next entry to possibly timeout
     next: Token,
-    // Masks the target tick to get the slot
-    mask: u64,
+    // Number of entries cancelled but still linked in the wheel
+    cancelled: usize,
+    // Source of the current time
+    clock: Arc<Clock>,
+    // The time read by `cache_now`, used instead of the clock until
+    // `uncache_now`
+    cached_ms: Option<u64>,
+    // Timeouts scheduled from other threads, created with the first
+    // `Scheduler`
+    remote: Option<Arc<Remote<T>>>,
 }
 
 #[derive(Copy, Clone)]
@@ -44,36 +66,79 @@ pub struct Timeout {
 }
 
 impl<T> Timer<T> {
-    pub fn new(tick_ms: u64, mut slots: usize, mut capacity: usize) -> Timer<T> {
+    pub fn new(tick_ms: u64, mut slots: usize, mut capacity: usize, clock: Arc<Clock>) -> Timer<T> {
         slots = slots.next_power_of_two();
         capacity = capacity.next_power_of_two();
 
         Timer {
             tick_ms: tick_ms,
             entries: Slab::new(capacity),
-            wheel: iter::repeat(EMPTY).take(slots).collect(),
+            wheel: iter::repeat(EMPTY).take(slots).collect::<Vec<Token>>().into_boxed_slice(),
             start: 0,
             tick: 0,
             next: EMPTY,
-            mask: (slots as u64) - 1
+            cancelled: 0,
+            clock: clock,
+            cached_ms: None,
+            remote: None,
         }
     }
 
-    #[cfg(test)]
+    // Number of pending timeouts, not counting cancelled ones
     pub fn count(&self) -> usize {
-        self.entries.count()
+        self.entries.count() - self.cancelled
     }
 
-    // Number of ms remaining until the next tick
-    pub fn next_tick_in_ms(&self) -> u64 {
-        let now = self.now_ms();
-        let nxt = self.start + (self.tick + 1) * self.tick_ms;
+    // Number of ms remaining until the earliest pending timeout is due, if
+    // there is any
+    pub fn next_timeout_in_ms(&self) -> Option<u64> {
+        self.next_timeout_tick().map(|tick| {
+            let now = self.now_ms();
+            let at = self.start.saturating_add(tick.saturating_mul(self.tick_ms));
+
+            if at <= now {
+                return 0;
+            }
+
+            at - now
+        })
+    }
+
+    // The tick of the earliest pending timeout. Slots are visited in tick
+    // order, so the search ends at the first entry due in the tick being
+    // visited, and at most visits the whole wheel once.
+    fn next_timeout_tick(&self) -> Option<u64> {
+        if self.count() == 0 {
+            return None;
+        }
+
+        let mut earliest = u64::MAX;
 
-        if nxt <= now {
-            return 0;
+        for tick in self.tick..self.tick + self.wheel.len() as u64 {
+            let mut curr = self.wheel[self.slot_for(tick)];
+
+            while curr != EMPTY {
+                let entry = &self.entries[curr];
+                let links = entry.links;
+
+                if entry.token.is_none() {
+                    curr = links.next;
+                    continue;
+                }
+
+                if links.tick <= tick {
+                    return Some(links.tick);
+                }
+
+                if links.tick < earliest {
+                    earliest = links.tick;
+                }
+
+                curr = links.next;
+            }
         }
 
-        nxt - now
+        Some(earliest)
     }
 
     /*
@@ -99,44 +164,143 @@ impl<T> Timer<T> {
      *
      */
 
+    // Delays too long to be represented, such as `u64::MAX` used as a
+    // "never" sentinel, are clamped to a deadline that never comes.
     pub fn timeout_ms(&mut self, token: T, delay: u64) -> TimerResult<Timeout> {
-        let at = self.now_ms() + max(0, delay);
+        let at = self.now_ms().saturating_add(max(0, delay));
         self.timeout_at_ms(token, at)
     }
 
     pub fn timeout_at_ms(&mut self, token: T, mut at: u64) -> TimerResult<Timeout> {
         // Make relative to start
-        at -= self.start;
-        // Calculate tick
-        let mut tick = (at + self.tick_ms - 1) / self.tick_ms;
+        at = at.saturating_sub(self.start);
+        // Calculate tick, rounding up without overflowing
+        let mut tick = at / self.tick_ms;
+
+        if at % self.tick_ms != 0 {
+            tick += 1;
+        }
 
         // Always target at least 1 tick in the future
         if tick <= self.tick {
             tick = self.tick + 1;
         }
 
+        if tick > MAX_TICK {
+            tick = MAX_TICK;
+        }
+
         self.insert(token, tick)
     }
 
+    // Cancels the timeout. The entry is only marked as cancelled, it stays
+    // in the wheel until it is due, or until the next compaction.
     pub fn clear(&mut self, timeout: Timeout) -> bool {
-        let links = match self.entries.get(timeout.token) {
-            Some(e) => e.links,
+        // Detached timeouts are cleared with `clear_detached`, so that their
+        // kernel timer is disarmed as well
+        if timeout.tick == DETACHED {
+            return false;
+        }
+
+        let entry = match self.entries.get_mut(timeout.token) {
+            Some(e) => e,
             None => return false
         };
 
         // Sanity check
-        if links.tick != timeout.tick {
+        if entry.links.tick != timeout.tick || entry.token.is_none() {
             return false;
         }
 
-        self.unlink(&links, timeout.token);
-        self.entries.remove(timeout.token);
+        entry.token = None;
+        self.cancelled += 1;
+
+        if self.cancelled >= COMPACT_MIN && self.cancelled * 2 >= self.entries.count() {
+            self.compact();
+        }
+
         true
     }
 
+    /*
+     *
+     * ===== Detached timeouts =====
+     *
+     */
+
+    // Stores a timeout that is not linked into the wheel, because it is
+    // driven by a kernel timer instead. Returns the timeout, and the id
+    // identifying it to the kernel.
+    pub fn insert_detached(&mut self, token: T) -> TimerResult<(Timeout, usize)> {
+        if !self.entries.has_remaining() && self.cancelled > 0 {
+            self.compact();
+        }
+
+        let token = try!(
+            self.entries.insert(Entry::new(token, DETACHED, EMPTY))
+            .map_err(|_| TimerError::overflow()));
+
+        Ok((Timeout { token: token, tick: DETACHED }, token.as_usize()))
+    }
+
+    // Removes the detached timeout identified by `id` once its kernel timer
+    // fired, returning its token
+    pub fn take_detached(&mut self, id: usize) -> Option<T> {
+        let token = Token(id);
+
+        match self.entries.get(token) {
+            Some(entry) if entry.links.tick == DETACHED => {}
+            _ => return None,
+        }
+
+        self.entries.remove(token).and_then(|e| e.token)
+    }
+
+    // Cancels a detached timeout, returning the id of the kernel timer to
+    // disarm. Detached entries are not in the wheel, so they are removed
+    // right away rather than lazily.
+    pub fn clear_detached(&mut self, timeout: Timeout) -> Option<usize> {
+        if timeout.tick != DETACHED {
+            return None;
+        }
+
+        self.take_detached(timeout.token.as_usize())
+            .map(|_| timeout.token.as_usize())
+    }
+
+    // Removes all the cancelled entries from the wheel
+    fn compact(&mut self) {
+        trace!("compacting timer; cancelled={}", self.cancelled);
+
+        for slot in 0..self.wheel.len() {
+            let mut curr = self.wheel[slot];
+
+            while curr != EMPTY {
+                let (links, cancelled) = {
+                    let entry = &self.entries[curr];
+                    (entry.links, entry.token.is_none())
+                };
+
+                if cancelled {
+                    self.unlink(&links, curr);
+                    self.entries.remove(curr);
+                }
+
+                curr = links.next;
+            }
+        }
+
+        self.cancelled = 0;
+    }
+
     fn insert(&mut self, token: T, tick: u64) -> TimerResult<Timeout> {
+        // Make room by dropping the cancelled entries when the slab is full
+        if !self.entries.has_remaining() && self.cancelled > 0 {
+            self.compact();
+        }
+
         // Get the slot for the requested tick
-        let slot = (tick & self.mask) as usize;
+        let slot = self.slot_for(tick);
         let curr = self.wheel[slot];
 
         // Insert the new entry
@@ -184,6 +348,54 @@ impl<T> Timer<T> {
         }
     }
 
+    /*
+     *
+     * ===== Remote scheduling =====
+     *
+     */
+
+    // Returns a handle scheduling timeouts from other threads, which wakes up
+    // the event loop with `waker`. The first call sets up `shards` queues of
+    // `capacity` requests each, later calls share them.
+    pub fn scheduler(&mut self, waker: Waker, shards: usize, capacity: usize) -> Scheduler<T> {
+        if self.remote.is_none() {
+            let shards = max(shards, 1);
+
+            self.remote = Some(Arc::new(Remote {
+                shards: (0..shards).map(|_| BoundedQueue::with_capacity(capacity)).collect(),
+                next_shard: AtomicUsize::new(0),
+                pending: AtomicBool::new(false),
+                closed: AtomicBool::new(false),
+                clock: self.clock.clone(),
+                waker: waker,
+            }));
+        }
+
+        Scheduler::new(self.remote.clone().unwrap())
+    }
+
+    // Inserts the timeouts scheduled from other threads since the last call.
+    // Returns the number of timeouts dropped because the timer is full.
+    pub fn merge_remote(&mut self) -> usize {
+        let remote = match self.remote {
+            Some(ref remote) if remote.pending.swap(false, SeqCst) => remote.clone(),
+            _ => return 0,
+        };
+
+        let mut dropped = 0;
+
+        for shard in remote.shards.iter() {
+            while let Some((token, at)) = shard.pop() {
+                if self.timeout_at_ms(token, at).is_err() {
+                    warn!("dropping timeout scheduled from another thread; err=too many timer entries");
+                    dropped += 1;
+                }
+            }
+        }
+
+        dropped
+    }
+
     /*
      *
      * ===== Advance time =====
@@ -209,14 +421,18 @@ impl<T> Timer<T> {
                 let links = self.entries[curr].links;
 
                 if links.tick <= self.tick {
-                    trace!("triggering; token={:?}", curr);
-
                     // Unlink will also advance self.next
                     self.unlink(&links, curr);
 
-                    // Remove and return the token
-                    return self.entries.remove(curr)
-                        .map(|e| e.token);
+                    // Remove the entry, and return the token unless the
+                    // timeout was cancelled
+                    match self.entries.remove(curr).and_then(|e| e.token) {
+                        Some(token) => {
+                            trace!("triggering; token={:?}", curr);
+                            return Some(token);
+                        }
+                        None => self.cancelled -= 1,
+                    }
                 } else {
                     self.next = links.next;
                 }
@@ -240,7 +456,8 @@ impl<T> Timer<T> {
 
     #[inline]
     fn slot_for(&self, tick: u64) -> usize {
-        (self.mask & tick) as usize
+        // The wheel size is a power of two
+        (tick & (self.wheel.len() as u64 - 1)) as usize
     }
 
     // Convert a ms duration into a number of ticks, rounds up
@@ -249,23 +466,162 @@ impl<T> Timer<T> {
         (ms - self.start) / self.tick_ms
     }
 
+    // Reads the clock once, and uses that time until `uncache_now`
+    pub fn cache_now(&mut self) {
+        self.cached_ms = Some(self.clock.now_ms());
+    }
+
+    pub fn uncache_now(&mut self) {
+        self.cached_ms = None;
+    }
+
     #[inline]
-    fn now_ms(&self) -> u64 {
-        precise_time_ns() / NS_PER_MS
+    pub fn now_ms(&self) -> u64 {
+        match self.cached_ms {
+            Some(ms) => ms,
+            None => self.clock.now_ms(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Timer<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Timer {{ tick_ms: {}, tick: {}, count: {} }}", self.tick_ms, self.tick, self.count())
+    }
+}
+
+impl<T> Drop for Timer<T> {
+    fn drop(&mut self) {
+        if let Some(ref remote) = self.remote {
+            remote.closed.store(true, SeqCst);
+        }
+    }
+}
+
+/// Schedules timeouts on an event loop from any thread, see
+/// `EventLoop::scheduler`.
+///
+/// Requests are pushed without locking onto one of several queues, each
+/// clone of a scheduler being assigned its own, so that producers do not
+/// contend with each other. The event loop merges the queues into its timer
+/// on every tick, waking up to do so if it is sleeping. The delay is
+/// measured from the time of the call, not from the time the event loop
+/// picks up the request.
+pub struct Scheduler<T> {
+    remote: Arc<Remote<T>>,
+    shard: usize,
+}
+
+impl<T> Scheduler<T> {
+    fn new(remote: Arc<Remote<T>>) -> Scheduler<T> {
+        let shard = remote.next_shard.fetch_add(1, Relaxed) % remote.shards.len();
+
+        Scheduler {
+            remote: remote,
+            shard: shard,
+        }
+    }
+
+    /// Schedules `token` to be passed to `Handler::timeout` after `delay`
+    /// milliseconds. Never blocks; if this scheduler's queue is full the
+    /// token is handed back in `NotifyError::Full`, and once the event loop
+    /// has been dropped, `NotifyError::Closed` is returned.
+    ///
+    /// Scheduled timeouts cannot be cleared, as the `Timeout` is only known
+    /// to the event loop.
+    pub fn timeout_ms(&self, token: T, delay: u64) -> Result<(), NotifyError<T>> {
+        self.schedule(token, delay)
+    }
+
+    /// Schedules `token` to be passed to `Handler::timeout` after `delay`,
+    /// rounded up to the millisecond. See `timeout_ms`.
+    pub fn timeout(&self, token: T, delay: Duration) -> Result<(), NotifyError<T>> {
+        self.schedule(token, duration_to_ms(delay))
+    }
+
+    fn schedule(&self, token: T, delay: u64) -> Result<(), NotifyError<T>> {
+        let remote = &*self.remote;
+
+        if remote.closed.load(SeqCst) {
+            return Err(NotifyError::Closed(Some(token)));
+        }
+
+        let at = remote.clock.now_ms().saturating_add(delay);
+
+        if let Err((token, _)) = remote.shards[self.shard].push((token, at)) {
+            return Err(NotifyError::Full(token));
+        }
+
+        remote.pending.store(true, SeqCst);
+
+        if let Err(e) = remote.waker.wakeup() {
+            return Err(NotifyError::Io(e));
+        }
+
+        Ok(())
     }
 }
 
+impl<T> Clone for Scheduler<T> {
+    fn clone(&self) -> Scheduler<T> {
+        Scheduler::new(self.remote.clone())
+    }
+}
+
+impl<T> fmt::Debug for Scheduler<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Scheduler {{ shard: {} }}", self.shard)
+    }
+}
+
+unsafe impl<T: Send> Sync for Scheduler<T> { }
+unsafe impl<T: Send> Send for Scheduler<T> { }
+
+struct Remote<T> {
+    // Requests as (token, deadline in ms) pairs
+    shards: Vec<BoundedQueue<(T, u64)>>,
+    // Shard assigned to the next scheduler
+    next_shard: AtomicUsize,
+    // Set when requests have been pushed since the last merge
+    pending: AtomicBool,
+    // Set once the timer has been dropped
+    closed: AtomicBool,
+    clock: Arc<Clock>,
+    waker: Waker,
+}
+
+// Converts `delay` to milliseconds, rounding up so that a timeout never
+// fires early. Saturates at `u64::MAX`.
+pub fn duration_to_ms(delay: Duration) -> u64 {
+    let nanos = delay.subsec_nanos() as u64;
+    let ms = (nanos + 999_999) / 1_000_000;
+
+    delay.as_secs().saturating_mul(1_000).saturating_add(ms)
+}
+
+// Postpones the deadline `at` by less than `slack` ms, rounding it up to a
+// multiple of `slack`, so that the deadlines given the same slack that fall
+// within the same `slack` ms share one wakeup.
+pub fn with_slack(at: u64, slack: u64) -> u64 {
+    if slack == 0 || at % slack == 0 || at > u64::MAX - slack {
+        return at;
+    }
+
+    (at / slack + 1) * slack
+}
+
 // Doubly linked list of timer entries. Allows for efficient insertion /
 // removal of timeouts.
 struct Entry<T> {
-    token: T,
+    // None once the timeout has been cancelled
+    token: Option<T>,
     links: EntryLinks,
 }
 
 impl<T> Entry<T> {
     fn new(token: T, tick: u64, next: Token) -> Entry<T> {
         Entry {
-            token: token,
+            token: Some(token),
             links: EntryLinks {
                 tick: tick,
                 prev: EMPTY,
@@ -306,7 +662,11 @@ pub enum TimerErrorKind {
 
 #[cfg(test)]
 mod test {
-    use super::Timer;
+    use super::{duration_to_ms, with_slack, Timer};
+    use clock::{Clock, MockClock};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use std::u64;
 
     #[test]
     pub fn test_timeout_next_tick() {
@@ -467,10 +827,161 @@ mod test {
         assert_eq!(0, t.count());
     }
 
+    #[test]
+    pub fn test_clearing_timeout_twice() {
+        let mut t = timer();
+
+        let a = t.timeout_at_ms("a", 100).unwrap();
+        assert!(t.clear(a));
+        assert!(!t.clear(a));
+        assert_eq!(0, t.count());
+
+        let tick = t.ms_to_tick(100);
+        assert_eq!(None, t.tick_to(tick));
+        assert_eq!(0, t.entries.count());
+    }
+
+    #[test]
+    pub fn test_compacting_cancelled_timeouts() {
+        let mut t: Timer<usize> = Timer::new(TICK, SLOTS, 256, Arc::new(MockClock::new(0)));
+
+        let timeouts: Vec<_> = (0..super::COMPACT_MIN * 2)
+            .map(|i| t.timeout_at_ms(i, 100 + i as u64 * TICK).unwrap())
+            .collect();
+
+        // Cancelled entries linger until half of the entries are cancelled
+        for to in timeouts[..super::COMPACT_MIN - 1].iter() {
+            assert!(t.clear(*to));
+        }
+
+        assert_eq!(super::COMPACT_MIN + 1, t.count());
+        assert_eq!(super::COMPACT_MIN * 2, t.entries.count());
+
+        assert!(t.clear(timeouts[super::COMPACT_MIN - 1]));
+        assert_eq!(super::COMPACT_MIN, t.count());
+        assert_eq!(super::COMPACT_MIN, t.entries.count());
+
+        let tick = t.ms_to_tick(100 + super::COMPACT_MIN as u64 * TICK);
+        assert_eq!(Some(super::COMPACT_MIN), t.tick_to(tick));
+    }
+
+    #[test]
+    pub fn test_insert_compacts_when_full() {
+        let mut t = timer();
+
+        let timeouts: Vec<_> = (0..32).map(|_| t.timeout_at_ms("a", 100).unwrap()).collect();
+        assert!(t.timeout_at_ms("b", 100).is_err());
+
+        t.clear(timeouts[0]);
+        t.timeout_at_ms("b", 200).unwrap();
+        assert_eq!(32, t.count());
+    }
+
     const TICK: u64 = 100;
     const SLOTS: usize = 16;
 
+    #[test]
+    pub fn test_next_timeout_tick() {
+        let mut t = timer();
+        assert_eq!(None, t.next_timeout_tick());
+
+        t.timeout_at_ms("a", 500).unwrap();
+        let b = t.timeout_at_ms("b", 200).unwrap();
+
+        // Lands in the same slot as "b", one rotation later
+        t.timeout_at_ms("c", 200 + SLOTS as u64 * TICK).unwrap();
+
+        assert_eq!(Some(t.ms_to_tick(200)), t.next_timeout_tick());
+
+        t.clear(b);
+        assert_eq!(Some(t.ms_to_tick(500)), t.next_timeout_tick());
+
+        let tick = t.ms_to_tick(500);
+        assert_eq!(Some("a"), t.tick_to(tick));
+
+        // Only remains the one beyond the first rotation
+        assert_eq!(Some(t.ms_to_tick(200 + SLOTS as u64 * TICK)), t.next_timeout_tick());
+    }
+
+    #[test]
+    pub fn test_timeout_at_wheel_horizon() {
+        let mut t = timer();
+        let horizon = SLOTS as u64 * TICK;
+
+        // Last tick of the first rotation, then the first tick of the next
+        // one, which shares the slot of tick 0
+        t.timeout_at_ms("a", horizon - TICK).unwrap();
+        t.timeout_at_ms("b", horizon).unwrap();
+        t.timeout_at_ms("c", horizon + TICK).unwrap();
+
+        let tick = t.ms_to_tick(horizon - TICK);
+        assert_eq!(Some("a"), t.tick_to(tick));
+        assert_eq!(None, t.tick_to(tick));
+
+        let tick = t.ms_to_tick(horizon);
+        assert_eq!(Some("b"), t.tick_to(tick));
+        assert_eq!(None, t.tick_to(tick));
+
+        let tick = t.ms_to_tick(horizon + TICK);
+        assert_eq!(Some("c"), t.tick_to(tick));
+        assert_eq!(0, t.count());
+    }
+
+    #[test]
+    pub fn test_timeout_never() {
+        let clock = MockClock::new(1_000);
+        let mut t = Timer::new(TICK, SLOTS, 32, Arc::new(clock.clone()));
+        t.setup();
+
+        t.timeout_ms("a", u64::MAX).unwrap();
+        t.timeout_at_ms("b", u64::MAX).unwrap();
+
+        // Several rotations of the wheel later, neither has fired
+        clock.advance_ms(4 * SLOTS as u64 * TICK);
+        let now = t.now();
+        assert_eq!(None, t.tick_to(now));
+        assert_eq!(2, t.count());
+
+        assert_eq!(Some(u64::MAX - clock.now_ms()), t.next_timeout_in_ms());
+    }
+
+    #[test]
+    pub fn test_duration_to_ms() {
+        assert_eq!(0, duration_to_ms(Duration::new(0, 0)));
+        assert_eq!(1, duration_to_ms(Duration::new(0, 1)));
+        assert_eq!(1_500, duration_to_ms(Duration::new(1, 500_000_000)));
+        assert_eq!(u64::MAX, duration_to_ms(Duration::new(u64::MAX, 999_999_999)));
+    }
+
+    #[test]
+    pub fn test_with_slack() {
+        assert_eq!(with_slack(1_234, 0), 1_234);
+        assert_eq!(with_slack(1_234, 100), 1_300);
+        assert_eq!(with_slack(1_201, 100), 1_300);
+        assert_eq!(with_slack(1_200, 100), 1_200);
+        assert_eq!(with_slack(1_199, 100), 1_200);
+        assert_eq!(with_slack(u64::MAX, 100), u64::MAX);
+    }
+
+    #[test]
+    pub fn test_timeout_with_mock_clock() {
+        let clock = MockClock::new(1_000);
+        let mut t = Timer::new(TICK, SLOTS, 32, Arc::new(clock.clone()));
+        t.setup();
+
+        t.timeout_ms("a", 250).unwrap();
+
+        clock.advance_ms(200);
+        let now = t.now();
+        assert_eq!(None, t.tick_to(now));
+
+        clock.advance_ms(100);
+        let now = t.now();
+        assert_eq!(Some("a"), t.tick_to(now));
+        assert_eq!(0, t.count());
+    }
+
     fn timer() -> Timer<&'static str> {
-        Timer::new(TICK, SLOTS, 32)
+        Timer::new(TICK, SLOTS, 32, Arc::new(MockClock::new(0)))
     }
 }
@@ -1,7 +1,23 @@
+use slab;
+use std::usize;
+
+/// Identifies a registered handle in the events reported to the
+/// `Handler`.
+///
+/// The tokens above `MAX_TOKEN` are reserved for the handles of the event
+/// loop itself, such as the wakeup of its channel. Registering a handle
+/// under one of them fails with `Error::ReservedToken`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Token(pub usize);
 
-use slab;
+// Number of tokens at the top of the range reserved for the event loop
+const RESERVED: usize = 16;
+
+/// The largest token a handle can be registered under.
+pub const MAX_TOKEN: Token = Token(usize::MAX - RESERVED);
+
+// Wakeup of the event loop channel, and self-pipe of the signals
+pub const NOTIFY: Token = Token(usize::MAX);
 
 impl Token {
     #[inline]
@@ -9,6 +25,13 @@ impl Token {
         let Token(inner) = self;
         inner
     }
+
+    /// Returns true if the token is reserved for the event loop's own
+    /// handles, see `MAX_TOKEN`.
+    #[inline]
+    pub fn is_reserved(self) -> bool {
+        self.0 > MAX_TOKEN.0
+    }
 }
 
 impl slab::Index for Token {
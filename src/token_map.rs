@@ -0,0 +1,302 @@
+use {Token};
+use std::{cmp, fmt, mem, slice};
+use std::collections::HashMap;
+use std::collections::hash_map;
+
+// Tokens below it are stored densely by default, 4M slots
+const DEFAULT_DENSE_LIMIT: usize = 1 << 22;
+
+// The dense range grows to any index below it at once, and past it to at
+// most twice its length
+const MIN_DENSE_GROWTH: usize = 1024;
+
+/// Maps tokens chosen elsewhere to the state of their handles, for very
+/// large numbers of connections.
+///
+/// Unlike `TokenSlab`, which hands out tokens, any token may be inserted,
+/// such as the tokens a listener assigns to connections it spreads over
+/// several event loops. Tokens whose index is below the dense limit are
+/// looked up by indexing a vector, which grows up to the largest index
+/// inserted, at most doubling at once. The other tokens, such as tokens
+/// encoding the event loop in their high bits, go to a hash map instead of
+/// growing the vector that much. Looking up the state of an event is then a
+/// bounds check and an index for the tokens of the dense range.
+///
+/// The index of a token is the token itself, unless another function is
+/// given to `with_index`. A `TokenSlab` keeps the generation of its slots
+/// in the high bits of its tokens, so that a slot reused even once gives a
+/// token beyond any dense limit: index its tokens with `util::slab_index`
+/// to keep them dense. Each slot of the vector holds a single token; a token
+/// whose index is taken by another one, such as a stale generation that was
+/// not removed, goes to the hash map.
+///
+/// `usage` reports the memory held by both tiers.
+pub struct TokenMap<T> {
+    dense: Vec<Option<(Token, T)>>,
+    dense_limit: usize,
+    // Entries of the dense range
+    dense_len: usize,
+    sparse: HashMap<Token, T>,
+    index: fn(Token) -> usize,
+}
+
+/// The memory held by a `TokenMap`, see `TokenMap::usage`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TokenMapUsage {
+    /// The number of entries stored densely.
+    pub dense_len: usize,
+    /// The number of slots of the dense range allocated, vacant or not.
+    pub dense_capacity: usize,
+    /// The number of entries stored in the hash map.
+    pub sparse_len: usize,
+    /// An estimate of the bytes allocated by both tiers, not counting
+    /// allocations the values own.
+    pub bytes: usize,
+}
+
+impl<T> TokenMap<T> {
+    /// Stores the tokens below `Token(4_194_304)` densely.
+    pub fn new() -> TokenMap<T> {
+        TokenMap::with_dense_limit(DEFAULT_DENSE_LIMIT)
+    }
+
+    /// Stores the tokens below `Token(limit)` densely, and the others in a
+    /// hash map.
+    pub fn with_dense_limit(limit: usize) -> TokenMap<T> {
+        TokenMap::with_index(limit, Token::as_usize)
+    }
+
+    /// Stores the tokens whose index, as returned by `index`, is below
+    /// `limit` densely, and the others in a hash map. See `TokenMap` for
+    /// the tokens of a `TokenSlab`.
+    pub fn with_index(limit: usize, index: fn(Token) -> usize) -> TokenMap<T> {
+        TokenMap {
+            dense: vec![],
+            dense_limit: limit,
+            dense_len: 0,
+            sparse: HashMap::new(),
+            index: index,
+        }
+    }
+
+    /// Reserves dense slots for the indices below `len`, up to the dense
+    /// limit, so that inserting them does not reallocate.
+    pub fn reserve_dense(&mut self, len: usize) {
+        let len = if len < self.dense_limit { len } else { self.dense_limit };
+
+        if len > self.dense.len() {
+            let additional = len - self.dense.len();
+            self.dense.reserve_exact(additional);
+        }
+    }
+
+    /// Stores `value` under `token`, returning the value it replaces.
+    pub fn insert(&mut self, token: Token, value: T) -> Option<T> {
+        let idx = (self.index)(token);
+
+        if idx >= self.dense.len() {
+            if !self.can_grow_to(idx) {
+                return self.sparse.insert(token, value);
+            }
+
+            let len = idx + 1;
+            let additional = len - self.dense.len();
+
+            self.dense.reserve(additional);
+
+            for _ in 0..additional {
+                self.dense.push(None);
+            }
+        }
+
+        // The token went to the hash map while its slot was taken
+        let in_sparse = !self.sparse.is_empty() && self.sparse.contains_key(&token);
+
+        match self.dense[idx] {
+            Some((t, ref mut prev)) if t == token => return Some(mem::replace(prev, value)),
+            None if !in_sparse => {}
+            _ => return self.sparse.insert(token, value),
+        }
+
+        self.dense[idx] = Some((token, value));
+        self.dense_len += 1;
+        None
+    }
+
+    #[inline]
+    pub fn get(&self, token: Token) -> Option<&T> {
+        let idx = (self.index)(token);
+
+        if idx < self.dense.len() {
+            if let Some((t, ref value)) = self.dense[idx] {
+                if t == token {
+                    return Some(value);
+                }
+            }
+        }
+
+        if self.sparse.is_empty() {
+            return None;
+        }
+
+        self.sparse.get(&token)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, token: Token) -> Option<&mut T> {
+        let idx = (self.index)(token);
+
+        if idx < self.dense.len() {
+            if let Some((t, ref mut value)) = self.dense[idx] {
+                if t == token {
+                    return Some(value);
+                }
+            }
+        }
+
+        if self.sparse.is_empty() {
+            return None;
+        }
+
+        self.sparse.get_mut(&token)
+    }
+
+    pub fn contains(&self, token: Token) -> bool {
+        self.get(token).is_some()
+    }
+
+    /// Removes the value stored under `token` and returns it. The dense
+    /// slots are kept for the next values, see `shrink_to_fit`.
+    pub fn remove(&mut self, token: Token) -> Option<T> {
+        let idx = (self.index)(token);
+
+        if idx < self.dense.len() {
+            let found = match self.dense[idx] {
+                Some((t, _)) => t == token,
+                None => false,
+            };
+
+            if found {
+                self.dense_len -= 1;
+                return self.dense[idx].take().map(|(_, value)| value);
+            }
+        }
+
+        if self.sparse.is_empty() {
+            return None;
+        }
+
+        self.sparse.remove(&token)
+    }
+
+    /// Returns the number of values stored.
+    pub fn len(&self) -> usize {
+        self.dense_len + self.sparse.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the values stored and their tokens, those of the dense range
+    /// first, in the order of their indices, then the others in no
+    /// particular order.
+    pub fn iter(&self) -> TokenMapIter<T> {
+        TokenMapIter {
+            dense: self.dense.iter(),
+            sparse: self.sparse.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> TokenMapIterMut<T> {
+        TokenMapIterMut {
+            dense: self.dense.iter_mut(),
+            sparse: self.sparse.iter_mut(),
+        }
+    }
+
+    /// Removes every value, keeping the memory allocated.
+    pub fn clear(&mut self) {
+        for slot in self.dense.iter_mut() {
+            *slot = None;
+        }
+
+        self.dense_len = 0;
+        self.sparse.clear();
+    }
+
+    /// Releases the dense slots above the largest index stored, and the
+    /// spare capacity of both tiers.
+    pub fn shrink_to_fit(&mut self) {
+        while let Some(&None) = self.dense.last() {
+            self.dense.pop();
+        }
+
+        self.dense.shrink_to_fit();
+        self.sparse.shrink_to_fit();
+    }
+
+    /// Returns the memory held by the map.
+    pub fn usage(&self) -> TokenMapUsage {
+        // A hash map stores a hash along with each key and value
+        let sparse_entry = mem::size_of::<u64>() + mem::size_of::<Token>() + mem::size_of::<T>();
+
+        TokenMapUsage {
+            dense_len: self.dense_len,
+            dense_capacity: self.dense.capacity(),
+            sparse_len: self.sparse.len(),
+            bytes: self.dense.capacity() * mem::size_of::<Option<(Token, T)>>() + self.sparse.capacity() * sparse_entry,
+        }
+    }
+
+    // A single token cannot grow the dense range to millions of slots: past
+    // `MIN_DENSE_GROWTH`, the range at most doubles
+    fn can_grow_to(&self, idx: usize) -> bool {
+        idx < self.dense_limit && idx < cmp::max(self.dense.len() * 2, MIN_DENSE_GROWTH)
+    }
+}
+
+impl<T> fmt::Debug for TokenMap<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "TokenMap {{ dense: {}, sparse: {}, dense_limit: {} }}",
+               self.dense_len, self.sparse.len(), self.dense_limit)
+    }
+}
+
+pub struct TokenMapIter<'a, T: 'a> {
+    dense: slice::Iter<'a, Option<(Token, T)>>,
+    sparse: hash_map::Iter<'a, Token, T>,
+}
+
+impl<'a, T> Iterator for TokenMapIter<'a, T> {
+    type Item = (Token, &'a T);
+
+    fn next(&mut self) -> Option<(Token, &'a T)> {
+        while let Some(slot) = self.dense.next() {
+            if let Some((token, ref value)) = *slot {
+                return Some((token, value));
+            }
+        }
+
+        self.sparse.next().map(|(&token, value)| (token, value))
+    }
+}
+
+pub struct TokenMapIterMut<'a, T: 'a> {
+    dense: slice::IterMut<'a, Option<(Token, T)>>,
+    sparse: hash_map::IterMut<'a, Token, T>,
+}
+
+impl<'a, T> Iterator for TokenMapIterMut<'a, T> {
+    type Item = (Token, &'a mut T);
+
+    fn next(&mut self) -> Option<(Token, &'a mut T)> {
+        while let Some(slot) = self.dense.next() {
+            if let Some((token, ref mut value)) = *slot {
+                return Some((token, value));
+            }
+        }
+
+        self.sparse.next().map(|(&token, value)| (token, value))
+    }
+}
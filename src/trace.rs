@@ -0,0 +1,86 @@
+//! Lifecycle tracing, enabled with the `trace` cargo feature.
+//!
+//! A `Trace` sink installed with `EventLoop::set_trace` receives a callback
+//! for every registration change, poll and dispatch performed by the event
+//! loop. Without the feature, the hooks are compiled out entirely.
+
+#[cfg(feature = "trace")]
+pub use self::enabled::{Sink, Trace};
+
+#[cfg(not(feature = "trace"))]
+pub use self::disabled::Sink;
+
+#[cfg(feature = "trace")]
+mod enabled {
+    use {io, Callback, EventSet, PollOpt, Token};
+    use std::fmt;
+
+    /// Receives the lifecycle events of an event loop. All methods default to
+    /// doing nothing.
+    #[allow(unused_variables)]
+    pub trait Trace: Send {
+        /// An IO handle was registered, `res` is the result of the operation.
+        fn register(&mut self, token: Token, interest: EventSet, opts: PollOpt, res: &io::Result<()>) {
+        }
+
+        /// An IO handle was re-registered.
+        fn reregister(&mut self, token: Token, interest: EventSet, opts: PollOpt, res: &io::Result<()>) {
+        }
+
+        /// An IO handle was re-armed with its previous registration.
+        fn rearm(&mut self, token: Token, res: &io::Result<()>) {
+        }
+
+        /// An IO handle was deregistered.
+        fn deregister(&mut self, res: &io::Result<()>) {
+        }
+
+        /// The event loop is about to poll for IO events, blocking for at
+        /// most `timeout_ms`.
+        fn poll_enter(&mut self, timeout_ms: usize) {
+        }
+
+        /// The poll completed, `res` holds the number of events returned.
+        fn poll_exit(&mut self, res: &io::Result<usize>) {
+        }
+
+        /// An IO event is about to be dispatched to the handler.
+        fn dispatch(&mut self, token: Token, events: EventSet) {
+        }
+
+        /// A timeout is about to be passed to the handler.
+        fn timeout(&mut self) {
+        }
+
+        /// A message is about to be passed to the handler.
+        fn notify(&mut self) {
+        }
+
+        /// A handler callback ran for longer than
+        /// `EventLoopConfig::slow_callback_ms`.
+        fn slow_callback(&mut self, callback: Callback, elapsed_ns: u64) {
+        }
+
+        /// A handler callback panicked, with `EventLoopConfig::catch_panics`
+        /// set.
+        fn panicked(&mut self, callback: Callback) {
+        }
+    }
+
+    #[derive(Default)]
+    pub struct Sink {
+        pub sink: Option<Box<Trace>>,
+    }
+
+    impl fmt::Debug for Sink {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmt, "Sink {{ installed: {} }}", self.sink.is_some())
+        }
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+mod disabled {
+    #[derive(Debug, Default)]
+    pub struct Sink;
+}
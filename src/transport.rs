@@ -0,0 +1,38 @@
+use {io, Evented, EventSet};
+use net::ConnectState;
+use std::io::{Read, Write};
+
+/// A bidirectional connection that can be driven by an event loop, such as
+/// a plain socket or a TLS session layered over one.
+///
+/// Being `Read` and `Write`, a transport gets `TryRead` and `TryWrite`. The
+/// readiness hooks let a layered transport wait for other readiness than
+/// the application asked for: a TLS session in a handshake may need to read
+/// before it can write, or to write before it can read. Register the
+/// transport with `interest(..)` instead of the application's interest,
+/// and pass the events received for it through `readiness` before acting
+/// on them.
+///
+/// Plain sockets implement both hooks as the identity.
+pub trait Transport : Read + Write + Evented {
+    /// Returns the readiness of the underlying socket the transport has to
+    /// wait for, for the application to become ready for `interest`.
+    fn interest(&self, interest: EventSet) -> EventSet {
+        interest
+    }
+
+    /// Translates the readiness of the underlying socket into the readiness
+    /// of the transport, after making progress with it if needed, such as
+    /// advancing a handshake. The returned readiness may be empty when the
+    /// transport consumed the events itself.
+    fn readiness(&mut self, events: EventSet) -> EventSet {
+        events
+    }
+
+    /// Completes a non-blocking connect once the underlying socket is
+    /// writable, see `TcpStream::finish_connect`. Defaults to
+    /// `ConnectState::Connected`, for transports that do not connect.
+    fn finish_connect(&self) -> io::Result<ConnectState> {
+        Ok(ConnectState::Connected)
+    }
+}
@@ -1,7 +1,12 @@
 //! Utilities for non-blocking IO programs
 
 pub use self::mpmc_bounded_queue::Queue as BoundedQueue;
+pub use self::token_slab::{slab_index, TokenSlab, TokenSlabDrain, TokenSlabIter, TokenSlabIterMut};
+
+#[cfg(unix)]
+pub use sys::set_cpu_affinity;
 
 mod mpmc_bounded_queue;
+mod token_slab;
 
 pub type Slab<T> = ::slab::Slab<T, ::Token>;
@@ -0,0 +1,441 @@
+use {io, EventLoop, Evented, Handler};
+use token::Token;
+use std::{iter, mem, ops, slice};
+use std::sync::Arc;
+
+/// A slab that hands out `Token`s which encode both a slot index and the
+/// generation of the slot.
+///
+/// Every time a slot is freed, its generation is bumped. Looking up a token
+/// that refers to a value which has since been removed (even if the slot has
+/// been reused) returns `None` instead of the new occupant. This prevents
+/// late events for a closed connection from being delivered to the next
+/// connection that happens to land in the same slot.
+///
+/// The lower half of the token's bits hold the index and the upper half the
+/// generation, so on 64 bit platforms up to 2^32 - 1 entries may be live at
+/// once. The last generation is skipped, so that tokens stay below
+/// `MAX_TOKEN`.
+///
+/// Storing `Arc`s lets the state of a connection be shared with other
+/// threads: `insert_arc` and `get_arc` hand out references to it, and
+/// dispatching an event is still a direct index rather than a map lookup.
+/// `deregister` removes the value along with the registration of its
+/// handle, so that neither outlives the other.
+///
+/// Keep one slab per event loop for the state of its connections, rather
+/// than a `Box` each: the values are stored inline in a single vector, next
+/// to each other, and the slots of removed values are reused. Tokens are
+/// indices, so they stay valid when the vector grows, unlike references into
+/// it. `iter_mut` visits every value, and `drain` removes them all, for a
+/// sweep on shutdown.
+#[derive(Debug)]
+pub struct TokenSlab<T> {
+    slots: Vec<Slot<T>>,
+    // Indices of vacant slots
+    free: Vec<usize>,
+    len: usize,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    generation: usize,
+    value: Option<T>,
+}
+
+impl<T> TokenSlab<T> {
+    /// Returns a new, empty, slab.
+    pub fn new() -> TokenSlab<T> {
+        TokenSlab::with_capacity(0)
+    }
+
+    /// Returns a new, empty, slab with space pre-allocated for `capacity`
+    /// entries.
+    pub fn with_capacity(capacity: usize) -> TokenSlab<T> {
+        TokenSlab {
+            slots: Vec::with_capacity(capacity),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of live entries.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a value into the slab, returning the token referencing it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index space of the token is exhausted.
+    pub fn insert(&mut self, value: T) -> Token {
+        self.insert_with(|_| value)
+    }
+
+    /// Inserts the value returned by `f`, which is passed the token the value
+    /// will be stored under. This is convenient for connection state that
+    /// needs to know its own token in order to register itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index space of the token is exhausted.
+    pub fn insert_with<F>(&mut self, f: F) -> Token
+        where F: FnOnce(Token) -> T
+    {
+        let idx = match self.free.pop() {
+            Some(idx) => idx,
+            None => {
+                let idx = self.slots.len();
+                assert!(idx < index_mask(), "token slab index space exhausted");
+
+                self.slots.push(Slot { generation: 0, value: None });
+                idx
+            }
+        };
+
+        let token = pack(idx, self.slots[idx].generation);
+
+        self.slots[idx].value = Some(f(token));
+        self.len += 1;
+
+        token
+    }
+
+    /// Returns true if `token` refers to a live entry.
+    pub fn contains(&self, token: Token) -> bool {
+        self.get(token).is_some()
+    }
+
+    /// Returns a reference to the value referenced by `token`, or `None` if
+    /// the token is stale or was never issued by this slab.
+    pub fn get(&self, token: Token) -> Option<&T> {
+        let (idx, generation) = unpack(token);
+
+        match self.slots.get(idx) {
+            Some(slot) if slot.generation == generation => slot.value.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the value referenced by `token`, or
+    /// `None` if the token is stale or was never issued by this slab.
+    pub fn get_mut(&mut self, token: Token) -> Option<&mut T> {
+        let (idx, generation) = unpack(token);
+
+        match self.slots.get_mut(idx) {
+            Some(slot) if slot.generation == generation => slot.value.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// Removes the value referenced by `token`, invalidating the token. The
+    /// associated IO handle should be deregistered from the event loop first.
+    pub fn remove(&mut self, token: Token) -> Option<T> {
+        let (idx, generation) = unpack(token);
+
+        let value = match self.slots.get_mut(idx) {
+            Some(slot) if slot.generation == generation => {
+                let value = slot.value.take();
+
+                if value.is_some() {
+                    slot.generation = next_generation(slot.generation);
+                }
+
+                value
+            }
+            _ => None,
+        };
+
+        if value.is_some() {
+            self.free.push(idx);
+            self.len -= 1;
+        }
+
+        value
+    }
+
+    /// Returns an iterator over all live entries and their tokens, in the
+    /// order of their slots.
+    pub fn iter(&self) -> TokenSlabIter<T> {
+        TokenSlabIter {
+            slots: self.slots.iter().enumerate(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> TokenSlabIterMut<T> {
+        TokenSlabIterMut {
+            slots: self.slots.iter_mut().enumerate(),
+        }
+    }
+
+    /// Removes every entry, returning the values along with their tokens,
+    /// which no longer resolve to any value. The values not consumed are
+    /// dropped along with the iterator. The storage is kept for the next
+    /// values.
+    pub fn drain(&mut self) -> TokenSlabDrain<T> {
+        TokenSlabDrain {
+            slab: self,
+            idx: 0,
+        }
+    }
+
+    /// Returns the number of entries the slab can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    /// Reserves room for at least `additional` more entries.
+    pub fn reserve(&mut self, additional: usize) {
+        let vacant = self.free.len();
+
+        if additional > vacant {
+            self.slots.reserve(additional - vacant);
+        }
+    }
+}
+
+impl<T> TokenSlab<Arc<T>> {
+    /// Stores `value` in an `Arc`, returning the token referencing it along
+    /// with a reference to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index space of the token is exhausted.
+    pub fn insert_arc(&mut self, value: T) -> (Token, Arc<T>) {
+        let value = Arc::new(value);
+        (self.insert(value.clone()), value)
+    }
+
+    /// Returns a new reference to the value referenced by `token`, which
+    /// remains usable once the value is removed from the slab, or `None` if
+    /// the token is stale.
+    pub fn get_arc(&self, token: Token) -> Option<Arc<T>> {
+        self.get(token).cloned()
+    }
+}
+
+impl<T: Evented> TokenSlab<T> {
+    /// Deregisters the handle referenced by `token` from `event_loop`, then
+    /// removes it from the slab and returns it. Returns `Ok(None)` if the
+    /// token is stale. If deregistering fails, the handle is left in place.
+    pub fn deregister<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token) -> io::Result<Option<T>> {
+        match self.get(token) {
+            Some(io) => try!(event_loop.deregister(io)),
+            None => return Ok(None),
+        }
+
+        Ok(self.remove(token))
+    }
+}
+
+impl<T> ops::Index<Token> for TokenSlab<T> {
+    type Output = T;
+
+    fn index(&self, token: Token) -> &T {
+        self.get(token).expect("invalid or stale token")
+    }
+}
+
+impl<T> ops::IndexMut<Token> for TokenSlab<T> {
+    fn index_mut(&mut self, token: Token) -> &mut T {
+        self.get_mut(token).expect("invalid or stale token")
+    }
+}
+
+pub struct TokenSlabIter<'a, T: 'a> {
+    slots: iter::Enumerate<slice::Iter<'a, Slot<T>>>,
+}
+
+impl<'a, T> Iterator for TokenSlabIter<'a, T> {
+    type Item = (Token, &'a T);
+
+    fn next(&mut self) -> Option<(Token, &'a T)> {
+        while let Some((idx, slot)) = self.slots.next() {
+            if let Some(ref value) = slot.value {
+                return Some((pack(idx, slot.generation), value));
+            }
+        }
+
+        None
+    }
+}
+
+pub struct TokenSlabIterMut<'a, T: 'a> {
+    slots: iter::Enumerate<slice::IterMut<'a, Slot<T>>>,
+}
+
+impl<'a, T> Iterator for TokenSlabIterMut<'a, T> {
+    type Item = (Token, &'a mut T);
+
+    fn next(&mut self) -> Option<(Token, &'a mut T)> {
+        while let Some((idx, slot)) = self.slots.next() {
+            let generation = slot.generation;
+
+            if let Some(ref mut value) = slot.value {
+                return Some((pack(idx, generation), value));
+            }
+        }
+
+        None
+    }
+}
+
+pub struct TokenSlabDrain<'a, T: 'a> {
+    slab: &'a mut TokenSlab<T>,
+    idx: usize,
+}
+
+impl<'a, T> Iterator for TokenSlabDrain<'a, T> {
+    type Item = (Token, T);
+
+    fn next(&mut self) -> Option<(Token, T)> {
+        while self.idx < self.slab.slots.len() {
+            let idx = self.idx;
+            self.idx += 1;
+
+            let token = pack(idx, self.slab.slots[idx].generation);
+
+            if let Some(value) = self.slab.remove(token) {
+                return Some((token, value));
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, T> Drop for TokenSlabDrain<'a, T> {
+    fn drop(&mut self) {
+        for _ in self.by_ref() {}
+    }
+}
+
+/// Returns the index of the `TokenSlab` slot `token` refers to, without its
+/// generation. Pass it to `TokenMap::with_index` to store the tokens of a
+/// slab densely.
+pub fn slab_index(token: Token) -> usize {
+    unpack(token).0
+}
+
+/*
+ *
+ * ===== Token packing =====
+ *
+ */
+
+#[inline]
+fn index_bits() -> usize {
+    mem::size_of::<usize>() * 4
+}
+
+#[inline]
+fn index_mask() -> usize {
+    (1 << index_bits()) - 1
+}
+
+#[inline]
+fn generation_mask() -> usize {
+    index_mask()
+}
+
+// The last generation wraps around to 0, so that the tokens stay below
+// `MAX_TOKEN`
+#[inline]
+fn next_generation(generation: usize) -> usize {
+    if generation + 1 >= generation_mask() { 0 } else { generation + 1 }
+}
+
+#[inline]
+fn pack(idx: usize, generation: usize) -> Token {
+    Token(generation << index_bits() | idx)
+}
+
+#[inline]
+fn unpack(token: Token) -> (usize, usize) {
+    let val = token.as_usize();
+    (val & index_mask(), val >> index_bits())
+}
+
+#[cfg(test)]
+mod test {
+    use super::TokenSlab;
+    use token::Token;
+
+    #[test]
+    pub fn test_insert_get_remove() {
+        let mut slab = TokenSlab::new();
+
+        let a = slab.insert("a");
+        let b = slab.insert("b");
+
+        assert_eq!(2, slab.len());
+        assert_eq!(Some(&"a"), slab.get(a));
+        assert_eq!("b", slab[b]);
+
+        assert_eq!(Some("a"), slab.remove(a));
+        assert_eq!(None, slab.remove(a));
+        assert_eq!(None, slab.get(a));
+        assert_eq!(1, slab.len());
+    }
+
+    #[test]
+    pub fn test_stale_token_after_reuse() {
+        let mut slab = TokenSlab::new();
+
+        let a = slab.insert("a");
+        slab.remove(a);
+
+        // The slot is reused, but with a new generation
+        let b = slab.insert("b");
+        assert!(a != b);
+
+        assert_eq!(None, slab.get(a));
+        assert!(!slab.contains(a));
+        assert_eq!(None, slab.remove(a));
+        assert_eq!(Some(&"b"), slab.get(b));
+    }
+
+    #[test]
+    pub fn test_insert_with_token() {
+        let mut slab = TokenSlab::new();
+
+        let token = slab.insert_with(|token| token);
+        assert_eq!(token, slab[token]);
+
+        // Tokens never collide with the reserved notify token
+        assert!(token != Token(::std::usize::MAX));
+    }
+
+    #[test]
+    pub fn test_tokens_below_max_token() {
+        let mut slab = TokenSlab::new();
+        slab.insert(());
+
+        slab.slots[0].generation = super::generation_mask() - 1;
+        let last = super::pack(0, super::generation_mask() - 1);
+        assert!(!last.is_reserved());
+
+        // The generation wraps around rather than reaching the reserved
+        // tokens
+        assert_eq!(Some(()), slab.remove(last));
+        assert_eq!(0, slab.slots[0].generation);
+    }
+
+    #[test]
+    pub fn test_iter() {
+        let mut slab = TokenSlab::new();
+
+        let a = slab.insert(1);
+        let b = slab.insert(2);
+        let c = slab.insert(3);
+        slab.remove(b);
+
+        let entries: Vec<(Token, i32)> = slab.iter().map(|(t, v)| (t, *v)).collect();
+        assert_eq!(entries, vec![(a, 1), (c, 3)]);
+    }
+}
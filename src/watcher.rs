@@ -0,0 +1,173 @@
+use {io, EventLoop, EventSet, Handler, PollOpt, Token};
+use net::unix::EventedFd;
+use std::fmt;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::os::unix::io::RawFd;
+use std::rc::Rc;
+
+/// Watch requests made by a library, queued until its `FdWatcher` applies
+/// them.
+///
+/// Libraries such as c-ares ask to watch their fds from callbacks, which may
+/// run while the watcher is dispatching events. The requests are queued
+/// instead, and applied once the callback returns, or with `apply`.
+#[derive(Clone)]
+pub struct WatchRequests {
+    queue: Rc<RefCell<Vec<(RawFd, EventSet)>>>,
+}
+
+impl WatchRequests {
+    /// Watches `fd` for `interest`, or stops watching it if `interest` is
+    /// empty.
+    pub fn watch(&self, fd: RawFd, interest: EventSet) {
+        self.queue.borrow_mut().push((fd, interest));
+    }
+
+    /// Stops watching `fd`. Must be requested before the fd is closed.
+    pub fn unwatch(&self, fd: RawFd) {
+        self.watch(fd, EventSet::none())
+    }
+}
+
+impl fmt::Debug for WatchRequests {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "WatchRequests {{ queued: {} }}", self.queue.borrow().len())
+    }
+}
+
+/// Registers the fds of a library with "watch these fds, call me back"
+/// interfaces, such as c-ares or libpq, with an event loop.
+///
+/// The fds are registered level-triggered, under the tokens from `base` to
+/// `base + capacity`, which must not be used for other handles. When one of
+/// them is ready, the handler passes the event to `ready`, which calls
+/// `callback` with the fd and its readiness, for example to run
+/// `ares_process_fd`. Watch requests are made through `requests`.
+///
+/// The fds are owned by the library, the watcher never closes them.
+pub struct FdWatcher<F> {
+    base: usize,
+    // Fd and interest of the watched fds, indexed by token offset
+    slots: Vec<Option<(RawFd, EventSet)>>,
+    by_fd: HashMap<RawFd, usize>,
+    requests: WatchRequests,
+    callback: F,
+}
+
+impl<F: FnMut(RawFd, EventSet)> FdWatcher<F> {
+    pub fn new(base: Token, capacity: usize, callback: F) -> FdWatcher<F> {
+        FdWatcher {
+            base: base.as_usize(),
+            slots: (0..capacity).map(|_| None).collect(),
+            by_fd: HashMap::new(),
+            requests: WatchRequests { queue: Rc::new(RefCell::new(Vec::new())) },
+            callback: callback,
+        }
+    }
+
+    /// Returns a handle to queue watch requests with, to hand to the
+    /// library.
+    pub fn requests(&self) -> WatchRequests {
+        self.requests.clone()
+    }
+
+    /// Returns the number of fds watched.
+    pub fn len(&self) -> usize {
+        self.by_fd.len()
+    }
+
+    /// Returns true if `token` belongs to the watcher's namespace.
+    pub fn owns(&self, token: Token) -> bool {
+        let token = token.as_usize();
+        token >= self.base && token - self.base < self.slots.len()
+    }
+
+    /// Applies the queued watch requests, registering, reregistering or
+    /// deregistering the fds with the event loop.
+    ///
+    /// Fails when more fds than `capacity` are watched. The requests after
+    /// the failing one stay queued.
+    pub fn apply<H: Handler>(&mut self, event_loop: &mut EventLoop<H>) -> io::Result<()> {
+        loop {
+            let (fd, interest) = {
+                let mut queue = self.requests.queue.borrow_mut();
+
+                if queue.is_empty() {
+                    return Ok(());
+                }
+
+                queue.remove(0)
+            };
+
+            try!(self.apply_one(event_loop, fd, interest));
+        }
+    }
+
+    fn apply_one<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, fd: RawFd, interest: EventSet) -> io::Result<()> {
+        let existing = self.by_fd.get(&fd).map(|&idx| idx);
+
+        match existing {
+            Some(idx) if interest == EventSet::none() => {
+                self.slots[idx] = None;
+                self.by_fd.remove(&fd);
+
+                // A library closing the fd right after unwatching it is
+                // not an error
+                let _ = event_loop.deregister(&EventedFd(&fd));
+                Ok(())
+            }
+            Some(idx) => {
+                self.slots[idx] = Some((fd, interest));
+                event_loop.reregister(&EventedFd(&fd), Token(self.base + idx), interest, PollOpt::level())
+            }
+            None if interest == EventSet::none() => Ok(()),
+            None => {
+                let idx = match self.slots.iter().position(|slot| slot.is_none()) {
+                    Some(idx) => idx,
+                    None => return Err(io::Error::new(ErrorKind::Other, "watcher token namespace exhausted")),
+                };
+
+                try!(event_loop.register_opt(&EventedFd(&fd), Token(self.base + idx), interest, PollOpt::level()));
+
+                self.slots[idx] = Some((fd, interest));
+                self.by_fd.insert(fd, idx);
+                Ok(())
+            }
+        }
+    }
+
+    /// Dispatches an event received by `Handler::ready`: calls the callback
+    /// with the fd and its readiness, restricted to the watched interest
+    /// plus errors and hangups, then applies the watch requests it made.
+    ///
+    /// Returns false if `token` does not belong to the watcher, or no longer
+    /// refers to a watched fd.
+    pub fn ready<H: Handler>(&mut self, event_loop: &mut EventLoop<H>, token: Token, events: EventSet) -> io::Result<bool> {
+        if !self.owns(token) {
+            return Ok(false);
+        }
+
+        let (fd, interest) = match self.slots[token.as_usize() - self.base] {
+            Some(watch) => watch,
+            None => return Ok(false),
+        };
+
+        let events = events & (interest | EventSet::error() | EventSet::hup());
+
+        if events != EventSet::none() {
+            (self.callback)(fd, events);
+        }
+
+        try!(self.apply(event_loop));
+        Ok(true)
+    }
+}
+
+impl<F> fmt::Debug for FdWatcher<F> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "FdWatcher {{ base: {}, capacity: {}, watched: {} }}",
+               self.base, self.slots.len(), self.by_fd.len())
+    }
+}
@@ -0,0 +1,200 @@
+use {io, Drain, EventLoop, Evented, EventSet, Handler, PollOpt, Token, TrySend, TryWrite};
+use buf::Buf;
+use std::fmt;
+use std::collections::VecDeque;
+
+/// A crossing of one of the watermarks of a `WriteQueue`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Watermark {
+    /// The bytes queued reached the high-water mark, producers should stop
+    /// pushing.
+    High,
+    /// The bytes queued dropped to the low-water mark after reaching the
+    /// high-water mark, producers can resume.
+    Low,
+}
+
+/// Outbound buffers of a connection, written in order as the connection
+/// becomes writable.
+///
+/// `push` queues a buffer, and `flush` writes as much as the connection
+/// takes without blocking, or `flush_more` for sockets, to coalesce small
+/// buffers into full segments. They report crossing the watermarks, so that
+/// producers pause once the queue reaches `high` bytes and resume once it
+/// drains to `low` bytes.
+///
+/// `update` keeps the writable interest of the connection registered while
+/// bytes are queued only: call it after pushing or flushing, with the
+/// interest the connection has otherwise.
+pub struct WriteQueue<B> {
+    bufs: VecDeque<B>,
+    queued: usize,
+    high: usize,
+    low: usize,
+    above: bool,
+    // Whether the writable interest is registered, if `update` was called
+    writable: Option<bool>,
+}
+
+impl<B: Buf> WriteQueue<B> {
+    /// # Panics
+    ///
+    /// This method will panic if `low` is greater than `high`.
+    pub fn new(high: usize, low: usize) -> WriteQueue<B> {
+        assert!(low <= high, "low-water mark must not be above the high-water mark");
+
+        WriteQueue {
+            bufs: VecDeque::new(),
+            queued: 0,
+            high: high,
+            low: low,
+            above: false,
+            writable: None,
+        }
+    }
+
+    /// Queues `buf`, returning `Watermark::High` if the queue reached the
+    /// high-water mark with it. The buffer is queued either way.
+    pub fn push(&mut self, buf: B) -> Option<Watermark> {
+        let len = buf.remaining();
+
+        if len == 0 {
+            return None;
+        }
+
+        self.queued += len;
+        self.bufs.push_back(buf);
+
+        if !self.above && self.queued >= self.high {
+            self.above = true;
+            return Some(Watermark::High);
+        }
+
+        None
+    }
+
+    /// Writes the queued buffers to `io` until it would block or the queue
+    /// is empty, returning `Watermark::Low` if the queue drained to the
+    /// low-water mark.
+    ///
+    /// On error, the bytes written before it are still removed from the
+    /// queue.
+    pub fn flush<W: TryWrite>(&mut self, io: &mut W) -> io::Result<Option<Watermark>> {
+        self.flush_with(|buf, _| io.try_write_buf_all(buf))
+    }
+
+    /// Like `flush`, but sends every buffer followed by another one with
+    /// the hint that more bytes follow, as `MSG_MORE`. On Linux, small
+    /// buffers queued back to back then go out in full segments rather than
+    /// one segment each, with no need to copy them into a single buffer.
+    /// The last buffer is sent without the hint, so nothing is held back
+    /// once the queue is empty.
+    pub fn flush_more<S: TrySend>(&mut self, io: &mut S) -> io::Result<Option<Watermark>> {
+        self.flush_with(|buf, more| io.try_send_buf_all(buf, more))
+    }
+
+    // `write` is passed the buffer at the front of the queue, and whether
+    // another one follows it
+    fn flush_with<F>(&mut self, mut write: F) -> io::Result<Option<Watermark>>
+        where F: FnMut(&mut B, bool) -> io::Result<Drain>
+    {
+        let mut ret = Ok(());
+
+        loop {
+            let more = self.bufs.len() > 1;
+
+            let (res, done) = match self.bufs.front_mut() {
+                Some(buf) => {
+                    let before = buf.remaining();
+                    let res = write(buf, more);
+
+                    self.queued -= before - buf.remaining();
+                    (res, !buf.has_remaining())
+                }
+                None => break,
+            };
+
+            if done {
+                self.bufs.pop_front();
+            }
+
+            match res {
+                Ok(..) if done => {}
+                Ok(..) => break,
+                Err(err) => {
+                    ret = Err(err);
+                    break;
+                }
+            }
+        }
+
+        let crossed = self.crossed_low();
+
+        try!(ret);
+        Ok(crossed)
+    }
+
+    /// Drops the queued buffers, returning `Watermark::Low` if the queue
+    /// was above the high-water mark.
+    pub fn clear(&mut self) -> Option<Watermark> {
+        self.bufs.clear();
+        self.queued = 0;
+        self.crossed_low()
+    }
+
+    /// Returns the number of bytes queued.
+    pub fn len(&self) -> usize {
+        self.queued
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued == 0
+    }
+
+    /// Returns the number of buffers queued, including the one being
+    /// written.
+    pub fn bufs(&self) -> usize {
+        self.bufs.len()
+    }
+
+    /// Returns true from reaching the high-water mark until draining to the
+    /// low-water mark.
+    pub fn is_above(&self) -> bool {
+        self.above
+    }
+
+    /// Reregisters `io` under `token` with `interest`, plus writable while
+    /// bytes are queued, if that changed since the last call.
+    pub fn update<H, E: ?Sized>(&mut self, event_loop: &mut EventLoop<H>, io: &E, token: Token,
+                                interest: EventSet, opts: PollOpt) -> io::Result<()>
+        where H: Handler, E: Evented
+    {
+        let writable = !self.is_empty();
+
+        if self.writable == Some(writable) {
+            return Ok(());
+        }
+
+        let interest = if writable { interest | EventSet::writable() } else { interest - EventSet::writable() };
+
+        try!(event_loop.reregister(io, token, interest, opts));
+        self.writable = Some(writable);
+        Ok(())
+    }
+
+    fn crossed_low(&mut self) -> Option<Watermark> {
+        if self.above && self.queued <= self.low {
+            self.above = false;
+            return Some(Watermark::Low);
+        }
+
+        None
+    }
+}
+
+impl<B> fmt::Debug for WriteQueue<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "WriteQueue {{ bufs: {}, queued: {}, high: {}, low: {} }}",
+               self.bufs.len(), self.queued, self.high, self.low)
+    }
+}
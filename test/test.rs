@@ -7,15 +7,117 @@ extern crate tempdir;
 
 pub use ports::localhost;
 
+mod test_accept_batch;
+mod test_addr_cache;
 mod test_battery;
+mod test_broadcast;
+#[cfg(target_os = "linux")]
+mod test_buf_chain;
+mod test_buf_pool;
+mod test_busy_poll;
+mod test_byte_buf;
+mod test_cached_clock;
+#[cfg(feature = "capi")]
+mod test_capi;
+mod test_catch_panics;
+mod test_channel;
 mod test_close_on_drop;
+#[cfg(target_os = "linux")]
+mod test_close_on_exec;
+mod test_coalesce_events;
+mod test_composite;
+mod test_connect_state;
+mod test_connection;
+mod test_connector;
+mod test_cross_registration;
+#[cfg(target_os = "linux")]
+mod test_cpu_affinity;
+#[cfg(target_os = "linux")]
+mod test_custom_backend;
+mod test_deadline_map;
+mod test_defer;
+mod test_deferred_registration;
+mod test_diagnostics;
+mod test_drain;
+mod test_drop_deregisters;
 mod test_echo_server;
+#[cfg(target_os = "linux")]
+mod test_error;
+#[cfg(target_os = "linux")]
+mod test_embedded_loop;
+mod test_event_budget;
+mod test_event_set;
+mod test_evented_fd;
+mod test_events_capacity;
+mod test_fd_limit;
+#[cfg(feature = "fd-tracking")]
+mod test_fd_tracking;
+mod test_fd_watcher;
+mod test_file_io;
+mod test_frame;
+mod test_graceful_shutdown;
+mod test_hand_off;
+mod test_hup;
+mod test_idle_strategy;
+mod test_idle_timeouts;
+mod test_interface;
+#[cfg(target_os = "linux")]
+mod test_interrupted;
+mod test_into_raw_fd;
+mod test_ioctl;
+mod test_kernel_timers;
+mod test_long_timeout;
+mod test_metrics;
+mod test_mmap_buf;
+mod test_mock_clock;
 mod test_multicast;
+mod test_nested_loop;
+mod test_non_block;
 mod test_notify;
+mod test_offload;
+mod test_oneshot_rearm;
+mod test_pausable_listener;
+mod test_rate_limit;
+mod test_read_exact;
 mod test_register_deregister;
+mod test_registrar;
+mod test_registration_guard;
+mod test_relay;
+#[cfg(target_os = "linux")]
+mod test_reregister_unchanged;
+mod test_reserved_tokens;
+mod test_resolver;
+mod test_ring_buf;
+mod test_run_until_idle;
+mod test_scheduler;
+mod test_selector_kind;
+mod test_set_nonblocking;
+mod test_shutdown_with;
+mod test_signal;
+mod test_slow_callback;
+mod test_socket_error;
+mod test_stale_events;
+mod test_std_conversions;
+mod test_take;
+mod test_test_selector;
+mod test_tick;
 mod test_timer;
+mod test_timer_slack;
+mod test_token_map;
+mod test_token_slab;
+#[cfg(feature = "trace")]
+mod test_trace;
+mod test_transport;
+mod test_try_clone;
+mod test_try_read_write;
 mod test_udp_socket;
 mod test_unix_echo_server;
+mod test_unsupported;
+mod test_user_readiness;
+mod test_validate_interest;
+mod test_vectored_io;
+mod test_worker_pool;
+mod test_write_queue;
 
 mod ports {
     use std::net::SocketAddr;
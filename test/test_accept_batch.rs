@@ -0,0 +1,30 @@
+use mio::*;
+use mio::tcp::TcpListener;
+use std::net;
+
+#[test]
+pub fn test_accept_batch() {
+    let addr = ::localhost();
+    let listener = TcpListener::bind(&addr).unwrap();
+
+    let _clients: Vec<net::TcpStream> = (0..3).map(|_| net::TcpStream::connect(&addr).unwrap()).collect();
+
+    // Give the connections time to reach the backlog
+    ::sleep_ms(100);
+
+    let batch = listener.accept_batch(2);
+    assert_eq!(batch.accepted.len(), 2);
+
+    match batch.stopped {
+        AcceptStop::Limit => {}
+        stopped => panic!("unexpected stop; stopped={:?}", stopped),
+    }
+
+    let batch = listener.accept_batch(10);
+    assert_eq!(batch.accepted.len(), 1);
+
+    match batch.stopped {
+        AcceptStop::WouldBlock => {}
+        stopped => panic!("unexpected stop; stopped={:?}", stopped),
+    }
+}
@@ -0,0 +1,45 @@
+use mio::ConnectState;
+use mio::tcp::*;
+use std::net::SocketAddr;
+
+#[test]
+pub fn test_addr_cache() {
+    let addr = ::localhost();
+    let listener = TcpListener::bind(&addr).unwrap();
+    assert_eq!(listener.local_addr().unwrap(), addr);
+
+    let (stream, _) = TcpSocket::v4().unwrap()
+        .connect(&addr).unwrap();
+
+    ::sleep_ms(100);
+    assert_eq!(stream.finish_connect().unwrap(), ConnectState::Connected);
+
+    let accepted = listener.accept().unwrap().unwrap();
+
+    for _ in 0..3 {
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+        assert_eq!(accepted.peer_addr().unwrap(), stream.local_addr().unwrap());
+        assert_eq!(accepted.local_addr().unwrap(), addr);
+    }
+
+    // The clone starts with the cached addresses
+    let clone = stream.try_clone().unwrap();
+    assert_eq!(clone.peer_addr().unwrap(), addr);
+    assert_eq!(clone.local_addr().unwrap(), stream.local_addr().unwrap());
+}
+
+#[test]
+pub fn test_addr_cache_rebind() {
+    let sock = TcpSocket::v4().unwrap();
+
+    // Unbound, the address is not final yet
+    let unbound: SocketAddr = "0.0.0.0:0".parse().unwrap();
+    assert_eq!(sock.local_addr().unwrap(), unbound);
+
+    let addr = ::localhost();
+    sock.bind(&addr).unwrap();
+    assert_eq!(sock.local_addr().unwrap(), addr);
+
+    let listener = sock.listen(16).unwrap();
+    assert_eq!(listener.local_addr().unwrap(), addr);
+}
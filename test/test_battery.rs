@@ -217,6 +217,7 @@ pub fn test_echo_server() {
             timer_tick_ms: 100,
             timer_wheel_size: 1_024,
             timer_capacity: 65_536,
+            .. EventLoopConfig::default()
         };
     let mut event_loop = EventLoop::configured(config).unwrap();
 
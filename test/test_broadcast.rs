@@ -0,0 +1,52 @@
+use mio::*;
+use mio::buf::{Buf, SharedBuf};
+use mio::unix;
+
+#[test]
+pub fn test_shared_buf() {
+    let mut a = SharedBuf::from(b"hello".to_vec());
+    let b = a.clone();
+
+    a.advance(2);
+    assert_eq!(a.bytes(), &b"llo"[..]);
+    assert_eq!(a.position(), 2);
+
+    // Clones share the bytes, not the position
+    assert_eq!(b.bytes(), &b"hello"[..]);
+    assert_eq!(a.get_ref().as_ptr(), b.get_ref().as_ptr());
+}
+
+#[test]
+pub fn test_broadcast() {
+    let (mut r1, mut w1) = unix::pipe().unwrap();
+    let (_r2, _w2) = unix::pipe().unwrap();
+
+    let mut broadcast = Broadcast::new(8, 0);
+
+    assert!(broadcast.subscribe(Token(1)));
+    assert!(broadcast.subscribe(Token(2)));
+    assert!(!broadcast.subscribe(Token(2)));
+
+    assert!(broadcast.publish(b"hello".to_vec()).is_empty());
+
+    let mut slow = broadcast.publish(b"world".to_vec());
+    slow.sort_by(|a, b| a.as_usize().cmp(&b.as_usize()));
+    assert_eq!(slow, [Token(1), Token(2)]);
+
+    assert_eq!(broadcast.flush(Token(1), &mut w1).unwrap(), Some(Watermark::Low));
+
+    let mut buf = [0; 16];
+    assert_eq!(r1.try_read(&mut buf).unwrap(), Some(10));
+    assert_eq!(&buf[..10], &b"helloworld"[..]);
+
+    assert_eq!(broadcast.progress(Token(1)), Some(Progress { queued: 2, delivered: 2, pending: 0 }));
+    assert_eq!(broadcast.progress(Token(2)), Some(Progress { queued: 2, delivered: 0, pending: 10 }));
+
+    // Only queued for the subscriber
+    assert_eq!(broadcast.send(Token(2), b"!".to_vec()), None);
+    assert_eq!(broadcast.progress(Token(2)).unwrap().pending, 11);
+
+    assert!(broadcast.unsubscribe(Token(2)));
+    assert_eq!(broadcast.progress(Token(2)), None);
+    assert_eq!(broadcast.len(), 1);
+}
@@ -0,0 +1,59 @@
+use mio::*;
+use mio::buf::{Buf, Chain, IoVec, SliceBuf, VecBuf};
+use mio::unix;
+
+fn response() -> Chain<Chain<SliceBuf<'static>, SliceBuf<'static>>, SliceBuf<'static>> {
+    let head = Chain::new(SliceBuf::wrap(b"HEAD "), SliceBuf::wrap(b"body"));
+    Chain::new(head, SliceBuf::wrap(b" TAIL"))
+}
+
+#[test]
+pub fn test_buf_chain_read() {
+    let mut buf = response();
+
+    assert_eq!(buf.remaining(), 14);
+    assert_eq!(buf.bytes(), b"HEAD ");
+
+    // Advancing crosses into the following buffers
+    buf.advance(7);
+    assert_eq!(buf.bytes(), b"dy");
+
+    let mut dst = [0; 7];
+    buf.read_slice(&mut dst);
+
+    assert_eq!(&dst, b"dy TAIL");
+    assert!(!buf.has_remaining());
+}
+
+#[test]
+pub fn test_buf_chain_try_write_buf() {
+    let (reader, writer) = unix::pipe().unwrap();
+    let mut buf = response();
+
+    assert_eq!((&writer).try_write_buf(&mut buf).unwrap(), Some(14));
+
+    let mut dst = [0; 14];
+    assert_eq!((&reader).try_read(&mut dst).unwrap(), Some(14));
+    assert_eq!(&dst, b"HEAD body TAIL");
+}
+
+#[test]
+pub fn test_buf_chain_try_writev() {
+    let (reader, mut writer) = unix::pipe().unwrap();
+    let mut buf = response();
+    buf.advance(2);
+
+    let cnt = {
+        let mut iovs = [IoVec::empty(); 4];
+
+        assert_eq!(buf.bytes_vec(&mut iovs), 3);
+        writer.try_writev(&iovs[..3]).unwrap().unwrap()
+    };
+
+    assert_eq!(cnt, 12);
+    buf.advance(cnt);
+
+    let mut dst = [0; 12];
+    assert_eq!((&reader).try_read(&mut dst).unwrap(), Some(12));
+    assert_eq!(&dst, b"AD body TAIL");
+}
@@ -0,0 +1,87 @@
+use mio::buf::{Buf, BufPool, BufPoolConfig, MutBuf};
+use std::thread;
+
+fn config(thread_cache: usize) -> BufPoolConfig {
+    BufPoolConfig {
+        classes: vec![4_096, 64],
+        max_free: 2,
+        thread_cache: thread_cache,
+    }
+}
+
+#[test]
+pub fn test_buf_pool_checkout() {
+    let pool = BufPool::configured(config(0));
+
+    assert_eq!(pool.classes(), vec![64, 4_096]);
+    assert_eq!(pool.checkout(10).unwrap().capacity(), 64);
+    assert_eq!(pool.checkout(65).unwrap().capacity(), 4_096);
+    assert!(pool.checkout(4_097).is_none());
+
+    let mut buf = pool.checkout(64).unwrap();
+
+    assert_eq!(buf.write_slice(b"hello"), 5);
+    assert_eq!(Buf::remaining(&buf), 5);
+
+    let mut dst = [0; 5];
+    buf.read_slice(&mut dst);
+
+    assert_eq!(&dst, b"hello");
+    assert!(buf.is_empty());
+    assert_eq!(MutBuf::remaining(&buf), 64);
+}
+
+#[test]
+pub fn test_buf_pool_return() {
+    let pool = BufPool::configured(config(0));
+
+    let bufs: Vec<_> = (0..3).map(|_| pool.checkout(64).unwrap()).collect();
+    assert_eq!(pool.free(), 0);
+
+    // Only `max_free` buffers are kept
+    drop(bufs);
+    assert_eq!(pool.free(), 2);
+
+    let mut buf = pool.checkout(64).unwrap();
+    assert_eq!(pool.free(), 1);
+
+    // Returned buffers are handed out empty
+    buf.write_slice(b"stale");
+    drop(buf);
+
+    assert!(pool.checkout(64).unwrap().is_empty());
+}
+
+#[test]
+pub fn test_buf_pool_thread_cache() {
+    let pool = BufPool::configured(config(1));
+
+    // The first buffer is cached by this thread, the second one goes back to
+    // the pool
+    let a = pool.checkout(64).unwrap();
+    let b = pool.checkout(64).unwrap();
+    drop(a);
+    drop(b);
+
+    assert_eq!(pool.free(), 1);
+
+    let _a = pool.checkout(64).unwrap();
+    assert_eq!(pool.free(), 1);
+}
+
+#[test]
+pub fn test_buf_pool_send() {
+    let pool = BufPool::configured(config(0));
+    let mut buf = pool.checkout(64).unwrap();
+
+    buf.write_slice(b"hello");
+
+    let handle = thread::spawn(move || {
+        let mut dst = [0; 5];
+        buf.read_slice(&mut dst);
+        dst
+    });
+
+    assert_eq!(&handle.join().unwrap(), b"hello");
+    assert_eq!(pool.free(), 1);
+}
@@ -0,0 +1,67 @@
+use mio::*;
+use mio::udp::*;
+use super::localhost;
+use std::os::unix::io::AsRawFd;
+use std::thread;
+
+extern {
+    fn getsockopt(fd: i32, level: i32, name: i32, val: *mut i32, len: *mut u32) -> i32;
+}
+
+const SOL_SOCKET: i32 = 1;
+const SO_BUSY_POLL: i32 = 46;
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = u32;
+
+    fn notify(&mut self, event_loop: &mut EventLoop<TestHandler>, msg: u32) {
+        assert_eq!(msg, 7);
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_set_busy_poll() {
+    let sock = UdpSocket::v4().unwrap();
+    sock.set_busy_poll(50).unwrap();
+
+    let mut val = 0i32;
+    let mut len = 4u32;
+    assert_eq!(0, unsafe { getsockopt(sock.as_raw_fd(), SOL_SOCKET, SO_BUSY_POLL, &mut val, &mut len) });
+    assert_eq!(val, 50);
+}
+
+#[test]
+#[allow(deprecated)]
+pub fn test_busy_poll_loop() {
+    let config = EventLoopConfig {
+        busy_poll_us: Some(500),
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let sock = UdpSocket::bound(&localhost()).unwrap();
+    event_loop.register_opt(&sock, Token(0), EventSet::readable(), PollOpt::edge()).unwrap();
+
+    let sender = event_loop.channel();
+    let th = thread::spawn(move || sender.send(7).unwrap());
+
+    event_loop.run(&mut TestHandler).unwrap();
+    th.join().unwrap();
+}
+
+#[test]
+#[allow(deprecated)]
+pub fn test_busy_poll_conflict() {
+    let config = EventLoopConfig {
+        busy_poll_us: Some(500),
+        idle: IdleStrategy::Yield(500),
+        .. EventLoopConfig::default()
+    };
+
+    let err = EventLoop::<TestHandler>::configured(config).err().unwrap();
+    assert_eq!(err.kind(), ::std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,69 @@
+use mio::buf::{Buf, ByteBuf, MutBuf};
+
+fn read_all(buf: &mut ByteBuf) -> Vec<u8> {
+    let mut dst = vec![0; buf.remaining()];
+    buf.read_slice(&mut dst);
+    dst
+}
+
+#[test]
+pub fn test_byte_buf_compact() {
+    let mut buf = ByteBuf::mut_with_capacity(8);
+    buf.write_slice(b"GET /in");
+
+    // Parse the method, keeping the incomplete path
+    let mut buf = buf.flip();
+    buf.advance(4);
+
+    let mut buf = buf.compact();
+    assert_eq!(buf.bytes(), b"/in");
+    assert_eq!(MutBuf::remaining(&buf), 5);
+
+    buf.write_slice(b"dex");
+
+    let mut buf = buf.flip();
+    assert_eq!(read_all(&mut buf), b"/index");
+}
+
+#[test]
+pub fn test_byte_buf_clear() {
+    let mut buf = ByteBuf::from_slice(b"hello");
+
+    buf.clear();
+    assert!(!buf.has_remaining());
+
+    let mut buf = buf.flip();
+    buf.write_slice(b"abc");
+    buf.clear();
+
+    assert!(buf.bytes().is_empty());
+}
+
+#[test]
+pub fn test_byte_buf_split() {
+    let mut buf = ByteBuf::from_slice(b"head:body:tail");
+
+    let mut head = buf.split_to(5);
+    assert_eq!(read_all(&mut head), b"head:");
+
+    let mut tail = buf.split_off(4);
+    assert_eq!(read_all(&mut tail), b":tail");
+    assert_eq!(read_all(&mut buf), b"body");
+
+    let mut buf = ByteBuf::mut_with_capacity(8);
+    buf.write_slice(b"onetwo");
+
+    let mut two = buf.split_off(3);
+    assert_eq!(read_all(&mut two), b"two");
+
+    // The first part can still be written to
+    buf.write_slice(b"!");
+    assert_eq!(buf.bytes(), b"one!");
+}
+
+#[test]
+#[should_panic]
+pub fn test_byte_buf_split_out_of_bounds() {
+    let mut buf = ByteBuf::from_slice(b"short");
+    buf.split_to(6);
+}
@@ -0,0 +1,59 @@
+use mio::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const CONN: Token = Token(0);
+
+// A clock standing still, counting how many times it is read
+#[derive(Debug)]
+struct CountingClock {
+    reads: Arc<AtomicUsize>,
+}
+
+impl Clock for CountingClock {
+    fn now_ms(&self) -> u64 {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        1_000
+    }
+}
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, _: Token, _: EventSet) {
+        for i in 0..10 {
+            event_loop.timeout_ms((), 100 + i).unwrap();
+            assert_eq!(event_loop.now_ms(), 1_000);
+        }
+    }
+}
+
+// Returns the number of clock reads during a tick dispatching one event
+fn reads_per_tick(cache_now: bool) -> usize {
+    let reads = Arc::new(AtomicUsize::new(0));
+
+    let config = EventLoopConfig {
+        clock: Arc::new(CountingClock { reads: reads.clone() }),
+        cache_now: cache_now,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let selector = event_loop.test_selector();
+
+    selector.set_ready(CONN, EventSet::readable());
+
+    let before = reads.load(Ordering::SeqCst);
+    event_loop.run_once(&mut TestHandler).unwrap();
+
+    reads.load(Ordering::SeqCst) - before
+}
+
+#[test]
+pub fn test_cached_clock() {
+    assert!(reads_per_tick(true) <= 2);
+    assert!(reads_per_tick(false) >= 20);
+}
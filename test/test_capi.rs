@@ -0,0 +1,73 @@
+use mio::capi::*;
+use mio::unix;
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+
+struct State {
+    events: Vec<u32>,
+    timer: i64,
+    fired: bool,
+}
+
+extern "C" fn on_ready(lp: *mut MioLoop, handle: i64, _: c_int, events: u32, data: *mut c_void) {
+    let state = unsafe { &mut *(data as *mut State) };
+    state.events.push(events);
+
+    // Calls made from a callback are applied once it returns
+    unsafe {
+        assert_eq!(mio_deregister(lp, handle), 0);
+        state.timer = mio_timeout(lp, 10, on_timeout, data);
+    }
+}
+
+extern "C" fn on_timeout(lp: *mut MioLoop, id: i64, data: *mut c_void) {
+    let state = unsafe { &mut *(data as *mut State) };
+    assert_eq!(state.timer, id);
+    state.fired = true;
+
+    unsafe { mio_stop(lp); }
+}
+
+#[test]
+pub fn test_capi_run() {
+    let lp = mio_loop_new();
+    assert!(!lp.is_null());
+
+    let (reader, mut writer) = unix::pipe().unwrap();
+    let mut state = State { events: vec![], timer: -1, fired: false };
+    let data = &mut state as *mut State as *mut c_void;
+
+    unsafe {
+        let handle = mio_register(lp, reader.as_raw_fd(), MIO_READABLE, MIO_LEVEL, on_ready, data);
+        assert!(handle >= 0);
+
+        writer.write(b"ping").unwrap();
+        assert_eq!(mio_run(lp), 0);
+
+        // Unknown handles are refused
+        assert!(mio_deregister(lp, handle) < 0);
+
+        mio_loop_free(lp);
+    }
+
+    assert_eq!(state.events, vec![MIO_READABLE]);
+    assert!(state.fired);
+}
+
+#[test]
+pub fn test_capi_clear_timeout() {
+    let lp = mio_loop_new();
+    let mut state = State { events: vec![], timer: -1, fired: false };
+    let data = &mut state as *mut State as *mut c_void;
+
+    unsafe {
+        let id = mio_timeout(lp, 10, on_timeout, data);
+        assert!(id >= 0);
+        assert_eq!(mio_clear_timeout(lp, id), 0);
+
+        assert_eq!(mio_run_once(lp, 50), 0);
+        mio_loop_free(lp);
+    }
+
+    assert!(!state.fired);
+}
@@ -0,0 +1,76 @@
+use mio::*;
+use std::io;
+
+struct TestHandler {
+    panicked: Vec<(Callback, String)>,
+    notified: Vec<u32>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = u32;
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, token: Token, _: EventSet) {
+        panic!("ready; token={:?}", token);
+    }
+
+    fn notify(&mut self, _: &mut EventLoop<TestHandler>, msg: u32) {
+        self.notified.push(msg);
+    }
+
+    fn panicked(&mut self, _: &mut EventLoop<TestHandler>, callback: Callback, msg: String) {
+        self.panicked.push((callback, msg));
+    }
+}
+
+struct DefaultHandler;
+
+impl Handler for DefaultHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn timeout(&mut self, _: &mut EventLoop<DefaultHandler>, _: ()) {
+        panic!("boom");
+    }
+}
+
+fn configured<H: Handler>() -> EventLoop<H> {
+    let config = EventLoopConfig {
+        catch_panics: true,
+        .. EventLoopConfig::default()
+    };
+
+    EventLoop::configured(config).unwrap()
+}
+
+#[test]
+pub fn test_catch_panics() {
+    let mut event_loop = configured();
+    let selector = event_loop.test_selector();
+    let mut handler = TestHandler { panicked: vec![], notified: vec![] };
+
+    selector.set_ready(Token(7), EventSet::readable());
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.panicked.len(), 1);
+    assert_eq!(handler.panicked[0].0, Callback::Ready(Token(7), EventSet::readable()));
+    assert_eq!(handler.panicked[0].1, "ready; token=Token(7)");
+
+    // The event loop remains usable
+    event_loop.channel().send(3).unwrap();
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.notified, vec![3]);
+}
+
+#[test]
+pub fn test_catch_panics_default_shuts_down() {
+    let mut event_loop = configured();
+    event_loop.timeout_ms((), 10).unwrap();
+
+    let err = event_loop.run(&mut DefaultHandler).unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::Other);
+    assert_eq!(format!("{}", err), "handler panicked; callback=Timeout; msg=boom");
+    assert!(!event_loop.is_running());
+}
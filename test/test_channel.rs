@@ -0,0 +1,84 @@
+use mio::*;
+use std::thread;
+
+const RX: Token = Token(0);
+
+#[test]
+pub fn test_channel_receiver_with_poll() {
+    let mut poll = Poll::new().unwrap();
+    let (tx, rx) = channel::<usize>(16).unwrap();
+
+    poll.register(&rx, RX, EventSet::readable(), PollOpt::level()).unwrap();
+    assert_eq!(poll.poll(100).unwrap(), 0);
+
+    let handle = thread::spawn(move || {
+        for i in 0..3 {
+            tx.send(i).unwrap();
+        }
+
+        tx
+    });
+
+    let tx = handle.join().unwrap();
+
+    assert_eq!(poll.poll(1_000).unwrap(), 1);
+    assert_eq!(poll.event(0).token, RX);
+
+    assert_eq!(rx.try_recv(), Some(0));
+    assert_eq!(rx.try_recv(), Some(1));
+    assert_eq!(rx.try_recv(), Some(2));
+    assert_eq!(rx.try_recv(), None);
+
+    // Drained, no longer readable until the next message
+    assert_eq!(poll.poll(100).unwrap(), 0);
+
+    tx.send_priority(3, Priority::Normal).unwrap();
+    tx.send_priority(4, Priority::High).unwrap();
+    assert_eq!(poll.poll(1_000).unwrap(), 1);
+
+    assert_eq!(rx.try_recv(), Some(4));
+    assert_eq!(rx.try_recv(), Some(3));
+    assert_eq!(rx.try_recv(), None);
+}
+
+struct TestHandler {
+    rx: Receiver<&'static str>,
+    received: Vec<&'static str>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, _: EventSet) {
+        assert_eq!(token, RX);
+
+        while let Some(msg) = self.rx.try_recv() {
+            self.received.push(msg);
+        }
+
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_channel_receiver_with_event_loop() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (tx, rx) = channel(16).unwrap();
+
+    event_loop.register_opt(&rx, RX, EventSet::readable(), PollOpt::edge()).unwrap();
+    tx.send("hello").unwrap();
+
+    let mut handler = TestHandler { rx: rx, received: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.received, vec!["hello"]);
+
+    // Closed once the receiver is gone
+    drop(handler);
+
+    match tx.send("bye") {
+        Err(NotifyError::Closed(..)) => {}
+        _ => panic!("expected the channel to be closed"),
+    }
+}
@@ -0,0 +1,43 @@
+use mio::*;
+use mio::unix;
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::io::AsRawFd;
+
+// Reads the close-on-exec flag from the octal open flags in fdinfo
+fn is_cloexec<T: AsRawFd>(io: &T) -> bool {
+    let mut info = String::new();
+    File::open(format!("/proc/self/fdinfo/{}", io.as_raw_fd())).unwrap()
+        .read_to_string(&mut info).unwrap();
+
+    let flags = info.lines()
+        .find(|line| line.starts_with("flags:"))
+        .map(|line| u32::from_str_radix(line[6..].trim(), 8).unwrap())
+        .unwrap();
+
+    flags & 0o2000000 != 0
+}
+
+#[test]
+pub fn test_close_on_exec_by_default() {
+    let (reader, writer) = unix::pipe().unwrap();
+    assert!(is_cloexec(&reader));
+    assert!(is_cloexec(&writer));
+
+    let sock = tcp::TcpSocket::v4().unwrap();
+    assert!(is_cloexec(&sock));
+
+    let clone = reader.try_clone().unwrap();
+    assert!(is_cloexec(&clone));
+}
+
+#[test]
+pub fn test_set_inheritable() {
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    reader.set_inheritable(true).unwrap();
+    assert!(!is_cloexec(&reader));
+
+    reader.set_inheritable(false).unwrap();
+    assert!(is_cloexec(&reader));
+}
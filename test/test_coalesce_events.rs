@@ -0,0 +1,74 @@
+use mio::*;
+use mio::unix;
+
+const PIPE: Token = Token(0);
+
+struct TestHandler {
+    calls: Vec<EventSet>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, PIPE);
+        self.calls.push(events);
+    }
+}
+
+#[test]
+pub fn test_coalesce_events() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let selector = event_loop.test_selector();
+    let (_reader, writer) = unix::pipe().unwrap();
+    let mut handler = TestHandler { calls: vec![] };
+
+    event_loop.register_opt(&writer, PIPE, EventSet::writable(), PollOpt::level()).unwrap();
+
+    // The selector reports the pipe writable, the test selector readable
+    selector.set_ready(PIPE, EventSet::readable() | EventSet::user(0));
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.calls, vec![EventSet::writable() | EventSet::readable() | EventSet::user(0)]);
+
+    // Only the selector reports it now
+    event_loop.run_once(&mut handler).unwrap();
+    assert_eq!(handler.calls.len(), 2);
+    assert_eq!(handler.calls[1], EventSet::writable());
+}
+
+struct OrderHandler {
+    calls: Vec<(Token, EventSet)>,
+}
+
+impl Handler for OrderHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<OrderHandler>, token: Token, events: EventSet) {
+        self.calls.push((token, events));
+    }
+}
+
+#[test]
+pub fn test_coalesce_events_keeps_order() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let selector = event_loop.test_selector();
+    let (_reader, writer) = unix::pipe().unwrap();
+    let mut handler = OrderHandler { calls: vec![] };
+
+    event_loop.register_opt(&writer, Token(5), EventSet::writable(), PollOpt::level()).unwrap();
+
+    selector.set_ready(Token(7), EventSet::readable());
+    selector.set_ready(Token(5), EventSet::readable());
+    selector.set_ready(Token(2), EventSet::user(0));
+    event_loop.run_once(&mut handler).unwrap();
+
+    // Dispatched in the order each token was first reported
+    assert_eq!(handler.calls, vec![
+        (Token(5), EventSet::writable() | EventSet::readable()),
+        (Token(7), EventSet::readable()),
+        (Token(2), EventSet::user(0)),
+    ]);
+}
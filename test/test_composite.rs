@@ -0,0 +1,56 @@
+use mio::*;
+use mio::unix;
+use std::io::Write;
+
+const CONN: Token = Token(3);
+
+struct TestHandler {
+    conn: Composite,
+    fired: Vec<(usize, EventSet)>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, CONN);
+        assert!(events.is_readable());
+
+        let merged = self.conn.poll().unwrap();
+        assert!(merged.is_readable());
+
+        self.fired = self.conn.fired().to_vec();
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_composite() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let (sock_r, _sock_w) = unix::pipe().unwrap();
+    let (helper_r, mut helper_w) = unix::pipe().unwrap();
+
+    let mut conn = Composite::new().unwrap();
+    let sock = conn.add(&sock_r, EventSet::readable(), PollOpt::level()).unwrap();
+    let helper = conn.add(&helper_r, EventSet::readable(), PollOpt::level()).unwrap();
+    assert!(sock != helper);
+
+    event_loop.register(&conn, CONN).unwrap();
+
+    // Only the helper pipe becomes ready
+    helper_w.write_all(b"done").unwrap();
+
+    let mut handler = TestHandler { conn: conn, fired: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.fired, [(helper, EventSet::readable())]);
+    assert!(handler.conn.readiness(helper).is_readable());
+    assert_eq!(handler.conn.readiness(sock), EventSet::none());
+
+    // Removed handles no longer make the composite ready
+    handler.conn.remove(&helper_r).unwrap();
+    assert_eq!(handler.conn.poll().unwrap(), EventSet::none());
+    assert!(handler.conn.fired().is_empty());
+}
@@ -0,0 +1,41 @@
+use mio::*;
+use mio::tcp::*;
+use super::localhost;
+use std::io::ErrorKind;
+
+#[test]
+pub fn test_finish_connect() {
+    let addr = localhost();
+    let _listener = TcpListener::bind(&addr).unwrap();
+
+    let (sock, _) = TcpSocket::v4().unwrap()
+        .connect(&addr).unwrap();
+
+    ::sleep_ms(100);
+    assert_eq!(sock.finish_connect().unwrap(), ConnectState::Connected);
+}
+
+#[test]
+pub fn test_finish_connect_refused() {
+    // Nothing is listening on this port, so the connect will be refused
+    let addr = localhost();
+
+    let (sock, state) = TcpSocket::v4().unwrap()
+        .connect(&addr).unwrap();
+
+    if state == ConnectState::InProgress {
+        ::sleep_ms(100);
+        assert_eq!(sock.finish_connect().unwrap_err().kind(), ErrorKind::ConnectionRefused);
+    }
+}
+
+#[test]
+pub fn test_finish_connect_in_progress() {
+    // A connect to a non-routable address stays pending
+    let addr = "10.255.255.1:80".parse().unwrap();
+
+    // Hosts without a route fail right away
+    if let Ok((sock, ConnectState::InProgress)) = TcpSocket::v4().unwrap().connect(&addr) {
+        assert_eq!(sock.finish_connect().unwrap(), ConnectState::InProgress);
+    }
+}
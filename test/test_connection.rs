@@ -0,0 +1,213 @@
+use super::localhost;
+use mio::*;
+use mio::buf::{Buf, MutBuf};
+use mio::tcp::{TcpListener, TcpSocket, TcpStream};
+
+const SERVER: Token = Token(0);
+const ACCEPTED: Token = Token(1);
+const CLIENT: Token = Token(2);
+
+struct Echo;
+
+impl Protocol<TcpStream> for Echo {
+    fn received(&mut self, conn: &mut Connection<TcpStream>) -> ::std::io::Result<()> {
+        let bytes = conn.received().bytes().to_vec();
+        let written = conn.write(&bytes);
+        Buf::advance(conn.received(), written);
+        Ok(())
+    }
+}
+
+struct Client {
+    received: Vec<u8>,
+    closed: bool,
+}
+
+impl Protocol<TcpStream> for Client {
+    fn connected(&mut self, conn: &mut Connection<TcpStream>) -> ::std::io::Result<()> {
+        assert_eq!(conn.write(b"hello"), 5);
+        Ok(())
+    }
+
+    fn received(&mut self, conn: &mut Connection<TcpStream>) -> ::std::io::Result<()> {
+        let len = {
+            let bytes = conn.received().bytes();
+            self.received.extend(bytes.iter().cloned());
+            bytes.len()
+        };
+
+        Buf::advance(conn.received(), len);
+
+        if self.received.len() == 5 {
+            conn.close();
+        }
+
+        Ok(())
+    }
+
+    fn closed(&mut self, conn: &mut Connection<TcpStream>, err: Option<::std::io::Error>) {
+        assert!(err.is_none());
+        assert!(conn.is_closed());
+        self.closed = true;
+    }
+}
+
+struct TestHandler {
+    listener: TcpListener,
+    accepted: Option<Connection<TcpStream>>,
+    client: Option<Connection<TcpStream>>,
+    echo: Echo,
+    protocol: Client,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        match token {
+            SERVER => {
+                let stream = self.listener.accept().unwrap().unwrap();
+                let mut conn = Connection::new(stream, ACCEPTED, 1024);
+
+                conn.register(event_loop).unwrap();
+                self.accepted = Some(conn);
+            }
+            ACCEPTED => {
+                let state = self.accepted.as_mut().unwrap().ready(event_loop, events, &mut self.echo);
+
+                // The client closing is read as EOF
+                if state == ConnectionState::Closed {
+                    self.accepted = None;
+                    event_loop.shutdown();
+                }
+            }
+            CLIENT => {
+                let state = self.client.as_mut().unwrap().ready(event_loop, events, &mut self.protocol);
+
+                // Dropping the stream closes it
+                if state == ConnectionState::Closed {
+                    self.client = None;
+                }
+            }
+            _ => panic!("unexpected token"),
+        }
+    }
+}
+
+#[test]
+pub fn test_connection_echo() {
+    let addr = localhost();
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let listener = TcpListener::bind(&addr).unwrap();
+    event_loop.register(&listener, SERVER).unwrap();
+
+    let mut client = Connection::connecting(TcpStream::connect(&addr).unwrap(), CLIENT, 1024);
+    assert_eq!(client.state(), ConnectionState::Connecting);
+    assert_eq!(client.interest(), EventSet::writable() | EventSet::hup());
+    client.register(&mut event_loop).unwrap();
+
+    let mut handler = TestHandler {
+        listener: listener,
+        accepted: None,
+        client: Some(client),
+        echo: Echo,
+        protocol: Client { received: vec![], closed: false },
+    };
+
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.protocol.received, b"hello".to_vec());
+    assert!(handler.protocol.closed);
+    assert!(handler.client.is_none());
+}
+
+#[test]
+pub fn test_connection_close_flushes() {
+    let addr = localhost();
+    let _listener = TcpListener::bind(&addr).unwrap();
+    let stream = TcpStream::connect(&addr).unwrap();
+
+    let mut conn = Connection::new(stream, CLIENT, 16);
+    assert_eq!(conn.interest(), EventSet::readable() | EventSet::hup());
+
+    // Only as many bytes as the send buffer holds are queued
+    assert_eq!(conn.write(&[0; 20]), 16);
+    assert_eq!(conn.pending(), 16);
+    assert_eq!(conn.interest(), EventSet::readable() | EventSet::writable() | EventSet::hup());
+
+    conn.close();
+    assert_eq!(conn.state(), ConnectionState::Closing);
+    assert_eq!(conn.write(b"late"), 0);
+    assert_eq!(conn.interest(), EventSet::writable());
+}
+
+#[test]
+pub fn test_connection_full_no_hup() {
+    let addr = localhost();
+    let _listener = TcpListener::bind(&addr).unwrap();
+    let stream = TcpStream::connect(&addr).unwrap();
+
+    let mut conn = Connection::new(stream, CLIENT, 16);
+
+    // A hangup could not be read as EOF before bytes are consumed
+    assert_eq!(MutBuf::write_slice(conn.received(), &[0; 16]), 16);
+    assert_eq!(conn.interest(), EventSet::none());
+
+    Buf::advance(conn.received(), 1);
+    assert_eq!(conn.interest(), EventSet::readable() | EventSet::hup());
+}
+
+struct Refused {
+    err: Option<::std::io::ErrorKind>,
+}
+
+impl Protocol<TcpStream> for Refused {
+    fn connected(&mut self, _: &mut Connection<TcpStream>) -> ::std::io::Result<()> {
+        panic!("connect was refused");
+    }
+
+    fn received(&mut self, _: &mut Connection<TcpStream>) -> ::std::io::Result<()> {
+        Ok(())
+    }
+
+    fn closed(&mut self, _: &mut Connection<TcpStream>, err: Option<::std::io::Error>) {
+        self.err = err.map(|err| err.kind());
+    }
+}
+
+struct RefusedHandler {
+    conn: Connection<TcpStream>,
+    protocol: Refused,
+}
+
+impl Handler for RefusedHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<RefusedHandler>, _: Token, events: EventSet) {
+        if self.conn.ready(event_loop, events, &mut self.protocol) == ConnectionState::Closed {
+            event_loop.shutdown();
+        }
+    }
+}
+
+#[test]
+pub fn test_connection_refused() {
+    // Nothing is listening on this port, so the connect will be refused
+    let addr = localhost();
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let (stream, state) = TcpSocket::v4().unwrap().connect(&addr).unwrap();
+
+    if state == ConnectState::InProgress {
+        let mut conn = Connection::connecting(stream, CLIENT, 16);
+        conn.register(&mut event_loop).unwrap();
+
+        let mut handler = RefusedHandler { conn: conn, protocol: Refused { err: None } };
+        event_loop.run(&mut handler).unwrap();
+
+        assert_eq!(handler.protocol.err, Some(::std::io::ErrorKind::ConnectionRefused));
+    }
+}
@@ -0,0 +1,142 @@
+use mio::*;
+use mio::tcp::TcpListener;
+use std::io;
+
+const DEST: Token = Token(0);
+
+// Destinations are given as addresses, nothing to resolve
+struct NoResolver;
+
+impl Resolver for NoResolver {
+    fn resolve(&mut self, _: &str, _: u16, _: Token) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "no resolver"))
+    }
+}
+
+struct TestHandler {
+    connector: Connector<NoResolver>,
+    dialed: Option<Dialed>,
+}
+
+impl TestHandler {
+    fn done(&mut self, event_loop: &mut EventLoop<TestHandler>, dialed: Option<Dialed>) {
+        if dialed.is_some() {
+            self.dialed = dialed;
+            event_loop.shutdown();
+        }
+    }
+}
+
+impl Handler for TestHandler {
+    type Timeout = Connecting;
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        let dialed = self.connector.ready(event_loop, token, events);
+        self.done(event_loop, dialed);
+    }
+
+    fn error(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet, err: io::Error) {
+        let dialed = self.connector.error(event_loop, token, events, err);
+        self.done(event_loop, dialed);
+    }
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, timeout: Connecting) {
+        let dialed = self.connector.timeout(event_loop, timeout);
+        self.done(event_loop, dialed);
+    }
+}
+
+fn policy() -> ConnectPolicy {
+    ConnectPolicy {
+        attempt_timeout_ms: 1_000,
+        initial_backoff_ms: 50,
+        max_backoff_ms: 100,
+        max_attempts: 3,
+    }
+}
+
+#[test]
+pub fn test_connector_connects() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let addr = ::localhost();
+    let listener = TcpListener::bind(&addr).unwrap();
+
+    let mut handler = TestHandler {
+        connector: Connector::new(NoResolver, policy()),
+        dialed: None,
+    };
+
+    handler.connector.connect_addrs(&mut event_loop, DEST, vec![addr]);
+    assert!(handler.connector.is_connecting(DEST));
+
+    event_loop.run(&mut handler).unwrap();
+
+    let dialed = handler.dialed.unwrap();
+    assert_eq!(dialed.token, DEST);
+
+    let stream = dialed.result.unwrap();
+    assert_eq!(stream.peer_addr().unwrap(), addr);
+    assert!(listener.accept().unwrap().is_some());
+
+    assert!(handler.connector.is_empty());
+}
+
+#[test]
+pub fn test_connector_gives_up() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    // Nothing is listening on this port, so the connects are refused
+    let addr = ::localhost();
+
+    let mut handler = TestHandler {
+        connector: Connector::new(NoResolver, policy()),
+        dialed: None,
+    };
+
+    handler.connector.connect_addrs(&mut event_loop, DEST, vec![addr]);
+    event_loop.run(&mut handler).unwrap();
+
+    let dialed = handler.dialed.unwrap();
+    assert_eq!(dialed.token, DEST);
+    assert_eq!(dialed.result.unwrap_err().kind(), io::ErrorKind::ConnectionRefused);
+
+    assert!(handler.connector.is_empty());
+}
+
+#[test]
+pub fn test_connector_gives_up_capturing_errors() {
+    let mut config = EventLoopConfig::default();
+    config.capture_errors = true;
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let addr = ::localhost();
+
+    let mut handler = TestHandler {
+        connector: Connector::new(NoResolver, policy()),
+        dialed: None,
+    };
+
+    handler.connector.connect_addrs(&mut event_loop, DEST, vec![addr]);
+    event_loop.run(&mut handler).unwrap();
+
+    let dialed = handler.dialed.unwrap();
+    assert_eq!(dialed.result.unwrap_err().kind(), io::ErrorKind::ConnectionRefused);
+    assert!(handler.connector.is_empty());
+}
+
+#[test]
+pub fn test_connector_resolve_error() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let mut handler = TestHandler {
+        connector: Connector::new(NoResolver, ConnectPolicy { max_attempts: 2, .. policy() }),
+        dialed: None,
+    };
+
+    handler.connector.connect(&mut event_loop, DEST, "localhost", 80);
+    event_loop.run(&mut handler).unwrap();
+
+    let dialed = handler.dialed.unwrap();
+    assert_eq!(dialed.result.unwrap_err().kind(), io::ErrorKind::Other);
+}
@@ -0,0 +1,46 @@
+use mio::*;
+use mio::util::set_cpu_affinity;
+use std::io::ErrorKind;
+use std::thread;
+
+extern {
+    fn sched_getaffinity(pid: i32, size: usize, mask: *mut u64) -> i32;
+}
+
+// The CPUs the calling thread may run on
+fn allowed_cpus() -> Vec<usize> {
+    let mut set = [0u64; 16];
+    assert_eq!(0, unsafe { sched_getaffinity(0, 128, set.as_mut_ptr()) });
+
+    (0..1024).filter(|&cpu| set[cpu / 64] & (1 << (cpu % 64)) != 0).collect()
+}
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+}
+
+#[test]
+pub fn test_cpu_affinity() {
+    let cpu = allowed_cpus()[0];
+
+    let pinned = thread::spawn(move || {
+        let config = EventLoopConfig {
+            cpu_affinity: Some(vec![cpu]),
+            .. EventLoopConfig::default()
+        };
+
+        let _event_loop: EventLoop<TestHandler> = EventLoop::configured(config).unwrap();
+        allowed_cpus()
+    }).join().unwrap();
+
+    assert_eq!(pinned, [cpu]);
+}
+
+#[test]
+pub fn test_cpu_affinity_out_of_range() {
+    let err = set_cpu_affinity(&[1 << 20]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
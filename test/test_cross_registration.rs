@@ -0,0 +1,43 @@
+use mio::*;
+use mio::unix;
+use std::io::ErrorKind;
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+}
+
+#[test]
+pub fn test_register_with_two_event_loops() {
+    let mut first: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    let mut second: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    first.register(&reader, Token(0)).unwrap();
+
+    let err = second.register(&reader, Token(0)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    assert!(second.deregister(&reader).is_err());
+
+    // Moving the handle
+    first.deregister(&reader).unwrap();
+    second.register(&reader, Token(0)).unwrap();
+}
+
+#[test]
+pub fn test_transfer() {
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    {
+        let mut first: EventLoop<TestHandler> = EventLoop::new().unwrap();
+        first.register(&reader, Token(0)).unwrap();
+    }
+
+    let mut second: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    assert!(second.register(&reader, Token(0)).is_err());
+
+    second.transfer(&reader, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    second.reregister(&reader, Token(0), EventSet::readable(), PollOpt::edge()).unwrap();
+}
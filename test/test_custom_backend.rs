@@ -0,0 +1,121 @@
+use mio::*;
+use mio::unix;
+use std::io::{self, Write};
+use std::os::unix::io::RawFd;
+use std::sync::{Arc, Mutex};
+use std::usize;
+
+// Minimal poll(2) binding, standing in for the readiness API of a platform
+// mio does not support natively.
+#[repr(C)]
+struct PollFd {
+    fd: i32,
+    events: i16,
+    revents: i16,
+}
+
+const POLLIN: i16 = 0x1;
+const POLLOUT: i16 = 0x4;
+
+extern {
+    fn poll(fds: *mut PollFd, nfds: u64, timeout: i32) -> i32;
+}
+
+// Level-triggered backend ignoring the poll options
+struct PollBackend {
+    fds: Vec<(RawFd, Token, EventSet)>,
+    calls: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Backend for PollBackend {
+    fn register(&mut self, fd: RawFd, token: Token, interest: EventSet, _: PollOpt) -> io::Result<()> {
+        self.calls.lock().unwrap().push("register");
+        self.fds.push((fd, token, interest));
+        Ok(())
+    }
+
+    fn reregister(&mut self, fd: RawFd, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        try!(self.deregister(fd));
+        self.register(fd, token, interest, opts)
+    }
+
+    fn deregister(&mut self, fd: RawFd) -> io::Result<()> {
+        self.calls.lock().unwrap().push("deregister");
+        self.fds.retain(|&(f, _, _)| f != fd);
+        Ok(())
+    }
+
+    fn select(&mut self, events: &mut Vec<IoEvent>, timeout_ms: usize) -> io::Result<()> {
+        let mut pfds: Vec<PollFd> = self.fds.iter().map(|&(fd, _, interest)| {
+            let mut evts = 0;
+            if interest.is_readable() { evts |= POLLIN; }
+            if interest.is_writable() { evts |= POLLOUT; }
+            PollFd { fd: fd, events: evts, revents: 0 }
+        }).collect();
+
+        let timeout = if timeout_ms > 1_000 { 1_000 } else { timeout_ms as i32 };
+        let res = unsafe { poll(pfds.as_mut_ptr(), pfds.len() as u64, timeout) };
+
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        for (pfd, &(_, token, _)) in pfds.iter().zip(self.fds.iter()) {
+            let mut kind = EventSet::none();
+            if pfd.revents & POLLIN != 0 { kind = kind | EventSet::readable(); }
+            if pfd.revents & POLLOUT != 0 { kind = kind | EventSet::writable(); }
+
+            if kind != EventSet::none() {
+                events.push(IoEvent::new(kind, token));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct TestHandler {
+    reader: unix::PipeReader,
+    msg: Option<u32>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = u32;
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, Token(1));
+        assert!(events.is_readable());
+
+        event_loop.deregister(&self.reader).unwrap();
+        event_loop.shutdown();
+    }
+
+    fn notify(&mut self, _: &mut EventLoop<TestHandler>, msg: u32) {
+        self.msg = Some(msg);
+    }
+}
+
+#[test]
+pub fn test_custom_backend() {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let backend = PollBackend { fds: vec![], calls: calls.clone() };
+
+    let mut event_loop = EventLoop::with_backend(EventLoopConfig::default(), Box::new(backend)).unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    event_loop.register(&reader, Token(1)).unwrap();
+    event_loop.channel().send(123).unwrap();
+
+    let mut handler = TestHandler { reader: reader, msg: None };
+
+    // Messages still wake up the loop through the backend
+    event_loop.run_once_timeout(&mut handler, usize::MAX).unwrap();
+    assert_eq!(handler.msg, Some(123));
+
+    writer.write_all(b"hello").unwrap();
+    event_loop.run(&mut handler).unwrap();
+
+    // The notify pipe, then the reader
+    assert_eq!(*calls.lock().unwrap(), ["register", "register", "deregister"]);
+}
@@ -0,0 +1,61 @@
+use mio::*;
+use std::sync::Arc;
+
+struct TestHandler {
+    deadlines: DeadlineMap,
+    expired: Vec<Vec<(Token, u64)>>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = DeadlineDue;
+    type Message = ();
+
+    fn timeout(&mut self, _: &mut EventLoop<TestHandler>, due: DeadlineDue) {
+        let batch = self.deadlines.expire(due);
+        self.expired.push(batch);
+    }
+}
+
+#[test]
+pub fn test_deadline_map() {
+    let clock = MockClock::new(0);
+
+    let config = EventLoopConfig {
+        clock: Arc::new(clock.clone()),
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let mut handler = TestHandler {
+        deadlines: DeadlineMap::new(100),
+        expired: vec![],
+    };
+
+    handler.deadlines.insert(&mut event_loop, Token(1), 1, 200).unwrap();
+    handler.deadlines.insert(&mut event_loop, Token(1), 2, 250).unwrap();
+    handler.deadlines.insert(&mut event_loop, Token(2), 1, 200).unwrap();
+    handler.deadlines.insert(&mut event_loop, Token(2), 2, 200).unwrap();
+    assert_eq!(handler.deadlines.len(), 4);
+
+    // Answered in time
+    assert!(handler.deadlines.cancel(&mut event_loop, Token(2), 1));
+    assert!(!handler.deadlines.cancel(&mut event_loop, Token(2), 1));
+
+    clock.advance_ms(200);
+    assert_eq!(1, event_loop.fire_timeouts(&mut handler));
+    assert_eq!(handler.expired, [vec![(Token(1), 1), (Token(2), 2)]]);
+
+    clock.advance_ms(100);
+    assert_eq!(1, event_loop.fire_timeouts(&mut handler));
+    assert_eq!(handler.expired[1], [(Token(1), 2)]);
+    assert!(handler.deadlines.is_empty());
+
+    // Cancelling every deadline of a bucket clears its timeout
+    handler.deadlines.insert(&mut event_loop, Token(3), 1, 500).unwrap();
+    handler.deadlines.insert(&mut event_loop, Token(3), 2, 500).unwrap();
+    assert_eq!(handler.deadlines.cancel_token(&mut event_loop, Token(3)), 2);
+    assert!(!handler.deadlines.contains(Token(3), 1));
+
+    clock.advance_ms(1_000);
+    assert_eq!(0, event_loop.fire_timeouts(&mut handler));
+}
@@ -0,0 +1,57 @@
+use mio::*;
+
+struct TestHandler {
+    log: Vec<String>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = u32;
+
+    fn notify(&mut self, event_loop: &mut EventLoop<TestHandler>, msg: u32) {
+        self.log.push(format!("notify {}", msg));
+
+        // Deferring from a deferred message waits for the next tick
+        if msg < 3 {
+            event_loop.defer(msg + 1);
+        }
+    }
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, _: ()) {
+        self.log.push("timeout".to_string());
+        event_loop.defer(1);
+        event_loop.channel().send(10).unwrap();
+    }
+
+    fn tick(&mut self, _: &mut EventLoop<TestHandler>) {
+        self.log.push("tick".to_string());
+    }
+}
+
+#[test]
+pub fn test_defer() {
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.timeout_ms((), 10).unwrap();
+
+    let mut handler = TestHandler { log: vec![] };
+
+    while !handler.log.iter().any(|l| l == "timeout") {
+        event_loop.run_once(&mut handler).unwrap();
+    }
+
+    // Delivered in the same tick as the timeout, before the tick hook
+    let n = handler.log.len();
+    assert_eq!(&handler.log[n - 3..], &["timeout", "notify 1", "tick"]);
+
+    // Deferred messages keep the event loop from blocking. The message sent
+    // over the channel is delivered first.
+    handler.log.clear();
+    event_loop.run_once(&mut handler).unwrap();
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.log, vec![
+        "notify 10", "notify 2", "tick",
+        "notify 3", "tick",
+    ]);
+    assert!(event_loop.is_idle());
+}
@@ -0,0 +1,85 @@
+use mio::*;
+use mio::buf::SliceBuf;
+use mio::unix::{self, PipeReader};
+
+const READER: Token = Token(0);
+
+struct TestHandler {
+    reader: PipeReader,
+    events: Vec<EventSet>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, READER);
+
+        let mut buf = [0; 16];
+        self.reader.try_read(&mut buf).unwrap();
+
+        self.events.push(events);
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_deferred_registration() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    writer.try_write_buf(&mut SliceBuf::wrap(b"x")).unwrap();
+
+    // Queue a registration and several modifications; they are coalesced and
+    // applied before the next poll.
+    event_loop.register_deferred(&reader, READER, EventSet::hup(), PollOpt::level()).unwrap();
+    event_loop.reregister_deferred(&reader, READER, EventSet::hup(), PollOpt::edge()).unwrap();
+    event_loop.reregister_deferred(&reader, READER, EventSet::readable(), PollOpt::edge()).unwrap();
+
+    let mut handler = TestHandler { reader: reader, events: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.events, vec![EventSet::readable()]);
+
+    // Errors are reported by an explicit flush
+    let (other, _) = unix::pipe().unwrap();
+    event_loop.reregister_deferred(&other, Token(1), EventSet::readable(), PollOpt::edge()).unwrap();
+    assert!(event_loop.flush_deferred().is_err());
+    assert!(event_loop.flush_deferred().is_ok());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+pub fn test_deferred_registration_undone() {
+    use mio::unix::EventedFd;
+    use std::fs::File;
+    use std::os::unix::io::AsRawFd;
+
+    let mut poll = Poll::new().unwrap();
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    // Deregistering before the queued add is applied succeeds
+    poll.register_deferred(&reader, Token(0), EventSet::readable(), PollOpt::edge()).unwrap();
+    poll.deregister(&reader).unwrap();
+    assert_eq!(poll.registered(), 0);
+    assert!(poll.flush_deferred().is_ok());
+
+    // A queued add failing once applied stops being tracked, regular files
+    // cannot be registered with epoll
+    let file = File::open("Cargo.toml").unwrap();
+    let fd = file.as_raw_fd();
+
+    poll.register_deferred(&EventedFd(&fd), Token(1), EventSet::readable(), PollOpt::edge()).unwrap();
+    assert_eq!(poll.registered(), 1);
+    assert!(poll.flush_deferred().is_err());
+    assert_eq!(poll.registered(), 0);
+
+    // The same goes for an add applied by a poll
+    poll.register_deferred(&EventedFd(&fd), Token(1), EventSet::readable(), PollOpt::edge()).unwrap();
+    poll.poll(0).unwrap();
+    assert_eq!(poll.registered(), 0);
+
+    poll.register(&reader, Token(1), EventSet::readable(), PollOpt::edge()).unwrap();
+    assert_eq!(poll.registered(), 1);
+}
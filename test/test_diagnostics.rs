@@ -0,0 +1,94 @@
+use mio::*;
+use std::sync::{Arc, Mutex};
+
+struct Recorder {
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl Diagnostics for Recorder {
+    fn timer_overflow(&mut self, dropped: usize) {
+        self.events.lock().unwrap().push(format!("timer_overflow {}", dropped));
+    }
+
+    fn notify_queue_full(&mut self, rejected: usize) {
+        self.events.lock().unwrap().push(format!("notify_queue_full {}", rejected));
+    }
+}
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = usize;
+    type Message = usize;
+}
+
+#[test]
+pub fn test_diagnostics_notify_queue_full() {
+    let events = Arc::new(Mutex::new(vec![]));
+
+    let config = EventLoopConfig {
+        notify_capacity: 2,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    event_loop.set_diagnostics(Recorder { events: events.clone() });
+
+    let sender = event_loop.channel();
+    let mut rejected = 0;
+
+    for i in 0..5 {
+        if let Err(NotifyError::Full(..)) = sender.send(i) {
+            rejected += 1;
+        }
+    }
+
+    assert!(rejected > 0);
+
+    event_loop.run_once(&mut TestHandler).unwrap();
+    assert_eq!(*events.lock().unwrap(), vec![format!("notify_queue_full {}", rejected)]);
+
+    // Reported once
+    event_loop.run_once_timeout(&mut TestHandler, 0).unwrap();
+    assert_eq!(events.lock().unwrap().len(), 1);
+}
+
+#[test]
+pub fn test_diagnostics_timer_overflow() {
+    let events = Arc::new(Mutex::new(vec![]));
+
+    let config = EventLoopConfig {
+        timer_capacity: 2,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    event_loop.set_diagnostics(Recorder { events: events.clone() });
+
+    let scheduler = event_loop.scheduler();
+
+    for i in 0..4 {
+        scheduler.timeout_ms(i, 60_000).unwrap();
+    }
+
+    event_loop.run_once_timeout(&mut TestHandler, 0).unwrap();
+    assert_eq!(*events.lock().unwrap(), vec!["timer_overflow 2".to_string()]);
+}
+
+#[test]
+pub fn test_diagnostics_default_sink() {
+    let config = EventLoopConfig {
+        timer_capacity: 2,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let scheduler = event_loop.scheduler();
+
+    for i in 0..4 {
+        scheduler.timeout_ms(i, 60_000).unwrap();
+    }
+
+    // Nothing installed, dropping timeouts is not an error
+    event_loop.run_once_timeout(&mut TestHandler, 0).unwrap();
+}
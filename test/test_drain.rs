@@ -0,0 +1,45 @@
+use mio::*;
+use mio::buf::{ByteBuf, SliceBuf};
+use mio::unix;
+
+#[test]
+pub fn test_drain_pipe() {
+    let (mut reader, mut writer) = unix::pipe().unwrap();
+
+    // Fill the pipe until the kernel buffer is full
+    let data = vec![7u8; 1024 * 1024];
+    let mut src = SliceBuf::wrap(&data[..]);
+
+    let written = writer.try_write_buf_all(&mut src).unwrap();
+    assert!(written.would_block);
+    assert!(!written.eof);
+    assert!(written.count > 0);
+    assert_eq!(written.count, data.len() - Buf::remaining(&src));
+
+    // Drain everything back out, in fixed size chunks
+    let mut total = 0;
+
+    loop {
+        let mut dst = ByteBuf::mut_with_capacity(4096);
+        let read = reader.try_read_buf_all(&mut dst).unwrap();
+
+        total += read.count;
+
+        if read.would_block {
+            break;
+        }
+
+        assert!(!read.eof);
+        assert!(!MutBuf::has_remaining(&dst));
+    }
+
+    assert_eq!(total, written.count);
+
+    // Closing the write half results in EOF
+    drop(writer);
+
+    let mut dst = ByteBuf::mut_with_capacity(16);
+    let read = reader.try_read_buf_all(&mut dst).unwrap();
+
+    assert_eq!(read, Drain { count: 0, would_block: false, eof: true });
+}
@@ -0,0 +1,86 @@
+use mio::*;
+use mio::unix;
+use std::io::Write;
+
+const FIRST: Token = Token(0);
+const SECOND: Token = Token(1);
+
+struct TestHandler {
+    readers: Vec<Option<unix::PipeReader>>,
+    ready: Vec<Token>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, token: Token, _: EventSet) {
+        self.ready.push(token);
+
+        // Drop both readers, still registered, on the first event
+        for reader in self.readers.iter_mut() {
+            reader.take();
+        }
+    }
+}
+
+fn readable_pipe(event_loop: &mut EventLoop<TestHandler>, token: Token) -> (unix::PipeReader, unix::PipeWriter) {
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    event_loop.register_opt(&reader, token, EventSet::readable(), PollOpt::level()).unwrap();
+    writer.write(b"ping").unwrap();
+
+    (reader, writer)
+}
+
+fn drop_while_dispatching(config: EventLoopConfig) {
+    let mut event_loop = EventLoop::configured(config).unwrap();
+
+    let (first, _w1) = readable_pipe(&mut event_loop, FIRST);
+    let (second, _w2) = readable_pipe(&mut event_loop, SECOND);
+
+    let mut handler = TestHandler {
+        readers: vec![Some(first), Some(second)],
+        ready: vec![],
+    };
+
+    // Both pipes are readable, only the first event is dispatched
+    event_loop.run_once(&mut handler).unwrap();
+    assert_eq!(handler.ready.len(), 1);
+
+    // The dropped readers are no longer registered, the level-triggered
+    // registrations do not report them again
+    event_loop.run_until_idle(&mut handler).unwrap();
+    assert_eq!(handler.ready.len(), 1);
+    assert!(event_loop.is_idle());
+}
+
+#[test]
+pub fn test_drop_deregisters_native() {
+    drop_while_dispatching(EventLoopConfig::default());
+}
+
+#[test]
+pub fn test_drop_deregisters_poll() {
+    drop_while_dispatching(EventLoopConfig {
+        selector: SelectorKind::Poll,
+        .. EventLoopConfig::default()
+    });
+}
+
+#[test]
+pub fn test_drop_clone_deregisters() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let (reader, _writer) = readable_pipe(&mut event_loop, FIRST);
+
+    // The clone keeps the pipe open, but was never registered
+    let clone = reader.try_clone().unwrap();
+    drop(reader);
+
+    let mut handler = TestHandler { readers: vec![], ready: vec![] };
+    event_loop.run_until_idle(&mut handler).unwrap();
+
+    assert!(handler.ready.is_empty());
+    drop(clone);
+}
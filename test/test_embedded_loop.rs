@@ -0,0 +1,80 @@
+use mio::*;
+use std::os::unix::io::AsRawFd;
+
+// Minimal poll(2) binding, used to wait on the event loop fd the way a
+// foreign event loop would.
+#[repr(C)]
+struct PollFd {
+    fd: i32,
+    events: i16,
+    revents: i16,
+}
+
+const POLLIN: i16 = 0x1;
+
+extern {
+    fn poll(fds: *mut PollFd, nfds: u64, timeout: i32) -> i32;
+}
+
+fn wait_readable<T: AsRawFd>(io: &T, timeout_ms: usize) -> bool {
+    let mut pfd = PollFd { fd: io.as_raw_fd(), events: POLLIN, revents: 0 };
+    let res = unsafe { poll(&mut pfd, 1, timeout_ms as i32) };
+
+    assert!(res >= 0, "poll failed");
+    res == 1 && pfd.revents & POLLIN != 0
+}
+
+struct TestHandler {
+    msgs: Vec<u32>,
+    timeouts: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = u32;
+
+    fn notify(&mut self, _: &mut EventLoop<TestHandler>, msg: u32) {
+        self.msgs.push(msg);
+    }
+
+    fn timeout(&mut self, _: &mut EventLoop<TestHandler>, _: ()) {
+        self.timeouts += 1;
+    }
+}
+
+#[test]
+pub fn test_embedded_loop() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let mut handler = TestHandler { msgs: vec![], timeouts: 0 };
+
+    // Nothing pending, the outer loop may wait
+    let timeout = event_loop.prepare_poll();
+    assert!(timeout > 0);
+    assert!(!wait_readable(&event_loop, 0));
+
+    // A message wakes up the outer loop
+    event_loop.channel().send(1).unwrap();
+    assert!(wait_readable(&event_loop, 1_000));
+
+    event_loop.run_once_timeout(&mut handler, 0).unwrap();
+    assert_eq!(handler.msgs, vec![1]);
+
+    // Messages sent while the loop is not prepared to sleep are picked up
+    // without waiting
+    event_loop.channel().send(2).unwrap();
+    assert_eq!(0, event_loop.prepare_poll());
+
+    event_loop.run_once_timeout(&mut handler, 0).unwrap();
+    assert_eq!(handler.msgs, vec![1, 2]);
+
+    // The wait is bounded by the next timeout
+    event_loop.timeout_ms((), 50).unwrap();
+    let timeout = event_loop.prepare_poll();
+    assert!(timeout > 0 && timeout <= 200, "timeout={}", timeout);
+
+    while handler.timeouts == 0 {
+        let timeout = event_loop.prepare_poll();
+        wait_readable(&event_loop, timeout);
+        event_loop.run_once_timeout(&mut handler, 0).unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+use mio::*;
+use mio::unix;
+use std::io;
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+}
+
+#[test]
+pub fn test_registration_errors() {
+    let mut event_loop: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    match Error::from(event_loop.rearm(&reader, Token(1)).unwrap_err()) {
+        Error::NotRegistered(token) => assert_eq!(token, Token(1)),
+        err => panic!("unexpected error; err={:?}", err),
+    }
+
+    event_loop.register(&reader, Token(1)).unwrap();
+
+    let err = event_loop.register(&reader, Token(2)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+    match Error::from(err) {
+        Error::TokenInUse(_) => {}
+        err => panic!("unexpected error; err={:?}", err),
+    }
+}
+
+#[test]
+pub fn test_error_conversions() {
+    let err: io::Error = Error::NotifyClosed.into();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+    match Error::from(err) {
+        Error::NotifyClosed => {}
+        err => panic!("unexpected error; err={:?}", err),
+    }
+
+    match Error::from(NotifyError::Full(())) {
+        Error::NotifyFull => {}
+        err => panic!("unexpected error; err={:?}", err),
+    }
+
+    let err = io::Error::new(io::ErrorKind::Other, "other");
+
+    match Error::from(err) {
+        Error::Io(err) => assert_eq!(err.kind(), io::ErrorKind::Other),
+        err => panic!("unexpected error; err={:?}", err),
+    }
+}
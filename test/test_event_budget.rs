@@ -0,0 +1,111 @@
+use mio::*;
+use mio::buf::SliceBuf;
+use mio::unix::{self, PipeReader, PipeWriter};
+
+struct TestHandler {
+    // Dispatched event counts, one entry per tick
+    ticks: Vec<usize>,
+    current: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, _: Token, events: EventSet) {
+        assert!(events.is_readable());
+        self.current += 1;
+    }
+
+    fn tick(&mut self, _: &mut EventLoop<TestHandler>) {
+        self.ticks.push(self.current);
+        self.current = 0;
+    }
+}
+
+#[test]
+pub fn test_event_budget() {
+    let config = EventLoopConfig {
+        events_per_tick: 2,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let mut pipes: Vec<(PipeReader, PipeWriter)> = vec![];
+
+    // Five readable pipes, registered edge-triggered so each only produces a
+    // single event
+    for i in 0..5 {
+        let (reader, mut writer) = unix::pipe().unwrap();
+        writer.try_write_buf(&mut SliceBuf::wrap(b"x")).unwrap();
+        event_loop.register_opt(&reader, Token(i), EventSet::readable(), PollOpt::edge()).unwrap();
+        pipes.push((reader, writer));
+    }
+
+    let mut handler = TestHandler { ticks: vec![], current: 0 };
+
+    for _ in 0..3 {
+        event_loop.run_once(&mut handler).unwrap();
+    }
+
+    // No event is lost, and none is dispatched twice
+    assert_eq!(handler.ticks, vec![2, 2, 1]);
+}
+
+#[test]
+pub fn test_event_budget_zero() {
+    let config = EventLoopConfig {
+        events_per_tick: 0,
+        .. EventLoopConfig::default()
+    };
+
+    let err = EventLoop::<TestHandler>::configured(config).err().unwrap();
+    assert_eq!(err.kind(), ::std::io::ErrorKind::InvalidInput);
+}
+
+struct OrderHandler {
+    order: Vec<Token>,
+}
+
+impl Handler for OrderHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<OrderHandler>, token: Token, _: EventSet) {
+        self.order.push(token);
+    }
+}
+
+#[test]
+pub fn test_fair_dispatch() {
+    let config = EventLoopConfig {
+        fair_dispatch: true,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let mut pipes: Vec<(PipeReader, PipeWriter)> = vec![];
+
+    // Level-triggered and never drained, so every poll returns all three
+    for i in 0..3 {
+        let (reader, mut writer) = unix::pipe().unwrap();
+        writer.try_write_buf(&mut SliceBuf::wrap(b"x")).unwrap();
+        event_loop.register_opt(&reader, Token(i), EventSet::readable(), PollOpt::level()).unwrap();
+        pipes.push((reader, writer));
+    }
+
+    let mut firsts = vec![];
+
+    for _ in 0..3 {
+        let mut handler = OrderHandler { order: vec![] };
+        event_loop.run_once(&mut handler).unwrap();
+
+        assert_eq!(handler.order.len(), 3);
+        firsts.push(handler.order[0].as_usize());
+    }
+
+    // Each tick starts dispatching with a different handle
+    firsts.sort();
+    firsts.dedup();
+    assert_eq!(firsts.len(), 3);
+}
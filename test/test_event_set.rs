@@ -0,0 +1,52 @@
+use mio::*;
+
+#[test]
+pub fn test_event_set_iter() {
+    let events = EventSet::readable() | EventSet::hup() | EventSet::user(2);
+    let flags: Vec<EventSet> = events.iter().collect();
+
+    assert_eq!(flags, vec![EventSet::readable(), EventSet::hup(), EventSet::user(2)]);
+    assert_eq!(EventSet::none().iter().count(), 0);
+    assert!(events.contains(EventSet::readable() | EventSet::hup()));
+    assert!(!events.contains(EventSet::writable()));
+}
+
+#[test]
+pub fn test_event_set_display() {
+    assert_eq!(format!("{}", EventSet::all()), "Readable | Writable | Error | Hup");
+    assert_eq!(format!("{}", EventSet::writable() | EventSet::user(1)), "Writable | User(1)");
+    assert_eq!(format!("{}", EventSet::none()), "None");
+    assert_eq!(format!("{:?}", EventSet::error()), "Error");
+}
+
+#[test]
+pub fn test_poll_opt_iter() {
+    let opts = PollOpt::edge() | PollOpt::oneshot();
+    let flags: Vec<PollOpt> = opts.iter().collect();
+
+    assert_eq!(flags, vec![PollOpt::edge(), PollOpt::oneshot()]);
+    assert_eq!(PollOpt::empty().iter().count(), 0);
+}
+
+#[test]
+pub fn test_poll_opt_display() {
+    assert_eq!(format!("{}", PollOpt::edge() | PollOpt::oneshot()), "Edge-Triggered | OneShot");
+    assert_eq!(format!("{:?}", PollOpt::level()), "Level-Triggered");
+    assert_eq!(format!("{}", PollOpt::empty()), "Empty");
+}
+
+#[test]
+pub fn test_io_event_repr() {
+    use std::mem;
+
+    // The token, plus 32 bits each for the readiness and the error
+    assert_eq!(mem::size_of::<IoEvent>(), mem::size_of::<usize>() + 8);
+
+    let mut evt = IoEvent::new(EventSet::readable() | EventSet::error(), Token(7));
+    assert_eq!(evt.token, Token(7));
+    assert_eq!(evt.kind, EventSet::readable() | EventSet::error());
+    assert_eq!(evt.error(), None);
+
+    evt.set_error(111);
+    assert_eq!(evt.error(), Some(111));
+}
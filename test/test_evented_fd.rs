@@ -0,0 +1,69 @@
+use mio::*;
+use mio::unix::{self, EventedFd, EventedIo};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+
+// A pipe whose read end is a plain `File`, still in non-blocking mode
+fn file_pipe() -> (File, unix::PipeWriter) {
+    let (reader, writer) = unix::pipe().unwrap();
+    let reader = unsafe { File::from_raw_fd(reader.into_raw_fd()) };
+
+    (reader, writer)
+}
+
+#[test]
+pub fn test_evented_fd() {
+    let mut poll = Poll::new().unwrap();
+    let (mut reader, mut writer) = file_pipe();
+    let fd = reader.as_raw_fd();
+
+    poll.register(&EventedFd(&fd), Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    assert_eq!(poll.poll(100).unwrap(), 0);
+
+    writer.write(b"hello").unwrap();
+    assert_eq!(poll.poll(100).unwrap(), 1);
+    assert_eq!(poll.event(0).token, Token(0));
+
+    let mut dst = [0; 5];
+    assert_eq!(reader.read(&mut dst).unwrap(), 5);
+    assert_eq!(poll.poll(100).unwrap(), 0);
+
+    poll.deregister(&EventedFd(&fd)).unwrap();
+}
+
+#[test]
+pub fn test_evented_io() {
+    let mut poll = Poll::new().unwrap();
+    let (reader, mut writer) = file_pipe();
+    let mut reader = EventedIo::new(reader);
+
+    poll.register(&reader, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    writer.write(b"hello").unwrap();
+    assert_eq!(poll.poll(100).unwrap(), 1);
+
+    let mut dst = [0; 5];
+    assert_eq!(reader.get_mut().read(&mut dst).unwrap(), 5);
+
+    // Registering with a second selector is refused
+    let mut other = Poll::new().unwrap();
+    assert!(other.register(&reader, Token(0), EventSet::readable(), PollOpt::level()).is_err());
+
+    // Taking the handle back out deregisters it
+    writer.write(b"hello").unwrap();
+    let _reader = reader.into_inner();
+    assert_eq!(poll.poll(100).unwrap(), 0);
+}
+
+#[test]
+pub fn test_evented_io_drop_deregisters() {
+    let mut poll = Poll::new().unwrap();
+    let (reader, mut writer) = file_pipe();
+    let reader = EventedIo::new(reader);
+
+    poll.register(&reader, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    writer.write(b"hello").unwrap();
+
+    drop(reader);
+    assert_eq!(poll.poll(100).unwrap(), 0);
+}
@@ -0,0 +1,59 @@
+use mio::*;
+use mio::buf::SliceBuf;
+use mio::unix::{self, PipeReader, PipeWriter};
+
+#[test]
+pub fn test_events_capacity_adapts() {
+    let mut poll = Poll::new().unwrap();
+    poll.set_events_capacity(2, 8);
+    assert_eq!(poll.events_capacity(), 2);
+
+    let mut pipes: Vec<(PipeReader, PipeWriter)> = vec![];
+
+    // Ten pipes that stay readable, polls keep returning a full buffer
+    for i in 0..10 {
+        let (reader, mut writer) = unix::pipe().unwrap();
+        writer.try_write_buf(&mut SliceBuf::wrap(b"x")).unwrap();
+        poll.register(&reader, Token(i), EventSet::readable(), PollOpt::level()).unwrap();
+        pipes.push((reader, writer));
+    }
+
+    for _ in 0..10 {
+        poll.poll(0).unwrap();
+    }
+
+    assert_eq!(poll.events_capacity(), 8);
+    assert_eq!(poll.poll(0).unwrap(), 8);
+
+    for &(ref reader, _) in pipes.iter() {
+        poll.deregister(reader).unwrap();
+    }
+
+    // Idle polls shrink the buffer back, but not below the minimum
+    for _ in 0..1_000 {
+        assert_eq!(poll.poll(0).unwrap(), 0);
+    }
+
+    assert_eq!(poll.events_capacity(), 2);
+}
+
+#[test]
+pub fn test_events_capacity_fixed() {
+    let mut poll = Poll::new().unwrap();
+    poll.set_events_capacity(4, 4);
+
+    let mut pipes: Vec<(PipeReader, PipeWriter)> = vec![];
+
+    for i in 0..6 {
+        let (reader, mut writer) = unix::pipe().unwrap();
+        writer.try_write_buf(&mut SliceBuf::wrap(b"x")).unwrap();
+        poll.register(&reader, Token(i), EventSet::readable(), PollOpt::level()).unwrap();
+        pipes.push((reader, writer));
+    }
+
+    for _ in 0..10 {
+        assert_eq!(poll.poll(0).unwrap(), 4);
+    }
+
+    assert_eq!(poll.events_capacity(), 4);
+}
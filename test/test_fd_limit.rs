@@ -0,0 +1,66 @@
+use mio::*;
+use mio::tcp::*;
+use std::io::Read;
+use std::net;
+
+const SERVER: Token = Token(0);
+
+struct TestHandler {
+    events: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, SERVER);
+        assert!(events.is_readable());
+        self.events += 1;
+    }
+}
+
+#[test]
+pub fn test_fd_reserve_reject() {
+    let addr = ::localhost();
+    let listener = TcpListener::bind(&addr).unwrap();
+    let mut reserve = FdReserve::new().unwrap();
+
+    // Nothing pending
+    assert!(!reserve.reject(&listener).unwrap());
+
+    let mut client = net::TcpStream::connect(&addr).unwrap();
+    ::sleep_ms(100);
+
+    assert!(reserve.reject(&listener).unwrap());
+    assert!(reserve.is_reserved());
+
+    // The client sees the connection closed
+    let mut buf = [0; 1];
+    assert_eq!(client.read(&mut buf).unwrap(), 0);
+
+    assert!(listener.accept().unwrap().is_none());
+}
+
+#[test]
+pub fn test_pause_listener() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let addr = ::localhost();
+    let listener = TcpListener::bind(&addr).unwrap();
+
+    event_loop.register_opt(&listener, SERVER, EventSet::readable(), PollOpt::level()).unwrap();
+    event_loop.pause(&listener, SERVER).unwrap();
+
+    let _client = net::TcpStream::connect(&addr).unwrap();
+
+    let mut handler = TestHandler { events: 0 };
+    event_loop.run_once_timeout(&mut handler, 100).unwrap();
+    assert_eq!(handler.events, 0);
+
+    // Re-arming restores the level-triggered readable interest
+    event_loop.rearm(&listener, SERVER).unwrap();
+    event_loop.run_once_timeout(&mut handler, 1_000).unwrap();
+    assert_eq!(handler.events, 1);
+
+    assert!(event_loop.pause(&listener, Token(123)).is_err());
+}
@@ -0,0 +1,19 @@
+use mio::fd_tracking::{dump_open_fds, open_fds};
+use mio::unix;
+use std::os::unix::io::AsRawFd;
+
+#[test]
+pub fn test_fd_tracking() {
+    let (reader, writer) = unix::pipe().unwrap();
+    let (rd, wr) = (reader.as_raw_fd(), writer.as_raw_fd());
+
+    {
+        let open = open_fds();
+        assert!(open.iter().any(|fd| fd.fd == rd));
+        assert!(open.iter().any(|fd| fd.fd == wr));
+    }
+
+    let mut dump = Vec::new();
+    dump_open_fds(&mut dump).unwrap();
+    assert!(!dump.is_empty());
+}
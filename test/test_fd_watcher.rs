@@ -0,0 +1,105 @@
+use mio::*;
+use mio::unix::{self, PipeReader};
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::rc::Rc;
+
+const BASE: Token = Token(100);
+
+struct TestHandler<F> {
+    watcher: FdWatcher<F>,
+    foreign: usize,
+}
+
+impl<F: FnMut(RawFd, EventSet)> Handler for TestHandler<F> {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler<F>>, token: Token, events: EventSet) {
+        if !self.watcher.ready(event_loop, token, events).unwrap() {
+            self.foreign += 1;
+        }
+
+        if self.watcher.len() == 0 {
+            event_loop.shutdown();
+        }
+    }
+}
+
+#[test]
+pub fn test_fd_watcher() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let (first, mut first_w) = unix::pipe().unwrap();
+    let (second, mut second_w) = unix::pipe().unwrap();
+
+    let readers: Rc<RefCell<Vec<PipeReader>>> = Rc::new(RefCell::new(vec![first, second]));
+    let processed = Rc::new(RefCell::new(vec![]));
+
+    // Plays the library: reads what is ready, then stops watching the fd
+    let watcher = {
+        let readers = readers.clone();
+        let processed = processed.clone();
+        let requests: Rc<RefCell<Option<WatchRequests>>> = Rc::new(RefCell::new(None));
+        let cb_requests = requests.clone();
+
+        let watcher = FdWatcher::new(BASE, 4, move |fd: RawFd, events: EventSet| {
+            assert!(events.is_readable());
+
+            let mut readers = readers.borrow_mut();
+            let reader = readers.iter_mut().find(|r| r.as_raw_fd() == fd).unwrap();
+
+            let mut dst = [0; 4];
+            assert_eq!(reader.read(&mut dst).unwrap(), 4);
+
+            processed.borrow_mut().push(fd);
+            cb_requests.borrow().as_ref().unwrap().unwatch(fd);
+        });
+
+        *requests.borrow_mut() = Some(watcher.requests());
+        watcher
+    };
+
+    let requests = watcher.requests();
+
+    for reader in readers.borrow().iter() {
+        requests.watch(reader.as_raw_fd(), EventSet::readable());
+    }
+
+    let mut handler = TestHandler { watcher: watcher, foreign: 0 };
+    handler.watcher.apply(&mut event_loop).unwrap();
+    assert_eq!(handler.watcher.len(), 2);
+
+    first_w.write(b"ping").unwrap();
+    second_w.write(b"pong").unwrap();
+
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(processed.borrow().len(), 2);
+    assert_eq!(handler.foreign, 0);
+}
+
+#[test]
+pub fn test_fd_watcher_capacity() {
+    let mut event_loop: EventLoop<TestHandler<fn(RawFd, EventSet)>> = EventLoop::new().unwrap();
+
+    fn ignore(_: RawFd, _: EventSet) {}
+
+    let mut watcher = FdWatcher::new(BASE, 1, ignore as fn(RawFd, EventSet));
+    let (first, _w1) = unix::pipe().unwrap();
+    let (second, _w2) = unix::pipe().unwrap();
+
+    watcher.requests().watch(first.as_raw_fd(), EventSet::readable());
+    watcher.requests().watch(second.as_raw_fd(), EventSet::readable());
+    assert!(watcher.apply(&mut event_loop).is_err());
+
+    // Unwatching frees the token for the next fd
+    watcher.requests().unwatch(first.as_raw_fd());
+    watcher.requests().watch(second.as_raw_fd(), EventSet::readable());
+    watcher.apply(&mut event_loop).unwrap();
+
+    assert_eq!(watcher.len(), 1);
+    assert!(watcher.owns(BASE));
+    assert!(!watcher.owns(Token(101)));
+}
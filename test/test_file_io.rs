@@ -0,0 +1,64 @@
+use mio::*;
+use mio::buf::{Buf, BufPool, MutBuf};
+use std::fs::OpenOptions;
+use tempdir::TempDir;
+
+struct TestHandler {
+    completed: Vec<Completed<FileOp>>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = Completed<FileOp>;
+
+    fn notify(&mut self, event_loop: &mut EventLoop<TestHandler>, msg: Completed<FileOp>) {
+        self.completed.push(msg);
+        event_loop.shutdown();
+    }
+}
+
+fn complete(event_loop: &mut EventLoop<TestHandler>, token: Token) -> FileOp {
+    let mut handler = TestHandler { completed: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    let completed = handler.completed.pop().unwrap();
+    assert_eq!(completed.token, token);
+    completed.result.unwrap()
+}
+
+#[test]
+pub fn test_file_io() {
+    let dir = TempDir::new("test_file_io").unwrap();
+    let mut event_loop = EventLoop::new().unwrap();
+    let bufs = BufPool::new();
+    let files = FileIo::new(1, event_loop.channel(), bufs.clone()).unwrap();
+
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create(true);
+
+    files.open(Token(1), dir.path().join("file"), &options).unwrap();
+
+    let file = match complete(&mut event_loop, Token(1)) {
+        FileOp::Opened(res) => res.unwrap(),
+        op => panic!("unexpected completion; op={:?}", op),
+    };
+
+    let mut buf = bufs.checkout(11).unwrap();
+    buf.write_slice(b"hello world");
+    files.write_at(Token(2), &file, 0, buf).unwrap();
+
+    match complete(&mut event_loop, Token(2)) {
+        FileOp::Written(res) => assert_eq!(res.unwrap(), 11),
+        op => panic!("unexpected completion; op={:?}", op),
+    }
+
+    files.read_at(Token(3), &file, 6, 100).unwrap();
+
+    match complete(&mut event_loop, Token(3)) {
+        FileOp::Read(res) => assert_eq!(Buf::bytes(&res.unwrap()), &b"world"[..]),
+        op => panic!("unexpected completion; op={:?}", op),
+    }
+
+    // Reads larger than the pooled buffers fail right away
+    assert!(files.read_at(Token(4), &file, 0, 1024 * 1024).is_err());
+}
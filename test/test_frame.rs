@@ -0,0 +1,61 @@
+use mio::*;
+use mio::unix;
+use std::io::ErrorKind;
+
+#[test]
+pub fn test_frame_round_trip() {
+    let (mut reader, mut writer) = unix::pipe().unwrap();
+    let mut frames = FrameWriter::new(1024);
+    let mut read = FrameReader::new(1024);
+
+    frames.push(b"hello".to_vec()).unwrap();
+    frames.push(vec![]).unwrap();
+    frames.push(vec![7; 1000]).unwrap();
+    assert_eq!(frames.len(), 3);
+
+    assert_eq!(frames.write_to(&mut writer).unwrap(), Some(()));
+    assert!(frames.is_empty());
+
+    assert_eq!(read.read_from(&mut reader).unwrap(), Some(Some(b"hello".to_vec())));
+    assert_eq!(read.read_from(&mut reader).unwrap(), Some(Some(vec![])));
+    assert_eq!(read.read_from(&mut reader).unwrap(), Some(Some(vec![7; 1000])));
+    assert_eq!(read.read_from(&mut reader).unwrap(), None);
+
+    drop(writer);
+    assert_eq!(read.read_from(&mut reader).unwrap(), Some(None));
+}
+
+#[test]
+pub fn test_frame_partial() {
+    let (mut reader, writer) = unix::pipe().unwrap();
+    let mut read = FrameReader::new(1024);
+
+    // Header split across readiness events
+    (&writer).try_write(&[0, 0]).unwrap();
+    assert_eq!(read.read_from(&mut reader).unwrap(), None);
+    assert!(read.is_partial());
+
+    (&writer).try_write(&[0, 3, b'a']).unwrap();
+    assert_eq!(read.read_from(&mut reader).unwrap(), None);
+
+    (&writer).try_write(b"bc").unwrap();
+    assert_eq!(read.read_from(&mut reader).unwrap(), Some(Some(b"abc".to_vec())));
+    assert!(!read.is_partial());
+
+    // EOF in the middle of a frame
+    (&writer).try_write(&[0, 0, 0, 5, b'a']).unwrap();
+    drop(writer);
+    assert_eq!(read.read_from(&mut reader).unwrap_err().kind(), ErrorKind::Other);
+}
+
+#[test]
+pub fn test_frame_max_len() {
+    let (mut reader, writer) = unix::pipe().unwrap();
+    let mut read = FrameReader::new(4);
+
+    (&writer).try_write(&[0, 0, 0, 5]).unwrap();
+    assert_eq!(read.read_from(&mut reader).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    let mut frames = FrameWriter::new(4);
+    assert_eq!(frames.push(vec![0; 5]).unwrap_err().kind(), ErrorKind::InvalidInput);
+}
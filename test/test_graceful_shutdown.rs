@@ -0,0 +1,98 @@
+use mio::*;
+use mio::tcp::TcpListener;
+use std::sync::Arc;
+
+const SERVER: Token = Token(0);
+
+struct TestHandler {
+    shutdown: GracefulShutdown,
+    forced: Vec<Token>,
+    complete: bool,
+}
+
+impl Handler for TestHandler {
+    type Timeout = Graceful;
+    type Message = ();
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, timeout: Graceful) {
+        match timeout {
+            Graceful::Deadline => self.forced = self.shutdown.expire(event_loop).unwrap(),
+            Graceful::Complete => self.complete = true,
+        }
+    }
+}
+
+fn setup() -> (MockClock, EventLoop<TestHandler>, TestHandler) {
+    let clock = MockClock::new(0);
+
+    let config = EventLoopConfig {
+        clock: Arc::new(clock.clone()),
+        .. EventLoopConfig::default()
+    };
+
+    let handler = TestHandler {
+        shutdown: GracefulShutdown::new(),
+        forced: vec![],
+        complete: false,
+    };
+
+    (clock, EventLoop::configured(config).unwrap(), handler)
+}
+
+#[test]
+pub fn test_graceful_shutdown_drained() {
+    let (clock, mut event_loop, mut handler) = setup();
+    let listener = TcpListener::bind(&::localhost()).unwrap();
+
+    event_loop.register(&listener, SERVER).unwrap();
+
+    handler.shutdown.opened(Token(1));
+    handler.shutdown.opened(Token(2));
+    handler.shutdown.begin(&mut event_loop, vec![listener], 1_000).unwrap();
+    assert!(handler.shutdown.is_shutting_down());
+
+    handler.shutdown.closed(&mut event_loop, Token(1)).unwrap();
+    assert!(!handler.shutdown.is_complete());
+
+    handler.shutdown.closed(&mut event_loop, Token(2)).unwrap();
+    assert!(handler.shutdown.is_complete());
+
+    clock.advance_ms(100);
+    assert_eq!(1, event_loop.fire_timeouts(&mut handler));
+    assert!(handler.complete);
+
+    // The deadline was cleared
+    clock.advance_ms(1_000);
+    assert_eq!(0, event_loop.fire_timeouts(&mut handler));
+    assert!(handler.forced.is_empty());
+}
+
+#[test]
+pub fn test_graceful_shutdown_deadline() {
+    let (clock, mut event_loop, mut handler) = setup();
+
+    handler.shutdown.opened(Token(1));
+    handler.shutdown.begin::<_, TcpListener>(&mut event_loop, vec![], 200).unwrap();
+
+    clock.advance_ms(200);
+    assert_eq!(1, event_loop.fire_timeouts(&mut handler));
+    assert_eq!(handler.forced, [Token(1)]);
+    assert!(handler.shutdown.is_empty());
+    assert!(!handler.complete);
+
+    clock.advance_ms(100);
+    assert_eq!(1, event_loop.fire_timeouts(&mut handler));
+    assert!(handler.complete);
+}
+
+#[test]
+pub fn test_graceful_shutdown_idle() {
+    let (clock, mut event_loop, mut handler) = setup();
+
+    handler.shutdown.begin::<_, TcpListener>(&mut event_loop, vec![], 1_000).unwrap();
+    assert!(handler.shutdown.is_complete());
+
+    clock.advance_ms(100);
+    event_loop.fire_timeouts(&mut handler);
+    assert!(handler.complete);
+}
@@ -0,0 +1,86 @@
+use super::localhost;
+use mio::*;
+use mio::tcp::{TcpListener, TcpStream};
+use std::io::{Read, Write};
+use std::net;
+use std::thread;
+
+const CLIENT: Token = Token(0);
+
+struct TestHandler {
+    ready: Vec<(Token, EventSet)>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        self.ready.push((token, events));
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_hand_off_and_take_back() {
+    let addr = localhost();
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let listener = net::TcpListener::bind(&addr).unwrap();
+    let stream = TcpStream::connect(&addr).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+
+    event_loop.register_opt(&stream, CLIENT, EventSet::readable(), PollOpt::level()).unwrap();
+
+    // Served by blocking code in another thread, which waits for the reply
+    let stream = event_loop.hand_off(stream).unwrap();
+
+    let handle = thread::spawn(move || {
+        let mut stream = stream;
+        let mut dst = [0; 4];
+
+        stream.write(b"ping").unwrap();
+        assert_eq!(stream.read(&mut dst).unwrap(), 4);
+        assert_eq!(&dst, b"pong");
+        stream
+    });
+
+    let mut dst = [0; 4];
+    assert_eq!(server.read(&mut dst).unwrap(), 4);
+    server.write(b"pong").unwrap();
+
+    let stream = handle.join().unwrap();
+
+    // Back on the event loop, non-blocking and registered again
+    let stream = event_loop.take_back(stream, CLIENT, EventSet::readable(), PollOpt::level()).unwrap();
+    assert_eq!((&stream).try_read(&mut dst).unwrap(), None);
+
+    server.write(b"more").unwrap();
+
+    let mut handler = TestHandler { ready: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.ready.len(), 1);
+    assert_eq!(handler.ready[0].0, CLIENT);
+    assert!(handler.ready[0].1.is_readable());
+}
+
+#[test]
+pub fn test_hand_off_deregisters() {
+    let addr = localhost();
+    let mut event_loop: EventLoop<TestHandler> = EventLoop::new().unwrap();
+
+    let _listener = TcpListener::bind(&addr).unwrap();
+
+    // Not registered
+    let stream = TcpStream::connect(&addr).unwrap();
+    assert!(event_loop.hand_off(stream).is_ok());
+
+    // Registered, forgotten right away
+    let stream = TcpStream::connect(&addr).unwrap();
+    event_loop.register(&stream, CLIENT).unwrap();
+    assert!(!event_loop.is_idle());
+
+    let _stream = event_loop.hand_off(stream).unwrap();
+    assert!(event_loop.is_idle());
+}
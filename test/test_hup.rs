@@ -0,0 +1,43 @@
+use mio::*;
+use mio::unix::*;
+use tempdir::TempDir;
+
+const PEER: Token = Token(0);
+
+struct TestHandler {
+    events: Vec<EventSet>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, PEER);
+        self.events.push(events);
+    }
+}
+
+#[test]
+pub fn test_peer_close_is_readable_and_hup() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let tmp_dir = TempDir::new("test_hup").unwrap();
+    let addr = tmp_dir.path().join("sock");
+
+    let srv = UnixListener::bind(&addr).unwrap();
+    let cli = UnixStream::connect(&addr).unwrap();
+    let peer = srv.accept().unwrap().expect("connection not accepted");
+
+    event_loop.register_opt(&peer, PEER, EventSet::readable() | EventSet::hup(), PollOpt::level()).unwrap();
+
+    // Close without writing anything, no data is readable
+    drop(cli);
+
+    let mut handler = TestHandler { events: vec![] };
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.events.len(), 1);
+    assert!(handler.events[0].is_readable(), "actual={:?}", handler.events[0]);
+    assert!(handler.events[0].is_hup(), "actual={:?}", handler.events[0]);
+}
@@ -0,0 +1,68 @@
+use mio::*;
+use mio::unix;
+use std::io::Write;
+use std::thread;
+
+const PIPE: Token = Token(0);
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+}
+
+fn event_loop(idle: IdleStrategy) -> EventLoop<TestHandler> {
+    let config = EventLoopConfig {
+        idle: idle,
+        metrics: true,
+        .. EventLoopConfig::default()
+    };
+
+    EventLoop::configured(config).unwrap()
+}
+
+#[test]
+pub fn test_idle_spin() {
+    let mut event_loop = event_loop(IdleStrategy::Spin(500_000));
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    event_loop.register(&reader, PIPE).unwrap();
+
+    let th = thread::spawn(move || {
+        ::sleep_ms(20);
+        writer.write_all(b"x").unwrap();
+    });
+
+    // The byte arrives while spinning
+    event_loop.run_once_timeout(&mut TestHandler, 1_000).unwrap();
+    th.join().unwrap();
+
+    let metrics = event_loop.metrics();
+    assert_eq!(metrics.io_events, 1);
+    assert_eq!(metrics.idle_spin_hits, 1);
+    assert_eq!(metrics.idle_blocks, 0);
+}
+
+#[test]
+pub fn test_idle_block() {
+    let mut event_loop = event_loop(IdleStrategy::Block);
+
+    event_loop.run_once_timeout(&mut TestHandler, 10).unwrap();
+
+    let metrics = event_loop.metrics();
+    assert_eq!(metrics.idle_spin_hits, 0);
+    assert_eq!(metrics.idle_blocks, 1);
+}
+
+#[test]
+pub fn test_idle_park() {
+    let mut event_loop = event_loop(IdleStrategy::Park(50));
+
+    // Parking bounds the wait instead of `io_poll_timeout_ms`
+    let start = SystemClock.now_ms();
+    event_loop.run_once(&mut TestHandler).unwrap();
+    assert!(SystemClock.now_ms() - start < 1_000);
+
+    assert_eq!(event_loop.metrics().idle_blocks, 1);
+}
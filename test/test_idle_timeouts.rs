@@ -0,0 +1,75 @@
+use mio::*;
+use std::sync::Arc;
+
+struct TestHandler {
+    idle: IdleTimeouts,
+    expired: Vec<Token>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = Idle;
+    type Message = ();
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, timeout: Idle) {
+        match timeout {
+            Idle::Tick => self.idle.tick(event_loop).unwrap(),
+            Idle::Expired(token) => self.expired.push(token),
+        }
+    }
+}
+
+#[test]
+pub fn test_idle_timeouts() {
+    let clock = MockClock::new(0);
+
+    let config = EventLoopConfig {
+        clock: Arc::new(clock.clone()),
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let mut handler = TestHandler {
+        idle: IdleTimeouts::new(300, 100),
+        expired: vec![],
+    };
+
+    handler.idle.start(&mut event_loop).unwrap();
+    handler.idle.touch(Token(1));
+    handler.idle.touch(Token(2));
+    handler.idle.touch(Token(3));
+    assert_eq!(3, handler.idle.len());
+
+    assert!(handler.idle.remove(Token(3)));
+    assert!(!handler.idle.contains(Token(3)));
+
+    clock.advance_ms(100);
+    event_loop.fire_timeouts(&mut handler);
+    clock.advance_ms(100);
+    event_loop.fire_timeouts(&mut handler);
+
+    // Activity pushes the expiration back
+    handler.idle.touch(Token(2));
+
+    // Expired on the tick at 300ms, delivered with the next timer tick
+    clock.advance_ms(100);
+    event_loop.fire_timeouts(&mut handler);
+    assert!(handler.expired.is_empty());
+
+    clock.advance_ms(100);
+    event_loop.fire_timeouts(&mut handler);
+    assert_eq!(handler.expired, [Token(1)]);
+    assert!(!handler.idle.contains(Token(1)));
+    assert!(handler.idle.contains(Token(2)));
+
+    clock.advance_ms(100);
+    event_loop.fire_timeouts(&mut handler);
+    clock.advance_ms(100);
+    event_loop.fire_timeouts(&mut handler);
+    assert_eq!(handler.expired, [Token(1), Token(2)]);
+    assert!(handler.idle.is_empty());
+
+    // Stopped, nothing is scheduled anymore
+    handler.idle.stop(&mut event_loop);
+    clock.advance_ms(1_000);
+    assert_eq!(0, event_loop.fire_timeouts(&mut handler));
+}
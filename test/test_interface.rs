@@ -0,0 +1,42 @@
+use mio::*;
+use mio::interface;
+use mio::udp::UdpSocket;
+use std::io::ErrorKind;
+use super::localhost;
+
+fn loopback() -> interface::Interface {
+    interface::interfaces().unwrap()
+        .into_iter()
+        .find(|iface| iface.is_loopback())
+        .expect("no loopback interface")
+}
+
+#[test]
+pub fn test_interfaces() {
+    let lo = loopback();
+
+    assert!(lo.is_up());
+    assert!(lo.index() > 0);
+    assert!(lo.addrs().contains(&IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))));
+    assert_eq!(lo.ipv4_addr(), Some(Ipv4Addr::new(127, 0, 0, 1)));
+
+    assert_eq!(interface::index(lo.name()).unwrap(), lo.index());
+    assert_eq!(interface::interface(lo.name()).unwrap().index(), lo.index());
+}
+
+#[test]
+pub fn test_interface_not_found() {
+    assert_eq!(interface::index("mio-nonexistent0").unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(interface::interface("mio-nonexistent0").unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(interface::index("lo\0").unwrap_err().kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+pub fn test_join_multicast_on_interface() {
+    let lo = loopback();
+    let group = "227.1.1.102".parse().unwrap();
+
+    let socket = UdpSocket::bound(&localhost()).unwrap();
+    socket.join_multicast_on(&group, &lo).unwrap();
+    socket.leave_multicast_on(&group, &lo).unwrap();
+}
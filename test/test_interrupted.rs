@@ -0,0 +1,114 @@
+use mio::*;
+use std::thread;
+use sleep_ms;
+
+const SIGURG: i32 = 23;
+
+const SIG_BLOCK: i32 = 0;
+const SIG_SETMASK: i32 = 2;
+
+// glibc's sigset_t
+type SigSet = [u64; 16];
+
+extern {
+    fn signal(signum: i32, handler: usize) -> usize;
+    fn pthread_self() -> usize;
+    fn pthread_kill(thread: usize, sig: i32) -> i32;
+    fn pthread_sigmask(how: i32, set: *const SigSet, old: *mut SigSet) -> i32;
+    fn sigemptyset(set: *mut SigSet) -> i32;
+    fn sigaddset(set: *mut SigSet, signum: i32) -> i32;
+}
+
+extern fn noop(_: i32) {
+}
+
+struct TestHandler {
+    interrupted: usize,
+    timeouts: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn interrupted(&mut self, _: &mut EventLoop<TestHandler>) {
+        self.interrupted += 1;
+    }
+
+    fn timeout(&mut self, _: &mut EventLoop<TestHandler>, _: ()) {
+        self.timeouts += 1;
+    }
+}
+
+// Runs a single tick of an event loop waiting for a 300ms timeout, while
+// another thread interrupts it with a signal
+fn interrupted_tick(retry: bool) -> TestHandler {
+    let config = EventLoopConfig {
+        retry_interrupted: retry,
+        .. EventLoopConfig::default()
+    };
+
+    interrupted_tick_with(config)
+}
+
+fn interrupted_tick_with(config: EventLoopConfig) -> TestHandler {
+    unsafe { signal(SIGURG, noop as extern fn(i32) as usize); }
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    event_loop.timeout_ms((), 300).unwrap();
+
+    let target = unsafe { pthread_self() };
+    let child = thread::spawn(move || {
+        sleep_ms(50);
+        unsafe { pthread_kill(target, SIGURG); }
+    });
+
+    let mut handler = TestHandler { interrupted: 0, timeouts: 0 };
+
+    while handler.timeouts == 0 && handler.interrupted == 0 {
+        event_loop.run_once(&mut handler).unwrap();
+    }
+
+    child.join().unwrap();
+    handler
+}
+
+#[test]
+pub fn test_interrupted_poll_is_retried() {
+    let handler = interrupted_tick(true);
+    assert_eq!(0, handler.interrupted);
+    assert_eq!(1, handler.timeouts);
+}
+
+#[test]
+pub fn test_interrupted_hook() {
+    let handler = interrupted_tick(false);
+    assert_eq!(1, handler.interrupted);
+    assert_eq!(0, handler.timeouts);
+}
+
+#[test]
+pub fn test_poll_sigmask() {
+    let mut blocked = [0; 16];
+    let mut prev = [0; 16];
+
+    // SIGURG stays blocked outside of the poll
+    unsafe {
+        sigemptyset(&mut blocked);
+        sigaddset(&mut blocked, SIGURG);
+        pthread_sigmask(SIG_BLOCK, &blocked, &mut prev);
+    }
+
+    let config = EventLoopConfig {
+        retry_interrupted: false,
+        poll_sigmask: Some(vec![]),
+        .. EventLoopConfig::default()
+    };
+
+    let handler = interrupted_tick_with(config);
+
+    unsafe { pthread_sigmask(SIG_SETMASK, &prev, 0 as *mut SigSet); }
+
+    assert_eq!(1, handler.interrupted);
+    assert_eq!(0, handler.timeouts);
+}
@@ -0,0 +1,37 @@
+use mio::*;
+use mio::unix;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::{FromRawFd, IntoRawFd};
+
+#[test]
+pub fn test_into_raw_fd_keeps_fd_open() {
+    let (reader, writer) = unix::pipe().unwrap();
+
+    let mut writer = unsafe { File::from_raw_fd(writer.into_raw_fd()) };
+    writer.write(b"hello").unwrap();
+
+    let mut reader = unsafe { File::from_raw_fd(reader.into_raw_fd()) };
+    let mut dst = [0; 5];
+    assert_eq!(reader.read(&mut dst).unwrap(), 5);
+    assert_eq!(&dst, b"hello");
+}
+
+#[test]
+pub fn test_into_raw_fd_deregisters() {
+    let mut poll = Poll::new().unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    poll.register(&reader, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    writer.write(b"hello").unwrap();
+    assert_eq!(poll.poll(100).unwrap(), 1);
+
+    // The fd outlives the handle, but is no longer polled
+    let reader = unsafe { File::from_raw_fd(reader.into_raw_fd()) };
+    assert_eq!(poll.poll(100).unwrap(), 0);
+
+    // And can be registered again once back in a handle
+    let reader = unsafe { unix::PipeReader::from_raw_fd(reader.into_raw_fd()) };
+    poll.register(&reader, Token(1), EventSet::readable(), PollOpt::level()).unwrap();
+    assert_eq!(poll.poll(100).unwrap(), 1);
+}
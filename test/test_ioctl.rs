@@ -0,0 +1,25 @@
+use mio::*;
+use mio::unix;
+use std::io::Write;
+use std::os::unix::io::IntoRawFd;
+use std::ptr;
+
+#[test]
+pub fn test_bytes_readable() {
+    let (reader, mut writer) = unix::pipe().unwrap();
+    let reader = Io::from_raw_fd(reader.into_raw_fd());
+
+    assert_eq!(reader.bytes_readable().unwrap(), 0);
+
+    writer.write(b"hello").unwrap();
+    assert_eq!(reader.bytes_readable().unwrap(), 5);
+}
+
+#[test]
+pub fn test_ioctl_error() {
+    let (reader, _writer) = unix::pipe().unwrap();
+    let reader = Io::from_raw_fd(reader.into_raw_fd());
+
+    // Not a request pipes understand
+    assert!(unsafe { reader.ioctl(0, ptr::null_mut()) }.is_err());
+}
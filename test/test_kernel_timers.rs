@@ -0,0 +1,43 @@
+use mio::*;
+
+struct TestHandler {
+    fired: Vec<&'static str>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = &'static str;
+    type Message = ();
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, name: &'static str) {
+        self.fired.push(name);
+
+        if name == "last" {
+            event_loop.shutdown();
+        }
+    }
+}
+
+// Kernel timers are only used on kqueue platforms, the timeouts behave the
+// same everywhere else
+#[test]
+pub fn test_kernel_timers() {
+    let config = EventLoopConfig {
+        kernel_timers: true,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let mut handler = TestHandler { fired: vec![] };
+
+    event_loop.timeout_ms("first", 10).unwrap();
+    let cleared = event_loop.timeout_ms("cleared", 20).unwrap();
+    event_loop.timeout_ms("last", 150).unwrap();
+
+    assert!(event_loop.clear_timeout(cleared));
+    assert!(!event_loop.clear_timeout(cleared));
+
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.fired, vec!["first", "last"]);
+    assert!(event_loop.is_idle());
+}
@@ -0,0 +1,49 @@
+use mio::*;
+use std::time::Duration;
+use std::u64;
+
+const NEVER: u32 = 0;
+const SOON: u32 = 1;
+
+struct TestHandler {
+    fired: Vec<u32>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = u32;
+    type Message = ();
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, timeout: u32) {
+        self.fired.push(timeout);
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_never_timeout_does_not_fire() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    event_loop.timeout_ms(NEVER, u64::MAX).unwrap();
+    event_loop.timeout(NEVER, Duration::new(u64::MAX, 999_999_999)).unwrap();
+    event_loop.timeout(SOON, Duration::new(0, 50_000_000)).unwrap();
+
+    let mut handler = TestHandler { fired: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.fired, vec![SOON]);
+}
+
+#[test]
+pub fn test_never_timeout_can_be_cleared() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let never = event_loop.timeout_ms(NEVER, u64::MAX).unwrap();
+    assert!(event_loop.clear_timeout(never));
+
+    event_loop.timeout(SOON, Duration::new(0, 10_000_000)).unwrap();
+
+    let mut handler = TestHandler { fired: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.fired, vec![SOON]);
+}
@@ -0,0 +1,130 @@
+use mio::*;
+use mio::unix;
+use std::io::Write;
+
+const PIPE: Token = Token(0);
+
+struct TestHandler {
+    reader: unix::PipeReader,
+}
+
+impl Handler for TestHandler {
+    type Timeout = bool;
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, _: EventSet) {
+        assert_eq!(token, PIPE);
+        event_loop.deregister(&self.reader).unwrap();
+    }
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, stop: bool) {
+        if stop {
+            event_loop.shutdown();
+        }
+    }
+}
+
+fn metrics_config() -> EventLoopConfig {
+    EventLoopConfig {
+        metrics: true,
+        .. EventLoopConfig::default()
+    }
+}
+
+#[test]
+pub fn test_metrics() {
+    let mut event_loop = EventLoop::configured(metrics_config()).unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    writer.write_all(b"hello").unwrap();
+    event_loop.register(&reader, PIPE).unwrap();
+
+    event_loop.channel().send(()).unwrap();
+    event_loop.channel().send(()).unwrap();
+
+    event_loop.timeout_ms(true, 50).unwrap();
+    event_loop.timeout_ms(false, 60_000).unwrap();
+
+    let mut handler = TestHandler { reader: reader };
+    event_loop.run(&mut handler).unwrap();
+
+    event_loop.channel().send(()).unwrap();
+
+    let metrics = event_loop.metrics();
+
+    assert!(metrics.ticks > 0);
+    assert_eq!(1, metrics.io_events);
+    assert_eq!(1, metrics.max_io_events_per_tick);
+    assert_eq!(2, metrics.messages);
+    assert_eq!(1, metrics.timeouts_fired);
+    assert!(metrics.poll_wait_ns > 0);
+    assert_eq!(1, metrics.timeouts_pending);
+    assert_eq!(1, metrics.notify_queue_depth);
+
+    event_loop.reset_metrics();
+
+    let metrics = event_loop.metrics();
+    assert_eq!(0, metrics.ticks);
+    assert_eq!(0, metrics.io_events);
+    assert_eq!(0, metrics.messages);
+    assert_eq!(1, metrics.timeouts_pending);
+}
+
+#[test]
+pub fn test_metrics_disabled() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    event_loop.channel().send(()).unwrap();
+    event_loop.timeout_ms(true, 10).unwrap();
+
+    let mut handler = TestHandler { reader: reader };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(Metrics::default(), event_loop.metrics());
+}
+
+struct SteadyHandler;
+
+impl Handler for SteadyHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<SteadyHandler>, _: Token, _: EventSet) {
+        event_loop.channel().send(()).unwrap();
+    }
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<SteadyHandler>, _: ()) {
+        event_loop.timeout_ms((), 0).unwrap();
+    }
+}
+
+#[test]
+pub fn test_metrics_steady_state_reallocations() {
+    let mut event_loop = EventLoop::configured(metrics_config()).unwrap();
+    let (_r1, w1) = unix::pipe().unwrap();
+    let (_r2, w2) = unix::pipe().unwrap();
+
+    // Both pipes stay writable, every tick polls two events, delivers
+    // messages and reschedules a timeout
+    event_loop.register_opt(&w1, Token(0), EventSet::writable(), PollOpt::level()).unwrap();
+    event_loop.register_opt(&w2, Token(1), EventSet::writable(), PollOpt::level()).unwrap();
+    event_loop.timeout_ms((), 0).unwrap();
+
+    let mut handler = SteadyHandler;
+
+    for _ in 0..10 {
+        event_loop.run_once_timeout(&mut handler, 0).unwrap();
+    }
+
+    event_loop.reset_metrics();
+
+    for _ in 0..100 {
+        event_loop.run_once_timeout(&mut handler, 0).unwrap();
+    }
+
+    let metrics = event_loop.metrics();
+    assert_eq!(100, metrics.ticks);
+    assert_eq!(200, metrics.io_events);
+    assert_eq!(0, metrics.buffer_reallocations);
+}
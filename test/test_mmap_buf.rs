@@ -0,0 +1,52 @@
+use mio::*;
+use mio::buf::{Advice, Buf, MmapBuf};
+use mio::unix;
+use std::fs::File;
+use std::io::Write;
+use tempdir::TempDir;
+
+#[test]
+pub fn test_mmap_buf_try_write_buf() {
+    let dir = TempDir::new("test_mmap_buf").unwrap();
+    let path = dir.path().join("file");
+
+    File::create(&path).unwrap().write_all(b"hello world").unwrap();
+
+    let mut buf = MmapBuf::open(&path).unwrap();
+    buf.advise(Advice::Sequential).unwrap();
+
+    assert_eq!(buf.len(), 11);
+    assert_eq!(buf.as_slice(), b"hello world");
+
+    let (reader, writer) = unix::pipe().unwrap();
+
+    assert_eq!((&writer).try_write_buf(&mut buf).unwrap(), Some(11));
+    assert!(!buf.has_remaining());
+
+    let mut dst = [0; 11];
+    assert_eq!((&reader).try_read(&mut dst).unwrap(), Some(11));
+    assert_eq!(&dst, b"hello world");
+}
+
+#[test]
+pub fn test_mmap_buf_range() {
+    let dir = TempDir::new("test_mmap_buf").unwrap();
+    let path = dir.path().join("file");
+
+    let contents: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+    File::create(&path).unwrap().write_all(&contents).unwrap();
+
+    // The offset is not page aligned
+    let file = File::open(&path).unwrap();
+    let buf = MmapBuf::from_file_range(&file, 5_000, 100).unwrap();
+
+    assert_eq!(buf.bytes(), &contents[5_000..5_100]);
+
+    // Empty files cannot be mapped, but make an empty buffer
+    let empty = dir.path().join("empty");
+    File::create(&empty).unwrap();
+
+    let buf = MmapBuf::open(&empty).unwrap();
+    assert_eq!(buf.remaining(), 0);
+    buf.advise(Advice::WillNeed).unwrap();
+}
@@ -0,0 +1,46 @@
+use mio::*;
+use std::sync::Arc;
+
+struct TestHandler {
+    fired: Vec<&'static str>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = &'static str;
+    type Message = ();
+
+    fn timeout(&mut self, _: &mut EventLoop<TestHandler>, name: &'static str) {
+        self.fired.push(name);
+    }
+}
+
+#[test]
+pub fn test_mock_clock() {
+    let clock = MockClock::new(0);
+
+    let config = EventLoopConfig {
+        clock: Arc::new(clock.clone()),
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let mut handler = TestHandler { fired: vec![] };
+
+    event_loop.timeout_ms("short", 1_000).unwrap();
+    event_loop.timeout_ms("long", 60_000).unwrap();
+
+    // Time stands still, nothing is due
+    assert_eq!(0, event_loop.fire_timeouts(&mut handler));
+
+    clock.advance_ms(999);
+    assert_eq!(0, event_loop.fire_timeouts(&mut handler));
+
+    clock.advance_ms(1);
+    assert_eq!(1, event_loop.fire_timeouts(&mut handler));
+    assert_eq!(handler.fired, ["short"]);
+
+    // Due timeouts also fire from a regular tick
+    clock.advance_ms(59_000);
+    event_loop.run_once_timeout(&mut handler, 0).unwrap();
+    assert_eq!(handler.fired, ["short", "long"]);
+}
@@ -0,0 +1,62 @@
+use mio::*;
+
+const CHILD: Token = Token(0);
+
+struct Child {
+    msgs: Vec<u32>,
+}
+
+impl Handler for Child {
+    type Timeout = ();
+    type Message = u32;
+
+    fn notify(&mut self, _: &mut EventLoop<Child>, msg: u32) {
+        self.msgs.push(msg);
+    }
+}
+
+struct Parent {
+    child: EventLoop<Child>,
+    handler: Child,
+}
+
+impl Handler for Parent {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<Parent>, token: Token, events: EventSet) {
+        assert_eq!(token, CHILD);
+        assert!(events.is_readable());
+
+        self.child.run_once_timeout(&mut self.handler, 0).unwrap();
+        self.child.prepare_poll();
+
+        if self.handler.msgs.len() == 2 {
+            event_loop.shutdown();
+        }
+    }
+
+    fn timeout(&mut self, _: &mut EventLoop<Parent>, _: ()) {
+        panic!("child loop never became readable");
+    }
+}
+
+#[test]
+pub fn test_nested_loop() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let mut child = EventLoop::new().unwrap();
+    let sender = child.channel();
+    child.prepare_poll();
+
+    event_loop.register(&&child, CHILD).unwrap();
+    event_loop.timeout_ms((), 5_000).unwrap();
+
+    sender.send(1).unwrap();
+    sender.send(2).unwrap();
+
+    let mut handler = Parent { child: child, handler: Child { msgs: vec![] } };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.handler.msgs, vec![1, 2]);
+}
@@ -0,0 +1,19 @@
+use mio::*;
+use mio::unix;
+use std::io::{Error, ErrorKind, Read};
+
+#[test]
+pub fn test_map_non_block() {
+    let (mut reader, writer) = unix::pipe().unwrap();
+    let mut dst = [0; 4];
+
+    // An empty pipe would block
+    assert_eq!(reader.read(&mut dst).map_non_block().unwrap(), None);
+
+    (&writer).try_write(b"ping").unwrap();
+    assert_eq!(reader.read(&mut dst).map_non_block().unwrap(), Some(4));
+
+    // Other errors are passed through
+    let res: NonBlock<()> = Err(Error::new(ErrorKind::BrokenPipe, "closed")).map_non_block();
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::BrokenPipe);
+}
@@ -197,3 +197,160 @@ pub fn test_notify_drop() {
 
     handle.join().unwrap();
 }
+
+#[test]
+pub fn test_sender_shared_between_threads() {
+    use std::sync::Arc;
+    use std::thread;
+
+    fn is_sync<T: Sync + Send>(_: &T) {}
+
+    struct Count(usize);
+
+    impl Handler for Count {
+        type Message = usize;
+        type Timeout = ();
+
+        fn notify(&mut self, event_loop: &mut EventLoop<Count>, _: usize) {
+            self.0 += 1;
+
+            if self.0 == 40 {
+                event_loop.shutdown();
+            }
+        }
+    }
+
+    let mut event_loop = EventLoop::new().unwrap();
+    let sender = event_loop.channel();
+    is_sync(&sender);
+
+    // Half of the producers share a single sender, the others own a clone
+    let shared = Arc::new(sender.clone());
+
+    let handles: Vec<_> = (0..4).map(|i| {
+        let shared = shared.clone();
+        let sender = sender.clone();
+
+        thread::spawn(move || {
+            for n in 0..10 {
+                if i % 2 == 0 {
+                    shared.send(n).unwrap();
+                } else {
+                    sender.send(n).unwrap();
+                }
+            }
+        })
+    }).collect();
+
+    let mut handler = Count(0);
+    event_loop.run(&mut handler).unwrap();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(40, handler.0);
+}
+
+#[test]
+pub fn test_send_full() {
+    struct Noop;
+
+    impl Handler for Noop {
+        type Message = i32;
+        type Timeout = ();
+    }
+
+    let config = EventLoopConfig {
+        notify_capacity: 2,
+        .. EventLoopConfig::default()
+    };
+
+    let event_loop: EventLoop<Noop> = EventLoop::configured(config).unwrap();
+    let sender = event_loop.channel();
+
+    sender.send(1).unwrap();
+    sender.send(2).unwrap();
+
+    // Sending does not block, the message is handed back when the queue is
+    // full
+    match sender.send(3) {
+        Err(NotifyError::Full(3)) => {}
+        res => panic!("unexpected result; res={:?}", res),
+    }
+}
+
+#[test]
+pub fn test_notify_priority() {
+    struct Order(Vec<&'static str>);
+
+    impl Handler for Order {
+        type Message = &'static str;
+        type Timeout = ();
+
+        fn notify(&mut self, event_loop: &mut EventLoop<Order>, msg: &'static str) {
+            self.0.push(msg);
+
+            if self.0.len() == 4 {
+                event_loop.shutdown();
+            }
+        }
+    }
+
+    let config = EventLoopConfig {
+        notify_capacity: 2,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let sender = event_loop.channel();
+
+    sender.send("data-1").unwrap();
+    sender.send("data-2").unwrap();
+    assert!(sender.send("data-3").is_err());
+
+    // The high priority lane has its own capacity
+    sender.send_priority("reload", Priority::High).unwrap();
+    sender.send_priority("shutdown", Priority::High).unwrap();
+
+    let mut handler = Order(vec![]);
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.0, ["reload", "shutdown", "data-1", "data-2"]);
+}
+
+#[test]
+pub fn test_notify_slots_reused() {
+    struct Count(usize);
+
+    impl Handler for Count {
+        type Message = usize;
+        type Timeout = ();
+
+        fn notify(&mut self, _: &mut EventLoop<Count>, msg: usize) {
+            assert_eq!(msg, self.0);
+            self.0 += 1;
+        }
+    }
+
+    let config = EventLoopConfig {
+        notify_capacity: 4,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let sender = event_loop.channel();
+    let mut handler = Count(0);
+
+    // Many times the capacity goes through the preallocated slots, in order
+    for i in 0..256 {
+        for j in 0..4 {
+            sender.send(i * 4 + j).unwrap();
+        }
+
+        assert!(sender.send(0).is_err());
+        event_loop.run_once_timeout(&mut handler, 0).unwrap();
+    }
+
+    assert_eq!(handler.0, 1_024);
+}
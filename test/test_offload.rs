@@ -0,0 +1,49 @@
+use mio::*;
+
+struct TestHandler {
+    completed: Vec<Completed<usize>>,
+    expected: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = Completed<usize>;
+
+    fn notify(&mut self, event_loop: &mut EventLoop<TestHandler>, msg: Completed<usize>) {
+        self.completed.push(msg);
+
+        if self.completed.len() == self.expected {
+            event_loop.shutdown();
+        }
+    }
+}
+
+#[test]
+pub fn test_offload() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let pool = Offload::new(2, event_loop.channel()).unwrap();
+
+    for i in 0..4 {
+        pool.submit(Token(i), move || i * i).unwrap();
+    }
+
+    pool.submit(Token(4), || panic!("task failed")).unwrap();
+
+    let mut handler = TestHandler { completed: vec![], expected: 5 };
+    event_loop.run(&mut handler).unwrap();
+
+    handler.completed.sort_by(|a, b| a.token.0.cmp(&b.token.0));
+
+    for i in 0..4 {
+        assert_eq!(handler.completed[i].token, Token(i));
+        assert_eq!(*handler.completed[i].result.as_ref().unwrap(), i * i);
+    }
+
+    // The panic is delivered, and the threads are still running
+    assert!(handler.completed[4].result.is_err());
+
+    pool.submit(Token(5), || 25).unwrap();
+    handler.expected = 6;
+    event_loop.run(&mut handler).unwrap();
+    assert_eq!(*handler.completed[5].result.as_ref().unwrap(), 25);
+}
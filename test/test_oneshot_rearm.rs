@@ -0,0 +1,51 @@
+use mio::*;
+use mio::buf::SliceBuf;
+use mio::unix::{self, PipeReader, PipeWriter};
+
+const READER: Token = Token(0);
+
+struct TestHandler {
+    reader: PipeReader,
+    writer: PipeWriter,
+    count: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, READER);
+        assert!(events.is_readable());
+
+        let mut buf = [0; 16];
+        self.reader.try_read(&mut buf).unwrap().unwrap();
+        self.count += 1;
+
+        if self.count == 3 {
+            event_loop.shutdown();
+            return;
+        }
+
+        // Without the re-arm the oneshot registration would never fire again
+        self.writer.try_write_buf(&mut SliceBuf::wrap(b"x")).unwrap();
+        event_loop.rearm(&self.reader, READER).unwrap();
+    }
+}
+
+#[test]
+pub fn test_oneshot_rearm() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    writer.try_write_buf(&mut SliceBuf::wrap(b"x")).unwrap();
+    event_loop.register_opt(&reader, READER, EventSet::readable(), PollOpt::edge() | PollOpt::oneshot()).unwrap();
+
+    let mut handler = TestHandler { reader: reader, writer: writer, count: 0 };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.count, 3);
+
+    // Unknown tokens can't be re-armed
+    assert!(event_loop.rearm(&handler.reader, Token(123)).is_err());
+}
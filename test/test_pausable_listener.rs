@@ -0,0 +1,94 @@
+use mio::*;
+use mio::tcp::{TcpListener, TcpStream};
+use std::net;
+
+const SERVER: Token = Token(0);
+
+struct TestHandler {
+    listener: PausableListener<TcpListener>,
+    accepted: Vec<TcpStream>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, _: EventSet) {
+        assert_eq!(token, SERVER);
+
+        while !self.listener.is_paused() {
+            match self.listener.get_ref().accept().unwrap() {
+                Some(stream) => {
+                    self.accepted.push(stream);
+                    self.listener.opened(event_loop).unwrap();
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+#[test]
+pub fn test_pausable_listener_watermarks() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let addr = ::localhost();
+    let listener = TcpListener::bind(&addr).unwrap();
+
+    let mut handler = TestHandler {
+        listener: PausableListener::new(listener, SERVER),
+        accepted: vec![],
+    };
+
+    handler.listener.register(&mut event_loop, PollOpt::level()).unwrap();
+    handler.listener.set_watermarks(&mut event_loop, 2, 0).unwrap();
+
+    let _clients: Vec<net::TcpStream> = (0..3).map(|_| net::TcpStream::connect(&addr).unwrap()).collect();
+
+    while handler.accepted.len() < 2 {
+        event_loop.run_once_timeout(&mut handler, 1_000).unwrap();
+    }
+
+    // The third connection stays queued in the backlog
+    assert!(handler.listener.is_paused());
+    event_loop.run_once_timeout(&mut handler, 100).unwrap();
+    assert_eq!(handler.accepted.len(), 2);
+
+    // Still above the low-water mark
+    handler.listener.closed(&mut event_loop).unwrap();
+    assert!(handler.listener.is_paused());
+
+    handler.listener.closed(&mut event_loop).unwrap();
+    assert!(!handler.listener.is_paused());
+
+    while handler.accepted.len() < 3 {
+        event_loop.run_once_timeout(&mut handler, 1_000).unwrap();
+    }
+
+    assert_eq!(handler.listener.pending(), 1);
+}
+
+#[test]
+pub fn test_pausable_listener_manual() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let addr = ::localhost();
+    let listener = TcpListener::bind(&addr).unwrap();
+
+    let mut handler = TestHandler {
+        listener: PausableListener::new(listener, SERVER),
+        accepted: vec![],
+    };
+
+    handler.listener.register(&mut event_loop, PollOpt::level()).unwrap();
+    handler.listener.pause(&mut event_loop).unwrap();
+
+    let _client = net::TcpStream::connect(&addr).unwrap();
+
+    event_loop.run_once_timeout(&mut handler, 100).unwrap();
+    assert!(handler.accepted.is_empty());
+
+    handler.listener.resume(&mut event_loop).unwrap();
+
+    while handler.accepted.is_empty() {
+        event_loop.run_once_timeout(&mut handler, 1_000).unwrap();
+    }
+}
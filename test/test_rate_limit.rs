@@ -0,0 +1,84 @@
+use mio::*;
+use std::sync::Arc;
+
+struct TestHandler {
+    limiter: RateLimiter,
+    refilled: Vec<Token>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = Refilled;
+    type Message = ();
+
+    fn timeout(&mut self, _: &mut EventLoop<TestHandler>, timeout: Refilled) {
+        self.limiter.refilled(timeout.0);
+        self.refilled.push(timeout.0);
+    }
+}
+
+fn event_loop(clock: &MockClock) -> EventLoop<TestHandler> {
+    let config = EventLoopConfig {
+        clock: Arc::new(clock.clone()),
+        .. EventLoopConfig::default()
+    };
+
+    EventLoop::configured(config).unwrap()
+}
+
+#[test]
+pub fn test_rate_limit_per_token() {
+    let clock = MockClock::new(0);
+    let mut event_loop = event_loop(&clock);
+    let mut handler = TestHandler {
+        limiter: RateLimiter::new(Some(Rate::new(1_000, 500)), None),
+        refilled: vec![],
+    };
+
+    // Buckets start full
+    assert_eq!(500, handler.limiter.available(&event_loop, Token(1)));
+    handler.limiter.consume(&event_loop, Token(1), 500);
+    assert_eq!(0, handler.limiter.available(&event_loop, Token(1)));
+
+    // Other tokens have their own bucket
+    assert_eq!(500, handler.limiter.available(&event_loop, Token(2)));
+
+    handler.limiter.throttle(&mut event_loop, Token(1), 100).unwrap();
+    assert!(handler.limiter.is_throttled(Token(1)));
+
+    clock.advance_ms(50);
+    assert_eq!(0, event_loop.fire_timeouts(&mut handler));
+    assert_eq!(50, handler.limiter.available(&event_loop, Token(1)));
+
+    clock.advance_ms(50);
+    assert_eq!(1, event_loop.fire_timeouts(&mut handler));
+    assert_eq!(handler.refilled, [Token(1)]);
+    assert!(!handler.limiter.is_throttled(Token(1)));
+    assert_eq!(100, handler.limiter.available(&event_loop, Token(1)));
+
+    // Refills up to the burst
+    clock.advance_ms(10_000);
+    assert_eq!(500, handler.limiter.available(&event_loop, Token(1)));
+}
+
+#[test]
+pub fn test_rate_limit_global() {
+    let clock = MockClock::new(0);
+    let mut event_loop = event_loop(&clock);
+    let mut handler = TestHandler {
+        limiter: RateLimiter::new(Some(Rate::new(1_000, 500)), Some(Rate::new(1_000, 300))),
+        refilled: vec![],
+    };
+
+    assert_eq!(300, handler.limiter.available(&event_loop, Token(1)));
+    handler.limiter.consume(&event_loop, Token(1), 200);
+    assert_eq!(100, handler.limiter.available(&event_loop, Token(2)));
+
+    // Removing a token cancels its refill
+    handler.limiter.throttle(&mut event_loop, Token(2), 200).unwrap();
+    handler.limiter.remove(&mut event_loop, Token(2));
+    assert!(!handler.limiter.is_throttled(Token(2)));
+
+    clock.advance_ms(1_000);
+    assert_eq!(0, event_loop.fire_timeouts(&mut handler));
+    assert_eq!(300, handler.limiter.available(&event_loop, Token(1)));
+}
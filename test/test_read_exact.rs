@@ -0,0 +1,63 @@
+use mio::*;
+use mio::unix;
+
+#[test]
+pub fn test_read_exact_resumes() {
+    let (reader, writer) = unix::pipe().unwrap();
+    let mut header = ReadExact::new([0; 8]);
+
+    assert!(header.read_from(&mut &reader).unwrap().is_none());
+
+    (&writer).try_write(b"head").unwrap();
+
+    assert!(header.read_from(&mut &reader).unwrap().is_none());
+    assert_eq!(header.position(), 4);
+
+    (&writer).try_write(b"er!!next").unwrap();
+
+    assert!(header.read_from(&mut &reader).unwrap().is_some());
+    assert!(header.is_complete());
+    assert_eq!(&header.into_inner(), b"header!!");
+
+    // The following bytes were left in the pipe
+    let mut dst = [0; 4];
+    assert_eq!((&reader).try_read(&mut dst).unwrap(), Some(4));
+    assert_eq!(&dst, b"next");
+}
+
+#[test]
+pub fn test_read_exact_eof() {
+    let (reader, writer) = unix::pipe().unwrap();
+    let mut header = ReadExact::new(vec![0; 8]);
+
+    (&writer).try_write(b"head").unwrap();
+    drop(writer);
+
+    assert!(header.read_from(&mut &reader).is_err());
+    assert_eq!(header.position(), 4);
+}
+
+#[test]
+pub fn test_write_all_resumes() {
+    let (reader, writer) = unix::pipe().unwrap();
+
+    // Larger than the pipe buffer, so that writes would block
+    let mut body = WriteAll::new(vec![7; 1024 * 1024]);
+
+    assert!(body.write_to(&mut &writer).unwrap().is_none());
+
+    let written = body.position();
+    assert!(written > 0);
+
+    let mut dst = vec![0; 1024 * 1024];
+    let mut read = 0;
+
+    while body.write_to(&mut &writer).unwrap().is_none() || read < 1024 * 1024 {
+        if let Some(cnt) = (&reader).try_read(&mut dst[read..]).unwrap() {
+            read += cnt;
+        }
+    }
+
+    assert!(body.is_complete());
+    assert!(dst.iter().all(|&b| b == 7));
+}
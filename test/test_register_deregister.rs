@@ -95,3 +95,26 @@ pub fn test_register_deregister() {
 
     assert!(handler.state == 2, "unexpected final state {}", handler.state);
 }
+
+#[test]
+pub fn test_double_register() {
+    use std::io::ErrorKind;
+
+    let mut event_loop: EventLoop<TestHandler> = EventLoop::new().unwrap();
+
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    event_loop.register(&reader, Token(7)).unwrap();
+
+    // Same token and a different token both fail, identifying the existing one
+    for &token in [Token(7), Token(8)].iter() {
+        let err = event_loop.register(&reader, token).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert!(format!("{}", err).contains("Token(7)"), "actual={}", err);
+    }
+
+    // Once deregistered, the handle can be registered again
+    event_loop.deregister(&reader).unwrap();
+    event_loop.register(&reader, Token(8)).unwrap();
+}
@@ -0,0 +1,132 @@
+use mio::*;
+use mio::unix;
+use std::io::Write;
+use std::sync::mpsc;
+use std::thread;
+
+const PIPE: Token = Token(1);
+
+struct TestHandler {
+    readable: bool,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, PIPE);
+        assert!(events.is_readable());
+
+        self.readable = true;
+        event_loop.shutdown();
+    }
+
+    fn timeout(&mut self, _: &mut EventLoop<TestHandler>, _: ()) {
+        panic!("pipe never became readable");
+    }
+}
+
+#[test]
+pub fn test_register_from_other_thread() {
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.timeout_ms((), 5_000).unwrap();
+
+    let registrar = event_loop.registrar();
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+
+    let child = thread::spawn(move || {
+        let (reader, mut writer) = unix::pipe().unwrap();
+
+        // Blocks until the event loop thread has applied the registration
+        registrar.register(&reader, PIPE, EventSet::readable(), PollOpt::level()).wait().unwrap();
+
+        // Registering the same fd again is reported back to this thread
+        let res = registrar.register(&reader, PIPE, EventSet::readable(), PollOpt::level()).wait();
+        if cfg!(target_os = "linux") {
+            assert!(res.is_err());
+        }
+
+        writer.write_all(b"hello").unwrap();
+
+        // Keep the pipe open until the event loop is done with it
+        let _ = done_rx.recv();
+    });
+
+    let mut handler = TestHandler { readable: false };
+    event_loop.run(&mut handler).unwrap();
+    assert!(handler.readable);
+
+    done_tx.send(()).unwrap();
+    child.join().unwrap();
+}
+
+#[test]
+pub fn test_registrar_after_event_loop_dropped() {
+    let event_loop: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    let registrar = event_loop.registrar();
+    drop(event_loop);
+
+    let (reader, _writer) = unix::pipe().unwrap();
+    let completion = registrar.register(&reader, PIPE, EventSet::readable(), PollOpt::level());
+
+    assert!(completion.is_complete());
+    assert!(completion.wait().is_err());
+}
+
+struct WakeHandler {
+    events: Vec<(Token, EventSet)>,
+}
+
+impl Handler for WakeHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<WakeHandler>, token: Token, events: EventSet) {
+        self.events.push((token, events));
+        event_loop.shutdown();
+    }
+
+    fn timeout(&mut self, _: &mut EventLoop<WakeHandler>, _: ()) {
+        panic!("readiness never dispatched");
+    }
+}
+
+#[test]
+pub fn test_wake_from_other_thread() {
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.timeout_ms((), 5_000).unwrap();
+
+    let registrar = event_loop.registrar();
+
+    let child = thread::spawn(move || {
+        // The job for connection 7 is done while the event loop sleeps
+        thread::sleep_ms(100);
+        registrar.wake(Token(7)).wait().unwrap();
+    });
+
+    let mut handler = WakeHandler { events: vec![] };
+    event_loop.run(&mut handler).unwrap();
+    child.join().unwrap();
+
+    assert_eq!(handler.events, [(Token(7), EventSet::readable())]);
+}
+
+#[test]
+pub fn test_set_readiness_merged() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let registrar = event_loop.registrar();
+
+    let a = registrar.set_readiness(Token(8), EventSet::readable());
+    let b = registrar.set_readiness(Token(8), EventSet::writable());
+    let c = registrar.set_readiness(Token(9), EventSet::hup());
+
+    let mut handler = WakeHandler { events: vec![] };
+    event_loop.run_once_timeout(&mut handler, 0).unwrap();
+
+    assert!(a.is_complete() && b.is_complete() && c.is_complete());
+    assert_eq!(handler.events, [
+        (Token(8), EventSet::readable() | EventSet::writable()),
+        (Token(9), EventSet::hup()),
+    ]);
+}
@@ -0,0 +1,64 @@
+use mio::*;
+use mio::unix::{self, PipeReader};
+use std::io::Write;
+
+const PIPE: Token = Token(0);
+
+struct TestHandler {
+    guard: Option<RegistrationGuard<PipeReader>>,
+    ready: usize,
+    deregistered: Vec<Token>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, token: Token, _: EventSet) {
+        assert_eq!(token, PIPE);
+        self.ready += 1;
+
+        // Dropping the guard deregisters the pipe, even though it is still
+        // readable
+        self.guard.take();
+    }
+
+    fn deregistered(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token) {
+        self.deregistered.push(token);
+        event_loop.timeout_ms((), 200).unwrap();
+    }
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, _: ()) {
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_guard_deregisters_on_drop() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    writer.write_all(b"hello").unwrap();
+
+    let guard = event_loop.register_guarded(reader, PIPE, EventSet::readable(), PollOpt::level()).unwrap();
+    assert_eq!(PIPE, guard.token());
+
+    let mut handler = TestHandler { guard: Some(guard), ready: 0, deregistered: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    // Level triggered, so the pipe would have kept firing if still registered
+    assert_eq!(1, handler.ready);
+    assert_eq!(vec![PIPE], handler.deregistered);
+}
+
+#[test]
+pub fn test_guard_release() {
+    let mut event_loop: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    let guard = event_loop.register_guarded(reader, PIPE, EventSet::readable(), PollOpt::level()).unwrap();
+    let reader = event_loop.release(guard).unwrap();
+
+    // The handle is no longer registered, so it can be registered again
+    event_loop.register(&reader, PIPE).unwrap();
+}
@@ -0,0 +1,106 @@
+use mio::*;
+use mio::tcp::{TcpListener, TcpStream};
+use std::io::{Read, Write};
+use std::net;
+use std::thread;
+
+const LISTENER: Token = Token(0);
+const CLIENT: Token = Token(1);
+const UPSTREAM: Token = Token(2);
+
+struct RelayHandler {
+    listener: TcpListener,
+    upstream: net::SocketAddr,
+    relay: Option<Relay<TcpStream, TcpStream>>,
+    termination: Option<Termination>,
+}
+
+impl Handler for RelayHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<RelayHandler>, token: Token, events: EventSet) {
+        if token == LISTENER {
+            let client = self.listener.accept().unwrap().unwrap();
+            let upstream = TcpStream::connect(&self.upstream).unwrap();
+
+            let mut relay = Relay::new(client, CLIENT, upstream, UPSTREAM, 1024);
+            relay.register(event_loop).unwrap();
+
+            event_loop.deregister(&self.listener).unwrap();
+            self.relay = Some(relay);
+            return;
+        }
+
+        let termination = self.relay.as_mut().unwrap().ready(event_loop, token, events);
+
+        if termination.is_some() {
+            self.termination = termination;
+            event_loop.shutdown();
+        }
+    }
+}
+
+#[test]
+pub fn test_relay_echo() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    // Upstream echoes until the relay closes it
+    let upstream = net::TcpListener::bind(&::localhost()).unwrap();
+    let upstream_addr = upstream.local_addr().unwrap();
+
+    let echo = thread::spawn(move || {
+        let (mut sock, _) = upstream.accept().unwrap();
+        let mut buf = [0; 1024];
+
+        loop {
+            match sock.read(&mut buf).unwrap() {
+                0 => return,
+                cnt => sock.write_all(&buf[..cnt]).unwrap(),
+            }
+        }
+    });
+
+    let addr = ::localhost();
+    let listener = TcpListener::bind(&addr).unwrap();
+    event_loop.register(&listener, LISTENER).unwrap();
+
+    let client = thread::spawn(move || {
+        let mut sock = net::TcpStream::connect(&addr).unwrap();
+        let msg: Vec<u8> = (0..64 * 1024).map(|i| i as u8).collect();
+
+        sock.write_all(&msg).unwrap();
+
+        let mut echoed = vec![];
+        let mut buf = [0; 1024];
+
+        while echoed.len() < msg.len() {
+            match sock.read(&mut buf).unwrap() {
+                0 => panic!("relay closed early"),
+                cnt => echoed.extend(buf[..cnt].iter().cloned()),
+            }
+        }
+
+        assert!(echoed == msg);
+    });
+
+    let mut handler = RelayHandler {
+        listener: listener,
+        upstream: upstream_addr,
+        relay: None,
+        termination: None,
+    };
+
+    event_loop.run(&mut handler).unwrap();
+
+    match handler.termination {
+        Some(Termination::ClientClosed) => {}
+        termination => panic!("unexpected termination; termination={:?}", termination),
+    }
+
+    client.join().unwrap();
+
+    // Dropping the relay closes the upstream
+    drop(handler.relay.take());
+    echo.join().unwrap();
+}
@@ -0,0 +1,54 @@
+use mio::*;
+use mio::unix;
+use std::io::Write;
+
+const READER: Token = Token(0);
+
+struct TestHandler {
+    events: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, READER);
+        assert!(events.is_readable());
+        self.events += 1;
+    }
+}
+
+fn poll(event_loop: &mut EventLoop<TestHandler>, handler: &mut TestHandler) -> usize {
+    handler.events = 0;
+    event_loop.run_once_timeout(handler, 100).unwrap();
+    handler.events
+}
+
+#[test]
+pub fn test_reregister_unchanged() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let mut handler = TestHandler { events: 0 };
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    writer.write(b"x").unwrap();
+    event_loop.register_opt(&reader, READER, EventSet::readable(), PollOpt::edge()).unwrap();
+
+    assert_eq!(poll(&mut event_loop, &mut handler), 1);
+    assert_eq!(poll(&mut event_loop, &mut handler), 0);
+
+    // Nothing changed, the edge is not reported again
+    event_loop.reregister(&reader, READER, EventSet::readable(), PollOpt::edge()).unwrap();
+    assert_eq!(poll(&mut event_loop, &mut handler), 0);
+
+    // Re-registering anyway reports the readiness again
+    event_loop.reregister_force(&reader, READER, EventSet::readable(), PollOpt::edge()).unwrap();
+    assert_eq!(poll(&mut event_loop, &mut handler), 1);
+
+    // A paused handle is re-registered with the interest it had
+    event_loop.pause(&reader, READER).unwrap();
+    assert_eq!(poll(&mut event_loop, &mut handler), 0);
+
+    event_loop.reregister(&reader, READER, EventSet::readable(), PollOpt::edge()).unwrap();
+    assert_eq!(poll(&mut event_loop, &mut handler), 1);
+}
@@ -0,0 +1,54 @@
+use mio::*;
+use mio::unix;
+use std::io::Write;
+use std::usize;
+
+struct TestHandler {
+    ready: Vec<Token>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, _: EventSet) {
+        self.ready.push(token);
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_reserved_tokens_rejected() {
+    let mut event_loop: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    for &token in [Token(usize::MAX), Token(MAX_TOKEN.as_usize() + 1)].iter() {
+        assert!(token.is_reserved());
+
+        let err = event_loop.register_opt(&reader, token, EventSet::readable(), PollOpt::level()).unwrap_err();
+
+        match Error::from(err) {
+            Error::ReservedToken(t) => assert_eq!(t, token),
+            err => panic!("unexpected error; err={:?}", err),
+        }
+    }
+
+    let err = event_loop.registrar().set_readiness(Token(usize::MAX), EventSet::readable()).wait().unwrap_err();
+    assert_eq!(err.kind(), ::std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+pub fn test_max_token_is_usable() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    assert!(!MAX_TOKEN.is_reserved());
+    event_loop.register_opt(&reader, MAX_TOKEN, EventSet::readable(), PollOpt::level()).unwrap();
+
+    writer.write(b"ping").unwrap();
+
+    let mut handler = TestHandler { ready: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.ready, vec![MAX_TOKEN]);
+}
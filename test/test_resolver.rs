@@ -0,0 +1,44 @@
+use mio::*;
+use std::net::SocketAddr;
+
+struct TestHandler {
+    resolved: Vec<Resolved>,
+    expected: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = Resolved;
+
+    fn notify(&mut self, event_loop: &mut EventLoop<TestHandler>, msg: Resolved) {
+        self.resolved.push(msg);
+
+        if self.resolved.len() == self.expected {
+            event_loop.shutdown();
+        }
+    }
+}
+
+#[test]
+pub fn test_thread_resolver() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let mut resolver = ThreadResolver::new(2, event_loop.channel()).unwrap();
+
+    resolver.resolve("127.0.0.1", 80, Token(1)).unwrap();
+    resolver.resolve("localhost", 8080, Token(2)).unwrap();
+
+    let mut handler = TestHandler { resolved: vec![], expected: 2 };
+    event_loop.run(&mut handler).unwrap();
+
+    handler.resolved.sort_by(|a, b| a.token.0.cmp(&b.token.0));
+
+    let expected: SocketAddr = "127.0.0.1:80".parse().unwrap();
+    assert_eq!(handler.resolved[0].token, Token(1));
+    assert_eq!(*handler.resolved[0].result.as_ref().unwrap(), vec![expected]);
+
+    assert_eq!(handler.resolved[1].token, Token(2));
+
+    let addrs = handler.resolved[1].result.as_ref().unwrap();
+    assert!(!addrs.is_empty());
+    assert!(addrs.iter().all(|addr| addr.port() == 8080));
+}
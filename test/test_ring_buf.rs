@@ -0,0 +1,84 @@
+use mio::buf::{Buf, MutBuf, RingBuf};
+
+fn read_all(buf: &mut RingBuf) -> Vec<u8> {
+    let mut dst = vec![0; Buf::remaining(buf)];
+    buf.read_slice(&mut dst);
+    dst
+}
+
+#[test]
+pub fn test_ring_buf_fixed() {
+    let mut buf = RingBuf::new(6);
+
+    assert_eq!(buf.capacity(), 8);
+    assert_eq!(buf.write_slice(b"hello world"), 8);
+    assert!(buf.is_full());
+    assert_eq!(read_all(&mut buf), b"hello wo");
+}
+
+#[test]
+pub fn test_ring_buf_grows() {
+    let mut buf = RingBuf::growable(4, 16);
+
+    // Wrap the cursors around before growing
+    buf.write_slice(b"abc");
+    Buf::advance(&mut buf, 2);
+
+    assert_eq!(buf.write_slice(b"defghijklmnopqrstuvwxyz"), 15);
+    assert_eq!(buf.capacity(), 16);
+    assert!(!MutBuf::has_remaining(&buf));
+    assert_eq!(read_all(&mut buf), b"cdefghijklmnopqr");
+}
+
+#[test]
+pub fn test_ring_buf_grow_keeps_mark() {
+    let mut buf = RingBuf::growable(4, 8);
+
+    buf.write_slice(b"abcd");
+    Buf::advance(&mut buf, 1);
+    buf.mark();
+    Buf::advance(&mut buf, 2);
+
+    buf.write_slice(b"ef");
+    assert_eq!(buf.capacity(), 8);
+    assert_eq!(read_all(&mut buf), b"def");
+
+    buf.reset();
+    assert_eq!(read_all(&mut buf), b"bcdef");
+}
+
+#[test]
+pub fn test_ring_buf_reserve_and_shrink() {
+    let mut buf = RingBuf::new(4);
+
+    buf.write_slice(b"abc");
+    buf.reserve(10);
+
+    assert_eq!(buf.capacity(), 16);
+    assert_eq!(buf.write_slice(b"defghijklm"), 10);
+
+    Buf::advance(&mut buf, 11);
+    buf.shrink_to_fit();
+
+    assert_eq!(buf.capacity(), 4);
+    assert_eq!(read_all(&mut buf), b"lm");
+}
+
+#[test]
+pub fn test_ring_buf_windows() {
+    let mut buf = RingBuf::new(8);
+
+    buf.write_slice(b"abcdef");
+    Buf::advance(&mut buf, 4);
+
+    {
+        let (first, second) = buf.writable();
+        assert_eq!((first.len(), second.len()), (2, 4));
+    }
+
+    buf.write_slice(b"ghij");
+
+    let (first, second) = buf.readable();
+    assert_eq!(first, b"efgh");
+    assert_eq!(second, b"ij");
+}
@@ -0,0 +1,55 @@
+use mio::*;
+use mio::unix::{self, PipeReader};
+use std::io::{Read, Write};
+
+const PIPE: Token = Token(0);
+
+struct TestHandler {
+    reader: PipeReader,
+    log: Vec<&'static str>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = &'static str;
+    type Message = &'static str;
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, _: Token, _: EventSet) {
+        let mut buf = [0; 16];
+        self.reader.read(&mut buf).unwrap();
+
+        self.log.push("ready");
+        event_loop.deregister(&self.reader).unwrap();
+        event_loop.channel().send("msg").unwrap();
+    }
+
+    fn notify(&mut self, event_loop: &mut EventLoop<TestHandler>, msg: &'static str) {
+        self.log.push(msg);
+        event_loop.timeout_ms("timeout", 10).unwrap();
+    }
+
+    fn timeout(&mut self, _: &mut EventLoop<TestHandler>, timeout: &'static str) {
+        self.log.push(timeout);
+    }
+}
+
+#[test]
+pub fn test_run_until_idle() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    writer.write_all(b"hello").unwrap();
+    event_loop.register(&reader, PIPE).unwrap();
+    assert!(!event_loop.is_idle());
+
+    let mut handler = TestHandler { reader: reader, log: vec![] };
+
+    // Each step keeps the loop busy until the timeout has fired
+    event_loop.run_until_idle(&mut handler).unwrap();
+
+    assert_eq!(handler.log, vec!["ready", "msg", "timeout"]);
+    assert!(event_loop.is_idle());
+
+    // Returns right away when already idle
+    event_loop.run_until_idle(&mut handler).unwrap();
+    assert_eq!(3, handler.log.len());
+}
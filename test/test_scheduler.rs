@@ -0,0 +1,80 @@
+use mio::*;
+use std::thread;
+
+struct TestHandler {
+    fired: Vec<usize>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = usize;
+    type Message = ();
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, token: usize) {
+        self.fired.push(token);
+
+        if self.fired.len() == 40 {
+            event_loop.shutdown();
+        }
+    }
+}
+
+#[test]
+pub fn test_schedule_from_threads() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let scheduler = event_loop.scheduler();
+
+    let threads: Vec<_> = (0..4).map(|i| {
+        let scheduler = scheduler.clone();
+
+        thread::spawn(move || {
+            for j in 0..10 {
+                scheduler.timeout_ms(i * 10 + j, 10).unwrap();
+            }
+        })
+    }).collect();
+
+    let mut handler = TestHandler { fired: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    for th in threads {
+        th.join().unwrap();
+    }
+
+    handler.fired.sort();
+    assert_eq!(handler.fired, (0..40).collect::<Vec<usize>>());
+}
+
+#[test]
+pub fn test_schedule_honors_delay() {
+    let clock = MockClock::new(0);
+    let config = EventLoopConfig {
+        clock: ::std::sync::Arc::new(clock.clone()),
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let scheduler = event_loop.scheduler();
+    let mut handler = TestHandler { fired: vec![] };
+
+    scheduler.timeout_ms(1, 500).unwrap();
+
+    clock.advance_ms(300);
+    event_loop.run_once_timeout(&mut handler, 0).unwrap();
+    assert!(handler.fired.is_empty());
+
+    clock.advance_ms(300);
+    event_loop.run_once_timeout(&mut handler, 0).unwrap();
+    assert_eq!(handler.fired, [1]);
+}
+
+#[test]
+pub fn test_schedule_after_drop() {
+    let mut event_loop: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    let scheduler = event_loop.scheduler();
+    drop(event_loop);
+
+    match scheduler.timeout_ms(1, 0) {
+        Err(NotifyError::Closed(Some(1))) => {}
+        res => panic!("unexpected result; res={:?}", res),
+    }
+}
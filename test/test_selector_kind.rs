@@ -0,0 +1,66 @@
+use mio::*;
+use mio::unix;
+use std::io::Write;
+use std::usize;
+
+struct TestHandler {
+    reader: unix::PipeReader,
+    msg: Option<u32>,
+    ready: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = u32;
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, Token(1));
+        assert!(events.is_readable());
+
+        self.ready += 1;
+
+        event_loop.deregister(&self.reader).unwrap();
+        event_loop.shutdown();
+    }
+
+    fn notify(&mut self, _: &mut EventLoop<TestHandler>, msg: u32) {
+        self.msg = Some(msg);
+    }
+}
+
+#[test]
+pub fn test_poll_selector_kind() {
+    let config = EventLoopConfig {
+        selector: SelectorKind::Poll,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    event_loop.register(&reader, Token(1)).unwrap();
+    event_loop.channel().send(123).unwrap();
+
+    let mut handler = TestHandler { reader: reader, msg: None, ready: 0 };
+
+    // Messages wake up the loop through poll(2)
+    event_loop.run_once_timeout(&mut handler, usize::MAX).unwrap();
+    assert_eq!(handler.msg, Some(123));
+
+    writer.write_all(b"hello").unwrap();
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.ready, 1);
+}
+
+#[test]
+pub fn test_native_selector_kind() {
+    let mut poll = Poll::with_kind(SelectorKind::Native).unwrap();
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    poll.register(&reader, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    writer.write_all(b"hello").unwrap();
+
+    assert_eq!(poll.poll(1_000).unwrap(), 1);
+    assert_eq!(poll.event(0).token, Token(0));
+}
@@ -0,0 +1,27 @@
+use mio::*;
+use mio::unix;
+use std::io::Read;
+use std::thread;
+
+#[test]
+pub fn test_set_nonblocking_pipe() {
+    let (mut reader, writer) = unix::pipe().unwrap();
+
+    reader.set_nonblocking(false).unwrap();
+
+    let handle = thread::spawn(move || {
+        ::sleep_ms(100);
+        (&writer).try_write(b"hello").unwrap();
+        writer
+    });
+
+    // Blocks until the write
+    let mut dst = [0; 5];
+    assert_eq!(reader.read(&mut dst).unwrap(), 5);
+    assert_eq!(&dst, b"hello");
+
+    let _writer = handle.join().unwrap();
+
+    reader.set_nonblocking(true).unwrap();
+    assert_eq!(reader.try_read(&mut dst).unwrap(), None);
+}
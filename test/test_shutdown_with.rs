@@ -0,0 +1,29 @@
+use mio::*;
+use std::io;
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, _: ()) {
+        event_loop.shutdown_with(io::Error::new(io::ErrorKind::Other, "first"));
+        event_loop.shutdown_with(io::Error::new(io::ErrorKind::Other, "second"));
+    }
+}
+
+#[test]
+pub fn test_shutdown_with() {
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.timeout_ms((), 10).unwrap();
+
+    let err = event_loop.run(&mut TestHandler).unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::Other);
+    assert_eq!(format!("{}", err), "first");
+    assert!(!event_loop.is_running());
+
+    // The error is only reported once
+    event_loop.run_once(&mut TestHandler).unwrap();
+}
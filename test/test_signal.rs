@@ -0,0 +1,60 @@
+use mio::*;
+use mio::signal::{self, Signal};
+use std::process::Command;
+
+struct TestHandler {
+    signals: Vec<Signal>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn signal(&mut self, event_loop: &mut EventLoop<TestHandler>, signal: Signal) {
+        self.signals.push(signal);
+
+        if signal == signal::SIGUSR2 {
+            event_loop.shutdown();
+        }
+    }
+
+    fn timeout(&mut self, _: &mut EventLoop<TestHandler>, _: ()) {
+        panic!("signals never delivered");
+    }
+}
+
+fn raise(signal: Signal) {
+    let pid = unsafe { getpid() };
+    let status = Command::new("kill")
+        .arg(format!("-{}", signal))
+        .arg(format!("{}", pid))
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}
+
+extern {
+    fn getpid() -> i32;
+}
+
+#[test]
+pub fn test_signal() {
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.register_signals(&[signal::SIGUSR1]).unwrap();
+    event_loop.register_signals(&[signal::SIGUSR2]).unwrap();
+
+    // Only one event loop can handle signals
+    let mut other: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    assert!(other.register_signals(&[signal::SIGUSR1]).is_err());
+
+    event_loop.timeout_ms((), 5_000).unwrap();
+
+    raise(signal::SIGUSR1);
+    raise(signal::SIGUSR2);
+
+    let mut handler = TestHandler { signals: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.signals, vec![signal::SIGUSR1, signal::SIGUSR2]);
+}
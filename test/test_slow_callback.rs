@@ -0,0 +1,55 @@
+use {sleep_ms};
+use mio::*;
+
+struct TestHandler {
+    slow: Vec<(Callback, u64)>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = u32;
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, _: Token, _: EventSet) {
+        sleep_ms(30);
+    }
+
+    fn notify(&mut self, _: &mut EventLoop<TestHandler>, _: u32) {
+        // Fast enough to go unreported
+    }
+
+    fn slow_callback(&mut self, _: &mut EventLoop<TestHandler>, callback: Callback, elapsed_ns: u64) {
+        self.slow.push((callback, elapsed_ns));
+    }
+}
+
+#[test]
+pub fn test_slow_callback() {
+    let config = EventLoopConfig {
+        slow_callback_ms: Some(20),
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let selector = event_loop.test_selector();
+    let mut handler = TestHandler { slow: vec![] };
+
+    event_loop.channel().send(1).unwrap();
+    selector.set_ready(Token(4), EventSet::readable());
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.slow.len(), 1);
+    assert_eq!(handler.slow[0].0, Callback::Ready(Token(4), EventSet::readable()));
+    assert!(handler.slow[0].1 >= 20_000_000);
+}
+
+#[test]
+pub fn test_slow_callback_disabled() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let selector = event_loop.test_selector();
+    let mut handler = TestHandler { slow: vec![] };
+
+    selector.set_ready(Token(4), EventSet::readable());
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert!(handler.slow.is_empty());
+}
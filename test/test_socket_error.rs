@@ -0,0 +1,93 @@
+use mio::*;
+use mio::tcp::*;
+use super::localhost;
+use std::io::ErrorKind;
+
+const CLIENT: Token = Token(0);
+
+struct TestHandler {
+    errors: Vec<ErrorKind>,
+}
+
+struct ReadyHandler {
+    events: Vec<EventSet>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, _: Token, events: EventSet) {
+        panic!("expected the error hook to be invoked; events={:?}", events);
+    }
+
+    fn error(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, events: EventSet, err: ::std::io::Error) {
+        assert_eq!(token, CLIENT);
+        assert!(events.is_error(), "actual={:?}", events);
+
+        self.errors.push(err.kind());
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_socket_error() {
+    debug!("Starting TEST_SOCKET_ERROR");
+    let mut config = EventLoopConfig::default();
+    config.capture_errors = true;
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+
+    // Nothing is listening on this port, so the connect will be refused
+    let addr = localhost();
+
+    let (sock, _) = TcpSocket::v4().unwrap()
+        .connect(&addr).unwrap();
+
+    event_loop.register_opt(&sock, CLIENT, EventSet::all(), PollOpt::edge()).unwrap();
+
+    let mut handler = TestHandler { errors: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.errors, vec![ErrorKind::ConnectionRefused]);
+
+    // The error has been consumed by the selector
+    assert!(sock.take_socket_error().is_ok());
+}
+
+impl Handler for ReadyHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<ReadyHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, CLIENT);
+
+        self.events.push(events);
+        event_loop.shutdown();
+    }
+
+    fn error(&mut self, _: &mut EventLoop<ReadyHandler>, _: Token, events: EventSet, err: ::std::io::Error) {
+        panic!("errors are not captured by default; events={:?}; err={:?}", events, err);
+    }
+}
+
+#[test]
+pub fn test_socket_error_not_captured() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let addr = localhost();
+
+    let (sock, _) = TcpSocket::v4().unwrap()
+        .connect(&addr).unwrap();
+
+    event_loop.register_opt(&sock, CLIENT, EventSet::all(), PollOpt::edge()).unwrap();
+
+    let mut handler = ReadyHandler { events: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    assert_eq!(handler.events.len(), 1);
+
+    // The error is left on the socket
+    let err = sock.take_socket_error().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+}
@@ -0,0 +1,90 @@
+use mio::*;
+use mio::udp::UdpSocket;
+use std::str::FromStr;
+
+const LISTENER: Token = Token(1);
+const CONN: Token = Token(2);
+
+struct TestHandler {
+    sock: Option<UdpSocket>,
+    events: Vec<Token>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, _: EventSet) {
+        self.events.push(token);
+
+        if token == LISTENER {
+            // A new connection lands on the token of a closed one
+            let sock = UdpSocket::bound(&FromStr::from_str("127.0.0.1:0").unwrap()).unwrap();
+            event_loop.register_opt(&sock, CONN, EventSet::readable(), PollOpt::edge()).unwrap();
+            self.sock = Some(sock);
+        }
+    }
+}
+
+#[test]
+pub fn test_stale_events() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let selector = event_loop.test_selector();
+    let mut handler = TestHandler { sock: None, events: vec![] };
+
+    // Polled before `CONN` is registered again
+    selector.set_ready(LISTENER, EventSet::readable());
+    selector.set_ready(CONN, EventSet::readable());
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.events, vec![LISTENER]);
+
+    // Events polled afterwards are for the new handle
+    selector.set_ready(CONN, EventSet::readable());
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.events, vec![LISTENER, CONN]);
+}
+
+struct DeregisterHandler {
+    sock: UdpSocket,
+    events: Vec<Token>,
+}
+
+impl Handler for DeregisterHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<DeregisterHandler>, token: Token, events: EventSet) {
+        assert!(events != EventSet::none());
+        self.events.push(token);
+
+        if token == LISTENER {
+            event_loop.deregister(&self.sock).unwrap();
+        }
+    }
+}
+
+#[test]
+pub fn test_deregistered_events() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let selector = event_loop.test_selector();
+
+    let sock = UdpSocket::bound(&FromStr::from_str("127.0.0.1:0").unwrap()).unwrap();
+    event_loop.register_opt(&sock, CONN, EventSet::readable(), PollOpt::edge()).unwrap();
+
+    let mut handler = DeregisterHandler { sock: sock, events: vec![] };
+
+    // `CONN` is deregistered by the callback for `LISTENER`
+    selector.set_ready(LISTENER, EventSet::readable());
+    selector.set_ready(CONN, EventSet::readable());
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.events, vec![LISTENER]);
+
+    // Empty events are not dispatched
+    selector.set_ready(LISTENER, EventSet::none());
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.events, vec![LISTENER]);
+}
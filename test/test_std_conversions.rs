@@ -0,0 +1,75 @@
+use super::localhost;
+use mio::*;
+use mio::buf::MutSliceBuf;
+use mio::tcp::{Shutdown, TcpListener, TcpStream};
+use mio::udp::UdpSocket;
+use std::io::{Read, Write};
+use std::net;
+use std::thread;
+
+const SERVER: Token = Token(0);
+
+#[test]
+pub fn test_std_tcp_conversions() {
+    let addr = localhost();
+    let mut poll = Poll::new().unwrap();
+
+    // A std listener switched to non-blocking mode
+    let listener = TcpListener::from_listener(net::TcpListener::bind(&addr).unwrap()).unwrap();
+    assert!(listener.accept().unwrap().is_none());
+
+    poll.register(&listener, SERVER, EventSet::readable(), PollOpt::level()).unwrap();
+
+    let mut client = net::TcpStream::connect(&addr).unwrap();
+    assert_eq!(poll.poll(1_000).unwrap(), 1);
+
+    let stream = listener.accept().unwrap().unwrap();
+    let mut dst = [0; 5];
+    assert_eq!((&stream).try_read(&mut dst).unwrap(), None);
+
+    // Switched back to blocking mode, the read waits for the write
+    let mut stream = stream.into_stream().unwrap();
+
+    let handle = thread::spawn(move || {
+        ::sleep_ms(100);
+        client.write(b"hello").unwrap();
+        client
+    });
+
+    assert_eq!(stream.read(&mut dst).unwrap(), 5);
+    assert_eq!(&dst, b"hello");
+
+    // The listener is deregistered on conversion
+    let listener = listener.into_listener().unwrap();
+    let _client = handle.join().unwrap();
+    let _other = net::TcpStream::connect(&addr).unwrap();
+    assert_eq!(poll.poll(100).unwrap(), 0);
+
+    let _ = listener.accept().unwrap();
+
+    // And the other way around for a stream
+    let stream = TcpStream::from_stream(stream).unwrap();
+    assert_eq!((&stream).try_read(&mut dst).unwrap(), None);
+}
+
+#[test]
+pub fn test_std_udp_conversions() {
+    let addr = localhost();
+
+    let socket = UdpSocket::from_socket(net::UdpSocket::bind(&addr).unwrap()).unwrap();
+    let mut dst = [0; 5];
+    assert!(socket.recv_from(&mut MutSliceBuf::wrap(&mut dst)).unwrap().is_none());
+
+    let socket = socket.into_socket().unwrap();
+    net::UdpSocket::bind(&localhost()).unwrap().send_to(b"hello", &addr).unwrap();
+
+    assert_eq!(socket.recv_from(&mut dst).unwrap().0, 5);
+    assert_eq!(&dst, b"hello");
+}
+
+#[test]
+pub fn test_std_shutdown_conversion() {
+    assert_eq!(Shutdown::from(net::Shutdown::Read), Shutdown::Read);
+    assert_eq!(Shutdown::from(net::Shutdown::Write), Shutdown::Write);
+    assert_eq!(Shutdown::from(net::Shutdown::Both), Shutdown::Both);
+}
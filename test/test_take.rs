@@ -0,0 +1,50 @@
+use mio::*;
+use mio::buf::{Buf, MutBuf, RingBuf, Take};
+use mio::unix;
+
+fn read_all(buf: &mut RingBuf) -> Vec<u8> {
+    let mut dst = vec![0; Buf::remaining(buf)];
+    buf.read_slice(&mut dst);
+    dst
+}
+
+#[test]
+pub fn test_take_mut_buf() {
+    let (reader, writer) = unix::pipe().unwrap();
+
+    (&writer).try_write(b"framenext").unwrap();
+
+    let mut buf = Take::new(RingBuf::new(16), 5);
+
+    assert_eq!(MutBuf::remaining(&buf), 5);
+    assert_eq!((&reader).try_read_buf(&mut buf).unwrap(), Some(5));
+    assert_eq!(buf.limit(), 0);
+    assert!(!MutBuf::has_remaining(&buf));
+
+    let mut buf = buf.into_inner();
+    assert_eq!(read_all(&mut buf), b"frame");
+
+    // The next frame was left in the pipe
+    assert_eq!((&reader).try_read_buf(&mut buf).unwrap(), Some(4));
+    assert_eq!(read_all(&mut buf), b"next");
+}
+
+#[test]
+pub fn test_take_try_read() {
+    let (reader, writer) = unix::pipe().unwrap();
+
+    (&writer).try_write(b"framenext").unwrap();
+
+    let mut frame = (&reader).try_take(5);
+    let mut dst = [0; 16];
+
+    assert_eq!(frame.try_read(&mut dst).unwrap(), Some(5));
+    assert_eq!(&dst[..5], b"frame");
+
+    // The end of the frame reads as EOF
+    assert_eq!(frame.try_read(&mut dst).unwrap(), Some(0));
+
+    frame.set_limit(16);
+    assert_eq!(frame.try_read(&mut dst).unwrap(), Some(4));
+    assert_eq!(frame.try_read(&mut dst).unwrap(), None);
+}
@@ -0,0 +1,78 @@
+use mio::*;
+use std::sync::Arc;
+
+const CONN: Token = Token(1);
+
+#[derive(Debug, PartialEq)]
+enum State {
+    Handshake,
+    Open,
+    Idle,
+    Failed(i32),
+}
+
+struct ConnHandler {
+    state: State,
+}
+
+impl Handler for ConnHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<ConnHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, CONN);
+
+        if self.state == State::Handshake && events.is_writable() {
+            self.state = State::Open;
+            event_loop.timeout_ms((), 30_000).unwrap();
+        }
+    }
+
+    fn error(&mut self, _: &mut EventLoop<ConnHandler>, _: Token, _: EventSet, err: ::std::io::Error) {
+        self.state = State::Failed(err.raw_os_error().unwrap());
+    }
+
+    fn timeout(&mut self, _: &mut EventLoop<ConnHandler>, _: ()) {
+        self.state = State::Idle;
+    }
+}
+
+#[test]
+pub fn test_injected_readiness() {
+    let clock = MockClock::new(0);
+
+    let config = EventLoopConfig {
+        clock: Arc::new(clock.clone()),
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let selector = event_loop.test_selector();
+    let mut handler = ConnHandler { state: State::Handshake };
+
+    // Nothing injected, the tick returns right away
+    event_loop.run_once(&mut handler).unwrap();
+    assert_eq!(handler.state, State::Handshake);
+
+    selector.set_ready(CONN, EventSet::readable());
+    selector.set_ready(CONN, EventSet::writable());
+    assert_eq!(1, selector.pending());
+
+    event_loop.run_once(&mut handler).unwrap();
+    assert_eq!(handler.state, State::Open);
+    assert_eq!(0, selector.pending());
+
+    clock.advance_ms(30_000);
+    event_loop.run_once(&mut handler).unwrap();
+    assert_eq!(handler.state, State::Idle);
+
+    // Cleared readiness is never reported
+    selector.set_error(CONN, EventSet::readable(), 104);
+    selector.clear(CONN);
+    event_loop.run_once(&mut handler).unwrap();
+    assert_eq!(handler.state, State::Idle);
+
+    selector.set_error(CONN, EventSet::readable(), 104);
+    event_loop.run_once(&mut handler).unwrap();
+    assert_eq!(handler.state, State::Failed(104));
+}
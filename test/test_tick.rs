@@ -0,0 +1,54 @@
+use mio::*;
+
+struct TestHandler {
+    calls: Vec<&'static str>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, _: ()) {
+        self.calls.push("timeout");
+        event_loop.shutdown();
+    }
+
+    fn tick(&mut self, _: &mut EventLoop<TestHandler>) {
+        self.calls.push("tick");
+    }
+}
+
+#[test]
+pub fn test_tick() {
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.timeout_ms((), 50).unwrap();
+
+    let mut handler = TestHandler { calls: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    // The tick runs after the dispatch of the iteration that fired the
+    // timeout, even though the loop is shutting down.
+    let n = handler.calls.len();
+    assert!(n >= 2);
+    assert_eq!(&handler.calls[n - 2..], &["timeout", "tick"]);
+
+    // Each iteration produces exactly one tick
+    event_loop.run_once(&mut handler).unwrap();
+    assert_eq!(handler.calls.len(), n + 1);
+    assert_eq!(handler.calls[n], "tick");
+}
+
+#[test]
+pub fn test_tick_sleeps_until_timeout() {
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.timeout_ms((), 450).unwrap();
+
+    let mut handler = TestHandler { calls: vec![] };
+    event_loop.run(&mut handler).unwrap();
+
+    // The loop blocks until the timeout is due instead of waking up on
+    // every timer tick
+    let ticks = handler.calls.iter().filter(|c| **c == "tick").count();
+    assert!(ticks <= 2, "ticks={}", ticks);
+    assert!(handler.calls.contains(&"timeout"));
+}
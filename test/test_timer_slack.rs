@@ -0,0 +1,50 @@
+use mio::*;
+use std::sync::Arc;
+
+struct TestHandler {
+    fired: Vec<&'static str>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = &'static str;
+    type Message = ();
+
+    fn timeout(&mut self, _: &mut EventLoop<TestHandler>, name: &'static str) {
+        self.fired.push(name);
+    }
+}
+
+#[test]
+pub fn test_timer_slack() {
+    let clock = MockClock::new(0);
+
+    let config = EventLoopConfig {
+        clock: Arc::new(clock.clone()),
+        timer_slack_ms: 1_000,
+        .. EventLoopConfig::default()
+    };
+
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let mut handler = TestHandler { fired: vec![] };
+
+    // Both are postponed to the same wakeup, one second in
+    event_loop.timeout_ms("first", 150).unwrap();
+    event_loop.timeout_ms("second", 700).unwrap();
+
+    // Without slack, the deadline is kept
+    event_loop.timeout_ms_with_slack("exact", 150, 0).unwrap();
+
+    clock.advance_ms(200);
+    assert_eq!(1, event_loop.fire_timeouts(&mut handler));
+    assert_eq!(handler.fired, ["exact"]);
+
+    clock.advance_ms(700);
+    assert_eq!(0, event_loop.fire_timeouts(&mut handler));
+
+    clock.advance_ms(100);
+    assert_eq!(2, event_loop.fire_timeouts(&mut handler));
+
+    // Timeouts due on the same tick fire in no particular order
+    handler.fired[1..].sort();
+    assert_eq!(handler.fired, ["exact", "first", "second"]);
+}
@@ -0,0 +1,101 @@
+use mio::*;
+
+#[test]
+pub fn test_token_map_tiers() {
+    let mut map = TokenMap::with_dense_limit(1024);
+
+    assert_eq!(map.insert(Token(3), "dense"), None);
+    assert_eq!(map.insert(Token(1 << 20), "sparse"), None);
+    assert_eq!(map.insert(Token(3), "replaced"), Some("dense"));
+
+    assert_eq!(map.get(Token(3)), Some(&"replaced"));
+    assert_eq!(map.get(Token(1 << 20)), Some(&"sparse"));
+    assert_eq!(map.get(Token(2)), None);
+    assert_eq!(map.get(Token(512)), None);
+    assert_eq!(map.len(), 2);
+
+    // The dense range only grows up to the largest token in it
+    let usage = map.usage();
+    assert_eq!(usage.dense_len, 1);
+    assert!(usage.dense_capacity >= 4 && usage.dense_capacity < 1024);
+    assert_eq!(usage.sparse_len, 1);
+    assert!(usage.bytes > 0);
+
+    let tokens: Vec<Token> = map.iter().map(|(token, _)| token).collect();
+    assert_eq!(tokens, vec![Token(3), Token(1 << 20)]);
+
+    assert_eq!(map.remove(Token(1 << 20)), Some("sparse"));
+    assert_eq!(map.remove(Token(3)), Some("replaced"));
+    assert_eq!(map.remove(Token(3)), None);
+    assert!(map.is_empty());
+
+    map.shrink_to_fit();
+    assert_eq!(map.usage().dense_capacity, 0);
+}
+
+#[test]
+pub fn test_token_map_iter_mut() {
+    let mut map = TokenMap::with_dense_limit(16);
+
+    for i in 0..32 {
+        map.insert(Token(i), i);
+    }
+
+    for (token, value) in map.iter_mut() {
+        assert_eq!(token.as_usize(), *value);
+        *value += 1;
+    }
+
+    let mut values: Vec<usize> = map.iter().map(|(_, &value)| value).collect();
+    values.sort();
+    assert_eq!(values, (1..33).collect::<Vec<usize>>());
+
+    map.clear();
+    assert!(map.is_empty());
+    assert!(!map.contains(Token(0)));
+}
+
+#[test]
+pub fn test_token_map_bounded_growth() {
+    let mut map = TokenMap::new();
+
+    // A single large token does not allocate the dense range up to it
+    map.insert(Token(1 << 21), ());
+    assert_eq!(map.usage().dense_capacity, 0);
+    assert_eq!(map.usage().sparse_len, 1);
+
+    // Tokens inserted in order stay dense
+    for i in 0..4096 {
+        map.insert(Token(i), ());
+    }
+
+    assert_eq!(map.usage().dense_len, 4096);
+    assert!(map.contains(Token(1 << 21)));
+}
+
+#[test]
+pub fn test_token_map_slab_tokens() {
+    let mut slab = TokenSlab::new();
+    let mut map = TokenMap::with_index(1024, util::slab_index);
+
+    // The slot is reused, with a token beyond the dense limit
+    let first = slab.insert(());
+    slab.remove(first);
+    let second = slab.insert(());
+    assert!(second.as_usize() >= 1024);
+
+    map.insert(second, "second");
+    assert_eq!(map.usage().dense_len, 1);
+    assert_eq!(map.get(second), Some(&"second"));
+    assert_eq!(map.get(first), None);
+
+    // A stale token left in the map does not alias the live one
+    map.insert(first, "first");
+    assert_eq!(map.get(first), Some(&"first"));
+    assert_eq!(map.get(second), Some(&"second"));
+    assert_eq!(map.len(), 2);
+
+    assert_eq!(map.remove(second), Some("second"));
+    assert_eq!(map.remove(first), Some("first"));
+    assert!(map.is_empty());
+}
@@ -0,0 +1,157 @@
+use mio::*;
+use mio::unix::{self, PipeReader};
+use std::io::Write;
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[test]
+pub fn test_token_slab_generations() {
+    let mut slab = TokenSlab::new();
+
+    let first = slab.insert("first");
+    assert_eq!(slab.get(first), Some(&"first"));
+    assert_eq!(slab.remove(first), Some("first"));
+
+    // The slot is reused, the old token does not resolve to the new value
+    let second = slab.insert("second");
+    assert!(first != second);
+    assert_eq!(slab.get(first), None);
+    assert_eq!(slab.remove(first), None);
+    assert_eq!(slab.get(second), Some(&"second"));
+    assert_eq!(slab.len(), 1);
+
+    assert!(!second.is_reserved());
+}
+
+struct Conn(Rc<Cell<bool>>);
+
+impl Drop for Conn {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+#[test]
+pub fn test_token_slab_drops_on_remove() {
+    let dropped = Rc::new(Cell::new(false));
+    let mut slab = TokenSlab::new();
+
+    let token = slab.insert(Conn(dropped.clone()));
+    assert!(!dropped.get());
+
+    drop(slab.remove(token));
+    assert!(dropped.get());
+    assert!(slab.is_empty());
+}
+
+struct TestHandler {
+    conns: TokenSlab<PipeReader>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, token: Token, _: EventSet) {
+        // Looked up straight from the token, then deregistered and dropped
+        let conn = self.conns.remove(token).unwrap();
+        event_loop.deregister(&conn).unwrap();
+
+        if self.conns.is_empty() {
+            event_loop.shutdown();
+        }
+    }
+}
+
+#[test]
+pub fn test_token_slab_dispatch() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let mut handler = TestHandler { conns: TokenSlab::new() };
+    let mut writers = vec![];
+
+    for _ in 0..3 {
+        let (reader, mut writer) = unix::pipe().unwrap();
+        writer.write(b"x").unwrap();
+        writers.push(writer);
+
+        let token = handler.conns.insert(reader);
+        event_loop.register(handler.conns.get(token).unwrap(), token).unwrap();
+    }
+
+    event_loop.run(&mut handler).unwrap();
+    assert!(handler.conns.is_empty());
+}
+
+#[test]
+pub fn test_token_slab_arc() {
+    let dropped = Rc::new(Cell::new(false));
+    let mut slab = TokenSlab::new();
+
+    let (token, shared) = slab.insert_arc(Conn(dropped.clone()));
+
+    let found = slab.get_arc(token).unwrap();
+    assert!(&*found as *const Conn == &*shared as *const Conn);
+    drop(found);
+
+    // The value outlives its removal from the slab as long as it is shared
+    slab.remove(token);
+    assert!(slab.get_arc(token).is_none());
+    assert!(!dropped.get());
+
+    drop(shared);
+    assert!(dropped.get());
+}
+
+struct Unused;
+
+impl Handler for Unused {
+    type Timeout = ();
+    type Message = ();
+}
+
+#[test]
+pub fn test_token_slab_deregister() {
+    let mut event_loop: EventLoop<Unused> = EventLoop::new().unwrap();
+    let mut slab = TokenSlab::new();
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    let token = slab.insert(reader);
+    event_loop.register(&slab[token], token).unwrap();
+
+    let reader = slab.deregister(&mut event_loop, token).unwrap().unwrap();
+    assert!(slab.is_empty());
+    assert!(slab.deregister(&mut event_loop, token).unwrap().is_none());
+
+    // The handle is no longer registered, so it can be registered again
+    event_loop.register(&reader, Token(0)).unwrap();
+}
+
+#[test]
+pub fn test_token_slab_sweep() {
+    let mut slab = TokenSlab::with_capacity(1);
+    let first = slab.insert_with(|token| (token, 0));
+
+    // Tokens keep resolving as the storage grows
+    let tokens: Vec<Token> = (1..100).map(|i| slab.insert_with(|token| (token, i))).collect();
+    assert_eq!(slab[first], (first, 0));
+    assert_eq!(slab[tokens[98]], (tokens[98], 99));
+
+    slab.remove(tokens[0]);
+
+    for (token, value) in slab.iter_mut() {
+        assert_eq!(token, value.0);
+        value.1 += 1;
+    }
+
+    assert_eq!(slab.iter().count(), 99);
+    assert!(slab.iter().all(|(_, &(_, i))| i > 0));
+
+    let capacity = slab.capacity();
+    let drained: Vec<Token> = slab.drain().map(|(token, _)| token).collect();
+
+    assert_eq!(drained.len(), 99);
+    assert_eq!(drained[0], first);
+    assert!(slab.is_empty());
+    assert!(!slab.contains(first));
+    assert_eq!(slab.capacity(), capacity);
+}
@@ -0,0 +1,99 @@
+use mio::*;
+use mio::unix;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+const PIPE: Token = Token(0);
+
+struct Recorder {
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl Recorder {
+    fn push(&self, event: String) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+impl Trace for Recorder {
+    fn register(&mut self, token: Token, _: EventSet, _: PollOpt, res: &io::Result<()>) {
+        self.push(format!("register {:?} {}", token, res.is_ok()));
+    }
+
+    fn deregister(&mut self, res: &io::Result<()>) {
+        self.push(format!("deregister {}", res.is_ok()));
+    }
+
+    fn poll_exit(&mut self, res: &io::Result<usize>) {
+        if let Ok(n) = *res {
+            if n > 0 {
+                self.push(format!("poll {}", n));
+            }
+        }
+    }
+
+    fn dispatch(&mut self, token: Token, _: EventSet) {
+        self.push(format!("dispatch {:?}", token));
+    }
+
+    fn timeout(&mut self) {
+        self.push("timeout".to_string());
+    }
+
+    fn notify(&mut self) {
+        self.push("notify".to_string());
+    }
+}
+
+struct TestHandler {
+    reader: unix::PipeReader,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<TestHandler>, _: Token, _: EventSet) {
+        event_loop.deregister(&self.reader).unwrap();
+        event_loop.channel().send(()).unwrap();
+    }
+
+    fn notify(&mut self, event_loop: &mut EventLoop<TestHandler>, _: ()) {
+        event_loop.timeout_ms((), 10).unwrap();
+    }
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<TestHandler>, _: ()) {
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+pub fn test_trace() {
+    let events = Arc::new(Mutex::new(vec![]));
+
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.set_trace(Recorder { events: events.clone() });
+
+    let (reader, mut writer) = unix::pipe().unwrap();
+    writer.write_all(b"hello").unwrap();
+    event_loop.register(&reader, PIPE).unwrap();
+
+    let mut handler = TestHandler { reader: reader };
+    event_loop.run(&mut handler).unwrap();
+
+    let events = events.lock().unwrap();
+
+    // Polls waking up for the notify pipe add "poll" entries in between
+    let events: Vec<&str> = events.iter()
+        .map(|e| &e[..])
+        .filter(|e| !e.starts_with("poll"))
+        .collect();
+
+    assert_eq!(events, vec![
+        "register Token(0) true",
+        "dispatch Token(0)",
+        "deregister true",
+        "notify",
+        "timeout",
+    ]);
+}
@@ -0,0 +1,107 @@
+use super::localhost;
+use mio::*;
+use mio::tcp::{TcpListener, TcpStream};
+use std::io::{self, Read, Write};
+
+const CLIENT: Token = Token(0);
+
+// A transport that reads a one byte greeting before it can be written to,
+// like a TLS client waiting for the server's handshake
+struct Greeted {
+    stream: TcpStream,
+    greeted: bool,
+}
+
+impl Read for Greeted {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Greeted {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Evented for Greeted {
+    fn register(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        self.stream.register(selector, token, interest, opts)
+    }
+
+    fn reregister(&self, selector: &mut Selector, token: Token, interest: EventSet, opts: PollOpt) -> io::Result<()> {
+        self.stream.reregister(selector, token, interest, opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> io::Result<()> {
+        self.stream.deregister(selector)
+    }
+}
+
+impl Transport for Greeted {
+    fn interest(&self, interest: EventSet) -> EventSet {
+        if self.greeted { interest } else { EventSet::readable() }
+    }
+
+    fn readiness(&mut self, events: EventSet) -> EventSet {
+        if self.greeted {
+            return events;
+        }
+
+        let mut greeting = [0; 1];
+
+        if events.is_readable() && self.stream.try_read(&mut greeting).unwrap() == Some(1) {
+            self.greeted = true;
+        }
+
+        EventSet::none()
+    }
+}
+
+fn poll_transport<T: Transport>(poll: &mut Poll, transport: &mut T) -> EventSet {
+    assert_eq!(poll.poll(1_000).unwrap(), 1);
+    transport.readiness(poll.event(0).kind)
+}
+
+#[test]
+pub fn test_transport_waits_for_greeting() {
+    let addr = localhost();
+    let mut poll = Poll::new().unwrap();
+
+    let listener = TcpListener::bind(&addr).unwrap();
+    let stream = TcpStream::connect(&addr).unwrap();
+
+    let mut client = Greeted { stream: stream, greeted: false };
+
+    // Waits to read the greeting, although the socket is writable
+    let interest = client.interest(EventSet::writable());
+    assert_eq!(interest, EventSet::readable());
+    poll.register(&client, CLIENT, interest, PollOpt::level()).unwrap();
+    assert_eq!(poll.poll(100).unwrap(), 0);
+
+    ::sleep_ms(100);
+    let mut server = listener.accept().unwrap().unwrap();
+
+    server.write(b"!").unwrap();
+    assert_eq!(poll_transport(&mut poll, &mut client), EventSet::none());
+
+    // Greeted, the application's interest is passed through
+    let interest = client.interest(EventSet::writable());
+    assert_eq!(interest, EventSet::writable());
+    poll.reregister(&client, CLIENT, interest, PollOpt::level()).unwrap();
+    assert!(poll_transport(&mut poll, &mut client).is_writable());
+}
+
+#[test]
+pub fn test_plain_transport() {
+    let addr = localhost();
+    let _listener = TcpListener::bind(&addr).unwrap();
+    let mut stream = TcpStream::connect(&addr).unwrap();
+
+    assert_eq!(stream.interest(EventSet::readable()), EventSet::readable());
+    assert_eq!(stream.readiness(EventSet::writable()), EventSet::writable());
+}
@@ -0,0 +1,28 @@
+use mio::*;
+use mio::unix;
+use std::os::unix::io::AsRawFd;
+
+#[test]
+pub fn test_try_clone_pipe() {
+    let (reader, writer) = unix::pipe().unwrap();
+
+    let reader2 = reader.try_clone().unwrap();
+    let writer2 = writer.try_clone().unwrap();
+
+    assert!(reader2.as_raw_fd() != reader.as_raw_fd());
+
+    (&writer2).try_write(b"hello").unwrap();
+    drop(writer2);
+
+    let mut dst = [0; 5];
+    assert_eq!((&reader).try_read(&mut dst).unwrap(), Some(5));
+    assert_eq!(&dst, b"hello");
+
+    // The clone is still non-blocking
+    assert_eq!((&reader2).try_read(&mut dst).unwrap(), None);
+
+    // And closing it leaves the original open
+    drop(reader2);
+    (&writer).try_write(b"world").unwrap();
+    assert_eq!((&reader).try_read(&mut dst).unwrap(), Some(5));
+}
@@ -0,0 +1,115 @@
+use mio::*;
+use mio::buf::{Buf, MutBuf, RingBuf};
+use mio::tcp::*;
+use mio::unix;
+use localhost;
+
+#[test]
+pub fn test_try_read_write_pipe() {
+    let (reader, writer) = unix::pipe().unwrap();
+    let mut buf = [0; 5];
+
+    assert_eq!((&reader).try_read(&mut buf).unwrap(), None);
+    assert_eq!((&writer).try_write(b"hello").unwrap(), Some(5));
+    assert_eq!((&reader).try_read(&mut buf).unwrap(), Some(5));
+    assert_eq!(&buf, b"hello");
+
+    drop(writer);
+
+    // EOF
+    assert_eq!((&reader).try_read(&mut buf).unwrap(), Some(0));
+}
+
+#[test]
+pub fn test_try_read_write_tcp() {
+    let addr = localhost();
+    let server = TcpListener::bind(&addr).unwrap();
+    let client = TcpStream::connect(&addr).unwrap();
+
+    let mut accepted = None;
+
+    // The connection is established asynchronously
+    while accepted.is_none() {
+        accepted = server.accept().unwrap();
+    }
+
+    let accepted = accepted.unwrap();
+    let mut buf = [0; 5];
+
+    assert_eq!((&accepted).try_read(&mut buf).unwrap(), None);
+
+    while (&client).try_write(b"hello").unwrap().is_none() {}
+
+    let mut read = None;
+
+    while read.is_none() {
+        read = (&accepted).try_read(&mut buf).unwrap();
+    }
+
+    assert_eq!(read, Some(5));
+    assert_eq!(&buf, b"hello");
+}
+
+// Moves the cursors of `buf` so that its free space wraps around
+fn wrapped_ring() -> RingBuf {
+    let mut buf = RingBuf::new(8);
+
+    buf.write_slice(b"xxxxxx");
+    Buf::advance(&mut buf, 6);
+
+    buf
+}
+
+#[test]
+pub fn test_try_read_buf_wrapped() {
+    let (mut reader, mut writer) = unix::pipe().unwrap();
+    let mut buf = wrapped_ring();
+
+    writer.try_write(b"hello").unwrap();
+
+    // Fills the 2 bytes at the end, then the start of the ring
+    assert_eq!(reader.try_read_buf(&mut buf).unwrap(), Some(5));
+    assert_eq!(Buf::remaining(&buf), 5);
+
+    let mut dst = [0; 5];
+    buf.read_slice(&mut dst);
+    assert_eq!(&dst, b"hello");
+}
+
+#[test]
+pub fn test_try_write_buf_wrapped() {
+    let (mut reader, mut writer) = unix::pipe().unwrap();
+    let mut buf = wrapped_ring();
+
+    buf.write_slice(b"hello");
+
+    assert_eq!(writer.try_write_buf(&mut buf).unwrap(), Some(5));
+    assert!(!Buf::has_remaining(&buf));
+
+    let mut dst = [0; 5];
+    assert_eq!(reader.try_read(&mut dst).unwrap(), Some(5));
+    assert_eq!(&dst, b"hello");
+}
+
+#[test]
+pub fn test_try_read_to_end() {
+    let (reader, writer) = unix::pipe().unwrap();
+    let mut buf = b"prefix ".to_vec();
+
+    let drain = (&reader).try_read_to_end(&mut buf).unwrap();
+    assert_eq!(drain, Drain { count: 0, would_block: true, eof: false });
+
+    let data: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+    (&writer).try_write(&data).unwrap();
+
+    let drain = (&reader).try_read_to_end(&mut buf).unwrap();
+    assert_eq!(drain, Drain { count: 10_000, would_block: true, eof: false });
+    assert_eq!(&buf[..7], b"prefix ");
+    assert_eq!(&buf[7..], &data[..]);
+
+    drop(writer);
+
+    let drain = (&reader).try_read_to_end(&mut buf).unwrap();
+    assert_eq!(drain, Drain { count: 0, would_block: false, eof: true });
+    assert_eq!(buf.len(), 10_007);
+}
@@ -0,0 +1,29 @@
+use mio::*;
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+}
+
+#[test]
+pub fn test_unsupported() {
+    // The poll(2) selector cannot swap the signal mask with the wait
+    let config = EventLoopConfig {
+        selector: SelectorKind::Poll,
+        poll_sigmask: Some(vec![]),
+        .. EventLoopConfig::default()
+    };
+
+    let err = match EventLoop::<TestHandler>::configured(config) {
+        Ok(_) => panic!("the poll(2) selector accepted a signal mask"),
+        Err(e) => e,
+    };
+
+    assert!(Unsupported::is_unsupported(&err));
+    assert_eq!(format!("{}", err), "a signal mask during the wait is not supported by this selector");
+
+    let other = ::std::io::Error::new(::std::io::ErrorKind::Other, "oops");
+    assert!(!Unsupported::is_unsupported(&other));
+}
@@ -0,0 +1,64 @@
+use mio::*;
+
+const CONN: Token = Token(3);
+
+fn handshake_done() -> EventSet {
+    EventSet::user(0)
+}
+
+fn backpressure_cleared() -> EventSet {
+    EventSet::user(7)
+}
+
+struct TestHandler {
+    events: Vec<EventSet>,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, CONN);
+        self.events.push(events);
+    }
+}
+
+#[test]
+pub fn test_user_readiness() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let registrar = event_loop.registrar();
+    let mut handler = TestHandler { events: vec![] };
+
+    registrar.set_readiness(CONN, handshake_done());
+    registrar.set_readiness(CONN, EventSet::readable() | backpressure_cleared());
+    event_loop.run_once(&mut handler).unwrap();
+
+    assert_eq!(handler.events.len(), 1);
+
+    let events = handler.events[0];
+    assert!(events.is_readable());
+    assert!(events.is_user(0));
+    assert!(events.is_user(7));
+    assert!(!events.is_user(1));
+    assert_eq!(events.user_bits(), handshake_done() | backpressure_cleared());
+    assert_eq!(format!("{:?}", events), "Readable | User(0) | User(7)");
+}
+
+#[test]
+pub fn test_user_bits_disjoint() {
+    for n in 0..EventSet::user_count() {
+        let user = EventSet::user(n);
+
+        assert!(!EventSet::all().contains(user));
+        assert!(EventSet::all_user().contains(user));
+    }
+
+    assert_eq!(!EventSet::all(), EventSet::all_user());
+}
+
+#[test]
+#[should_panic]
+pub fn test_user_bit_out_of_range() {
+    EventSet::user(EventSet::user_count());
+}
@@ -0,0 +1,37 @@
+use mio::*;
+use mio::unix;
+use std::io::ErrorKind;
+
+struct TestHandler;
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+}
+
+#[test]
+pub fn test_validate_interest() {
+    let mut event_loop: EventLoop<TestHandler> = EventLoop::new().unwrap();
+    let (reader, _writer) = unix::pipe().unwrap();
+
+    let invalid = [
+        (EventSet::none(), PollOpt::edge()),
+        (EventSet::error() | EventSet::user(0), PollOpt::level()),
+        (EventSet::readable(), PollOpt::empty()),
+        (EventSet::readable(), PollOpt::oneshot()),
+        (EventSet::readable(), PollOpt::edge() | PollOpt::level()),
+    ];
+
+    for &(interest, opts) in invalid.iter() {
+        let err = event_loop.register_opt(&reader, Token(0), interest, opts).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    event_loop.register_opt(&reader, Token(0), EventSet::readable(), PollOpt::edge() | PollOpt::oneshot()).unwrap();
+
+    // Re-registrations may have no interest
+    event_loop.reregister(&reader, Token(0), EventSet::none(), PollOpt::edge()).unwrap();
+
+    let err = event_loop.reregister(&reader, Token(0), EventSet::readable(), PollOpt::empty()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,90 @@
+use mio::*;
+use mio::buf::{Buf, IoVec, IoVecMut, MutBuf, RingBuf, VecBuf, VecMutBuf};
+use mio::unix;
+
+#[test]
+pub fn test_vectored_io_pipe() {
+    let (mut reader, mut writer) = unix::pipe().unwrap();
+
+    let mut a = [0; 3];
+    let mut b = [0; 8];
+
+    assert_eq!(reader.try_readv(&mut [IoVecMut::new(&mut a)]).unwrap(), None);
+
+    let src = [IoVec::new(b"hel"), IoVec::new(b"lo "), IoVec::new(b"world")];
+    assert_eq!(writer.try_writev(&src).unwrap(), Some(11));
+
+    {
+        let mut dst = [IoVecMut::new(&mut a), IoVecMut::new(&mut b)];
+        assert_eq!(reader.try_readv(&mut dst).unwrap(), Some(11));
+    }
+
+    assert_eq!(&a, b"hel");
+    assert_eq!(&b, b"lo world");
+}
+
+#[test]
+pub fn test_vectored_io_ring_buf_regions() {
+    let mut buf = RingBuf::new(8);
+
+    // Wrap the cursors around
+    buf.write_slice(b"xxxxxx");
+    Buf::advance(&mut buf, 6);
+
+    {
+        let mut dst = [IoVecMut::empty(), IoVecMut::empty(), IoVecMut::empty()];
+
+        assert_eq!(buf.mut_bytes_vec(&mut dst), 2);
+        assert_eq!(dst[0].len(), 2);
+        assert_eq!(dst[1].len(), 6);
+    }
+
+    buf.write_slice(b"abcdef");
+
+    let mut dst = [IoVec::empty(), IoVec::empty()];
+
+    assert_eq!(buf.bytes_vec(&mut dst), 2);
+    assert_eq!(&dst[0][..], b"ab");
+    assert_eq!(&dst[1][..], b"cdef");
+
+    // Only as many regions as fit are exposed
+    assert_eq!(buf.bytes_vec(&mut dst[..1]), 1);
+}
+
+#[test]
+pub fn test_vectored_io_ring_buf_single_call() {
+    let (mut reader, mut writer) = unix::pipe().unwrap();
+    let mut buf = RingBuf::new(8);
+
+    // Wrap the cursors around
+    buf.write_slice(b"xxxxxx");
+    Buf::advance(&mut buf, 6);
+
+    (&writer).try_write(b"abcdefghij").unwrap();
+
+    // Both windows of free space are filled by a single read
+    assert_eq!(reader.try_read_buf_vec(&mut buf).unwrap(), Some(8));
+    assert!(!MutBuf::has_remaining(&buf));
+
+    // And both windows of bytes are drained by a single write
+    assert_eq!(writer.try_write_buf_vec(&mut buf).unwrap(), Some(8));
+    assert!(!Buf::has_remaining(&buf));
+
+    let mut dst = [0; 10];
+    assert_eq!((&reader).try_read(&mut dst).unwrap(), Some(10));
+    assert_eq!(&dst, b"ijabcdefgh");
+}
+
+#[test]
+pub fn test_vectored_io_write_slices() {
+    let (reader, mut writer) = unix::pipe().unwrap();
+
+    let slices = [&b"one "[..], &b""[..], &b"two "[..], &b"three"[..]];
+    let written = writer.try_write_slices(slices.iter().cloned()).unwrap().unwrap();
+
+    assert_eq!(written, SlicesWritten { count: 13, slices: 4, offset: 0 });
+
+    let mut dst = [0; 13];
+    assert_eq!((&reader).try_read(&mut dst).unwrap(), Some(13));
+    assert_eq!(&dst, b"one two three");
+}
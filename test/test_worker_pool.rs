@@ -0,0 +1,149 @@
+use mio::*;
+use mio::tcp::*;
+use mio::util::TokenSlab;
+use std::io::{Read, Write};
+use std::net;
+use std::sync::{mpsc, Arc, Mutex};
+use super::localhost;
+
+const SERVER: Token = Token(0);
+const CONNS: usize = 4;
+
+struct Worker {
+    idx: usize,
+    load: WorkerLoad,
+    conns: TokenSlab<TcpStream>,
+    results: mpsc::Sender<(usize, Vec<u8>)>,
+}
+
+impl Handler for Worker {
+    type Timeout = ();
+    type Message = TcpStream;
+
+    fn notify(&mut self, event_loop: &mut EventLoop<Worker>, sock: TcpStream) {
+        let token = self.conns.insert(sock);
+        event_loop.register_opt(&self.conns[token], token, EventSet::readable(), PollOpt::level()).unwrap();
+    }
+
+    fn ready(&mut self, event_loop: &mut EventLoop<Worker>, token: Token, _: EventSet) {
+        let mut buf = [0; 64];
+        let n = match self.conns[token].read(&mut buf) {
+            Ok(n) => n,
+            Err(_) => return,
+        };
+
+        let sock = self.conns.remove(token).unwrap();
+        event_loop.deregister(&sock).unwrap();
+        self.load.release();
+
+        self.results.send((self.idx, buf[..n].to_vec())).unwrap();
+    }
+}
+
+struct Acceptor {
+    srv: TcpListener,
+    pool: WorkerPool<TcpStream>,
+    accepted: usize,
+}
+
+impl Handler for Acceptor {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<Acceptor>, token: Token, _: EventSet) {
+        assert_eq!(token, SERVER);
+
+        while let Some(sock) = self.srv.accept().unwrap() {
+            self.pool.handoff(sock).unwrap();
+            self.accepted += 1;
+        }
+
+        if self.accepted == CONNS {
+            event_loop.shutdown();
+        }
+    }
+}
+
+#[test]
+pub fn test_worker_pool_handoff() {
+    let (tx, rx) = mpsc::channel();
+    let tx = Arc::new(Mutex::new(tx));
+
+    let config = EventLoopConfig {
+        io_poll_timeout_ms: 100,
+        .. EventLoopConfig::default()
+    };
+
+    let pool = WorkerPool::spawn(2, config, Dispatch::RoundRobin, move |idx, load| {
+        Worker {
+            idx: idx,
+            load: load,
+            conns: TokenSlab::new(),
+            results: tx.lock().unwrap().clone(),
+        }
+    }).unwrap();
+
+    assert_eq!(2, pool.len());
+
+    let addr = localhost();
+    let srv = TcpListener::bind(&addr).unwrap();
+
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.register(&srv, SERVER).unwrap();
+
+    let clients: Vec<net::TcpStream> = (0..CONNS).map(|i| {
+        let mut sock = net::TcpStream::connect(&addr).unwrap();
+        sock.write_all(format!("conn {}", i).as_bytes()).unwrap();
+        sock
+    }).collect();
+
+    let mut handler = Acceptor { srv: srv, pool: pool, accepted: 0 };
+    event_loop.run(&mut handler).unwrap();
+
+    let mut per_worker = [0; 2];
+
+    for _ in 0..CONNS {
+        let (idx, data) = rx.recv().unwrap();
+        assert!(data.starts_with(b"conn "));
+        per_worker[idx] += 1;
+    }
+
+    // Round robin spreads the connections evenly
+    assert_eq!(per_worker, [2, 2]);
+    assert_eq!(0, handler.pool.load(0));
+    assert_eq!(0, handler.pool.load(1));
+
+    drop(clients);
+}
+
+struct Idle;
+
+impl Handler for Idle {
+    type Timeout = ();
+    type Message = u32;
+}
+
+#[test]
+pub fn test_worker_pool_least_loaded() {
+    let config = EventLoopConfig {
+        io_poll_timeout_ms: 100,
+        .. EventLoopConfig::default()
+    };
+
+    let mut pool = WorkerPool::spawn(3, config, Dispatch::LeastLoaded, |_, _| Idle).unwrap();
+
+    // Nothing is ever released, so handoffs fill the workers up evenly
+    pool.send_to(0, 0).unwrap();
+    pool.send_to(0, 0).unwrap();
+    pool.send_to(2, 0).unwrap();
+
+    assert_eq!(1, pool.handoff(0).unwrap());
+    assert_eq!(1, pool.handoff(0).unwrap());
+    assert_eq!(2, pool.handoff(0).unwrap());
+
+    assert_eq!(2, pool.load(0));
+    assert_eq!(2, pool.load(1));
+    assert_eq!(2, pool.load(2));
+
+    pool.shutdown().unwrap();
+}
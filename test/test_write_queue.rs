@@ -0,0 +1,123 @@
+use mio::*;
+use mio::buf::ByteBuf;
+use mio::tcp::{TcpListener, TcpStream};
+use mio::unix;
+
+const WRITER: Token = Token(0);
+
+#[test]
+pub fn test_write_queue_watermarks() {
+    let (mut reader, mut writer) = unix::pipe().unwrap();
+    let mut queue = WriteQueue::new(256 * 1024, 64 * 1024);
+
+    assert_eq!(queue.push(ByteBuf::from_slice(&[1; 128 * 1024])), None);
+    assert_eq!(queue.push(ByteBuf::from_slice(&[2; 128 * 1024])), Some(Watermark::High));
+    assert_eq!(queue.push(ByteBuf::from_slice(&[3; 128 * 1024])), None);
+    assert!(queue.is_above());
+
+    // The pipe only takes part of it
+    assert_eq!(queue.flush(&mut writer).unwrap(), None);
+    assert!(queue.len() > 0);
+
+    let mut read = vec![];
+    let mut low = 0;
+
+    while !queue.is_empty() {
+        let mut buf = [0; 64 * 1024];
+
+        loop {
+            match reader.try_read(&mut buf).unwrap() {
+                Some(0) | None => break,
+                Some(cnt) => read.extend(buf[..cnt].iter().cloned()),
+            }
+        }
+
+        if queue.flush(&mut writer).unwrap() == Some(Watermark::Low) {
+            assert!(queue.len() <= 64 * 1024);
+            low += 1;
+        }
+    }
+
+    assert_eq!(low, 1);
+    assert!(!queue.is_above());
+
+    let mut buf = [0; 64 * 1024];
+
+    while let Some(cnt) = reader.try_read(&mut buf).unwrap() {
+        read.extend(buf[..cnt].iter().cloned());
+    }
+
+    assert_eq!(read.len(), 384 * 1024);
+    assert!(read[..128 * 1024].iter().all(|&b| b == 1));
+    assert!(read[256 * 1024..].iter().all(|&b| b == 3));
+}
+
+struct TestHandler {
+    events: usize,
+}
+
+impl Handler for TestHandler {
+    type Timeout = ();
+    type Message = ();
+
+    fn ready(&mut self, _: &mut EventLoop<TestHandler>, token: Token, events: EventSet) {
+        assert_eq!(token, WRITER);
+        assert!(events.is_writable());
+        self.events += 1;
+    }
+}
+
+#[test]
+pub fn test_write_queue_interest() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (_reader, writer) = unix::pipe().unwrap();
+    let mut queue = WriteQueue::new(1024, 0);
+    let mut handler = TestHandler { events: 0 };
+
+    event_loop.register_opt(&writer, WRITER, EventSet::writable(), PollOpt::level()).unwrap();
+
+    // Nothing queued, writable readiness is not wanted
+    queue.update(&mut event_loop, &writer, WRITER, EventSet::none(), PollOpt::level()).unwrap();
+    event_loop.run_once_timeout(&mut handler, 100).unwrap();
+    assert_eq!(handler.events, 0);
+
+    queue.push(ByteBuf::from_slice(b"hello"));
+    queue.update(&mut event_loop, &writer, WRITER, EventSet::none(), PollOpt::level()).unwrap();
+    event_loop.run_once_timeout(&mut handler, 1_000).unwrap();
+    assert_eq!(handler.events, 1);
+}
+
+#[test]
+pub fn test_write_queue_flush_more() {
+    let addr = ::localhost();
+    let listener = TcpListener::bind(&addr).unwrap();
+    let mut stream = TcpStream::connect(&addr).unwrap();
+
+    ::sleep_ms(100);
+    let mut peer = listener.accept().unwrap().unwrap();
+
+    let mut queue = WriteQueue::new(1024, 0);
+
+    for i in 0..10 {
+        queue.push(ByteBuf::from_slice(&[i; 10]));
+    }
+
+    assert_eq!(queue.flush_more(&mut stream).unwrap(), None);
+    assert!(queue.is_empty());
+
+    // The last buffer went out without the hint, nothing is held back
+    ::sleep_ms(100);
+
+    let mut read = vec![];
+    let mut buf = [0; 1024];
+
+    while let Some(cnt) = peer.try_read(&mut buf).unwrap() {
+        read.extend(buf[..cnt].iter().cloned());
+    }
+
+    assert_eq!(read.len(), 100);
+
+    for (i, chunk) in read.chunks(10).enumerate() {
+        assert!(chunk.iter().all(|&b| b == i as u8));
+    }
+}